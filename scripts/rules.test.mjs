@@ -0,0 +1,37 @@
+// 규칙 TXT 접두사 왕복 테스트 (npm test)
+// src/utils/rules.ts를 TypeScript로 변환해 불러옵니다.
+import assert from 'node:assert/strict';
+import { readFileSync } from 'node:fs';
+import test from 'node:test';
+import ts from 'typescript';
+
+const source = readFileSync(new URL('../src/utils/rules.ts', import.meta.url), 'utf8');
+const { outputText } = ts.transpileModule(source, {
+  compilerOptions: { module: ts.ModuleKind.ESNext, target: ts.ScriptTarget.ES2022 },
+});
+const { parseKeywordPrefix, formatKeywordPrefix } = await import(
+  `data:text/javascript,${encodeURIComponent(outputText)}`
+);
+
+test('접두사로 시작하는 일반 키워드는 일반 키워드로 돌아옵니다', () => {
+  for (const keyword of ['query: cat', 'regex:a+', 'regex/i:A', 'tags:1girl', 'match/word: x', 'literal: y']) {
+    const rule = { keyword, newFileName: 'a', enabled: true };
+    assert.match(formatKeywordPrefix(rule), /^literal: /);
+    assert.deepEqual(parseKeywordPrefix(formatKeywordPrefix(rule)), { keyword });
+  }
+  assert.equal(formatKeywordPrefix({ keyword: 'cat', newFileName: 'a', enabled: true }), 'cat');
+});
+
+test('규칙 종류는 접두사로 왕복합니다', () => {
+  const rules = [
+    { keyword: 'model:x AND cat', kind: 'query' },
+    { keyword: 'a+b', kind: 'regex', caseInsensitive: true },
+    { keyword: 'query:x', kind: 'regex' },
+    { keyword: '1girl', normalize: true, tagMatch: 'word' },
+    { keyword: 'regex:x', tagMatch: 'exact' },
+  ];
+  for (const rule of rules) {
+    const { keyword, ...options } = parseKeywordPrefix(formatKeywordPrefix(rule));
+    assert.deepEqual({ ...options, keyword }, rule);
+  }
+});
@@ -1,12 +1,98 @@
 use tauri::Manager;
 
+pub mod matching;
+pub mod metadata;
+
+/// 파일 경로의 이미지나 동영상에서 메타데이터와 진단 보고서를 추출합니다.
+/// `sidecar_patterns`를 주지 않으면 기본 사이드카 파일 이름 패턴을 씁니다.
+#[tauri::command(async)]
+fn read_metadata(
+    path: String,
+    sidecar_patterns: Option<Vec<String>>,
+) -> Result<metadata::Extraction, metadata::Error> {
+    let sidecars = sidecar_patterns
+        .map(|patterns| metadata::SidecarOptions { patterns })
+        .unwrap_or_default();
+    metadata::read_metadata(path, &sidecars)
+}
+
+/// ZIP, tar, tar.gz 아카이브를 풀지 않고 안의 이미지마다 메타데이터를 추출합니다.
+#[tauri::command(async)]
+fn read_archive(path: String) -> Result<Vec<metadata::archive::ArchiveEntry>, metadata::Error> {
+    metadata::archive::read_archive(path)
+}
+
+/// `read_archive` 목록의 `indices` 항목 압축을 풀어 이어 붙인 바이트를 JSON 대신 그대로 돌려줍니다.
+#[tauri::command(async)]
+fn read_archive_entries(
+    path: String,
+    indices: Vec<usize>,
+) -> Result<tauri::ipc::Response, metadata::Error> {
+    metadata::archive::read_entries(path, &indices).map(tauri::ipc::Response::new)
+}
+
+/// 매칭할 이미지의 메타데이터 필드를 등록합니다. 이미 있는 ID는 필드를 바꿉니다.
+#[tauri::command]
+fn register_images(images: Vec<matching::ImageFields>, store: tauri::State<matching::ImageStore>) {
+    store.insert(images);
+}
+
+/// 등록한 이미지를 지웁니다. `ids`를 주지 않으면 모두 지웁니다.
+#[tauri::command]
+fn unregister_images(ids: Option<Vec<String>>, store: tauri::State<matching::ImageStore>) {
+    match ids {
+        Some(ids) => store.remove(&ids),
+        None => store.clear(),
+    }
+}
+
+/// 등록한 이미지 `image_ids`에 규칙을 적용해 이미지마다 맞은 규칙과 부분 매칭 후보를 돌려줍니다.
+#[tauri::command(async)]
+fn match_images(
+    rule_set: matching::RuleSet,
+    image_ids: Vec<String>,
+    store: tauri::State<'_, matching::ImageStore>,
+) -> Result<Vec<matching::ImageMatch>, matching::RuleError> {
+    matching::match_images(&rule_set, &store, &image_ids)
+}
+
+/// 규칙을 만들거나 고칠 때 정규식, 캡처 그룹 참조, 쿼리, 가중치 조건을 검사합니다.
+/// 태그 단위 규칙은 현재 부분 매칭 설정의 구분자로 나눕니다.
+#[tauri::command]
+fn validate_rule(
+    rule: matching::Rule,
+    settings: matching::PartialMatchSettings,
+) -> Result<(), matching::RuleError> {
+    matching::validate_rule(&rule, &settings)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(matching::ImageStore::default())
+        .invoke_handler(tauri::generate_handler![
+            read_metadata,
+            read_archive,
+            read_archive_entries,
+            register_images,
+            unregister_images,
+            match_images,
+            validate_rule
+        ])
         .setup(|app| {
+            // 앱 설정 폴더의 C2PA 신뢰 앵커
+            #[cfg(feature = "c2pa-verify")]
+            if let Ok(pem) = app
+                .path()
+                .app_config_dir()
+                .and_then(|dir| Ok(std::fs::read(dir.join("trust_anchors.pem"))?))
+            {
+                metadata::c2pa::verify::add_trust_anchors(&pem);
+            }
+
             // 메인 윈도우 포커스
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.set_focus();
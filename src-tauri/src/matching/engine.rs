@@ -0,0 +1,765 @@
+//! Aho-Corasick 매칭 엔진
+//!
+//! 키워드와 토큰을 중복 없이 패턴으로 모으고, 패턴마다 그 패턴을 쓰는 규칙을 역으로 기록합니다.
+//! 필드 하나를 훑으면 들어 있는 패턴 목록이 나오고, 역색인으로 완전 일치 규칙과
+//! 규칙별 일치 토큰 수를 바로 얻습니다. 쿼리 규칙은 검색어를 패턴으로 넣고, 필드별 패턴 목록으로
+//! 쿼리를 평가합니다. 정규식 규칙은 오토마톤에 넣지 않고 따로 검사합니다.
+//! 태그 단위 규칙(정규화 규칙과 [`TagMatch`]를 고른 규칙)은 토큰으로 두 번째 오토마톤을 만들고,
+//! 필드를 태그로 나눠 태그마다 훑은 뒤 찾은 위치가 비교 방식에 맞는지 봅니다.
+//! 토큰 → (규칙, 토큰 위치) 역색인으로 규칙별 일치 토큰 수를 셉니다.
+
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+
+use aho_corasick::{AhoCorasick, MatchKind};
+
+use super::normalize::{self, TagPattern};
+use super::query::field_matches;
+use super::{
+    tag_patterns, tokenize, trim, ImageMatch, Match, Query, RegexRule, Rule, RuleError, RuleKind,
+    RuleSet, TagMatch,
+};
+
+// 스레드 하나가 맡는 최소 이미지 수
+const MIN_CHUNK: usize = 64;
+
+/// 태그 단위 규칙의 (비교 방식, 정규화 여부)
+type TagMode = (TagMatch, bool);
+
+/// 규칙 목록을 컴파일한 매처
+#[derive(Debug)]
+pub struct Matcher<'r> {
+    rules: &'r [Rule],
+    /// 중복 없는 키워드, 토큰, 쿼리 검색어. 오토마톤의 패턴 ID가 인덱스입니다.
+    patterns: Vec<String>,
+    automaton: Option<AhoCorasick>,
+    /// 패턴 → 그 패턴을 키워드로 쓰는 규칙 (규칙 순서)
+    keyword_rules: Vec<Vec<usize>>,
+    /// 패턴 → (부분 매칭 규칙, 그 규칙 안의 토큰 수)
+    token_rules: Vec<Vec<(usize, usize)>>,
+    /// 규칙 → 토큰 패턴 (키워드 안의 순서, 중복 포함). 부분 매칭하지 않는 규칙은 비어 있습니다.
+    tokens: Vec<Vec<usize>>,
+    /// 키워드가 빈 첫 규칙. 빈 문자열은 어느 필드에나 들어 있습니다.
+    empty_keyword: Option<usize>,
+    /// 오토마톤 밖에서 따로 검사하는 (규칙, 정규식이나 쿼리). 규칙 순서
+    checks: Vec<(usize, Check)>,
+    /// 태그 단위 규칙의 토큰 (중복 없음). 패턴 ID가 토큰 ID입니다.
+    tag_automaton: Option<AhoCorasick>,
+    /// 토큰 ID → (태그 단위 규칙, 그 규칙 안의 토큰 위치)
+    tag_rules: Vec<Vec<(usize, usize)>>,
+    /// 규칙 → (토큰 ID, 가중치 조건). 태그 단위 규칙이 아니면 비어 있습니다.
+    tag_tokens: Vec<Vec<(usize, TagPattern)>>,
+    /// 규칙 → 비교 방식. 태그 단위 규칙이 아니면 `None`
+    tag_modes: Vec<Option<TagMode>>,
+    /// 필드를 구분자로 나눠야 하는지, 정규화해야 하는지
+    split_tags: (bool, bool),
+    /// 부분 매칭하는 규칙
+    partial: Vec<bool>,
+    /// 필드 값을 태그로 나눌 구분자
+    separator: String,
+    min_match_ratio: f64,
+}
+
+impl<'r> Matcher<'r> {
+    /// 활성화된 정규식 규칙이나 쿼리 규칙이 잘못되었거나,
+    /// 패턴이 너무 많아 오토마톤이 크기 상한을 넘으면 오류입니다.
+    pub fn new(rule_set: &'r RuleSet) -> Result<Self, RuleError> {
+        let settings = rule_set.partial_match.clone().unwrap_or_default();
+        let separator = settings.separator();
+
+        let mut patterns: Vec<String> = Vec::new();
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        let mut intern = |pattern: &str| {
+            if let Some(&id) = ids.get(pattern) {
+                return id;
+            }
+            patterns.push(pattern.to_owned());
+            ids.insert(pattern.to_owned(), patterns.len() - 1);
+            patterns.len() - 1
+        };
+
+        let mut keywords = Vec::new();
+        let mut tokens = vec![Vec::new(); rule_set.rules.len()];
+        let mut empty_keyword = None;
+        let mut checks = Vec::new();
+        let mut tag_texts: Vec<String> = Vec::new();
+        let mut tag_ids: HashMap<String, usize> = HashMap::new();
+        let mut tag_tokens = vec![Vec::new(); rule_set.rules.len()];
+        let mut tag_modes = vec![None; rule_set.rules.len()];
+        let mut partial = vec![false; rule_set.rules.len()];
+        for (index, rule) in rule_set.rules.iter().enumerate() {
+            if !rule.enabled {
+                continue;
+            }
+            partial[index] = settings.global_enabled || rule.partial_match;
+            match rule.kind {
+                RuleKind::Literal if rule.normalize || rule.tag_match.is_some() => {
+                    let rule_tokens = if rule.normalize {
+                        tag_patterns(rule, separator)?
+                    } else {
+                        tokenize(&rule.keyword, separator)
+                            .into_iter()
+                            .map(TagPattern::literal)
+                            .collect()
+                    };
+                    tag_tokens[index] = rule_tokens
+                        .into_iter()
+                        .map(|pattern| {
+                            let id = *tag_ids.entry(pattern.text.clone()).or_insert_with(|| {
+                                tag_texts.push(pattern.text.clone());
+                                tag_texts.len() - 1
+                            });
+                            (id, pattern)
+                        })
+                        .collect();
+                    tag_modes[index] = Some((rule.tag_match.unwrap_or_default(), rule.normalize));
+                    continue;
+                }
+                RuleKind::Literal => {}
+                RuleKind::Regex => {
+                    checks.push((index, Check::Regex(RegexRule::compile(rule)?)));
+                    continue;
+                }
+                RuleKind::Query => {
+                    let query = Query::parse(&rule.keyword)
+                        .map_err(|error| RuleError::query(rule, error))?;
+                    checks.push((index, Check::Query(CompiledQuery::new(&query, &mut intern))));
+                    continue;
+                }
+            }
+            if rule.keyword.is_empty() {
+                empty_keyword = empty_keyword.or(Some(index));
+            } else {
+                keywords.push((intern(&rule.keyword), index));
+            }
+            if partial[index] {
+                tokens[index] = tokenize(&rule.keyword, separator)
+                    .into_iter()
+                    .map(&mut intern)
+                    .collect();
+            }
+        }
+
+        let mut keyword_rules = vec![Vec::new(); patterns.len()];
+        for (pattern, index) in keywords {
+            keyword_rules[pattern].push(index);
+        }
+        let mut token_rules: Vec<Vec<(usize, usize)>> = vec![Vec::new(); patterns.len()];
+        for (index, rule_tokens) in tokens.iter().enumerate() {
+            for &pattern in rule_tokens {
+                match token_rules[pattern].last_mut() {
+                    Some((last, count)) if *last == index => *count += 1,
+                    _ => token_rules[pattern].push((index, 1)),
+                }
+            }
+        }
+
+        let mut tag_rules = vec![Vec::new(); tag_texts.len()];
+        for (index, rule_tags) in tag_tokens.iter().enumerate() {
+            for (token, (tag, _)) in rule_tags.iter().enumerate() {
+                tag_rules[*tag].push((index, token));
+            }
+        }
+
+        let automaton = build_automaton(&patterns)?;
+        let tag_automaton = build_automaton(&tag_texts)?;
+        Ok(Self {
+            rules: &rule_set.rules,
+            patterns,
+            automaton,
+            keyword_rules,
+            token_rules,
+            tokens,
+            empty_keyword,
+            checks,
+            tag_automaton,
+            tag_rules,
+            tag_tokens,
+            split_tags: (
+                tag_modes
+                    .iter()
+                    .flatten()
+                    .any(|&(_, normalized)| !normalized),
+                tag_modes
+                    .iter()
+                    .flatten()
+                    .any(|&(_, normalized)| normalized),
+            ),
+            tag_modes,
+            partial,
+            separator: separator.to_owned(),
+            min_match_ratio: settings.min_match_ratio(),
+        })
+    }
+
+    /// 이미지마다 규칙을 적용합니다. 이미지가 많으면 여러 스레드로 나눕니다.
+    pub fn match_all(&self, images: &[(&str, &[(String, String)])]) -> Vec<ImageMatch> {
+        let threads = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk = images.len().div_ceil(threads).max(MIN_CHUNK);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = images
+                .chunks(chunk)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|&(id, fields)| self.match_image(id, fields))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        })
+    }
+
+    /// `fields`는 (필드 이름, 값)을 메타데이터 순서대로 담습니다.
+    pub fn match_image(&self, image_id: &str, fields: &[(String, String)]) -> ImageMatch {
+        let mut result = ImageMatch {
+            image_id: image_id.to_owned(),
+            matched: None,
+            candidates: Vec::new(),
+            is_partial_match: false,
+        };
+        if fields.is_empty() {
+            return result;
+        }
+        let present: Vec<Vec<usize>> = fields
+            .iter()
+            .map(|(_, value)| self.present(value))
+            .collect();
+        let hits: Vec<TagHits> = if self.tag_automaton.is_none() {
+            Vec::new()
+        } else {
+            fields
+                .iter()
+                .map(|(_, value)| self.tag_hits(value))
+                .collect()
+        };
+        let tag_counts: Vec<HashMap<usize, usize>> =
+            hits.iter().map(|hits| self.tag_counts(hits)).collect();
+
+        // 1단계: 완전 일치. 규칙 순서가 먼저, 같은 규칙이면 앞 필드
+        let mut exact = self.empty_keyword.map(|rule| (rule, 0));
+        for (field, patterns) in present.iter().enumerate() {
+            for &pattern in patterns {
+                if let Some(&rule) = self.keyword_rules[pattern].first() {
+                    if exact.is_none_or(|(best, _)| rule < best) {
+                        exact = Some((rule, field));
+                    }
+                }
+            }
+        }
+        for (field, counts) in tag_counts.iter().enumerate() {
+            for (&rule, &count) in counts {
+                if count == self.tag_tokens[rule].len() && exact.is_none_or(|(best, _)| rule < best)
+                {
+                    exact = Some((rule, field));
+                }
+            }
+        }
+        // 이미 찾은 규칙보다 앞선 정규식, 쿼리 규칙만 검사합니다.
+        for (rule, check) in &self.checks {
+            if exact.is_some_and(|(best, _)| best < *rule) {
+                break;
+            }
+            let field = match check {
+                Check::Regex(regex) => fields.iter().position(|(_, value)| regex.is_match(value)),
+                Check::Query(query) => query.matched_field(fields, &present),
+            };
+            if let Some(field) = field {
+                exact = Some((*rule, field));
+                break;
+            }
+        }
+        if let Some((rule, field)) = exact {
+            result.matched = Some(Match {
+                rule_id: self.rules[rule].id.clone(),
+                new_file_name: self.new_file_name(rule, &fields[field].1),
+                matched_field: fields[field].0.clone(),
+                match_score: 1.0,
+                matched_tokens: Vec::new(),
+                total_tokens: 0,
+            });
+            return result;
+        }
+
+        // 2단계: 부분 매칭
+        let mut candidates = Vec::new();
+        for (field, patterns) in present.iter().enumerate() {
+            let mut counts: HashMap<usize, usize> = HashMap::new();
+            for &pattern in patterns {
+                for &(rule, count) in &self.token_rules[pattern] {
+                    *counts.entry(rule).or_default() += count;
+                }
+            }
+            for (rule, matched) in counts {
+                let score = matched as f64 / self.tokens[rule].len() as f64;
+                if score >= self.min_match_ratio && score < 1.0 {
+                    candidates.push((score, rule, field));
+                }
+            }
+        }
+        for (field, counts) in tag_counts.iter().enumerate() {
+            for (&rule, &matched) in counts {
+                let score = matched as f64 / self.tag_tokens[rule].len() as f64;
+                if self.partial[rule] && score >= self.min_match_ratio && score < 1.0 {
+                    candidates.push((score, rule, field));
+                }
+            }
+        }
+        // 점수가 높은 순. 같은 점수는 프론트엔드의 안정 정렬처럼 규칙, 필드 순서
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+        let mut seen = HashSet::new();
+        for (score, rule, field) in candidates {
+            if !seen.insert(self.rules[rule].id.as_str()) {
+                continue;
+            }
+            let (matched_tokens, total_tokens) = match self.tag_modes[rule] {
+                None => {
+                    let matched = self.tokens[rule]
+                        .iter()
+                        .filter(|pattern| present[field].binary_search(pattern).is_ok())
+                        .map(|&pattern| self.patterns[pattern].clone())
+                        .collect();
+                    (matched, self.tokens[rule].len())
+                }
+                Some(mode) => {
+                    let matched = self.tag_tokens[rule]
+                        .iter()
+                        .filter(|(token, pattern)| {
+                            token_allowed(&hits[field], *token, mode, pattern)
+                        })
+                        .map(|(_, pattern)| pattern.text.clone())
+                        .collect();
+                    (matched, self.tag_tokens[rule].len())
+                }
+            };
+            result.candidates.push(Match {
+                rule_id: self.rules[rule].id.clone(),
+                new_file_name: self.rules[rule].new_file_name.clone(),
+                matched_field: fields[field].0.clone(),
+                match_score: score,
+                matched_tokens,
+                total_tokens,
+            });
+        }
+        result.matched = result.candidates.first().cloned();
+        result.is_partial_match = result.matched.is_some();
+        result
+    }
+
+    // 정규식 규칙은 `value`에서 찾은 캡처 그룹을 채웁니다.
+    fn new_file_name(&self, rule: usize, value: &str) -> String {
+        let template = &self.rules[rule].new_file_name;
+        match self.checks.binary_search_by_key(&rule, |(index, _)| *index) {
+            Ok(position) => match &self.checks[position].1 {
+                Check::Regex(regex) => regex.render(template, value),
+                Check::Query(_) => template.clone(),
+            },
+            Err(_) => template.clone(),
+        }
+    }
+
+    // `text`를 구분자로 나눈 태그와 정규화한 태그에서 토큰을 찾습니다.
+    // 정규화 규칙이 없으면 정규화하지 않고, 구분자만 쓰는 규칙이 없으면 나누지 않습니다.
+    fn tag_hits(&self, text: &str) -> TagHits {
+        let mut hits = TagHits::new();
+        let Some(automaton) = &self.tag_automaton else {
+            return hits;
+        };
+        let (split, normalized) = self.split_tags;
+        if split {
+            for tag in text.split(self.separator.as_str()).map(trim) {
+                record_hits(automaton, tag, 1.0, false, &mut hits);
+            }
+        }
+        if normalized {
+            for tag in normalize::tags(text, &self.separator) {
+                record_hits(automaton, &tag.text, tag.weight, true, &mut hits);
+            }
+        }
+        hits
+    }
+
+    // 태그 단위 규칙별 일치 토큰 수
+    fn tag_counts(&self, hits: &TagHits) -> HashMap<usize, usize> {
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for (&(token, mode, normalized), weights) in hits {
+            for &(rule, position) in &self.tag_rules[token] {
+                let pattern = &self.tag_tokens[rule][position].1;
+                if self.tag_modes[rule] == Some((mode, normalized))
+                    && weights.iter().any(|&weight| pattern.allows(weight))
+                {
+                    *counts.entry(rule).or_default() += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    // `text`에 들어 있는 패턴 (정렬, 중복 없음)
+    fn present(&self, text: &str) -> Vec<usize> {
+        let Some(automaton) = &self.automaton else {
+            return Vec::new();
+        };
+        let mut patterns: Vec<usize> = automaton
+            .find_overlapping_iter(text)
+            .map(|found| found.pattern().as_usize())
+            .collect();
+        patterns.sort_unstable();
+        patterns.dedup();
+        patterns
+    }
+}
+
+#[derive(Debug)]
+enum Check {
+    Regex(RegexRule),
+    Query(CompiledQuery),
+}
+
+/// 검색어를 패턴 ID로 바꾼 쿼리
+#[derive(Debug)]
+struct CompiledQuery {
+    root: Node,
+    /// `NOT` 아래에 있지 않은 (한정자, 패턴)
+    positive: Vec<(Option<String>, usize)>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Term {
+        field: Option<String>,
+        pattern: usize,
+    },
+    Not(Box<Node>),
+    And(Vec<Node>),
+    Or(Vec<Node>),
+}
+
+impl CompiledQuery {
+    fn new(query: &Query, intern: &mut impl FnMut(&str) -> usize) -> Self {
+        let positive = query
+            .positive_terms()
+            .into_iter()
+            .map(|(field, text)| (field.map(str::to_owned), intern(text)))
+            .collect();
+        Self {
+            root: Node::new(query, intern),
+            positive,
+        }
+    }
+
+    /// 쿼리가 참이면 검색어가 처음 나온 필드. 부정 검색어만 있으면 첫 필드입니다.
+    fn matched_field(&self, fields: &[(String, String)], present: &[Vec<usize>]) -> Option<usize> {
+        if !self.root.eval(fields, present) {
+            return None;
+        }
+        let field = (0..fields.len()).find(|&i| {
+            self.positive.iter().any(|(field, pattern)| {
+                term_in(field.as_deref(), *pattern, &fields[i].0, &present[i])
+            })
+        });
+        Some(field.unwrap_or(0))
+    }
+}
+
+impl Node {
+    fn new(query: &Query, intern: &mut impl FnMut(&str) -> usize) -> Self {
+        match query {
+            Query::Term { field, text } => Self::Term {
+                field: field.clone(),
+                pattern: intern(text),
+            },
+            Query::Not(query) => Self::Not(Box::new(Self::new(query, intern))),
+            Query::And(queries) => {
+                Self::And(queries.iter().map(|q| Self::new(q, intern)).collect())
+            }
+            Query::Or(queries) => Self::Or(queries.iter().map(|q| Self::new(q, intern)).collect()),
+        }
+    }
+
+    fn eval(&self, fields: &[(String, String)], present: &[Vec<usize>]) -> bool {
+        match self {
+            Self::Term { field, pattern } => fields
+                .iter()
+                .zip(present)
+                .any(|((name, _), patterns)| term_in(field.as_deref(), *pattern, name, patterns)),
+            Self::Not(node) => !node.eval(fields, present),
+            Self::And(nodes) => nodes.iter().all(|node| node.eval(fields, present)),
+            Self::Or(nodes) => nodes.iter().any(|node| node.eval(fields, present)),
+        }
+    }
+}
+
+// 패턴이 없으면 `None`입니다. 빌드는 오토마톤이 크기 상한을 넘을 때만 실패합니다.
+fn build_automaton(patterns: &[String]) -> Result<Option<AhoCorasick>, RuleError> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    AhoCorasick::builder()
+        .match_kind(MatchKind::Standard)
+        .build(patterns)
+        .map(Some)
+        .map_err(|error| RuleError::TooManyPatterns {
+            message: error.to_string(),
+        })
+}
+
+/// 필드 하나에서 찾은 (토큰 ID, 비교 방식, 정규화 여부) → 그 태그들의 가중치
+type TagHits = HashMap<(usize, TagMatch, bool), Vec<f64>>;
+
+// 태그 하나를 훑어 찾은 토큰을 그 위치가 맞는 비교 방식마다 기록합니다.
+fn record_hits(
+    automaton: &AhoCorasick,
+    tag: &str,
+    weight: f64,
+    normalized: bool,
+    hits: &mut TagHits,
+) {
+    for found in automaton.find_overlapping_iter(tag) {
+        let (start, end) = (found.start(), found.end());
+        let modes = [
+            (TagMatch::Substring, true),
+            (TagMatch::Prefix, start == 0),
+            (TagMatch::Exact, start == 0 && end == tag.len()),
+            (
+                TagMatch::Word,
+                word_boundary(tag, start) && word_boundary(tag, end),
+            ),
+        ];
+        for (mode, hit) in modes {
+            if hit {
+                hits.entry((found.pattern().as_usize(), mode, normalized))
+                    .or_default()
+                    .push(weight);
+            }
+        }
+    }
+}
+
+// `index`의 양쪽이 모두 글자나 숫자가 아니면 단어 경계입니다.
+fn word_boundary(text: &str, index: usize) -> bool {
+    let before = text[..index].chars().next_back();
+    let after = text[index..].chars().next();
+    !(before.is_some_and(char::is_alphanumeric) && after.is_some_and(char::is_alphanumeric))
+}
+
+// 토큰이 비교 방식에 맞게, 조건에 맞는 가중치로 나왔는지
+fn token_allowed(
+    hits: &TagHits,
+    token: usize,
+    (mode, normalized): TagMode,
+    pattern: &TagPattern,
+) -> bool {
+    hits.get(&(token, mode, normalized))
+        .is_some_and(|weights| weights.iter().any(|&weight| pattern.allows(weight)))
+}
+
+// 필드 `name`이 한정자에 맞고 그 값에 `pattern`이 들어 있는지
+fn term_in(field: Option<&str>, pattern: usize, name: &str, present: &[usize]) -> bool {
+    field.is_none_or(|field| field_matches(name, field)) && present.binary_search(&pattern).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::matching::testing::{rule, store};
+    use crate::matching::{match_images, ImageFields, ImageStore, PartialMatchSettings};
+
+    // 프론트엔드 `matchImage`를 그대로 옮긴 구현
+    fn match_image(rule_set: &RuleSet, id: &str, fields: &[(String, String)]) -> ImageMatch {
+        let mut result = ImageMatch {
+            image_id: id.to_owned(),
+            matched: None,
+            candidates: Vec::new(),
+            is_partial_match: false,
+        };
+        if fields.is_empty() {
+            return result;
+        }
+        let enabled: Vec<&Rule> = rule_set.rules.iter().filter(|rule| rule.enabled).collect();
+        for rule in &enabled {
+            for (name, value) in fields {
+                if value.contains(&rule.keyword) {
+                    result.matched = Some(Match {
+                        rule_id: rule.id.clone(),
+                        new_file_name: rule.new_file_name.clone(),
+                        matched_field: name.clone(),
+                        match_score: 1.0,
+                        matched_tokens: Vec::new(),
+                        total_tokens: 0,
+                    });
+                    return result;
+                }
+            }
+        }
+
+        let settings = rule_set.partial_match.clone().unwrap_or_default();
+        let mut candidates = Vec::new();
+        for rule in &enabled {
+            if !(settings.global_enabled || rule.partial_match) {
+                continue;
+            }
+            let tokens = tokenize(&rule.keyword, settings.separator());
+            if tokens.is_empty() {
+                continue;
+            }
+            for (name, value) in fields {
+                let matched: Vec<String> = tokens
+                    .iter()
+                    .filter(|&&token| value.contains(token))
+                    .map(|&token| token.to_owned())
+                    .collect();
+                let score = matched.len() as f64 / tokens.len() as f64;
+                if score >= settings.min_match_ratio() && score < 1.0 {
+                    candidates.push(Match {
+                        rule_id: rule.id.clone(),
+                        new_file_name: rule.new_file_name.clone(),
+                        matched_field: name.clone(),
+                        match_score: score,
+                        matched_tokens: matched,
+                        total_tokens: tokens.len(),
+                    });
+                }
+            }
+        }
+        // 안정 정렬이므로 점수가 같으면 규칙과 필드 순서를 유지합니다.
+        candidates.sort_by(|a, b| b.match_score.total_cmp(&a.match_score));
+        let mut seen = HashSet::new();
+        result.candidates = candidates
+            .into_iter()
+            .filter(|candidate| seen.insert(candidate.rule_id.clone()))
+            .collect();
+        result.matched = result.candidates.first().cloned();
+        result.is_partial_match = result.matched.is_some();
+        result
+    }
+
+    // xorshift
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+            items[self.below(items.len())]
+        }
+    }
+
+    // 겹치는 단어(`girl`과 `1girl`, `hat`과 `chat`), 빈 토큰, 한글을 섞습니다.
+    const WORDS: &[&str] = &[
+        "1girl",
+        "girl",
+        "solo",
+        "smile",
+        "hat",
+        "chat",
+        "white background",
+        "halo",
+        "blue eyes",
+        "eyes",
+        "",
+        " ",
+        "모자",
+        "소녀",
+        "a",
+        "ab",
+    ];
+
+    fn phrase(random: &mut Random, separator: &str) -> String {
+        let count = random.below(5);
+        (0..count)
+            .map(|_| random.pick(WORDS))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    #[test]
+    fn same_as_frontend() {
+        let mut random = Random(0x1234_5678_9abc_def1);
+        for round in 0..400 {
+            let separator = random.pick(&[",", ", ", "|", ""]);
+            let keyword_separator = if separator.is_empty() { "," } else { separator };
+            let rules: Vec<Rule> = (0..random.below(12))
+                .map(|i| {
+                    // 가끔 ID가 겹치는 규칙을 만듭니다.
+                    let id = if random.below(10) == 0 { 0 } else { i };
+                    Rule {
+                        new_file_name: format!("n{i}.png"),
+                        enabled: random.below(5) != 0,
+                        partial_match: random.below(2) == 0,
+                        ..rule(
+                            &format!("r{id}"),
+                            RuleKind::Literal,
+                            &phrase(&mut random, keyword_separator),
+                        )
+                    }
+                })
+                .collect();
+            let rule_set = RuleSet {
+                rules,
+                partial_match: (random.below(4) != 0).then(|| PartialMatchSettings {
+                    global_enabled: random.below(2) == 0,
+                    min_match_ratio: random.pick(&[0.0, 0.1, 0.5, 0.7, 0.99]),
+                    token_separator: separator.to_owned(),
+                }),
+            };
+            let images: Vec<ImageFields> = (0..random.below(200))
+                .map(|i| ImageFields {
+                    id: format!("img{i}"),
+                    fields: (0..random.below(4))
+                        .map(|field| (format!("F{field}"), phrase(&mut random, ", ")))
+                        .collect(),
+                })
+                .collect();
+            let store = ImageStore::default();
+            store.insert(images.clone());
+
+            let mut ids: Vec<String> = images.iter().map(|image| image.id.clone()).collect();
+            ids.push("missing".to_owned());
+            let matches = match_images(&rule_set, &store, &ids).unwrap();
+            assert_eq!(matches.len(), ids.len());
+            for (image, got) in images.iter().zip(&matches) {
+                let expected = match_image(&rule_set, &image.id, &image.fields);
+                assert_eq!(got, &expected, "{round}: {rule_set:?} {:?}", image.fields);
+            }
+            assert_eq!(matches.last().unwrap().matched, None);
+        }
+    }
+
+    #[test]
+    fn frontend_rule_set() {
+        let rule_set: RuleSet = serde_json::from_str(
+            r#"{"rules": [{"id": "a", "keyword": "1girl, hat", "newFileName": "x.png", "enabled": true, "matchCount": 3}],
+                "partialMatch": {"globalEnabled": true, "minMatchRatio": 0.5, "tokenSeparator": ","}}"#,
+        )
+        .unwrap();
+        let store = store(&[("i", &[("PNG:parameters", "1girl, solo")])]);
+        let ids = ["i".to_owned()];
+        let matches = match_images(&rule_set, &store, &ids).unwrap();
+        let json = serde_json::to_value(matches).unwrap();
+        assert_eq!(json[0]["matched"]["ruleId"], "a");
+        assert_eq!(json[0]["matched"]["matchScore"], 0.5);
+        assert_eq!(json[0]["matched"]["matchedTokens"][0], "1girl");
+        assert_eq!(json[0]["isPartialMatch"], true);
+
+        store.remove(&ids);
+        assert!(store.is_empty());
+    }
+}
@@ -0,0 +1,408 @@
+//! 키워드 규칙 매칭
+//!
+//! 모든 규칙의 키워드와 부분 매칭 토큰을 하나의 Aho-Corasick 오토마톤으로 컴파일하고,
+//! 메타데이터 필드마다 한 번만 훑어 어떤 패턴이 들어 있는지 찾습니다.
+//! 결과는 프론트엔드 `applyRules`의 의미와 같습니다.
+//!
+//! 1. 완전 일치: 활성화된 규칙 순서대로, 키워드가 들어 있는 첫 필드. 점수 1.0
+//! 2. 부분 매칭: 키워드를 구분자로 나눈 토큰 중 필드에 들어 있는 비율이
+//!    최소 일치율 이상 1.0 미만인 (규칙, 필드). 점수가 높은 순, 같으면 규칙과 필드 순서
+//!
+//! 정규식 규칙([`RuleKind::Regex`])과 쿼리 규칙([`RuleKind::Query`])은 완전 일치 단계에서
+//! 같은 규칙 순서로 검사하고, 부분 매칭에는 참여하지 않습니다. 쿼리의 검색어는 키워드와 같은
+//! 오토마톤에 들어갑니다.
+//!
+//! 태그 단위 규칙([`Rule::tag_match`], [`Rule::normalize`])은 키워드와 필드 값을 모두 태그로 나눠
+//! 태그끼리 비교하므로 `girl`이 `1girl`에, `hat`이 `chat`에 맞지 않습니다. 모든 토큰이 한 필드에
+//! 있으면 완전 일치, 일부만 있으면 부분 매칭입니다.
+
+mod engine;
+pub mod normalize;
+mod pattern;
+pub mod query;
+mod store;
+#[cfg(test)]
+mod testing;
+
+use serde::{Deserialize, Serialize};
+
+pub use engine::Matcher;
+pub use normalize::{Tag, TagPattern};
+pub use pattern::RegexRule;
+pub use query::{Query, QueryError};
+pub use store::{ImageFields, ImageStore};
+
+// 프론트엔드의 `tokenSeparator || ','`, `minMatchRatio || 0.7`
+const DEFAULT_SEPARATOR: &str = ",";
+const DEFAULT_MIN_MATCH_RATIO: f64 = 0.7;
+
+/// 키워드 규칙 (`KeywordRule`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    pub id: String,
+    pub keyword: String,
+    pub new_file_name: String,
+    pub enabled: bool,
+    /// 전역 설정이 꺼져 있어도 이 규칙은 부분 매칭
+    #[serde(default)]
+    pub partial_match: bool,
+    #[serde(default)]
+    pub kind: RuleKind,
+    /// 정규식 규칙의 대소문자 무시
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// 리터럴 규칙을 프롬프트 문법을 벗긴 태그로 비교 ([`normalize`])
+    #[serde(default)]
+    pub normalize: bool,
+    /// 리터럴 규칙을 구분자로 나눈 태그 단위로 비교. `None`이면 필드 전체에서 찾고,
+    /// 정규화 규칙은 [`TagMatch::Exact`]입니다.
+    #[serde(default)]
+    pub tag_match: Option<TagMatch>,
+}
+
+/// 토큰과 태그를 비교하는 방식
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TagMatch {
+    /// 태그 전체가 같음
+    #[default]
+    Exact,
+    /// 태그 안에 들어 있음 (태그 경계를 넘지 않음)
+    Substring,
+    /// 태그가 토큰으로 시작함 (`long` → `long hair`)
+    Prefix,
+    /// 태그 안에서 단어 경계에 맞음 (`hat` → `red hat`, `chat`은 아님)
+    Word,
+}
+
+/// 키워드를 해석하는 방식
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RuleKind {
+    /// 필드에 그대로 들어 있는 문자열
+    #[default]
+    Literal,
+    /// 정규식. 새 파일 이름에 캡처 그룹(`{1}`, `{name}`)을 쓸 수 있습니다.
+    Regex,
+    /// `AND`, `OR`, `NOT`과 필드 한정자를 쓰는 쿼리 ([`query`])
+    Query,
+}
+
+/// 규칙을 컴파일할 수 없는 이유
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum RuleError {
+    #[error("잘못된 정규식입니다: {message}")]
+    InvalidPattern { rule_id: String, message: String },
+    /// 새 파일 이름이 정규식에 없는 캡처 그룹을 참조함
+    #[error("없는 캡처 그룹입니다: {{{group}}}")]
+    UnknownGroup { rule_id: String, group: String },
+    #[error("잘못된 쿼리입니다: {message}")]
+    InvalidQuery {
+        rule_id: String,
+        message: String,
+        /// 문제가 있는 곳의 글자 위치 (0부터)
+        position: usize,
+    },
+    /// 정규화 규칙의 태그에 붙은 `@` 가중치 조건을 읽을 수 없음
+    #[error("잘못된 가중치 조건입니다: {tag}")]
+    InvalidWeight { rule_id: String, tag: String },
+    /// 태그 단위 규칙의 키워드를 나누면 태그가 하나도 없음
+    #[error("비교할 태그가 없습니다")]
+    NoTags { rule_id: String },
+    /// 규칙 전체의 패턴이 너무 많아 매칭 오토마톤을 만들 수 없음
+    #[error("규칙이 너무 많습니다: {message}")]
+    TooManyPatterns { message: String },
+}
+
+impl RuleError {
+    fn query(rule: &Rule, error: QueryError) -> Self {
+        Self::InvalidQuery {
+            rule_id: rule.id.clone(),
+            message: error.to_string(),
+            position: error.position,
+        }
+    }
+}
+
+/// 규칙을 만들 때 검사합니다. 태그 단위가 아닌 리터럴 규칙은 항상 올바릅니다.
+/// 태그 단위 규칙은 매칭과 같은 구분자(`settings`)로 나눠 태그가 하나 이상 있는지 확인합니다.
+pub fn validate_rule(rule: &Rule, settings: &PartialMatchSettings) -> Result<(), RuleError> {
+    let separator = settings.separator();
+    let count = match rule.kind {
+        RuleKind::Literal if rule.normalize => tag_patterns(rule, separator)?.len(),
+        RuleKind::Literal if rule.tag_match.is_some() => tokenize(&rule.keyword, separator).len(),
+        RuleKind::Literal => return Ok(()),
+        RuleKind::Regex => return RegexRule::compile(rule).map(|_| ()),
+        RuleKind::Query => {
+            return Query::parse(&rule.keyword)
+                .map(|_| ())
+                .map_err(|error| RuleError::query(rule, error))
+        }
+    };
+    if count == 0 {
+        return Err(RuleError::NoTags {
+            rule_id: rule.id.clone(),
+        });
+    }
+    Ok(())
+}
+
+// 정규화 규칙의 키워드를 태그와 가중치 조건으로 나눕니다.
+fn tag_patterns(rule: &Rule, separator: &str) -> Result<Vec<TagPattern>, RuleError> {
+    normalize::tags(&rule.keyword, separator)
+        .into_iter()
+        .map(|tag| {
+            TagPattern::parse(&tag.text).ok_or_else(|| RuleError::InvalidWeight {
+                rule_id: rule.id.clone(),
+                tag: tag.text,
+            })
+        })
+        .collect()
+}
+
+/// 부분 매칭 설정 (`PartialMatchSettings`)
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PartialMatchSettings {
+    pub global_enabled: bool,
+    /// 0이면 기본값 0.7
+    pub min_match_ratio: f64,
+    /// 비어 있으면 기본값 `,`
+    pub token_separator: String,
+}
+
+impl PartialMatchSettings {
+    fn separator(&self) -> &str {
+        if self.token_separator.is_empty() {
+            DEFAULT_SEPARATOR
+        } else {
+            &self.token_separator
+        }
+    }
+
+    fn min_match_ratio(&self) -> f64 {
+        if self.min_match_ratio == 0.0 {
+            DEFAULT_MIN_MATCH_RATIO
+        } else {
+            self.min_match_ratio
+        }
+    }
+}
+
+/// 규칙 목록과 부분 매칭 설정
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleSet {
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub partial_match: Option<PartialMatchSettings>,
+}
+
+/// 이미지에 맞은 규칙 하나 (`MatchCandidate`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Match {
+    pub rule_id: String,
+    /// 확장자를 붙이기 전의 새 파일 이름. 정규식 규칙은 캡처 그룹을 채운 결과입니다.
+    pub new_file_name: String,
+    pub matched_field: String,
+    /// 일치율 (완전 일치는 1.0)
+    pub match_score: f64,
+    /// 필드에 들어 있던 토큰. 완전 일치면 비어 있습니다.
+    pub matched_tokens: Vec<String>,
+    pub total_tokens: usize,
+}
+
+/// 이미지 하나의 매칭 결과
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMatch {
+    pub image_id: String,
+    /// 가장 높은 점수의 규칙. 맞은 규칙이 없으면 `None`입니다.
+    pub matched: Option<Match>,
+    /// 부분 매칭 후보 (규칙마다 최고 점수 하나, 점수 순)
+    pub candidates: Vec<Match>,
+    pub is_partial_match: bool,
+}
+
+/// 저장소의 이미지 `image_ids`에 규칙을 적용합니다. 저장소에 없는 이미지는 필드가 없는 것으로 봅니다.
+pub fn match_images(
+    rule_set: &RuleSet,
+    store: &ImageStore,
+    image_ids: &[String],
+) -> Result<Vec<ImageMatch>, RuleError> {
+    let matcher = Matcher::new(rule_set)?;
+    Ok(store.with_images(image_ids, |images| matcher.match_all(images)))
+}
+
+// JS `String.prototype.trim`은 BOM(U+FEFF)도 공백으로 봅니다.
+fn trim(token: &str) -> &str {
+    token.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}')
+}
+
+/// 키워드를 구분자로 나눈 토큰. 빈 토큰은 버리고 중복은 그대로 둡니다.
+pub fn tokenize<'a>(keyword: &'a str, separator: &str) -> Vec<&'a str> {
+    keyword
+        .split(separator)
+        .map(trim)
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::testing::{rule, store};
+
+    fn tag_rule(keyword: &str, tag_match: Option<TagMatch>, normalize: bool) -> Rule {
+        Rule {
+            partial_match: true,
+            normalize,
+            tag_match,
+            ..rule("r", RuleKind::Literal, keyword)
+        }
+    }
+
+    fn run(rule: Rule, partial_match: Option<PartialMatchSettings>) -> ImageMatch {
+        let store = store(&[(
+            "i",
+            &[("F", "1girl, 2girls, chat, red hat, long_hair, (Smile:1.2)")],
+        )]);
+        let rule_set = RuleSet {
+            rules: vec![rule],
+            partial_match,
+        };
+        match_images(&rule_set, &store, &["i".into()])
+            .unwrap()
+            .remove(0)
+    }
+
+    // 완전 일치했는지
+    fn hit(keyword: &str, tag_match: Option<TagMatch>, normalize: bool) -> bool {
+        run(tag_rule(keyword, tag_match, normalize), None)
+            .matched
+            .is_some_and(|matched| matched.match_score == 1.0)
+    }
+
+    #[test]
+    fn field_substring() {
+        assert!(hit("girl", None, false));
+        assert!(hit("hat", None, false));
+        assert!(hit("girls, chat", None, false));
+    }
+
+    #[test]
+    fn tag_boundaries() {
+        use TagMatch::*;
+
+        assert!(!hit("girl", Some(Exact), false));
+        assert!(hit("1girl", Some(Exact), false));
+        assert!(!hit("hat", Some(Exact), false));
+        assert!(hit("red hat, chat", Some(Exact), false));
+        assert!(!hit("chat, red", Some(Exact), false));
+
+        assert!(hit("girl", Some(Substring), false));
+
+        assert!(hit("long", Some(Prefix), false));
+        assert!(!hit("hair", Some(Prefix), false));
+        assert!(!hit("girl", Some(Prefix), false));
+
+        assert!(hit("hat", Some(Word), false));
+        assert!(!hit("girl", Some(Word), false));
+        assert!(hit("hair", Some(Word), false));
+    }
+
+    #[test]
+    fn normalized_boundaries() {
+        assert!(hit("hair", Some(TagMatch::Word), true));
+        assert!(hit("long", Some(TagMatch::Prefix), true));
+        assert!(hit("smile@>1.1", None, true));
+        assert!(!hit("girl", None, true));
+    }
+
+    #[test]
+    fn partial_tags() {
+        let settings = PartialMatchSettings {
+            min_match_ratio: 0.6,
+            ..PartialMatchSettings::default()
+        };
+        let image = run(
+            tag_rule("hat, girl, 1girl", Some(TagMatch::Word), false),
+            Some(settings),
+        );
+        let matched = image.matched.unwrap();
+        assert_eq!(matched.matched_tokens, ["hat", "1girl"]);
+        assert_eq!(matched.total_tokens, 3);
+    }
+
+    #[test]
+    fn deserialize_tag_match() {
+        let rule: Rule = serde_json::from_str(
+            r#"{"id": "a", "keyword": "x", "newFileName": "x", "enabled": true, "tagMatch": "word"}"#,
+        )
+        .unwrap();
+        assert_eq!(rule.tag_match, Some(TagMatch::Word));
+        assert!(!rule.normalize);
+    }
+
+    #[test]
+    fn validate_requires_tokens() {
+        assert_eq!(
+            validate_rule(
+                &Rule {
+                    id: "e".into(),
+                    ..tag_rule(" , ,", Some(TagMatch::Word), false)
+                },
+                &PartialMatchSettings::default()
+            ),
+            Err(RuleError::NoTags {
+                rule_id: "e".into()
+            })
+        );
+        assert!(matches!(
+            validate_rule(
+                &tag_rule("()", None, true),
+                &PartialMatchSettings::default()
+            ),
+            Err(RuleError::NoTags { .. })
+        ));
+        assert_eq!(
+            validate_rule(
+                &tag_rule("hat", Some(TagMatch::Word), false),
+                &PartialMatchSettings::default()
+            ),
+            Ok(())
+        );
+        // 매칭과 같은 구분자로 나눕니다.
+        let settings = PartialMatchSettings {
+            token_separator: "|".into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            validate_rule(&tag_rule(" | |", Some(TagMatch::Word), false), &settings),
+            Err(RuleError::NoTags { .. })
+        ));
+        assert_eq!(
+            validate_rule(
+                &tag_rule(" | |", Some(TagMatch::Word), false),
+                &PartialMatchSettings::default()
+            ),
+            Ok(())
+        );
+        assert!(matches!(
+            validate_rule(&tag_rule("|", None, true), &settings),
+            Err(RuleError::NoTags { .. })
+        ));
+        // 필드 전체에서 찾는 리터럴 규칙은 빈 키워드도 올바릅니다.
+        assert_eq!(
+            validate_rule(&tag_rule("", None, false), &PartialMatchSettings::default()),
+            Ok(())
+        );
+    }
+}
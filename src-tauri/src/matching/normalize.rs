@@ -0,0 +1,570 @@
+//! 프롬프트 문법 정규화
+//!
+//! 가중치 문법을 벗겨 태그를 같은 형태로 만들고, 가중치는 따로 기록합니다.
+//!
+//! | 문법 | 예 | 가중치 |
+//! |---|---|---|
+//! | A1111 강조 | `(tag)`, `((tag))` | ×1.1씩 |
+//! | A1111 명시 | `(white background:1.2)` | 1.2 |
+//! | A1111 약화 | `[tag]` | ÷1.1씩 |
+//! | NovelAI 강조/약화 | `{{1girl}}`, `[tag]` | ×1.05씩, ÷1.05씩 |
+//! | NovelAI V4 | `1.3::halo::`, `-1::production_art::` | 1.3, -1 |
+//!
+//! - 텍스트에 `{`가 있으면 NovelAI 배율(1.05), 없으면 A1111 배율(1.1)을 씁니다.
+//! - `\(`처럼 이스케이프한 글자는 태그의 일부입니다 (`hatsune miku \(vocaloid\)`).
+//! - 밑줄은 공백으로 바꾸고 소문자로 바꾼 뒤 연속 공백을 하나로 줄입니다.
+//! - 프롬프트 편집 `[a:b:0.5]`와 교대 `[a|b]`는 각 태그를 가중치 1로 기록합니다.
+//! - `<lora:name:0.8>`은 그대로 태그 하나이고, `BREAK`는 버립니다.
+//! - 괄호의 시작과 끝은 태그 경계입니다. 256단계보다 깊은 괄호는 글자로 읽습니다.
+//!
+//! 규칙 쪽 태그에는 `@` 뒤에 가중치 조건을 붙일 수 있습니다.
+//!
+//! ```text
+//! halo@>=1.2        가중치 1.2 이상
+//! halo@>1@<1.5      1보다 크고 1.5보다 작음
+//! production art@-1 가중치가 -1
+//! ```
+
+use serde::Serialize;
+
+const A1111_FACTOR: f64 = 1.1;
+const NOVELAI_FACTOR: f64 = 1.05;
+// 가중치를 같다고 보는 차이 (1.1 × 1.1 = 1.2100000000000002)
+const WEIGHT_EPSILON: f64 = 1e-3;
+// 가중치를 적용하는 괄호 중첩 상한. 더 깊은 괄호는 글자로 읽습니다.
+const MAX_DEPTH: usize = 256;
+
+/// 가중치가 붙은 태그
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tag {
+    /// 정규화한 태그 (`white background`)
+    pub text: String,
+    pub weight: f64,
+}
+
+/// 텍스트를 정규화한 태그로 나눕니다. 쉼표, 줄바꿈, `|`, `separator`가 구분자입니다.
+pub fn tags(text: &str, separator: &str) -> Vec<Tag> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut parser = Parser {
+        closers: closers(&chars),
+        chars: &chars,
+        separator: separator.chars().collect(),
+        factor: if chars.contains(&'{') {
+            NOVELAI_FACTOR
+        } else {
+            A1111_FACTOR
+        },
+        buffer: String::new(),
+        buffer_weight: 1.0,
+        tags: Vec::new(),
+        depth: 0,
+    };
+    parser.parse(0, chars.len(), 1.0, false);
+    parser.flush();
+    parser.tags
+}
+
+/// 태그 하나를 정규화합니다: 밑줄 → 공백, 소문자, 연속 공백 하나로.
+pub fn canonical(tag: &str) -> String {
+    tag.replace('_', " ")
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 가중치 조건 하나
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeightBound {
+    Eq(f64),
+    Gt(f64),
+    Ge(f64),
+    Lt(f64),
+    Le(f64),
+}
+
+impl WeightBound {
+    pub fn allows(self, weight: f64) -> bool {
+        match self {
+            Self::Eq(value) => (weight - value).abs() < WEIGHT_EPSILON,
+            Self::Gt(value) => weight > value + WEIGHT_EPSILON,
+            Self::Ge(value) => weight > value - WEIGHT_EPSILON,
+            Self::Lt(value) => weight < value - WEIGHT_EPSILON,
+            Self::Le(value) => weight < value + WEIGHT_EPSILON,
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (bound, number): (fn(f64) -> Self, &str) = if let Some(rest) = text.strip_prefix(">=") {
+            (Self::Ge, rest)
+        } else if let Some(rest) = text.strip_prefix("<=") {
+            (Self::Le, rest)
+        } else if let Some(rest) = text.strip_prefix('>') {
+            (Self::Gt, rest)
+        } else if let Some(rest) = text.strip_prefix('<') {
+            (Self::Lt, rest)
+        } else {
+            (Self::Eq, text.strip_prefix('=').unwrap_or(text))
+        };
+        parse_number(number.trim()).map(bound)
+    }
+}
+
+/// 규칙 쪽 태그와 가중치 조건
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagPattern {
+    pub text: String,
+    pub bounds: Vec<WeightBound>,
+}
+
+impl TagPattern {
+    /// 정규화한 태그 `halo@>=1.2`를 태그와 조건으로 나눕니다.
+    /// 태그가 비었거나 조건을 읽을 수 없으면 `None`입니다.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let mut parts = tag.split('@');
+        let text = parts.next().unwrap_or_default().trim().to_owned();
+        let bounds = parts.map(WeightBound::parse).collect::<Option<Vec<_>>>()?;
+        (!text.is_empty()).then_some(Self { text, bounds })
+    }
+
+    /// 가중치 조건 없이 그대로 비교하는 토큰
+    pub fn literal(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            bounds: Vec::new(),
+        }
+    }
+
+    pub fn allows(&self, weight: f64) -> bool {
+        self.bounds.iter().all(|bound| bound.allows(weight))
+    }
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    let valid = !text.is_empty()
+        && text
+            .chars()
+            .enumerate()
+            .all(|(i, c)| c.is_ascii_digit() || c == '.' || (i == 0 && c == '-'));
+    valid.then(|| text.parse().ok()).flatten()
+}
+
+// 여는 괄호 위치 → 짝이 되는 닫는 괄호 위치. 이스케이프한 괄호와 짝이 안 맞는 괄호는 건너뜁니다.
+// 닫는 괄호는 같은 종류의 가장 안쪽 여는 괄호와 짝이 되고, 그 사이의 다른 여는 괄호는 버립니다.
+fn closers(chars: &[char]) -> Vec<Option<usize>> {
+    let mut closers = vec![None; chars.len()];
+    // 아직 열린 괄호 위치와, 종류별(`(`, `[`, `{`)로 그 괄호의 `stack` 안 위치
+    let mut stack: Vec<usize> = Vec::new();
+    let mut kinds: [Vec<usize>; 3] = Default::default();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            c @ ('(' | '[' | '{') => {
+                kinds[bracket_kind(c)].push(stack.len());
+                stack.push(i);
+            }
+            c @ (')' | ']' | '}') => {
+                if let Some(position) = kinds[bracket_kind(c)].pop() {
+                    closers[stack[position]] = Some(i);
+                    stack.truncate(position);
+                    for kind in &mut kinds {
+                        while kind.last().is_some_and(|&p| p >= position) {
+                            kind.pop();
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    closers
+}
+
+fn bracket_kind(c: char) -> usize {
+    match c {
+        '(' | ')' => 0,
+        '[' | ']' => 1,
+        _ => 2,
+    }
+}
+
+struct Parser<'a> {
+    chars: &'a [char],
+    closers: Vec<Option<usize>>,
+    separator: Vec<char>,
+    /// 괄호 하나의 배율
+    factor: f64,
+    buffer: String,
+    /// 지금 모으는 태그의 첫 글자가 나온 곳의 가중치
+    buffer_weight: f64,
+    tags: Vec<Tag>,
+    /// 지금 읽고 있는 괄호 중첩 깊이
+    depth: usize,
+}
+
+impl Parser<'_> {
+    // `start..end`를 가중치 `weight`로 읽습니다. `colon`이면 `:`도 구분자입니다(프롬프트 편집).
+    fn parse(&mut self, start: usize, end: usize, weight: f64, colon: bool) {
+        let chars = self.chars;
+        let mut i = start;
+        while i < end {
+            match chars[i] {
+                '\\' if i + 1 < end => {
+                    self.push(chars[i + 1], weight);
+                    i += 2;
+                }
+                '(' | '[' | '{' => i = self.group(i, end, weight),
+                // 확장 네트워크 태그 `<lora:...>`. `<1.5` 같은 가중치 조건은 글자입니다.
+                '<' if chars.get(i + 1).is_some_and(char::is_ascii_alphabetic) => {
+                    let close = chars[i..end]
+                        .iter()
+                        .position(|&c| c == '>')
+                        .map_or(end, |offset| i + offset + 1);
+                    self.flush();
+                    chars[i..close].iter().for_each(|&c| self.push(c, weight));
+                    self.flush();
+                    i = close;
+                }
+                ',' | '\n' | '|' | ')' | ']' | '}' => {
+                    self.flush();
+                    i += 1;
+                }
+                ':' if colon || chars.get(i + 1) == Some(&':') => {
+                    self.flush();
+                    i += 1;
+                }
+                _ if !self.separator.is_empty() && chars[i..end].starts_with(&self.separator) => {
+                    self.flush();
+                    i += self.separator.len();
+                }
+                c => match self.section(i, end) {
+                    Some((section_weight, from, to, next)) => {
+                        self.flush();
+                        self.parse(from, to, weight * section_weight, colon);
+                        self.flush();
+                        i = next;
+                    }
+                    None => {
+                        self.push(c, weight);
+                        i += 1;
+                    }
+                },
+            }
+        }
+    }
+
+    // 위치 `start`의 여는 괄호를 읽고 닫는 괄호 다음 위치를 돌려줍니다.
+    // 닫히지 않은 괄호는 `end`까지입니다.
+    fn group(&mut self, start: usize, end: usize, weight: f64) -> usize {
+        if self.depth >= MAX_DEPTH {
+            self.push(self.chars[start], weight);
+            return start + 1;
+        }
+        let close = self.closers[start].filter(|&close| close < end);
+        let content_end = close.unwrap_or(end);
+        let content = &self.chars[start + 1..content_end];
+        let (factor, inner_end, colon) = match self.chars[start] {
+            '(' => match explicit_weight(content) {
+                Some((value, colon)) => (value, start + 1 + colon, false),
+                None => (self.factor, content_end, false),
+            },
+            '{' => (self.factor, content_end, false),
+            _ if top_level(content, '|') => (1.0, content_end, false),
+            _ => match explicit_weight(content) {
+                Some((_, colon)) => (1.0, start + 1 + colon, true),
+                None => (1.0 / self.factor, content_end, false),
+            },
+        };
+        self.flush();
+        self.depth += 1;
+        self.parse(start + 1, inner_end, weight * factor, colon);
+        self.depth -= 1;
+        self.flush();
+        close.map_or(end, |close| close + 1)
+    }
+
+    // 태그가 시작하는 곳의 NovelAI V4 구간 `1.3::halo::`.
+    // (가중치, 내용 시작, 내용 끝, 다음 위치). 닫는 `::`가 없으면 `end`까지입니다.
+    fn section(&self, start: usize, end: usize) -> Option<(f64, usize, usize, usize)> {
+        if !self.buffer.trim().is_empty() {
+            return None;
+        }
+        let chars = self.chars;
+        let number_end = (start..end)
+            .find(|&i| !(chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '-'))?;
+        if chars[number_end..end].get(..2) != Some(&[':', ':'][..]) {
+            return None;
+        }
+        let number: String = chars[start..number_end].iter().collect();
+        let weight = parse_number(&number)?;
+        let from = number_end + 2;
+        let to = (from..end.saturating_sub(1))
+            .find(|&i| chars[i] == ':' && chars[i + 1] == ':')
+            .unwrap_or(end);
+        Some((weight, from, to, (to + 2).min(end)))
+    }
+
+    fn push(&mut self, c: char, weight: f64) {
+        if self.buffer.trim().is_empty() {
+            self.buffer_weight = weight;
+        }
+        self.buffer.push(c);
+    }
+
+    fn flush(&mut self) {
+        let text = canonical(&self.buffer);
+        self.buffer.clear();
+        if !text.is_empty() && text != "break" {
+            self.tags.push(Tag {
+                text,
+                weight: self.buffer_weight,
+            });
+        }
+    }
+}
+
+// 괄호 내용 끝의 `:1.2`. (가중치, `:`의 위치)
+// 숫자 부분만 뒤에서부터 읽어 중첩된 괄호마다 내용 전체를 다시 훑지 않습니다.
+fn explicit_weight(content: &[char]) -> Option<(f64, usize)> {
+    let colon = content
+        .iter()
+        .rposition(|&c| !(c.is_ascii_digit() || c == '.' || c == '-' || c.is_whitespace()))?;
+    if content[colon] != ':' || (colon > 0 && content[colon - 1] == '\\') {
+        return None;
+    }
+    let number: String = content[colon + 1..].iter().collect();
+    parse_number(number.trim()).map(|weight| (weight, colon))
+}
+
+// 중첩 괄호와 이스케이프 밖에 `target`이 있는지
+fn top_level(content: &[char], target: char) -> bool {
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < content.len() {
+        match content[i] {
+            '\\' => i += 1,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            c if c == target && depth == 0 => return true,
+            _ => {}
+        }
+        i += 1;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::testing::{rule, store};
+    use crate::matching::{
+        match_images, validate_rule, ImageMatch, PartialMatchSettings, Rule, RuleError, RuleKind,
+        RuleSet,
+    };
+
+    // 가중치는 소수점 셋째 자리까지 비교합니다.
+    fn weights(text: &str) -> Vec<(String, f64)> {
+        tags(text, "")
+            .into_iter()
+            .map(|tag| (tag.text, (tag.weight * 1000.0).round() / 1000.0))
+            .collect()
+    }
+
+    fn expected(tags: &[(&str, f64)]) -> Vec<(String, f64)> {
+        tags.iter()
+            .map(|&(text, weight)| (text.to_owned(), weight))
+            .collect()
+    }
+
+    #[test]
+    fn a1111() {
+        assert_eq!(
+            weights(
+                r"(white background:1.2), [standing], ((smile)), hatsune_miku \(vocaloid\), Long_Hair"
+            ),
+            expected(&[
+                ("white background", 1.2),
+                ("standing", 0.909),
+                ("smile", 1.21),
+                ("hatsune miku (vocaloid)", 1.0),
+                ("long hair", 1.0),
+            ])
+        );
+        assert_eq!(
+            weights("(a, (b:1.5)), c"),
+            expected(&[("a", 1.1), ("b", 1.65), ("c", 1.0)])
+        );
+        assert_eq!(
+            weights("[cat:dog:0.5], [red|blue] BREAK <lora:x_y:0.8>"),
+            expected(&[
+                ("cat", 1.0),
+                ("dog", 1.0),
+                ("red", 1.0),
+                ("blue", 1.0),
+                ("<lora:x y:0.8>", 1.0),
+            ])
+        );
+        assert_eq!(
+            weights("1girl, 2girls, artist:foo"),
+            expected(&[("1girl", 1.0), ("2girls", 1.0), ("artist:foo", 1.0)])
+        );
+    }
+
+    #[test]
+    fn novelai() {
+        assert_eq!(
+            weights("{{1girl}}, [bad]"),
+            expected(&[("1girl", 1.103), ("bad", 0.952)])
+        );
+        assert_eq!(
+            weights("-1::production_art::, 1.3::halo, wings::, solo"),
+            expected(&[
+                ("production art", -1.0),
+                ("halo", 1.3),
+                ("wings", 1.3),
+                ("solo", 1.0),
+            ])
+        );
+    }
+
+    #[test]
+    fn unbalanced_brackets() {
+        assert_eq!(
+            weights("(unclosed, x"),
+            expected(&[("unclosed", 1.1), ("x", 1.1)])
+        );
+        assert_eq!(
+            weights("a) b], c"),
+            expected(&[("a", 1.0), ("b", 1.0), ("c", 1.0)])
+        );
+    }
+
+    // 테스트 스레드의 스택(2MB)에서도 넘치지 않아야 합니다.
+    #[test]
+    fn deep_nesting() {
+        let text = format!("{}x{}", "(".repeat(20_000), ")".repeat(20_000));
+        let tags = tags(&text, "");
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].text, format!("{}x", "(".repeat(20_000 - MAX_DEPTH)));
+        let weight = A1111_FACTOR.powi(MAX_DEPTH as i32);
+        assert!((tags[0].weight / weight - 1.0).abs() < 1e-9);
+
+        let tags = weights(&format!("{}x", "(".repeat(20_000)));
+        assert_eq!(tags.len(), 1);
+    }
+
+    // 짝이 없는 닫는 괄호가 많아도 선형 시간에 끝나야 합니다.
+    #[test]
+    fn pathological_brackets() {
+        let text = format!("{}x{}", "[".repeat(100_000), ")".repeat(100_000));
+        let tags = tags(&text, "");
+        assert_eq!(tags.len(), 1);
+        assert_eq!(
+            tags[0].text,
+            format!("{}x", "[".repeat(100_000 - MAX_DEPTH))
+        );
+    }
+
+    #[test]
+    fn weight_bounds() {
+        assert_eq!(
+            weights("halo@>=1.2, x@<1.5"),
+            expected(&[("halo@>=1.2", 1.0), ("x@<1.5", 1.0)])
+        );
+        let pattern = TagPattern::parse("halo@>1@<=1.5").unwrap();
+        assert_eq!(pattern.text, "halo");
+        assert!(pattern.allows(1.21));
+        assert!(!pattern.allows(1.0));
+        assert!(pattern.allows(1.5));
+        assert!(!pattern.allows(1.6));
+        assert!(TagPattern::parse("production art@-1").unwrap().allows(-1.0));
+        assert!(TagPattern::parse("halo@big").is_none());
+        assert!(TagPattern::parse("@>1").is_none());
+    }
+
+    fn normalized(id: &str, keyword: &str) -> Rule {
+        Rule {
+            normalize: true,
+            ..rule(id, RuleKind::Literal, keyword)
+        }
+    }
+
+    fn run(rule_set: RuleSet) -> ImageMatch {
+        let store = store(&[(
+            "i",
+            &[
+                ("F0", "masterpiece"),
+                ("F1", "(white_background:1.2), {{1girl}}, 1.3::halo::, chat"),
+            ],
+        )]);
+        match_images(&rule_set, &store, &["i".into()])
+            .unwrap()
+            .remove(0)
+    }
+
+    fn matched(keyword: &str) -> bool {
+        let rule_set = RuleSet {
+            rules: vec![normalized("a", keyword)],
+            partial_match: None,
+        };
+        run(rule_set).matched.is_some()
+    }
+
+    #[test]
+    fn match_normalized_tags() {
+        let rule_set = RuleSet {
+            rules: vec![normalized("a", "White Background, 1girl")],
+            partial_match: None,
+        };
+        let matched_rule = run(rule_set).matched.unwrap();
+        assert_eq!(matched_rule.matched_field, "F1");
+        assert_eq!(matched_rule.match_score, 1.0);
+
+        assert!(!matched("girl"));
+        assert!(!matched("hat"));
+        assert!(matched("halo@>=1.2"));
+        assert!(!matched("halo@<1.2"));
+    }
+
+    #[test]
+    fn partial_normalized_tags() {
+        let rule_set = RuleSet {
+            rules: vec![
+                Rule {
+                    partial_match: true,
+                    ..normalized("a", "halo@<1.2, 1girl")
+                },
+                Rule {
+                    partial_match: true,
+                    ..normalized("b", "masterpiece, x")
+                },
+            ],
+            partial_match: Some(PartialMatchSettings {
+                min_match_ratio: 0.5,
+                ..PartialMatchSettings::default()
+            }),
+        };
+        let image = run(rule_set);
+        assert!(image.is_partial_match);
+        assert_eq!(image.candidates.len(), 2);
+        assert_eq!(image.candidates[0].matched_tokens, ["1girl"]);
+        assert_eq!(image.candidates[0].total_tokens, 2);
+    }
+
+    #[test]
+    fn invalid_weight() {
+        assert_eq!(
+            validate_rule(
+                &normalized("a", "halo@big"),
+                &PartialMatchSettings::default()
+            ),
+            Err(RuleError::InvalidWeight {
+                rule_id: "a".into(),
+                tag: "halo@big".into()
+            })
+        );
+    }
+}
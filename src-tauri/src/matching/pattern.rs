@@ -0,0 +1,224 @@
+//! 정규식 규칙
+//!
+//! 키워드를 정규식으로 컴파일하고, 새 파일 이름의 `{1}`, `{name}`을 캡처 그룹의 값으로 바꿉니다.
+//! `{0}`은 일치한 부분 전체입니다. 잘못된 패턴이나 없는 그룹 참조는 규칙을 만들 때 오류입니다.
+//!
+//! ```text
+//! 키워드: 1girl, (\w+) \(    새 파일 이름: char_{1}
+//! 키워드: seed_(?<seed>\d+)  새 파일 이름: seed{seed}
+//! ```
+
+use regex::{Captures, Regex, RegexBuilder};
+
+use super::{Rule, RuleError};
+
+// 컴파일한 정규식 크기 상한 (규칙 하나)
+const MAX_REGEX_SIZE: usize = 4 * 1024 * 1024;
+
+/// 컴파일한 정규식 규칙
+#[derive(Debug, Clone)]
+pub struct RegexRule {
+    regex: Regex,
+}
+
+impl RegexRule {
+    /// 패턴과 새 파일 이름의 그룹 참조를 검사해 컴파일합니다.
+    pub fn compile(rule: &Rule) -> Result<Self, RuleError> {
+        let regex = RegexBuilder::new(&rule.keyword)
+            .case_insensitive(rule.case_insensitive)
+            .size_limit(MAX_REGEX_SIZE)
+            .build()
+            .map_err(|error| RuleError::InvalidPattern {
+                rule_id: rule.id.clone(),
+                message: error.to_string(),
+            })?;
+        for group in placeholders(&rule.new_file_name) {
+            let exists = match group.parse::<usize>() {
+                Ok(index) => index < regex.captures_len(),
+                Err(_) => regex.capture_names().flatten().any(|name| name == group),
+            };
+            if !exists {
+                return Err(RuleError::UnknownGroup {
+                    rule_id: rule.id.clone(),
+                    group: group.to_owned(),
+                });
+            }
+        }
+        Ok(Self { regex })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// `text`에서 찾은 캡처 그룹으로 `template`의 자리표시자를 바꿉니다.
+    /// 참여하지 않은 그룹은 빈 문자열입니다.
+    pub fn render(&self, template: &str, text: &str) -> String {
+        let Some(captures) = self.regex.captures(text) else {
+            return template.to_owned();
+        };
+        render(template, &captures)
+    }
+}
+
+// `{숫자}`나 `{이름}` 자리표시자의 이름. 그 밖의 중괄호는 그대로 둡니다.
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template.split('{').skip(1).filter_map(|part| {
+        let (name, _) = part.split_once('}')?;
+        is_group_name(name).then_some(name)
+    })
+}
+
+fn is_group_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '[' | ']'))
+}
+
+fn render(template: &str, captures: &Captures) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.split_once('}') {
+            Some((name, tail)) if is_group_name(name) => {
+                let value = match name.parse::<usize>() {
+                    Ok(index) => captures.get(index),
+                    Err(_) => captures.name(name),
+                };
+                output.push_str(&sanitize(value.map_or("", |m| m.as_str())));
+                rest = tail;
+            }
+            _ => {
+                output.push('{');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+// 프론트엔드 `sanitizeFileName`과 같이 공백과 Windows 금지 문자를 `_`로 바꿉니다.
+fn sanitize(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    for c in value.chars() {
+        let c = if c.is_whitespace()
+            || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*')
+        {
+            '_'
+        } else {
+            c
+        };
+        if !(c == '_' && output.ends_with('_')) {
+            output.push(c);
+        }
+    }
+    output.trim_matches('_').to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::testing::{rule, store};
+    use crate::matching::{
+        match_images, validate_rule, Match, PartialMatchSettings, RuleKind, RuleSet,
+    };
+
+    fn regex(keyword: &str, new_file_name: &str) -> Rule {
+        Rule {
+            new_file_name: new_file_name.to_owned(),
+            ..rule("r", RuleKind::Regex, keyword)
+        }
+    }
+
+    fn run(rules: Vec<Rule>) -> Option<Match> {
+        let store = store(&[(
+            "i",
+            &[
+                ("A", "masterpiece, 1girl, Hatsune Miku (vocaloid), smile"),
+                ("B", "Steps: 28, Seed: 12345, seed_777"),
+            ],
+        )]);
+        let rule_set = RuleSet {
+            rules,
+            partial_match: None,
+        };
+        match_images(&rule_set, &store, &["i".to_owned()])
+            .unwrap()
+            .remove(0)
+            .matched
+    }
+
+    #[test]
+    fn captures_in_file_name() {
+        let matched = run(vec![regex(r"1girl, ([^,(]+?) \(", "char_{1}")]).unwrap();
+        assert_eq!(matched.new_file_name, "char_Hatsune_Miku");
+        assert_eq!(matched.matched_field, "A");
+
+        let matched = run(vec![regex(r"seed_(?<seed>\d+)", "s{seed}-{0}-{x")]).unwrap();
+        assert_eq!(matched.new_file_name, "s777-seed_777-{x");
+        assert_eq!(matched.matched_field, "B");
+    }
+
+    #[test]
+    fn case_insensitive() {
+        assert!(run(vec![regex("HATSUNE", "x")]).is_none());
+        let rule = Rule {
+            case_insensitive: true,
+            ..regex("HATSUNE", "x")
+        };
+        assert!(run(vec![rule]).is_some());
+    }
+
+    // 리터럴 규칙과 정규식 규칙은 규칙 순서대로 검사합니다.
+    #[test]
+    fn rule_order() {
+        let literal = rule("l", RuleKind::Literal, "smile");
+        let matched = run(vec![literal.clone(), regex("Seed", "re")]).unwrap();
+        assert_eq!(matched.rule_id, "l");
+        let matched = run(vec![regex("Seed", "re"), literal]).unwrap();
+        assert_eq!(matched.rule_id, "r");
+        assert_eq!(matched.matched_field, "B");
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(matches!(
+            validate_rule(&regex("(unclosed", "x"), &PartialMatchSettings::default()),
+            Err(RuleError::InvalidPattern { .. })
+        ));
+        assert_eq!(
+            validate_rule(
+                &regex("(a)(?<n>b)", "{1}{2}{n}{3}"),
+                &PartialMatchSettings::default()
+            ),
+            Err(RuleError::UnknownGroup {
+                rule_id: "r".into(),
+                group: "3".into()
+            })
+        );
+        let rule_set = RuleSet {
+            rules: vec![regex("(", "x")],
+            partial_match: None,
+        };
+        assert!(match_images(&rule_set, &store(&[]), &[]).is_err());
+
+        let error =
+            validate_rule(&regex("a", "{nope}"), &PartialMatchSettings::default()).unwrap_err();
+        let json = serde_json::to_value(error).unwrap();
+        assert_eq!(json["kind"], "unknownGroup");
+        assert_eq!(json["ruleId"], "r");
+    }
+
+    #[test]
+    fn sanitized_captures() {
+        assert_eq!(sanitize(" a: b/c? "), "a_b_c");
+        assert_eq!(
+            placeholders("{1}{name}{ x }{}{a.b}").collect::<Vec<_>>(),
+            ["1", "name", "a.b"]
+        );
+    }
+}
@@ -0,0 +1,599 @@
+//! 규칙 쿼리 언어
+//!
+//! ```text
+//! prompt:"white background" AND NOT negative:halo AND model:animagine
+//! (1girl OR 1boy) solo -- 나란히 쓰면 AND
+//! prompt:(smile OR grin)
+//! ```
+//!
+//! - 연산자는 대문자 `AND`, `OR`, `NOT`이고 우선순위는 `NOT` > `AND` > `OR`입니다.
+//! - 검색어는 공백, 괄호, 따옴표 전까지의 단어나 따옴표로 묶은 구(`\"`, `\\` 이스케이프)입니다.
+//! - `이름:` 한정자는 생성 도구 필드(`Generation:`, `SD:`, `NAI:` 등) 중 `:` 뒤가 `이름`과 같은
+//!   (대소문자 무시) 필드에서만 찾습니다. `prompt:`는 `Generation:Prompt`, `SD:Prompt` 등에 맞고,
+//!   `model:`은 카메라 모델인 `EXIF:Model`에 맞지 않습니다. 이름은 영문자로 시작해야 하며,
+//!   `artist:name`처럼 콜론이 든 태그를 그대로 찾으려면 따옴표로 묶습니다.
+
+use std::fmt;
+
+use crate::metadata::generators;
+
+// 괄호와 `NOT`의 중첩 상한
+const MAX_DEPTH: usize = 64;
+
+/// 쿼리 구문 트리
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    /// `field`가 있으면 그 필드에서만, 없으면 모든 필드에서 `text`를 찾음
+    Term {
+        field: Option<String>,
+        text: String,
+    },
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+/// 쿼리를 해석할 수 없는 이유
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{}번째 글자: {kind}", position + 1)]
+pub struct QueryError {
+    pub kind: QueryErrorKind,
+    /// 문제가 있는 곳의 글자 위치 (0부터)
+    pub position: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryErrorKind {
+    Empty,
+    /// 검색어나 `(`가 와야 할 곳에서 쿼리가 끝남
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    UnclosedQuote,
+    UnclosedParen,
+    UnmatchedParen,
+    EmptyPhrase,
+    /// `prompt:` 뒤에 검색어가 없음
+    MissingValue(String),
+    /// 괄호나 `NOT`이 너무 깊게 중첩됨
+    TooDeep,
+}
+
+impl fmt::Display for QueryErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "쿼리가 비어 있습니다"),
+            Self::UnexpectedEnd => write!(f, "검색어가 필요한 곳에서 쿼리가 끝났습니다"),
+            Self::UnexpectedToken(token) => write!(f, "여기에 `{token}`이(가) 올 수 없습니다"),
+            Self::UnclosedQuote => write!(f, "따옴표가 닫히지 않았습니다"),
+            Self::UnclosedParen => write!(f, "괄호가 닫히지 않았습니다"),
+            Self::UnmatchedParen => write!(f, "여는 괄호가 없는 `)`입니다"),
+            Self::EmptyPhrase => write!(f, "빈 구절입니다"),
+            Self::MissingValue(field) => write!(f, "`{field}:` 뒤에 검색어가 없습니다"),
+            Self::TooDeep => write!(f, "괄호나 NOT이 {MAX_DEPTH}단계보다 깊게 중첩되었습니다"),
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let tokens = lex(input)?;
+        let end = input.chars().count();
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            end,
+            depth: 0,
+        };
+        if parser.tokens.is_empty() {
+            return Err(QueryError {
+                kind: QueryErrorKind::Empty,
+                position: 0,
+            });
+        }
+        let query = parser.or(None)?;
+        match parser.peek() {
+            None => Ok(query),
+            Some((Token::RParen, position)) => Err(QueryError {
+                kind: QueryErrorKind::UnmatchedParen,
+                position,
+            }),
+            Some((token, position)) => Err(QueryError {
+                kind: QueryErrorKind::UnexpectedToken(token.to_string()),
+                position,
+            }),
+        }
+    }
+
+    /// `NOT` 아래에 있지 않은 검색어. 일치한 필드를 고를 때 씁니다.
+    pub fn positive_terms(&self) -> Vec<(Option<&str>, &str)> {
+        let mut terms = Vec::new();
+        self.collect_terms(false, &mut terms);
+        terms
+    }
+
+    fn collect_terms<'q>(&'q self, negated: bool, terms: &mut Vec<(Option<&'q str>, &'q str)>) {
+        match self {
+            Self::Term { field, text } if !negated => terms.push((field.as_deref(), text)),
+            Self::Term { .. } => {}
+            Self::Not(query) => query.collect_terms(!negated, terms),
+            Self::And(queries) | Self::Or(queries) => queries
+                .iter()
+                .for_each(|query| query.collect_terms(negated, terms)),
+        }
+    }
+}
+
+/// 필드 이름 `name`이 한정자 `field`에 맞는지 봅니다.
+/// 네임스페이스가 있는 필드는 생성 도구 필드만 맞습니다.
+pub fn field_matches(name: &str, field: &str) -> bool {
+    match name.split_once(':') {
+        Some((namespace, key)) => {
+            generators::NAMESPACES.contains(&namespace) && key.eq_ignore_ascii_case(field)
+        }
+        None => name.eq_ignore_ascii_case(field),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    /// `이름:` 한정자
+    Field(String),
+    Word(String),
+    Phrase(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LParen => write!(f, "("),
+            Self::RParen => write!(f, ")"),
+            Self::And => write!(f, "AND"),
+            Self::Or => write!(f, "OR"),
+            Self::Not => write!(f, "NOT"),
+            Self::Field(name) => write!(f, "{name}:"),
+            Self::Word(word) => write!(f, "{word}"),
+            Self::Phrase(phrase) => write!(f, "\"{phrase}\""),
+        }
+    }
+}
+
+fn lex(input: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        match chars[i] {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push((Token::LParen, start));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((Token::RParen, start));
+                i += 1;
+            }
+            '"' => {
+                let (phrase, next) = phrase(&chars, i)?;
+                tokens.push((Token::Phrase(phrase), start));
+                i = next;
+            }
+            _ => {
+                while i < chars.len() && !is_delimiter(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                match word.as_str() {
+                    "AND" => tokens.push((Token::And, start)),
+                    "OR" => tokens.push((Token::Or, start)),
+                    "NOT" => tokens.push((Token::Not, start)),
+                    _ => match qualifier(&word) {
+                        Some((field, value)) => {
+                            let value_start = start + field.chars().count() + 1;
+                            // `prompt:` 뒤에는 단어, 구, 괄호가 바로 와야 합니다.
+                            if value.is_empty() && !matches!(chars.get(i), Some('"' | '(')) {
+                                return Err(QueryError {
+                                    kind: QueryErrorKind::MissingValue(field.to_owned()),
+                                    position: value_start,
+                                });
+                            }
+                            tokens.push((Token::Field(field.to_owned()), start));
+                            if !value.is_empty() {
+                                tokens.push((Token::Word(value.to_owned()), value_start));
+                            }
+                        }
+                        None => tokens.push((Token::Word(word), start)),
+                    },
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '"')
+}
+
+// `이름:값`의 (이름, 값). `halo::`, `1.3::x`처럼 이름이 영문자로 시작하지 않거나
+// 콜론이 겹치면 한정자가 아닙니다.
+fn qualifier(word: &str) -> Option<(&str, &str)> {
+    let (field, value) = word.split_once(':')?;
+    let mut chars = field.chars();
+    let first = chars.next()?;
+    let valid = first.is_ascii_alphabetic()
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !value.starts_with(':');
+    valid.then_some((field, value))
+}
+
+// 여는 따옴표 위치 `start`부터 닫는 따옴표 다음 위치까지
+fn phrase(chars: &[char], start: usize) -> Result<(String, usize), QueryError> {
+    let mut text = String::new();
+    let mut i = start + 1;
+    while let Some(&c) = chars.get(i) {
+        match c {
+            '"' if text.is_empty() => {
+                return Err(QueryError {
+                    kind: QueryErrorKind::EmptyPhrase,
+                    position: start,
+                })
+            }
+            '"' => return Ok((text, i + 1)),
+            '\\' if matches!(chars.get(i + 1), Some('"' | '\\')) => {
+                text.push(chars[i + 1]);
+                i += 2;
+            }
+            _ => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+    Err(QueryError {
+        kind: QueryErrorKind::UnclosedQuote,
+        position: start,
+    })
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// 입력의 글자 수 (쿼리가 끝난 위치)
+    end: usize,
+    /// 지금 읽고 있는 괄호와 `NOT`의 중첩 깊이
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(Token, usize)> {
+        self.tokens.get(self.position).cloned()
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    // 위치 `position`의 괄호나 `NOT` 안을 `parse`로 읽습니다.
+    fn nested(
+        &mut self,
+        position: usize,
+        parse: impl FnOnce(&mut Self) -> Result<Query, QueryError>,
+    ) -> Result<Query, QueryError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(QueryError {
+                kind: QueryErrorKind::TooDeep,
+                position,
+            });
+        }
+        self.depth += 1;
+        let query = parse(self);
+        self.depth -= 1;
+        query
+    }
+
+    // or = and ("OR" and)*
+    fn or(&mut self, field: Option<&str>) -> Result<Query, QueryError> {
+        let mut queries = vec![self.and(field)?];
+        while matches!(self.peek(), Some((Token::Or, _))) {
+            self.next();
+            queries.push(self.and(field)?);
+        }
+        Ok(flatten(queries, Query::Or))
+    }
+
+    // and = unary (["AND"] unary)*
+    fn and(&mut self, field: Option<&str>) -> Result<Query, QueryError> {
+        let mut queries = vec![self.unary(field)?];
+        loop {
+            match self.peek() {
+                Some((Token::And, _)) => {
+                    self.next();
+                }
+                Some((Token::Or | Token::RParen, _)) | None => break,
+                Some(_) => {}
+            }
+            queries.push(self.unary(field)?);
+        }
+        Ok(flatten(queries, Query::And))
+    }
+
+    // unary = "NOT" unary | primary
+    fn unary(&mut self, field: Option<&str>) -> Result<Query, QueryError> {
+        if let Some((Token::Not, position)) = self.peek() {
+            self.next();
+            let query = self.nested(position, |parser| parser.unary(field))?;
+            return Ok(Query::Not(Box::new(query)));
+        }
+        self.primary(field)
+    }
+
+    // primary = "(" or ")" | [field ":"] (word | phrase | "(" or ")")
+    fn primary(&mut self, field: Option<&str>) -> Result<Query, QueryError> {
+        let Some((token, position)) = self.next() else {
+            return Err(QueryError {
+                kind: QueryErrorKind::UnexpectedEnd,
+                position: self.end,
+            });
+        };
+        match token {
+            Token::Word(text) | Token::Phrase(text) => Ok(Query::Term {
+                field: field.map(str::to_owned),
+                text,
+            }),
+            Token::Field(name) => match self.peek() {
+                Some((Token::Word(_) | Token::Phrase(_) | Token::LParen, _)) => {
+                    self.primary(Some(&name))
+                }
+                _ => Err(QueryError {
+                    kind: QueryErrorKind::MissingValue(name),
+                    position,
+                }),
+            },
+            Token::LParen => {
+                let query = self.nested(position, |parser| parser.or(field))?;
+                match self.next() {
+                    Some((Token::RParen, _)) => Ok(query),
+                    _ => Err(QueryError {
+                        kind: QueryErrorKind::UnclosedParen,
+                        position,
+                    }),
+                }
+            }
+            Token::RParen => Err(QueryError {
+                kind: QueryErrorKind::UnmatchedParen,
+                position,
+            }),
+            token => Err(QueryError {
+                kind: QueryErrorKind::UnexpectedToken(token.to_string()),
+                position,
+            }),
+        }
+    }
+}
+
+fn flatten(mut queries: Vec<Query>, combine: fn(Vec<Query>) -> Query) -> Query {
+    if queries.len() == 1 {
+        queries.remove(0)
+    } else {
+        combine(queries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::testing::{rule, store};
+    use crate::matching::{match_images, Rule, RuleError, RuleKind, RuleSet};
+
+    fn term(field: Option<&str>, text: &str) -> Query {
+        Query::Term {
+            field: field.map(str::to_owned),
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Query::parse(r#"prompt:"white background" AND NOT negative:halo AND model:animagine"#)
+                .unwrap(),
+            Query::And(vec![
+                term(Some("prompt"), "white background"),
+                Query::Not(Box::new(term(Some("negative"), "halo"))),
+                term(Some("model"), "animagine"),
+            ])
+        );
+        assert_eq!(
+            Query::parse("a OR b c").unwrap(),
+            Query::Or(vec![
+                term(None, "a"),
+                Query::And(vec![term(None, "b"), term(None, "c")]),
+            ])
+        );
+        // 한정자는 괄호 안의 검색어에 퍼지고, NovelAI 가중치 문법과 따옴표 안의 콜론은 검색어입니다.
+        assert_eq!(
+            Query::parse(r#"prompt:(a OR negative:b) -1::production_art:: 1.3::halo:: "artist:x""#)
+                .unwrap(),
+            Query::And(vec![
+                Query::Or(vec![term(Some("prompt"), "a"), term(Some("negative"), "b")]),
+                term(None, "-1::production_art::"),
+                term(None, "1.3::halo::"),
+                term(None, "artist:x"),
+            ])
+        );
+        assert_eq!(
+            Query::parse(r#""say \"hi\"""#).unwrap(),
+            term(None, "say \"hi\"")
+        );
+    }
+
+    #[test]
+    fn error_positions() {
+        let error = |input: &str| {
+            let error = Query::parse(input).unwrap_err();
+            (error.kind, error.position)
+        };
+        assert_eq!(error(""), (QueryErrorKind::Empty, 0));
+        assert_eq!(error("a AND"), (QueryErrorKind::UnexpectedEnd, 5));
+        assert_eq!(error("(a OR b"), (QueryErrorKind::UnclosedParen, 0));
+        assert_eq!(error("a OR b)"), (QueryErrorKind::UnmatchedParen, 6));
+        // 위치는 바이트가 아니라 글자 단위입니다.
+        assert_eq!(error("모자 \"abc"), (QueryErrorKind::UnclosedQuote, 3));
+        assert_eq!(
+            error("prompt: x"),
+            (QueryErrorKind::MissingValue("prompt".into()), 7)
+        );
+        assert_eq!(
+            error("a OR OR b"),
+            (QueryErrorKind::UnexpectedToken("OR".into()), 5)
+        );
+        assert_eq!(error("\"\""), (QueryErrorKind::EmptyPhrase, 0));
+        // 스택이 넘치기 전에 중첩 상한에서 멈춥니다.
+        assert_eq!(
+            error(&format!("{}a", "(".repeat(20_000))),
+            (QueryErrorKind::TooDeep, MAX_DEPTH)
+        );
+        assert_eq!(
+            error(&format!("{}a", "NOT ".repeat(20_000))),
+            (QueryErrorKind::TooDeep, MAX_DEPTH * 4)
+        );
+        let nested = format!("{}a{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(Query::parse(&nested), Ok(term(None, "a")));
+        assert_eq!(
+            Query::parse("a AND").unwrap_err().to_string(),
+            "6번째 글자: 검색어가 필요한 곳에서 쿼리가 끝났습니다"
+        );
+    }
+
+    #[test]
+    fn qualifiers() {
+        assert!(field_matches("Generation:Prompt", "prompt"));
+        assert!(field_matches("SD:NegativePrompt", "negativeprompt"));
+        assert!(field_matches("Generation:Model", "MODEL"));
+        assert!(!field_matches("Generation:Prompt", "negative"));
+        // 생성 도구 필드가 아닌 같은 이름의 필드
+        assert!(!field_matches("EXIF:Model", "model"));
+        assert!(!field_matches("XMP:dc:Prompt", "prompt"));
+        assert!(field_matches("Prompt", "prompt"));
+    }
+
+    fn run(rules: Vec<Rule>) -> Vec<Option<(String, String)>> {
+        let store = store(&[
+            (
+                "a",
+                &[
+                    (
+                        "PNG:parameters",
+                        "white background, 1girl, halo\nNegative prompt: bad",
+                    ),
+                    ("Generation:Prompt", "white background, 1girl, halo"),
+                    ("Generation:Negative", "bad"),
+                    ("Generation:Model", "animagineXL"),
+                    ("EXIF:Model", "Canon EOS R5"),
+                ],
+            ),
+            (
+                "b",
+                &[
+                    ("Generation:Prompt", "white background, 1boy"),
+                    ("SD:Prompt", "white background, 1boy"),
+                    ("Generation:Negative", "halo"),
+                    ("Generation:Model", "animagineXL"),
+                ],
+            ),
+        ]);
+        let rule_set = RuleSet {
+            rules,
+            partial_match: None,
+        };
+        match_images(&rule_set, &store, &["a".into(), "b".into()])
+            .unwrap()
+            .into_iter()
+            .map(|image| {
+                image
+                    .matched
+                    .map(|matched| (matched.rule_id, matched.matched_field))
+            })
+            .collect()
+    }
+
+    fn matched(rule_id: &str, field: &str) -> Option<(String, String)> {
+        Some((rule_id.to_owned(), field.to_owned()))
+    }
+
+    #[test]
+    fn evaluate() {
+        let query = r#"prompt:"white background" AND NOT negative:halo AND model:animagine"#;
+        assert_eq!(
+            run(vec![rule("q", RuleKind::Query, query)]),
+            [matched("q", "Generation:Prompt"), None]
+        );
+        // 한정자는 카메라 모델(`EXIF:Model`)에 맞지 않지만 한정자 없는 검색어는 맞습니다.
+        assert_eq!(
+            run(vec![rule("q", RuleKind::Query, "model:Canon")]),
+            [None, None]
+        );
+        assert_eq!(
+            run(vec![rule("q", RuleKind::Query, "Canon")]),
+            [matched("q", "EXIF:Model"), None]
+        );
+        // 한정자 없는 검색어는 그 검색어가 든 첫 필드, 부정뿐이면 첫 필드를 고릅니다.
+        assert_eq!(
+            run(vec![rule("q", RuleKind::Query, "1boy OR NOT 1boy")]),
+            [
+                matched("q", "PNG:parameters"),
+                matched("q", "Generation:Prompt")
+            ]
+        );
+    }
+
+    // 리터럴 규칙과 쿼리 규칙은 규칙 순서대로 검사합니다.
+    #[test]
+    fn rule_order() {
+        assert_eq!(
+            run(vec![
+                rule("l", RuleKind::Literal, "1boy"),
+                rule("q", RuleKind::Query, "prompt:1"),
+            ]),
+            [
+                matched("q", "Generation:Prompt"),
+                matched("l", "Generation:Prompt")
+            ]
+        );
+        assert_eq!(
+            run(vec![
+                rule("q", RuleKind::Query, "negative:halo"),
+                rule("l", RuleKind::Literal, "1boy"),
+            ]),
+            [None, matched("q", "Generation:Negative")]
+        );
+    }
+
+    #[test]
+    fn invalid_query_rule() {
+        let rule_set = RuleSet {
+            rules: vec![rule("q", RuleKind::Query, "a AND (b")],
+            partial_match: None,
+        };
+        let error = match_images(&rule_set, &store(&[]), &[]).unwrap_err();
+        assert_eq!(
+            error,
+            RuleError::InvalidQuery {
+                rule_id: "q".into(),
+                message: "7번째 글자: 괄호가 닫히지 않았습니다".into(),
+                position: 6,
+            }
+        );
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["kind"], "invalidQuery");
+        assert_eq!(json["position"], 6);
+    }
+}
@@ -0,0 +1,72 @@
+//! 매칭할 이미지의 메타데이터 저장소
+//!
+//! 프론트엔드가 이미지를 추가할 때 필드를 한 번 등록해 두면, 규칙이 바뀔 때마다
+//! 메타데이터 전체를 다시 보내지 않고 이미지 ID만으로 매칭할 수 있습니다.
+
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+
+use serde::Deserialize;
+
+/// 이미지 하나의 메타데이터 필드
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ImageFields {
+    pub id: String,
+    /// (필드 이름, 값). 순서가 완전 일치에서 어느 필드를 고를지 정합니다.
+    pub fields: Vec<(String, String)>,
+}
+
+/// 이미지 ID → 메타데이터 필드
+#[derive(Debug, Default)]
+pub struct ImageStore {
+    images: RwLock<HashMap<String, Vec<(String, String)>>>,
+}
+
+impl ImageStore {
+    /// 이미 있는 ID는 필드를 바꿉니다.
+    pub fn insert(&self, images: Vec<ImageFields>) {
+        let mut store = self.images.write().unwrap_or_else(PoisonError::into_inner);
+        for image in images {
+            store.insert(image.id, image.fields);
+        }
+    }
+
+    pub fn remove(&self, ids: &[String]) {
+        let mut store = self.images.write().unwrap_or_else(PoisonError::into_inner);
+        for id in ids {
+            store.remove(id);
+        }
+    }
+
+    pub fn clear(&self) {
+        self.images
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.images
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `ids` 순서대로 (ID, 필드)를 `f`에 넘깁니다. 없는 ID는 필드가 비어 있습니다.
+    pub fn with_images<T>(
+        &self,
+        ids: &[String],
+        f: impl FnOnce(&[(&str, &[(String, String)])]) -> T,
+    ) -> T {
+        let store = self.images.read().unwrap_or_else(PoisonError::into_inner);
+        let images: Vec<(&str, &[(String, String)])> = ids
+            .iter()
+            .map(|id| (id.as_str(), store.get(id).map_or(&[][..], Vec::as_slice)))
+            .collect();
+        f(&images)
+    }
+}
@@ -0,0 +1,36 @@
+//! 테스트용 규칙과 이미지 저장소
+
+use super::{ImageFields, ImageStore, Rule, RuleKind};
+
+/// 새 파일 이름이 `id`와 같은 활성화된 규칙
+pub fn rule(id: &str, kind: RuleKind, keyword: &str) -> Rule {
+    Rule {
+        id: id.to_owned(),
+        keyword: keyword.to_owned(),
+        new_file_name: id.to_owned(),
+        enabled: true,
+        partial_match: false,
+        kind,
+        case_insensitive: false,
+        normalize: false,
+        tag_match: None,
+    }
+}
+
+/// (ID, (필드 이름, 값) 목록) 이미지를 넣은 저장소
+pub fn store(images: &[(&str, &[(&str, &str)])]) -> ImageStore {
+    let store = ImageStore::default();
+    store.insert(
+        images
+            .iter()
+            .map(|(id, fields)| ImageFields {
+                id: (*id).to_owned(),
+                fields: fields
+                    .iter()
+                    .map(|&(name, value)| (name.to_owned(), value.to_owned()))
+                    .collect(),
+            })
+            .collect(),
+    );
+    store
+}
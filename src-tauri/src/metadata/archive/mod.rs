@@ -0,0 +1,210 @@
+//! ZIP, tar, tar.gz 아카이브 입력
+//!
+//! 아카이브를 디스크에 풀지 않고 항목마다 메모리에서 압축을 풀어 메타데이터를 추출합니다.
+//! 항목의 아카이브 안 경로는 이름을 바꾼 결과의 `originalName`이 됩니다.
+//! 항목의 내용이 필요하면 그 위치로 [`read_entries`]를 호출합니다.
+//!
+//! - [`zip`]: ZIP, ZIP64. 파일 이름은 UTF-8 플래그가 없으면 CP949 등 레거시 인코딩으로 읽습니다.
+//! - [`tar`]: ustar, GNU 긴 이름, PAX 경로. gzip으로 압축된 tar(.tar.gz, .tgz)도 읽습니다.
+
+pub mod tar;
+pub mod zip;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use flate2::read::MultiGzDecoder;
+use serde::Serialize;
+
+use super::{extract, Error, Extraction};
+
+// 압축을 푼 항목 하나의 크기 상한 (압축 폭탄 방지)
+pub const MAX_ENTRY_SIZE: u64 = 256 * 1024 * 1024;
+
+// 메타데이터를 읽을 항목의 확장자. 프론트엔드 DropZone이 받는 형식과 같습니다.
+const EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "webp", "avif", "heic", "heif", "jxl", "gif", "tif", "tiff", "svg",
+    "bmp", "ico", "mp4", "m4v", "mov", "webm", "mkv",
+];
+
+/// 항목을 읽지 못한 이유
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum EntryError {
+    #[error("지원하지 않는 압축 방식입니다: {method}")]
+    UnsupportedMethod { method: u16 },
+    #[error("암호화된 항목입니다")]
+    Encrypted,
+    #[error("항목이 너무 큽니다: {size}바이트")]
+    TooLarge { size: u64 },
+    #[error("압축을 풀 수 없습니다")]
+    Decompress,
+    #[error("CRC가 맞지 않습니다")]
+    CrcMismatch,
+    /// 아카이브가 항목 중간에서 끝남
+    #[error("항목이 잘렸습니다")]
+    Truncated,
+}
+
+/// 아카이브 안의 이미지 하나
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    /// 아카이브 안의 상대 경로 (`batch1/00001.png`)
+    pub original_name: String,
+    /// 압축을 푼 크기
+    pub size: u64,
+    pub extraction: Option<Extraction>,
+    pub error: Option<EntryError>,
+}
+
+impl ArchiveEntry {
+    fn new(name: &str, data: Result<Vec<u8>, EntryError>) -> Self {
+        let original_name = entry_name(name).to_owned();
+        match data {
+            Ok(data) => Self {
+                original_name,
+                size: data.len() as u64,
+                extraction: Some(Extraction::from(extract(&data))),
+                error: None,
+            },
+            Err(error) => Self {
+                original_name,
+                size: 0,
+                extraction: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+// 절대 경로나 `./`로 시작하는 경로도 아카이브 안의 상대 경로로 씁니다.
+fn entry_name(mut name: &str) -> &str {
+    while let Some(rest) = name.strip_prefix("./").or_else(|| name.strip_prefix('/')) {
+        name = rest;
+    }
+    name
+}
+
+/// 아카이브 항목을 하나씩 받는 함수. 항목의 압축을 푼 내용은 호출이 끝나면 버립니다.
+pub type Visit<'a> = dyn FnMut(&str, Result<Vec<u8>, EntryError>) + 'a;
+
+/// 압축을 풀 항목인지 이름으로 고르는 함수. 고르지 않은 항목은 읽지 않고 건너뜁니다.
+pub type Filter<'a> = dyn Fn(&str) -> bool + 'a;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Zip,
+    Tar,
+    Gzip,
+}
+
+impl Kind {
+    fn detect(head: &[u8]) -> Option<Self> {
+        if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if head.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::Gzip)
+        } else if tar::is_tar(head) {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// 아카이브 안의 이미지와 동영상마다 메타데이터와 진단 보고서를 추출합니다.
+pub fn read_archive(path: impl AsRef<Path>) -> Result<Vec<ArchiveEntry>, Error> {
+    let mut entries = Vec::new();
+    visit_archive(path.as_ref(), &is_candidate, &mut |name, data| {
+        entries.push(ArchiveEntry::new(name, data));
+    })?;
+    Ok(entries)
+}
+
+/// `indices`(`read_archive`가 돌려준 목록의 위치) 항목의 압축을 풀어 그 순서대로 이어 붙인
+/// 내용을 돌려줍니다. 각 항목의 길이는 `read_archive`가 돌려준 `size`와 같습니다. 아카이브를
+/// 한 번만 읽으므로 tar.gz처럼 처음부터 풀어야 하는 아카이브도 여러 항목을 한 번에 가져올 수 있습니다.
+/// 같은 경로의 항목이 여러 개여도 위치로 구분합니다.
+pub fn read_entries(path: impl AsRef<Path>, indices: &[usize]) -> Result<Vec<u8>, Error> {
+    // `read_archive`와 같은 항목을 같은 순서로 고르며 위치를 셉니다.
+    let position = Cell::new(0);
+    let wanted = |name: &str| {
+        if !is_candidate(name) {
+            return false;
+        }
+        let index = position.get();
+        position.set(index + 1);
+        indices.contains(&index)
+    };
+    let mut found: HashMap<usize, Vec<u8>> = HashMap::new();
+    visit_archive(path.as_ref(), &wanted, &mut |_, data| {
+        if let Ok(data) = data {
+            found.insert(position.get() - 1, data);
+        }
+    })?;
+
+    let mut out = Vec::new();
+    for &index in indices {
+        let data = found.get(&index).ok_or(Error::MissingEntry { index })?;
+        out.extend_from_slice(data);
+    }
+    Ok(out)
+}
+
+// 형식을 판별해 `wanted` 항목을 `visit`에 넘깁니다.
+fn visit_archive(path: &Path, wanted: &Filter, visit: &mut Visit) -> Result<(), Error> {
+    let mut file = File::open(path)?;
+    let mut head = Vec::new();
+    file.by_ref().take(512).read_to_end(&mut head)?;
+    file.rewind()?;
+
+    let reader = BufReader::new(file);
+    match Kind::detect(&head) {
+        Some(Kind::Zip) => zip::read(reader, wanted, visit),
+        Some(Kind::Tar) => tar::read(reader, wanted, visit),
+        Some(Kind::Gzip) => tar::read(MultiGzDecoder::new(reader), wanted, visit),
+        None => Err(Error::InvalidArchive),
+    }
+}
+
+/// 메타데이터를 읽을 항목인지 이름으로 판별합니다. macOS가 만드는 리소스 포크는 건너뜁니다.
+pub fn is_candidate(name: &str) -> bool {
+    let file_name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    if name.starts_with("__MACOSX/") || file_name.starts_with("._") {
+        return false;
+    }
+    file_name
+        .rsplit_once('.')
+        .is_some_and(|(_, extension)| EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+// `limit`까지만 읽습니다. 넘으면 `TooLarge`입니다.
+fn read_limited(reader: impl Read, limit: u64) -> Result<Vec<u8>, EntryError> {
+    let mut data = Vec::new();
+    reader
+        .take(limit + 1)
+        .read_to_end(&mut data)
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::UnexpectedEof => EntryError::Truncated,
+            _ => EntryError::Decompress,
+        })?;
+    if data.len() as u64 > limit {
+        return Err(EntryError::TooLarge {
+            size: data.len() as u64,
+        });
+    }
+    Ok(data)
+}
+
+// UTF-8이 아니면 CP949, Shift_JIS 순으로 추측합니다.
+fn decode_name(name: &[u8]) -> String {
+    super::text::decode_legacy(name).0.replace('\\', "/")
+}
@@ -0,0 +1,274 @@
+//! tar 읽기
+//!
+//! ```text
+//! (헤더 블록(512) + 데이터(512 단위로 채움))* + 빈 블록 2개
+//! ```
+//!
+//! 앞에서부터 차례로 읽으므로 gzip 스트림도 그대로 넘길 수 있습니다.
+//! 긴 경로는 GNU `L` 항목이나 PAX `x` 항목의 `path` 레코드로 다음 항목에 붙습니다.
+
+use std::io::{self, Read};
+
+use super::{decode_name, read_limited, EntryError, Filter, Visit, MAX_ENTRY_SIZE};
+use crate::metadata::Error;
+
+const BLOCK_SIZE: u64 = 512;
+
+// 헤더 필드 위치
+const NAME: std::ops::Range<usize> = 0..100;
+const SIZE: std::ops::Range<usize> = 124..136;
+const CHECKSUM: std::ops::Range<usize> = 148..156;
+const TYPE_FLAG: usize = 156;
+const MAGIC: std::ops::Range<usize> = 257..262;
+const PREFIX: std::ops::Range<usize> = 345..500;
+
+// 긴 이름, PAX 헤더 내용의 크기 상한
+const MAX_NAME_SIZE: u64 = 1024 * 1024;
+
+/// ustar 매직이 있거나 첫 블록의 체크섬이 맞는지 확인합니다.
+pub fn is_tar(head: &[u8]) -> bool {
+    let Ok(header) = <&[u8; 512]>::try_from(head.get(..512).unwrap_or(&[])) else {
+        return false;
+    };
+    &header[MAGIC] == b"ustar" || checksum_ok(header)
+}
+
+/// `wanted` 항목을 순서대로 `visit`에 넘깁니다. 아카이브가 중간에 끝나면 거기까지만 읽습니다.
+pub fn read(mut reader: impl Read, wanted: &Filter, visit: &mut Visit) -> Result<(), Error> {
+    let mut long_name: Option<String> = None;
+    let mut first = true;
+    loop {
+        let mut header = [0u8; BLOCK_SIZE as usize];
+        if reader.read_exact(&mut header).is_err() || header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if !checksum_ok(&header) {
+            if first {
+                return Err(Error::InvalidArchive);
+            }
+            break;
+        }
+        first = false;
+        let Some(size) = parse_number(&header[SIZE]) else {
+            break;
+        };
+        let padding = size.next_multiple_of(BLOCK_SIZE) - size;
+        match header[TYPE_FLAG] {
+            // GNU 긴 이름, PAX 확장 헤더
+            kind @ (b'L' | b'x') => {
+                let Ok(data) = read_limited((&mut reader).take(size), MAX_NAME_SIZE) else {
+                    break;
+                };
+                long_name = if kind == b'L' {
+                    Some(decode_name(trim_nul(&data)))
+                } else {
+                    pax_path(&data).or(long_name)
+                };
+                if !skip(&mut reader, padding) {
+                    break;
+                }
+                continue;
+            }
+            // 일반 파일
+            b'0' | b'\0' | b'7' => {
+                let name = long_name.take().unwrap_or_else(|| header_name(&header));
+                if !wanted(&name) {
+                    if !skip(&mut reader, size + padding) {
+                        break;
+                    }
+                    continue;
+                }
+                if size > MAX_ENTRY_SIZE {
+                    visit(&name, Err(EntryError::TooLarge { size }));
+                    if !skip(&mut reader, size + padding) {
+                        break;
+                    }
+                    continue;
+                }
+                let data = read_limited((&mut reader).take(size), size).and_then(|data| {
+                    if data.len() as u64 == size {
+                        Ok(data)
+                    } else {
+                        Err(EntryError::Truncated)
+                    }
+                });
+                let truncated = data.is_err();
+                visit(&name, data);
+                if truncated || !skip(&mut reader, padding) {
+                    break;
+                }
+            }
+            // 디렉터리, 링크 등
+            _ => {
+                long_name = None;
+                if !skip(&mut reader, size + padding) {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// 끝까지 건너뛰었으면 `true`
+fn skip(reader: &mut impl Read, length: u64) -> bool {
+    io::copy(&mut reader.take(length), &mut io::sink()).is_ok_and(|copied| copied == length)
+}
+
+// ustar는 155바이트 접두사와 100바이트 이름을 `/`로 잇습니다.
+fn header_name(header: &[u8; 512]) -> String {
+    let name = trim_nul(&header[NAME]);
+    let prefix = trim_nul(&header[PREFIX]);
+    if &header[MAGIC] == b"ustar" && !prefix.is_empty() {
+        decode_name(&[prefix, b"/", name].concat())
+    } else {
+        decode_name(name)
+    }
+}
+
+// 레코드: "길이 키=값\n" (길이는 레코드 전체의 바이트 수)
+fn pax_path(data: &[u8]) -> Option<String> {
+    let mut rest = data;
+    let mut path = None;
+    while !rest.is_empty() {
+        let space = rest.iter().position(|&b| b == b' ')?;
+        let length: usize = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+        let record = rest.get(space + 1..length)?;
+        let record = record.strip_suffix(b"\n").unwrap_or(record);
+        if let Some(value) = record.strip_prefix(b"path=") {
+            path = Some(String::from_utf8_lossy(value).into_owned());
+        }
+        rest = &rest[length..];
+    }
+    path
+}
+
+// 8진수 ASCII. 첫 바이트의 최상위 비트가 1이면 나머지가 빅 엔디언 이진수입니다(GNU).
+fn parse_number(field: &[u8]) -> Option<u64> {
+    if let Some((&first, rest)) = field.split_first() {
+        if first & 0x80 != 0 {
+            return rest.iter().try_fold(u64::from(first & 0x7f), |acc, &b| {
+                acc.checked_mul(256).map(|acc| acc | u64::from(b))
+            });
+        }
+    }
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+// 체크섬 필드를 공백으로 보고 모든 바이트를 더합니다.
+fn checksum_ok(header: &[u8; 512]) -> bool {
+    let Some(expected) = parse_number(&header[CHECKSUM]) else {
+        return false;
+    };
+    let sum: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if CHECKSUM.contains(&i) { b' ' } else { b } as u64)
+        .sum();
+    sum == expected
+}
+
+fn trim_nul(data: &[u8]) -> &[u8] {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    &data[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+    use crate::metadata::archive::{read_archive, read_entries};
+    use crate::metadata::testing::{parameters_png, TempDir};
+
+    fn header(name: &[u8], size: usize, kind: u8) -> [u8; 512] {
+        let mut header = [0; 512];
+        header[..name.len()].copy_from_slice(name);
+        header[100..108].copy_from_slice(b"0000644\0");
+        header[SIZE].copy_from_slice(format!("{size:011o}\0").as_bytes());
+        header[TYPE_FLAG] = kind;
+        header[257..265].copy_from_slice(b"ustar\x0000");
+        header[CHECKSUM].copy_from_slice(b"        ");
+        let sum: u32 = header.iter().map(|&b| b as u32).sum();
+        header[CHECKSUM].copy_from_slice(format!("{sum:06o}\0 ").as_bytes());
+        header
+    }
+
+    fn entry(out: &mut Vec<u8>, name: &[u8], data: &[u8], kind: u8) {
+        out.extend_from_slice(&header(name, data.len(), kind));
+        out.extend_from_slice(data);
+        out.resize(out.len().next_multiple_of(BLOCK_SIZE as usize), 0);
+    }
+
+    // 빈 블록 2개로 끝나지 않는 tar와, 긴 경로를 PAX 헤더로 붙인 항목의 이름
+    fn tar() -> (Vec<u8>, String) {
+        let long = format!("{}/deep.png", "d".repeat(120));
+        let record = format!("path={long}\n");
+        let record = format!("{} {record}", record.len() + 4);
+        let mut out = Vec::new();
+        entry(&mut out, b"dir/", b"", b'5');
+        entry(&mut out, b"dir/one.png", &parameters_png("one"), b'0');
+        entry(&mut out, b"PaxHeader", record.as_bytes(), b'x');
+        entry(&mut out, b"ignored", &parameters_png("deep"), b'0');
+        entry(&mut out, b"notes.txt", b"x", b'0');
+        entry(&mut out, b"last.png", &parameters_png("last"), b'0');
+        (out, long)
+    }
+
+    #[test]
+    fn entries() {
+        let (mut data, long) = tar();
+        data.extend_from_slice(&[0; 1024]);
+        assert!(is_tar(&data));
+        let dir = TempDir::new();
+        let path = dir.file("a.tar", &data);
+
+        let entries = read_archive(&path).unwrap();
+        let names: Vec<_> = entries
+            .iter()
+            .map(|entry| entry.original_name.as_str())
+            .collect();
+        assert_eq!(names, ["dir/one.png", long.as_str(), "last.png"]);
+        let extraction = entries[1].extraction.as_ref().unwrap();
+        assert_eq!(extraction.metadata.get("PNG:parameters"), Some("deep"));
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let path = dir.file("a.tar.gz", encoder.finish().unwrap());
+        assert_eq!(read_archive(&path).unwrap().len(), 3);
+        let bytes = read_entries(&path, &[2, 1]).unwrap();
+        assert_eq!(
+            bytes,
+            [parameters_png("last"), parameters_png("deep")].concat()
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let (data, _) = tar();
+        let dir = TempDir::new();
+        let path = dir.file("a.tar", &data[..data.len() - 500]);
+        let entries = read_archive(&path).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2].error, Some(EntryError::Truncated));
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(parse_number(b"00000001750\0"), Some(1000));
+        assert_eq!(parse_number(b" 17 \0"), Some(15));
+        // 8GB가 넘는 크기는 base-256으로 씁니다.
+        assert_eq!(
+            parse_number(&[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00]),
+            Some(256)
+        );
+    }
+}
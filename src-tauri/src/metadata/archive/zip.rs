@@ -0,0 +1,459 @@
+//! ZIP 읽기
+//!
+//! ```text
+//! [로컬 헤더 + 데이터]* + 중앙 디렉터리 + [ZIP64 EOCD + ZIP64 로케이터] + EOCD
+//! ```
+//!
+//! 중앙 디렉터리에서 항목 목록과 크기를 읽고, 항목마다 로컬 헤더 위치로 이동해 압축을 풉니다.
+//! 크기나 위치가 4GB를 넘으면 ZIP64 확장 필드의 값을 씁니다.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use flate2::read::DeflateDecoder;
+
+use super::{decode_name, read_limited, EntryError, Filter, Visit, MAX_ENTRY_SIZE};
+use crate::metadata::Error;
+
+const EOCD: u32 = 0x0605_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const ZIP64_EOCD: u32 = 0x0606_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+
+const EOCD_SIZE: usize = 22;
+const ZIP64_LOCATOR_SIZE: usize = 20;
+const ZIP64_EOCD_SIZE: usize = 56;
+const CENTRAL_HEADER_SIZE: usize = 46;
+const LOCAL_HEADER_SIZE: usize = 30;
+
+// EOCD 뒤의 주석은 최대 65535바이트입니다.
+const MAX_EOCD_SEARCH: u64 = EOCD_SIZE as u64 + 0xffff;
+// 중앙 디렉터리 크기 상한
+const MAX_CENTRAL_DIRECTORY: u64 = 64 * 1024 * 1024;
+
+// 확장 필드 ID
+const EXTRA_ZIP64: u16 = 0x0001;
+// Info-ZIP Unicode Path: 버전(1) + 헤더 이름의 CRC(4) + UTF-8 이름
+const EXTRA_UNICODE_PATH: u16 = 0x7075;
+
+const FLAG_ENCRYPTED: u16 = 1;
+const FLAG_UTF8: u16 = 1 << 11;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+// ZIP64 값이 확장 필드에 있음을 나타내는 값
+const ZIP64_MARKER: u32 = 0xffff_ffff;
+
+/// 중앙 디렉터리 항목
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    offset: u64,
+}
+
+/// 중앙 디렉터리에서 `wanted` 항목을 골라 순서대로 `visit`에 넘깁니다.
+pub fn read<R: Read + Seek>(
+    mut reader: R,
+    wanted: &Filter,
+    visit: &mut Visit,
+) -> Result<(), Error> {
+    for entry in central_directory(&mut reader)? {
+        if entry.name.ends_with('/') || !wanted(&entry.name) {
+            continue;
+        }
+        let data = read_entry(&mut reader, &entry);
+        visit(&entry.name, data);
+    }
+    Ok(())
+}
+
+fn central_directory<R: Read + Seek>(reader: &mut R) -> Result<Vec<Entry>, Error> {
+    let length = reader.seek(SeekFrom::End(0))?;
+    let tail_start = length.saturating_sub(MAX_EOCD_SEARCH);
+    let tail = read_at(reader, tail_start, (length - tail_start) as usize)?;
+    let eocd = (0..=tail.len().saturating_sub(EOCD_SIZE))
+        .rev()
+        .find(|&i| le32(&tail, i) == Some(EOCD))
+        .ok_or(Error::InvalidArchive)?;
+
+    let field = |offset| le16(&tail, eocd + offset).ok_or(Error::InvalidArchive);
+    let mut count = field(10)? as u64;
+    let mut size = le32(&tail, eocd + 12).ok_or(Error::InvalidArchive)? as u64;
+    let mut offset = le32(&tail, eocd + 16).ok_or(Error::InvalidArchive)? as u64;
+    // ZIP64 로케이터는 EOCD 바로 앞에 있습니다.
+    if let Some(locator) = eocd.checked_sub(ZIP64_LOCATOR_SIZE) {
+        if le32(&tail, locator) == Some(ZIP64_LOCATOR) {
+            let record = le64(&tail, locator + 8).ok_or(Error::InvalidArchive)?;
+            let record = read_at(reader, record, ZIP64_EOCD_SIZE)?;
+            if le32(&record, 0) != Some(ZIP64_EOCD) {
+                return Err(Error::InvalidArchive);
+            }
+            let field = |offset| le64(&record, offset).ok_or(Error::InvalidArchive);
+            count = field(32)?;
+            size = field(40)?;
+            offset = field(48)?;
+        }
+    }
+    if size > MAX_CENTRAL_DIRECTORY {
+        return Err(Error::InvalidArchive);
+    }
+    let directory = read_at(reader, offset, size as usize)?;
+
+    let mut entries = Vec::new();
+    let mut position = 0;
+    while (entries.len() as u64) < count {
+        let Some((entry, next)) = parse_entry(&directory, position) else {
+            break;
+        };
+        entries.push(entry);
+        position = next;
+    }
+    Ok(entries)
+}
+
+// 중앙 디렉터리 헤더(46) + 이름 + 확장 필드 + 주석
+fn parse_entry(directory: &[u8], position: usize) -> Option<(Entry, usize)> {
+    if le32(directory, position)? != CENTRAL_HEADER {
+        return None;
+    }
+    let field = |offset| le16(directory, position + offset);
+    let flags = field(8)?;
+    let method = field(10)?;
+    let crc = le32(directory, position + 16)?;
+    let compressed_size = le32(directory, position + 20)?;
+    let size = le32(directory, position + 24)?;
+    let name_length = field(28)? as usize;
+    let extra_length = field(30)? as usize;
+    let comment_length = field(32)? as usize;
+    let offset = le32(directory, position + 42)?;
+
+    let name_start = position + CENTRAL_HEADER_SIZE;
+    let raw_name = directory.get(name_start..name_start + name_length)?;
+    let extra_start = name_start + name_length;
+    let extra = directory.get(extra_start..extra_start + extra_length)?;
+
+    let mut entry = Entry {
+        name: if flags & FLAG_UTF8 != 0 {
+            String::from_utf8_lossy(raw_name).into_owned()
+        } else {
+            decode_name(raw_name)
+        },
+        flags,
+        method,
+        crc,
+        compressed_size: compressed_size as u64,
+        size: size as u64,
+        offset: offset as u64,
+    };
+    for (id, data) in extra_fields(extra) {
+        match id {
+            EXTRA_ZIP64 => {
+                // 헤더 값이 0xFFFFFFFF인 항목만 원래 크기, 압축 크기, 위치 순서로 들어 있습니다.
+                let mut values = data.chunks_exact(8).map(|value| le64(value, 0));
+                for (header, target) in [
+                    (size, &mut entry.size),
+                    (compressed_size, &mut entry.compressed_size),
+                    (offset, &mut entry.offset),
+                ] {
+                    if header == ZIP64_MARKER {
+                        *target = values.next().flatten()?;
+                    }
+                }
+            }
+            EXTRA_UNICODE_PATH => {
+                if let Some(name) = unicode_path(data, raw_name) {
+                    entry.name = name;
+                }
+            }
+            _ => {}
+        }
+    }
+    let next = extra_start + extra_length + comment_length;
+    Some((entry, next))
+}
+
+// ID(2) + 크기(2) + 데이터
+fn extra_fields(extra: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let id = le16(extra, offset)?;
+        let length = le16(extra, offset + 2)? as usize;
+        let data = extra.get(offset + 4..offset + 4 + length)?;
+        offset += 4 + length;
+        Some((id, data))
+    })
+}
+
+// 헤더의 이름이 바뀌지 않았을 때(CRC가 같을 때)만 씁니다.
+fn unicode_path(data: &[u8], raw_name: &[u8]) -> Option<String> {
+    let (&version, rest) = data.split_first()?;
+    let crc = le32(rest, 0)?;
+    if version != 1 || crc != crc32fast::hash(raw_name) {
+        return None;
+    }
+    String::from_utf8(rest[4..].to_vec()).ok()
+}
+
+fn read_entry<R: Read + Seek>(reader: &mut R, entry: &Entry) -> Result<Vec<u8>, EntryError> {
+    if entry.flags & FLAG_ENCRYPTED != 0 {
+        return Err(EntryError::Encrypted);
+    }
+    if entry.size > MAX_ENTRY_SIZE {
+        return Err(EntryError::TooLarge { size: entry.size });
+    }
+    // 로컬 헤더의 이름과 확장 필드 길이는 중앙 디렉터리와 다를 수 있습니다.
+    let header =
+        read_at(reader, entry.offset, LOCAL_HEADER_SIZE).map_err(|_| EntryError::Truncated)?;
+    if le32(&header, 0) != Some(LOCAL_HEADER) {
+        return Err(EntryError::Truncated);
+    }
+    let name_length = le16(&header, 26).unwrap_or(0) as u64;
+    let extra_length = le16(&header, 28).unwrap_or(0) as u64;
+    let start = entry.offset + LOCAL_HEADER_SIZE as u64 + name_length + extra_length;
+    reader
+        .seek(SeekFrom::Start(start))
+        .map_err(|_| EntryError::Truncated)?;
+
+    let compressed = reader.by_ref().take(entry.compressed_size);
+    let data = match entry.method {
+        METHOD_STORED => read_limited(compressed, MAX_ENTRY_SIZE)?,
+        METHOD_DEFLATED => read_limited(DeflateDecoder::new(compressed), MAX_ENTRY_SIZE)?,
+        method => return Err(EntryError::UnsupportedMethod { method }),
+    };
+    if data.len() as u64 != entry.size {
+        return Err(EntryError::Truncated);
+    }
+    if crc32fast::hash(&data) != entry.crc {
+        return Err(EntryError::CrcMismatch);
+    }
+    Ok(data)
+}
+
+// 파일이 `offset + length`보다 짧으면 손상된 아카이브입니다.
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, length: usize) -> Result<Vec<u8>, Error> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut data = vec![0; length];
+    reader
+        .read_exact(&mut data)
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::UnexpectedEof => Error::InvalidArchive,
+            _ => Error::Io(error),
+        })?;
+    Ok(data)
+}
+
+fn le16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn le32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn le64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes: [u8; 8] = data.get(offset..offset + 8)?.try_into().ok()?;
+    Some(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+
+    use super::*;
+    use crate::metadata::archive::{read_archive, read_entries};
+    use crate::metadata::testing::{parameters_png, TempDir};
+
+    struct Item {
+        name: Vec<u8>,
+        flags: u16,
+        data: Vec<u8>,
+        deflate: bool,
+        zip64: bool,
+    }
+
+    fn item(name: &[u8], data: Vec<u8>) -> Item {
+        Item {
+            name: name.to_vec(),
+            flags: 0,
+            data,
+            deflate: false,
+            zip64: false,
+        }
+    }
+
+    // EOCD는 항상 ZIP64 EOCD를 가리킵니다.
+    fn zip(items: &[Item]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for item in items {
+            let offset = out.len() as u32;
+            let body = if item.deflate {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&item.data).unwrap();
+                encoder.finish().unwrap()
+            } else {
+                item.data.clone()
+            };
+            let crc = crc32fast::hash(&item.data);
+            let method = if item.deflate {
+                METHOD_DEFLATED
+            } else {
+                METHOD_STORED
+            };
+            out.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+            out.extend_from_slice(&[20, 0]);
+            out.extend_from_slice(&item.flags.to_le_bytes());
+            out.extend_from_slice(&method.to_le_bytes());
+            out.extend_from_slice(&[0; 4]);
+            out.extend_from_slice(&crc.to_le_bytes());
+            out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(item.data.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(item.name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&[0; 2]);
+            out.extend_from_slice(&item.name);
+            out.extend_from_slice(&body);
+
+            let mut extra = Vec::new();
+            let (compressed_size, size, offset) = if item.zip64 {
+                extra.extend_from_slice(&EXTRA_ZIP64.to_le_bytes());
+                extra.extend_from_slice(&24u16.to_le_bytes());
+                extra.extend_from_slice(&(item.data.len() as u64).to_le_bytes());
+                extra.extend_from_slice(&(body.len() as u64).to_le_bytes());
+                extra.extend_from_slice(&(offset as u64).to_le_bytes());
+                (ZIP64_MARKER, ZIP64_MARKER, ZIP64_MARKER)
+            } else {
+                (body.len() as u32, item.data.len() as u32, offset)
+            };
+            central.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+            central.extend_from_slice(&[20, 0, 20, 0]);
+            central.extend_from_slice(&item.flags.to_le_bytes());
+            central.extend_from_slice(&method.to_le_bytes());
+            central.extend_from_slice(&[0; 4]);
+            central.extend_from_slice(&crc.to_le_bytes());
+            central.extend_from_slice(&compressed_size.to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&(item.name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 10]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(&item.name);
+            central.extend_from_slice(&extra);
+        }
+        let central_offset = out.len() as u64;
+        out.extend_from_slice(&central);
+
+        let zip64_eocd = out.len() as u64;
+        let count = items.len() as u64;
+        out.extend_from_slice(&ZIP64_EOCD.to_le_bytes());
+        out.extend_from_slice(&((ZIP64_EOCD_SIZE - 12) as u64).to_le_bytes());
+        out.extend_from_slice(&[45, 0, 45, 0]);
+        out.extend_from_slice(&[0; 8]);
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&(central.len() as u64).to_le_bytes());
+        out.extend_from_slice(&central_offset.to_le_bytes());
+        out.extend_from_slice(&ZIP64_LOCATOR.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&zip64_eocd.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&EOCD.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&[0xff; 12]);
+        out.extend_from_slice(&[0; 2]);
+        out
+    }
+
+    #[test]
+    fn entries() {
+        let (cp949, _, _) = encoding_rs::EUC_KR.encode("폴더/고양이.png");
+        let data = zip(&[
+            Item {
+                deflate: true,
+                ..item(b"./a/one.png", parameters_png("cat, dog"))
+            },
+            Item {
+                zip64: true,
+                ..item(&cp949, parameters_png("고양이"))
+            },
+            Item {
+                flags: FLAG_UTF8,
+                deflate: true,
+                ..item("한글.png".as_bytes(), parameters_png("utf-8"))
+            },
+            item(b"readme.txt", b"hi".to_vec()),
+            item(b"__MACOSX/._one.png", b"x".to_vec()),
+            Item {
+                flags: FLAG_ENCRYPTED,
+                ..item(b"enc.png", parameters_png("encrypted"))
+            },
+        ]);
+        let dir = TempDir::new();
+        let path = dir.file("a.zip", &data);
+
+        let entries = read_archive(&path).unwrap();
+        let names: Vec<_> = entries
+            .iter()
+            .map(|entry| entry.original_name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["a/one.png", "폴더/고양이.png", "한글.png", "enc.png"]
+        );
+        let parameters = |index: usize| {
+            let extraction = entries[index].extraction.as_ref().unwrap();
+            extraction.metadata.get("PNG:parameters").map(str::to_owned)
+        };
+        assert_eq!(parameters(0).as_deref(), Some("cat, dog"));
+        assert_eq!(parameters(1).as_deref(), Some("고양이"));
+        assert_eq!(entries[3].error, Some(EntryError::Encrypted));
+        let json = serde_json::to_value(&entries[3]).unwrap();
+        assert_eq!(json["error"]["kind"], "encrypted");
+        assert_eq!(json["originalName"], "enc.png");
+
+        // 요청한 순서대로 이어 붙입니다.
+        let bytes = read_entries(&path, &[1, 0]).unwrap();
+        assert_eq!(
+            bytes,
+            [parameters_png("고양이"), parameters_png("cat, dog")].concat()
+        );
+        assert!(matches!(
+            read_entries(&path, &[3]),
+            Err(Error::MissingEntry { index: 3 })
+        ));
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let data = zip(&[
+            item(b"a.png", parameters_png("first")),
+            item(b"a.png", parameters_png("second")),
+        ]);
+        let dir = TempDir::new();
+        let path = dir.file("a.zip", &data);
+        assert_eq!(read_archive(&path).unwrap().len(), 2);
+        // 같은 경로라도 위치로 구분합니다.
+        assert_eq!(
+            read_entries(&path, &[1, 0]).unwrap(),
+            [parameters_png("second"), parameters_png("first")].concat()
+        );
+    }
+
+    #[test]
+    fn missing_central_directory() {
+        let data = zip(&[item(b"one.png", parameters_png("cat"))]);
+        let dir = TempDir::new();
+        let path = dir.file("a.zip", &data[..data.len() / 2]);
+        assert!(matches!(read_archive(&path), Err(Error::InvalidArchive)));
+    }
+}
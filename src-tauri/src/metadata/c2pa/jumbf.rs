@@ -0,0 +1,65 @@
+//! JUMBF(ISO/IEC 19566-5) 박스 파서
+//!
+//! 슈퍼박스(`jumb`)는 설명 박스(`jumd`)와 내용 박스(`cbor`, `json`, 하위 `jumb` 등)로 이루어집니다.
+//!
+//! ```text
+//! jumd: 콘텐츠 타입 UUID(16) + 토글(1) [+ 레이블(NUL 종료)] [+ ID(4)] [+ 해시(32)]
+//! ```
+
+use crate::metadata::isobmff::{self, Atom};
+
+// 토글 비트: 레이블 있음
+const TOGGLE_LABEL: u8 = 0x02;
+
+/// JUMBF 슈퍼박스
+pub struct Superbox<'a> {
+    pub label: String,
+    /// `jumd`를 뺀 하위 박스
+    pub boxes: Vec<Atom<'a>>,
+}
+
+impl<'a> Superbox<'a> {
+    /// `jumb` 박스의 내용(헤더 제외)을 해석합니다.
+    pub fn parse(payload: &'a [u8]) -> Option<Self> {
+        let mut boxes = isobmff::boxes(payload);
+        let description = boxes.next().filter(|atom| &atom.kind == b"jumd")?;
+        let (&toggles, rest) = description.data.get(16..)?.split_first()?;
+        let label = if toggles & TOGGLE_LABEL != 0 {
+            let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+            String::from_utf8_lossy(&rest[..end]).into_owned()
+        } else {
+            String::new()
+        };
+        Some(Self {
+            label,
+            boxes: boxes.collect(),
+        })
+    }
+
+    /// 하위 슈퍼박스
+    pub fn children(&self) -> impl Iterator<Item = Superbox<'a>> + '_ {
+        self.boxes
+            .iter()
+            .filter(|atom| &atom.kind == b"jumb")
+            .filter_map(|atom| Superbox::parse(atom.data))
+    }
+
+    pub fn child(&self, label: &str) -> Option<Superbox<'a>> {
+        self.children().find(|child| child.label == label)
+    }
+
+    /// 처음 나오는 `kind` 내용 박스의 데이터
+    pub fn content(&self, kind: &[u8; 4]) -> Option<&'a [u8]> {
+        self.boxes
+            .iter()
+            .find(|atom| &atom.kind == kind)
+            .map(|atom| atom.data)
+    }
+}
+
+/// `jumb` 박스로 시작하는 데이터에서 최상위 슈퍼박스를 읽습니다.
+pub fn read(data: &[u8]) -> Option<Superbox<'_>> {
+    isobmff::boxes(data)
+        .find(|atom| &atom.kind == b"jumb")
+        .and_then(|atom| Superbox::parse(atom.data))
+}
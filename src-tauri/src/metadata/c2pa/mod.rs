@@ -0,0 +1,365 @@
+//! C2PA 콘텐츠 자격 증명(Content Credentials) 파서
+//!
+//! DALL-E, Bing Image Creator, Adobe Firefly 등은 JUMBF 매니페스트 저장소
+//! (JPEG APP11, PNG `caBX`, WebP `C2PA`)에 생성 도구와 편집 이력을 기록합니다.
+//! 프롬프트는 없지만 클레임 생성기와 액션, 소프트웨어 에이전트로 생성 도구를 알 수 있습니다.
+//!
+//! ```text
+//! jumb "c2pa"                     매니페스트 저장소 (마지막 매니페스트가 활성 매니페스트)
+//! └ jumb "urn:uuid:..."           매니페스트
+//!   ├ jumb "c2pa.assertions"
+//!   │ └ jumb "c2pa.actions"       cbor: {"actions": [{"action": "c2pa.created", "softwareAgent": ...}]}
+//!   ├ jumb "c2pa.claim"           cbor: {"claim_generator": ..., ...}
+//!   └ jumb "c2pa.signature"       cbor: COSE_Sign1
+//! ```
+//!
+//! 서명 검증은 `c2pa-verify` 기능을 켰을 때만 수행합니다. 신뢰 앵커는 포함하지 않으므로
+//! 실행 중에 앵커를 추가하지 않으면 체인은 검증하지 않습니다([`verify::add_trust_anchors`]).
+
+pub mod jumbf;
+#[cfg(feature = "c2pa-verify")]
+pub mod verify;
+
+use ciborium::Value;
+
+use self::jumbf::Superbox;
+use super::Metadata;
+
+const STORE_LABEL: &str = "c2pa";
+const ASSERTIONS_LABEL: &str = "c2pa.assertions";
+const CLAIM_LABELS: &[&str] = &["c2pa.claim.v2", "c2pa.claim"];
+const ACTIONS_LABELS: &[&str] = &["c2pa.actions.v2", "c2pa.actions"];
+const CREATED_ACTION: &str = "c2pa.created";
+
+/// 매니페스트 저장소(`jumb` 박스로 시작)를 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    let Some(store) = jumbf::read(data).filter(|store| store.label == STORE_LABEL) else {
+        return;
+    };
+    let Some(manifest) = store.children().last() else {
+        return;
+    };
+
+    let claim = claim(&manifest);
+    if let Some(claim) = &claim {
+        metadata.insert("C2PA:ClaimGenerator", claim_generator(claim));
+    }
+
+    let actions = actions(&manifest);
+    let names: Vec<&str> = actions
+        .iter()
+        .filter_map(|action| field(action, "action")?.as_text())
+        .collect();
+    metadata.insert("C2PA:Actions", names.join(", "));
+    if let Some(created) = actions
+        .iter()
+        .find(|action| field(action, "action").and_then(Value::as_text) == Some(CREATED_ACTION))
+    {
+        metadata.insert(
+            "C2PA:Created",
+            field(created, "softwareAgent")
+                .map(software_agent)
+                .unwrap_or_default(),
+        );
+        // "http://cv.iptc.org/newscodes/digitalsourcetype/trainedAlgorithmicMedia" -> "trainedAlgorithmicMedia"
+        if let Some(source) = field(created, "digitalSourceType").and_then(Value::as_text) {
+            let source = source.rsplit('/').next().unwrap_or(source);
+            metadata.insert("C2PA:DigitalSourceType", source);
+        }
+    }
+
+    #[cfg(feature = "c2pa-verify")]
+    if let (Some(claim), Some(signature)) = (
+        claim_bytes(&manifest),
+        manifest
+            .child("c2pa.signature")
+            .and_then(|signature| signature.content(b"cbor")),
+    ) {
+        metadata.insert("C2PA:Signature", verify::verify(claim, signature).as_str());
+    }
+}
+
+fn claim_bytes<'a>(manifest: &Superbox<'a>) -> Option<&'a [u8]> {
+    manifest
+        .children()
+        .find(|child| CLAIM_LABELS.contains(&child.label.as_str()))?
+        .content(b"cbor")
+}
+
+fn claim(manifest: &Superbox) -> Option<Value> {
+    decode(claim_bytes(manifest)?)
+}
+
+// 레이블이 같은 어서션이 여럿이면 `c2pa.actions__1`처럼 번호가 붙습니다.
+fn actions(manifest: &Superbox) -> Vec<Value> {
+    let Some(assertions) = manifest.child(ASSERTIONS_LABEL) else {
+        return Vec::new();
+    };
+    assertions
+        .children()
+        .filter(|assertion| {
+            let label = assertion.label.split("__").next().unwrap_or_default();
+            ACTIONS_LABELS.contains(&label)
+        })
+        .filter_map(|assertion| decode(assertion.content(b"cbor")?))
+        .filter_map(|assertion| match field(&assertion, "actions") {
+            Some(Value::Array(actions)) => Some(actions.clone()),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+// v1: "claim_generator": "Adobe_Firefly c2pa-rs/0.25.2"
+// v2: "claim_generator_info": {"name": ..., "version": ...} (또는 그 배열)
+fn claim_generator(claim: &Value) -> String {
+    if let Some(generator) = field(claim, "claim_generator").and_then(Value::as_text) {
+        return generator.to_owned();
+    }
+    match field(claim, "claim_generator_info") {
+        Some(Value::Array(infos)) => infos.first().map(software_agent).unwrap_or_default(),
+        Some(info) => software_agent(info),
+        None => String::new(),
+    }
+}
+
+// 문자열 또는 {"name": ..., "version": ...}
+fn software_agent(agent: &Value) -> String {
+    if let Some(name) = agent.as_text() {
+        return name.to_owned();
+    }
+    let name = field(agent, "name")
+        .and_then(Value::as_text)
+        .unwrap_or_default();
+    match field(agent, "version").and_then(Value::as_text) {
+        Some(version) => format!("{name}/{version}"),
+        None => name.to_owned(),
+    }
+}
+
+fn decode(data: &[u8]) -> Option<Value> {
+    ciborium::de::from_reader(data).ok()
+}
+
+/// CBOR 맵에서 문자열 키로 값을 찾습니다.
+fn field<'v>(map: &'v Value, key: &str) -> Option<&'v Value> {
+    map.as_map()?
+        .iter()
+        .find(|(k, _)| k.as_text() == Some(key))
+        .map(|(_, v)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::{bmff_box, chunk, png, segment};
+
+    fn superbox(label: &str, content: &[Vec<u8>]) -> Vec<u8> {
+        let mut description = vec![0; 16];
+        description.push(0x03);
+        description.extend_from_slice(label.as_bytes());
+        description.push(0);
+        let mut payload = bmff_box(b"jumd", &description);
+        for content in content {
+            payload.extend_from_slice(content);
+        }
+        bmff_box(b"jumb", &payload)
+    }
+
+    fn cbor(value: &Value) -> Vec<u8> {
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(value, &mut out).unwrap();
+        out
+    }
+
+    fn text(text: &str) -> Value {
+        Value::Text(text.into())
+    }
+
+    fn claim() -> Vec<u8> {
+        cbor(&Value::Map(vec![
+            (text("claim_generator"), text("ChatGPT c2pa-rs/0.31")),
+            (text("signature"), text("self#jumbf=c2pa.signature")),
+        ]))
+    }
+
+    // 앞의 매니페스트는 비어 있고, 마지막(활성) 매니페스트에만 내용이 있습니다.
+    fn store(claim: &[u8], signature: Option<Vec<u8>>) -> Vec<u8> {
+        let actions = Value::Map(vec![(
+            text("actions"),
+            Value::Array(vec![
+                Value::Map(vec![
+                    (text("action"), text("c2pa.created")),
+                    (text("softwareAgent"), text("DALL·E")),
+                    (
+                        text("digitalSourceType"),
+                        text("http://cv.iptc.org/newscodes/digitalsourcetype/trainedAlgorithmicMedia"),
+                    ),
+                ]),
+                Value::Map(vec![(text("action"), text("c2pa.converted"))]),
+            ]),
+        )]);
+        let mut manifest = vec![
+            superbox(
+                "c2pa.assertions",
+                &[superbox(
+                    "c2pa.actions",
+                    &[bmff_box(b"cbor", &cbor(&actions))],
+                )],
+            ),
+            superbox("c2pa.claim", &[bmff_box(b"cbor", claim)]),
+        ];
+        if let Some(signature) = signature {
+            manifest.push(superbox("c2pa.signature", &[bmff_box(b"cbor", &signature)]));
+        }
+        superbox(
+            "c2pa",
+            &[
+                superbox("urn:uuid:old", &[]),
+                superbox("urn:uuid:1234", &manifest),
+            ],
+        )
+    }
+
+    fn assert_fields(metadata: &Metadata) {
+        assert_eq!(
+            metadata.get("C2PA:ClaimGenerator"),
+            Some("ChatGPT c2pa-rs/0.31")
+        );
+        assert_eq!(
+            metadata.get("C2PA:Actions"),
+            Some("c2pa.created, c2pa.converted")
+        );
+        assert_eq!(metadata.get("C2PA:Created"), Some("DALL·E"));
+        assert_eq!(
+            metadata.get("C2PA:DigitalSourceType"),
+            Some("trainedAlgorithmicMedia")
+        );
+    }
+
+    #[test]
+    fn png_cabx() {
+        let data = png(&[chunk(b"caBX", &store(&claim(), None))]);
+        assert_fields(&extract(&data));
+    }
+
+    #[test]
+    fn jpeg_app11_packets() {
+        let jumbf = store(&claim(), None);
+        let (head, tail) = jumbf.split_at(40);
+        let mut data = vec![0xff, 0xd8];
+        // 이어지는 패킷은 박스 헤더를 반복합니다. 순서가 바뀌어도 순번으로 조립합니다.
+        for (sequence, part) in [(2u32, [&jumbf[..8], tail].concat()), (1, head.to_vec())] {
+            let mut payload = b"JP".to_vec();
+            payload.extend_from_slice(&1u16.to_be_bytes());
+            payload.extend_from_slice(&sequence.to_be_bytes());
+            payload.extend_from_slice(&part);
+            segment(&mut data, 0xeb, &payload);
+        }
+        data.extend_from_slice(&[0xff, 0xd9]);
+        let metadata = extract(&data);
+        assert_fields(&metadata);
+        assert_eq!(
+            metadata.get("JPEG:Segments"),
+            Some("APP11:JUMBF, APP11:JUMBF")
+        );
+    }
+
+    #[test]
+    fn claim_generator_info() {
+        let info = |name, version: Option<&str>| {
+            let mut map = vec![(text("name"), text(name))];
+            map.extend(version.map(|version| (text("version"), text(version))));
+            Value::Map(map)
+        };
+        let claim = Value::Map(vec![(
+            text("claim_generator_info"),
+            Value::Array(vec![
+                info("Adobe Firefly", Some("2.0")),
+                info("other", None),
+            ]),
+        )]);
+        assert_eq!(claim_generator(&claim), "Adobe Firefly/2.0");
+        assert_eq!(software_agent(&info("Bing", None)), "Bing");
+    }
+
+    #[cfg(feature = "c2pa-verify")]
+    #[test]
+    fn signature() {
+        use ring::rand::SystemRandom;
+        use ring::signature::{EcdsaKeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+
+        use super::verify::{add_trust_anchors, verify, Status};
+
+        let claim = claim();
+        // ES256, x5chain으로 `claim`에 서명한 COSE_Sign1
+        let sign = |key: &rcgen::KeyPair, cert: &rcgen::Certificate, tamper: bool| {
+            let rng = SystemRandom::new();
+            let key = EcdsaKeyPair::from_pkcs8(
+                &ECDSA_P256_SHA256_FIXED_SIGNING,
+                &key.serialize_der(),
+                &rng,
+            )
+            .unwrap();
+            let protected = cbor(&Value::Map(vec![
+                (Value::Integer(1.into()), Value::Integer((-7).into())),
+                (Value::Integer(33.into()), Value::Bytes(cert.der().to_vec())),
+            ]));
+            let to_be_signed = cbor(&Value::Array(vec![
+                text("Signature1"),
+                Value::Bytes(protected.clone()),
+                Value::Bytes(Vec::new()),
+                Value::Bytes(claim.clone()),
+            ]));
+            let mut signature = key.sign(&rng, &to_be_signed).unwrap().as_ref().to_vec();
+            if tamper {
+                signature[5] ^= 1;
+            }
+            cbor(&Value::Tag(
+                18,
+                Box::new(Value::Array(vec![
+                    Value::Bytes(protected),
+                    Value::Map(Vec::new()),
+                    Value::Null,
+                    Value::Bytes(signature),
+                ])),
+            ))
+        };
+        let signer = rcgen::generate_simple_self_signed(vec!["signer".into()]).unwrap();
+        let sign1 = sign(&signer.signing_key, &signer.cert, false);
+
+        // 신뢰 앵커는 포함하지 않습니다.
+        assert_eq!(verify(&claim, &sign1), Status::NoTrustAnchorsConfigured);
+        let other = rcgen::generate_simple_self_signed(vec!["other".into()]).unwrap();
+        assert_eq!(add_trust_anchors(other.cert.pem().as_bytes()), 1);
+        assert_eq!(verify(&claim, &sign1), Status::Untrusted);
+        let tampered = sign(&signer.signing_key, &signer.cert, true);
+        assert_eq!(verify(&claim, &tampered), Status::Invalid);
+
+        let mut metadata = Metadata::new();
+        read(&store(&claim, Some(sign1)), &mut metadata);
+        assert_eq!(metadata.get("C2PA:Signature"), Some("Untrusted"));
+
+        // 추가한 루트 CA가 발급한 서명 인증서
+        let ca_key = rcgen::KeyPair::generate().unwrap();
+        let mut ca_params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let leaf_key = rcgen::KeyPair::generate().unwrap();
+        let mut leaf_params = rcgen::CertificateParams::new(vec!["signer".into()]).unwrap();
+        leaf_params.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::EmailProtection];
+        let leaf = leaf_params
+            .signed_by(&leaf_key, &rcgen::Issuer::from_params(&ca_params, &ca_key))
+            .unwrap();
+        assert_eq!(add_trust_anchors(ca.pem().as_bytes()), 1);
+        assert_eq!(
+            verify(&claim, &sign(&leaf_key, &leaf, false)),
+            Status::Valid
+        );
+        assert_eq!(
+            verify(&claim, &sign(&signer.signing_key, &signer.cert, false)),
+            Status::Untrusted
+        );
+    }
+}
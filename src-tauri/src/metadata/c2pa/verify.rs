@@ -0,0 +1,235 @@
+//! C2PA 서명 검증
+//!
+//! `c2pa.signature`의 COSE_Sign1 서명을 클레임에 대해 확인하고, 서명 인증서 체인을
+//! 신뢰 앵커로 검증합니다. 네트워크(OCSP, 타임스탬프 서버)는 쓰지 않습니다.
+//!
+//! 앱은 신뢰 앵커 인증서를 포함하지 않습니다. 앵커는 [`add_trust_anchors`]로 실행 중에
+//! 추가하며(앱은 설정 폴더의 `trust_anchors.pem`을 읽습니다), 하나도 없으면 결과는
+//! [`Status::NoTrustAnchorsConfigured`]입니다.
+
+use std::sync::RwLock;
+
+use ciborium::Value;
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, SignatureVerificationAlgorithm, UnixTime};
+use webpki::{ring as algs, EndEntityCert, KeyUsage};
+
+use super::{decode, field};
+
+// 실행 중에 추가한 신뢰 앵커
+static TRUST_ANCHORS: RwLock<Vec<CertificateDer<'static>>> = RwLock::new(Vec::new());
+
+// COSE 태그와 헤더 레이블
+const COSE_SIGN1_TAG: u64 = 18;
+const HEADER_ALG: i64 = 1;
+const HEADER_X5CHAIN: i64 = 33;
+
+// C2PA 서명 인증서에 허용되는 확장 키 용도
+const SIGNING_USAGES: &[&[u8]] = &[
+    // id-kp-emailProtection (1.3.6.1.5.5.7.3.4)
+    &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x04],
+    // id-kp-documentSigning (1.3.6.1.5.5.7.3.36)
+    &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x24],
+    // c2pa-kp-claimSigning (1.3.6.1.4.1.62558.2.1)
+    &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xe8, 0x5e, 0x02, 0x01],
+];
+
+/// 검증 결과
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// 서명이 올바르고 인증서 체인이 신뢰 앵커까지 이어짐
+    Valid,
+    /// 서명은 올바르지만 신뢰 앵커로 체인을 검증하지 못함
+    Untrusted,
+    /// 서명은 올바르지만 설정한 신뢰 앵커가 없어 체인을 검증하지 않음
+    NoTrustAnchorsConfigured,
+    /// 서명이 클레임과 맞지 않거나 구조가 잘못됨
+    Invalid,
+    /// 지원하지 않는 서명 알고리즘 (ES512 등)
+    Unsupported,
+}
+
+impl Status {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Valid => "Valid",
+            Self::Untrusted => "Untrusted",
+            Self::NoTrustAnchorsConfigured => "NoTrustAnchorsConfigured",
+            Self::Invalid => "Invalid",
+            Self::Unsupported => "Unsupported",
+        }
+    }
+}
+
+/// PEM 인증서를 신뢰 앵커에 추가하고, 추가한 인증서 수를 돌려줍니다.
+/// 앵커로 쓸 수 없는 인증서는 건너뜁니다.
+pub fn add_trust_anchors(pem: &[u8]) -> usize {
+    let certs: Vec<CertificateDer<'static>> = CertificateDer::pem_slice_iter(pem)
+        .filter_map(Result::ok)
+        .filter(|cert| webpki::anchor_from_trusted_cert(cert).is_ok())
+        .collect();
+    let count = certs.len();
+    TRUST_ANCHORS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .extend(certs);
+    count
+}
+
+/// `claim`(클레임 CBOR)에 대한 `signature`(COSE_Sign1 CBOR)를 검증합니다.
+pub fn verify(claim: &[u8], signature: &[u8]) -> Status {
+    let Some(sign1) = decode(signature) else {
+        return Status::Invalid;
+    };
+    let sign1 = match sign1 {
+        Value::Tag(COSE_SIGN1_TAG, inner) => *inner,
+        other => other,
+    };
+    // [protected, unprotected, payload(분리됨), signature]
+    let Some([Value::Bytes(protected), unprotected, _, Value::Bytes(signature)]) =
+        sign1.as_array().map(Vec::as_slice)
+    else {
+        return Status::Invalid;
+    };
+    let Some(headers) = decode(protected) else {
+        return Status::Invalid;
+    };
+    let Some(alg) = header(&headers, HEADER_ALG).and_then(integer) else {
+        return Status::Invalid;
+    };
+    let Some((algorithm, fixed_ecdsa)) = algorithm(alg) else {
+        return Status::Unsupported;
+    };
+    let chain = header(&headers, HEADER_X5CHAIN)
+        .or_else(|| header(unprotected, HEADER_X5CHAIN))
+        .or_else(|| field(unprotected, "x5chain"))
+        .map(certificates)
+        .unwrap_or_default();
+    let Some((leaf, intermediates)) = chain.split_first() else {
+        return Status::Invalid;
+    };
+    let Ok(leaf_cert) = EndEntityCert::try_from(leaf) else {
+        return Status::Invalid;
+    };
+
+    // Sig_structure = ["Signature1", protected, external_aad, payload]
+    let to_be_signed = Value::Array(vec![
+        Value::Text("Signature1".to_owned()),
+        Value::Bytes(protected.clone()),
+        Value::Bytes(Vec::new()),
+        Value::Bytes(claim.to_vec()),
+    ]);
+    let mut message = Vec::new();
+    if ciborium::ser::into_writer(&to_be_signed, &mut message).is_err() {
+        return Status::Invalid;
+    }
+    // COSE의 ECDSA 서명은 r || s 고정 길이라 webpki가 받는 DER로 바꿉니다.
+    let signature = if fixed_ecdsa {
+        ecdsa_der(signature)
+    } else {
+        signature.clone()
+    };
+    if leaf_cert
+        .verify_signature(algorithm, &message, &signature)
+        .is_err()
+    {
+        return Status::Invalid;
+    }
+
+    let anchors = TRUST_ANCHORS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let anchors: Vec<_> = anchors
+        .iter()
+        .filter_map(|cert| webpki::anchor_from_trusted_cert(cert).ok())
+        .collect();
+    if anchors.is_empty() {
+        return Status::NoTrustAnchorsConfigured;
+    }
+    let trusted = SIGNING_USAGES.iter().any(|&usage| {
+        leaf_cert
+            .verify_for_usage(
+                webpki::ALL_VERIFICATION_ALGS,
+                &anchors,
+                intermediates,
+                UnixTime::now(),
+                KeyUsage::required(usage),
+                None,
+                None,
+            )
+            .is_ok()
+    });
+    if trusted {
+        Status::Valid
+    } else {
+        Status::Untrusted
+    }
+}
+
+/// COSE 알고리즘 번호 -> (webpki 알고리즘, 고정 길이 ECDSA 서명 여부)
+fn algorithm(alg: i64) -> Option<(&'static dyn SignatureVerificationAlgorithm, bool)> {
+    Some(match alg {
+        -7 => (algs::ECDSA_P256_SHA256, true),
+        -35 => (algs::ECDSA_P384_SHA384, true),
+        -37 => (algs::RSA_PSS_2048_8192_SHA256_LEGACY_KEY, false),
+        -38 => (algs::RSA_PSS_2048_8192_SHA384_LEGACY_KEY, false),
+        -39 => (algs::RSA_PSS_2048_8192_SHA512_LEGACY_KEY, false),
+        -8 => (algs::ED25519, false),
+        _ => return None,
+    })
+}
+
+fn header(headers: &Value, label: i64) -> Option<&Value> {
+    headers
+        .as_map()?
+        .iter()
+        .find(|(k, _)| integer(k) == Some(label))
+        .map(|(_, v)| v)
+}
+
+fn integer(value: &Value) -> Option<i64> {
+    i64::try_from(value.as_integer()?).ok()
+}
+
+// x5chain은 인증서 하나(bstr)이거나 배열입니다. 첫 번째가 서명 인증서입니다.
+fn certificates(chain: &Value) -> Vec<CertificateDer<'static>> {
+    match chain {
+        Value::Bytes(cert) => vec![CertificateDer::from(cert.clone())],
+        Value::Array(certs) => certs
+            .iter()
+            .filter_map(Value::as_bytes)
+            .map(|cert| CertificateDer::from(cert.clone()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// r || s -> SEQUENCE { INTEGER r, INTEGER s }
+fn ecdsa_der(signature: &[u8]) -> Vec<u8> {
+    let (r, s) = signature.split_at(signature.len() / 2);
+    let integer = |value: &[u8]| {
+        let start = value.iter().position(|&b| b != 0).unwrap_or(value.len());
+        let mut bytes = value[start..].to_vec();
+        // 최상위 비트가 1이면 음수로 읽히지 않도록 0을 붙입니다.
+        if bytes.first().is_none_or(|&b| b & 0x80 != 0) {
+            bytes.insert(0, 0);
+        }
+        let mut out = vec![0x02];
+        push_length(&mut out, bytes.len());
+        out.extend_from_slice(&bytes);
+        out
+    };
+    let body = [integer(r), integer(s)].concat();
+    let mut out = vec![0x30];
+    push_length(&mut out, body.len());
+    out.extend_from_slice(&body);
+    out
+}
+
+fn push_length(out: &mut Vec<u8>, length: usize) {
+    if length < 0x80 {
+        out.push(length as u8);
+    } else {
+        out.extend_from_slice(&[0x81, length as u8]);
+    }
+}
@@ -0,0 +1,34 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// 메타데이터 추출 중 발생하는 오류
+///
+/// 파일을 읽은 뒤의 손상은 오류가 아니라 [`Report`](super::Report)에 기록됩니다.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("파일을 읽을 수 없습니다: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("아카이브 형식이 아니거나 손상되었습니다")]
+    InvalidArchive,
+    #[error("아카이브에서 {index}번째 항목을 읽을 수 없습니다")]
+    MissingEntry { index: usize },
+}
+
+impl Error {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Io(_) => "io",
+            Self::InvalidArchive => "invalidArchive",
+            Self::MissingEntry { .. } => "missingEntry",
+        }
+    }
+}
+
+// IPC로 전달할 때는 종류와 사람이 읽을 수 있는 메시지를 보냅니다.
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("Error", 2)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
@@ -0,0 +1,744 @@
+//! EXIF(TIFF IFD) 파서
+//!
+//! IFD0 → ExifIFD/GPS → IFD1 순서로 IFD를 따라가며 `EXIF:태그이름` 필드를 만듭니다.
+//! 태그 이름은 ExifTool 표기를 따릅니다. TIFF 파일이면 방문한 IFD를 진단 보고서에 기록합니다.
+
+use super::{iptc, text, xmp, Block, Issue, Metadata, MetadataValue, Provenance, Recovery};
+
+// IFD 안의 하위 IFD 포인터
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+// TIFF 파일에 포함된 XMP 패킷, IPTC 레코드, Photoshop 이미지 리소스
+const TAG_XMP: u16 = 0x02bc;
+const TAG_IPTC: u16 = 0x83bb;
+const TAG_PHOTOSHOP: u16 = 0x8649;
+// 제조사별 비공개 구조이고 내부 오프셋을 신뢰할 수 없으므로 읽지 않습니다.
+const TAG_MAKER_NOTE: u16 = 0x927c;
+
+// 항목 수가 비정상적으로 많은 IFD는 손상된 것으로 봅니다.
+const MAX_IFD_ENTRIES: u16 = 1024;
+// 숫자 배열은 이 개수까지만 문자열로 만듭니다.
+const MAX_NUMERIC_VALUES: usize = 16;
+
+pub fn is_tiff(data: &[u8]) -> bool {
+    data.starts_with(b"II*\0") || data.starts_with(b"MM\0*")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ifd {
+    Ifd0,
+    Exif,
+    Gps,
+    Ifd1,
+}
+
+impl Ifd {
+    /// 진단 보고서의 블록 이름
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ifd0 => "TIFF:IFD0",
+            Self::Exif => "TIFF:ExifIFD",
+            Self::Gps => "TIFF:GPS",
+            Self::Ifd1 => "TIFF:IFD1",
+        }
+    }
+}
+
+/// TIFF 헤더부터 시작하는 EXIF 데이터를 읽어 `metadata`에 추가합니다.
+pub fn read(tiff: &[u8], metadata: &mut Metadata) {
+    if let Some(reader) = Reader::new(tiff) {
+        reader.read(metadata);
+    }
+}
+
+/// TIFF 파일을 읽습니다. [`read`]와 같지만 방문한 IFD를 진단 보고서에 기록합니다.
+/// 다른 형식에 들어 있는 EXIF는 IFD 위치가 파일 위치와 달라 기록하지 않습니다.
+pub fn read_file(data: &[u8], metadata: &mut Metadata) {
+    if let Some(reader) = Reader::new(data) {
+        Reader {
+            report: true,
+            ..reader
+        }
+        .read(metadata);
+    }
+}
+
+/// TIFF 헤더까지의 오프셋(4바이트)이 앞에 붙은 EXIF 데이터를 읽습니다.
+/// HEIF의 `Exif` 아이템과 JPEG XL의 `Exif` 박스가 이 형식입니다.
+pub fn read_with_offset(data: &[u8], metadata: &mut Metadata) {
+    let Some(&[a, b, c, d]) = data.get(..4) else {
+        return;
+    };
+    // 오프셋 뒤에는 보통 `Exif\0\0` 접두사가 있습니다.
+    let skip = u32::from_be_bytes([a, b, c, d]) as usize;
+    if let Some(tiff) = data.get(4 + skip..) {
+        read(tiff, metadata);
+    }
+}
+
+enum Value<'a> {
+    Ascii(&'a [u8]),
+    Unsigned(Vec<u32>),
+    Signed(Vec<i32>),
+    Rational(Vec<(u32, u32)>),
+    SignedRational(Vec<(i32, i32)>),
+    Float(Vec<f64>),
+    Undefined(&'a [u8]),
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+    /// IFD를 진단 보고서에 기록할지 여부
+    report: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let big_endian = match data.get(..4)? {
+            b"II*\0" => false,
+            b"MM\0*" => true,
+            _ => return None,
+        };
+        Some(Self {
+            data,
+            big_endian,
+            report: false,
+        })
+    }
+
+    fn read(&self, metadata: &mut Metadata) {
+        let mut visited = Vec::new();
+        let Some(ifd0) = self.u32(4) else {
+            let block = Block::new("TIFF", 0..self.data.len()).issue(Issue::Truncated {
+                expected: 8,
+                available: self.data.len(),
+            });
+            self.record(block, metadata);
+            return;
+        };
+        let next = self.walk(ifd0, Ifd::Ifd0, metadata, &mut visited);
+        if let Some(ifd1) = next.filter(|&offset| offset != 0) {
+            self.walk(ifd1, Ifd::Ifd1, metadata, &mut visited);
+        }
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset + 2)?;
+        let bytes = [bytes[0], bytes[1]];
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?;
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Option<u64> {
+        let high = self.u32(offset)? as u64;
+        let low = self.u32(offset + 4)? as u64;
+        Some(if self.big_endian {
+            (high << 32) | low
+        } else {
+            (low << 32) | high
+        })
+    }
+
+    /// IFD 하나를 읽고 다음 IFD 오프셋을 돌려줍니다.
+    fn walk(
+        &self,
+        offset: u32,
+        ifd: Ifd,
+        metadata: &mut Metadata,
+        visited: &mut Vec<u32>,
+    ) -> Option<u32> {
+        // 순환 참조 방지
+        if visited.contains(&offset) {
+            return None;
+        }
+        visited.push(offset);
+
+        let start = offset as usize;
+        let len = self.data.len();
+        let Some(count) = self.u16(start) else {
+            let block = Block::new(ifd.name(), start.min(len)..len).issue(Issue::Truncated {
+                expected: 2,
+                available: len.saturating_sub(start),
+            });
+            self.record(block, metadata);
+            return None;
+        };
+        if count > MAX_IFD_ENTRIES {
+            let block = Block::new(ifd.name(), start..start + 2).issue(Issue::InvalidLength {
+                length: count as usize,
+            });
+            self.record(block, metadata);
+            return None;
+        }
+        // 항목 수(2) + 항목(12 * n) + 다음 IFD 오프셋(4)
+        let end = start + 2 + count as usize * 12 + 4;
+        let before = metadata.len();
+        for index in 0..count as usize {
+            let entry = start + 2 + index * 12;
+            let Some(tag) = self.u16(entry) else {
+                break;
+            };
+            match (ifd, tag) {
+                (Ifd::Ifd0, TAG_EXIF_IFD) | (Ifd::Ifd0, TAG_GPS_IFD) => {
+                    let child = if tag == TAG_EXIF_IFD {
+                        Ifd::Exif
+                    } else {
+                        Ifd::Gps
+                    };
+                    if let Some(child_offset) = self.u32(entry + 8) {
+                        self.walk(child_offset, child, metadata, visited);
+                    }
+                }
+                (Ifd::Exif, TAG_MAKER_NOTE) => {}
+                (Ifd::Ifd0, TAG_XMP | TAG_IPTC | TAG_PHOTOSHOP) => {
+                    if let Some((_, _, bytes)) = self.raw(entry) {
+                        match tag {
+                            TAG_XMP => xmp::read(bytes, metadata),
+                            TAG_IPTC => iptc::read(bytes, metadata),
+                            _ => iptc::read_photoshop(bytes, metadata),
+                        }
+                    }
+                }
+                _ => {
+                    if let Some(value) = self.value(entry) {
+                        emit(ifd, tag, value, self.big_endian, metadata);
+                    }
+                }
+            }
+        }
+        let mut block = Block::new(ifd.name(), start..end.min(len));
+        if end > len {
+            block = block.issue(Issue::Truncated {
+                expected: end - start,
+                available: len - start,
+            });
+            if metadata.len() > before {
+                block = block.recovery(Recovery::Partial {
+                    fields: metadata.len() - before,
+                });
+            }
+        }
+        self.record(block, metadata);
+        self.u32(end - 4)
+    }
+
+    fn record(&self, block: Block, metadata: &mut Metadata) {
+        if self.report {
+            metadata.record(block);
+        }
+    }
+
+    /// IFD 항목의 타입, 값 위치, 값 바이트열. 4바이트 이하인 값은 항목 안에 들어 있습니다.
+    fn raw(&self, entry: usize) -> Option<(u16, usize, &'a [u8])> {
+        let kind = self.u16(entry + 2)?;
+        let count = self.u32(entry + 4)? as usize;
+        let size = count.checked_mul(type_size(kind)?)?;
+        let start = if size <= 4 {
+            entry + 8
+        } else {
+            self.u32(entry + 8)? as usize
+        };
+        Some((kind, start, self.data.get(start..start.checked_add(size)?)?))
+    }
+
+    fn value(&self, entry: usize) -> Option<Value<'a>> {
+        let (kind, start, bytes) = self.raw(entry)?;
+        let unit = type_size(kind)?;
+        let offsets = (0..bytes.len() / unit).map(|i| start + i * unit);
+
+        Some(match kind {
+            2 => Value::Ascii(bytes),
+            7 => Value::Undefined(bytes),
+            1 => Value::Unsigned(bytes.iter().map(|&b| b as u32).collect()),
+            6 => Value::Signed(bytes.iter().map(|&b| b as i8 as i32).collect()),
+            3 => Value::Unsigned(offsets.filter_map(|o| self.u16(o)).map(u32::from).collect()),
+            8 => Value::Signed(
+                offsets
+                    .filter_map(|o| self.u16(o))
+                    .map(|v| v as i16 as i32)
+                    .collect(),
+            ),
+            4 | 13 => Value::Unsigned(offsets.filter_map(|o| self.u32(o)).collect()),
+            9 => Value::Signed(
+                offsets
+                    .filter_map(|o| self.u32(o))
+                    .map(|v| v as i32)
+                    .collect(),
+            ),
+            5 => Value::Rational(
+                offsets
+                    .filter_map(|o| Some((self.u32(o)?, self.u32(o + 4)?)))
+                    .collect(),
+            ),
+            10 => Value::SignedRational(
+                offsets
+                    .filter_map(|o| Some((self.u32(o)? as i32, self.u32(o + 4)? as i32)))
+                    .collect(),
+            ),
+            11 => Value::Float(
+                offsets
+                    .filter_map(|o| self.u32(o))
+                    .map(|v| f32::from_bits(v) as f64)
+                    .collect(),
+            ),
+            _ => Value::Float(
+                offsets
+                    .filter_map(|o| self.u64(o))
+                    .map(f64::from_bits)
+                    .collect(),
+            ),
+        })
+    }
+}
+
+fn type_size(kind: u16) -> Option<usize> {
+    match kind {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 | 13 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+fn emit(ifd: Ifd, tag: u16, value: Value, big_endian: bool, metadata: &mut Metadata) {
+    let name = tag_name(ifd, tag);
+    // (표시 문자열, 숫자 값, 문자열 인코딩)
+    let (text, number, encoding) = match (tag, value) {
+        // Windows 탐색기 속성: 항상 UTF-16LE
+        (0x9c9b..=0x9c9f, Value::Unsigned(bytes)) if matches!(ifd, Ifd::Ifd0 | Ifd::Ifd1) => {
+            let bytes: Vec<u8> = bytes.iter().map(|&b| b as u8).collect();
+            (text::utf16(&bytes, false), None, Some(text::UTF16LE))
+        }
+        // UserComment, GPSProcessingMethod, GPSAreaInformation: 8바이트 문자셋 헤더
+        (0x9286, Value::Undefined(bytes)) if ifd == Ifd::Exif => {
+            let (text, encoding) = decode_comment(bytes, big_endian);
+            (text, None, Some(encoding))
+        }
+        (0x001b | 0x001c, Value::Undefined(bytes)) if ifd == Ifd::Gps => {
+            let (text, encoding) = decode_comment(bytes, big_endian);
+            (text, None, Some(encoding))
+        }
+        // ExifVersion, FlashpixVersion, InteropVersion: "0231" 같은 ASCII
+        (0x9000 | 0xa000, Value::Undefined(bytes)) => (text::latin1(bytes), None, None),
+        // 규격은 7비트 ASCII지만 기기와 도구가 UTF-8, CP949, Shift_JIS를 그대로 씁니다.
+        (_, Value::Ascii(bytes)) => {
+            let (text, encoding) = text::decode_legacy(bytes);
+            (text, None, Some(encoding))
+        }
+        (_, value) if name.is_some() => match format_numbers(&value) {
+            Some(text) => (text, numbers(&value), None),
+            None => return,
+        },
+        _ => return,
+    };
+
+    let name = match name {
+        Some(name) => name.to_owned(),
+        None => format!("Tag0x{tag:04X}"),
+    };
+    let key = match ifd {
+        Ifd::Ifd1 => format!("EXIF:IFD1:{name}"),
+        _ => format!("EXIF:{name}"),
+    };
+    let text = text::trim_nul(&text).trim();
+    let provenance = Provenance {
+        encoding: encoding.map(str::to_owned),
+        ..Provenance::default()
+    };
+    metadata.scoped(provenance, |metadata| match number {
+        Some(number) => metadata.insert_typed(key, text, number),
+        None => metadata.insert(key, text),
+    });
+}
+
+/// 숫자 태그의 형식 있는 값. 값이 여럿이면 목록입니다.
+fn numbers(value: &Value) -> Option<MetadataValue> {
+    let rational = |numerator: i64, denominator: i64| MetadataValue::Rational {
+        numerator,
+        denominator,
+    };
+    let mut values: Vec<MetadataValue> = match value {
+        Value::Unsigned(values) => values
+            .iter()
+            .map(|&v| MetadataValue::Number(v.into()))
+            .collect(),
+        Value::Signed(values) => values
+            .iter()
+            .map(|&v| MetadataValue::Number(v.into()))
+            .collect(),
+        Value::Float(values) => values.iter().map(|&v| MetadataValue::Number(v)).collect(),
+        Value::Rational(values) => values
+            .iter()
+            .map(|&(n, d)| rational(n.into(), d.into()))
+            .collect(),
+        Value::SignedRational(values) => values
+            .iter()
+            .map(|&(n, d)| rational(n.into(), d.into()))
+            .collect(),
+        Value::Ascii(_) | Value::Undefined(_) => return None,
+    };
+    match values.len() {
+        0 => None,
+        1 => values.pop(),
+        _ => Some(MetadataValue::List(values)),
+    }
+}
+
+fn format_numbers(value: &Value) -> Option<String> {
+    fn join<T: ToString>(values: impl ExactSizeIterator<Item = T>) -> Option<String> {
+        if values.len() == 0 || values.len() > MAX_NUMERIC_VALUES {
+            return None;
+        }
+        Some(values.map(|v| v.to_string()).collect::<Vec<_>>().join(" "))
+    }
+    fn ratio(numerator: f64, denominator: f64) -> String {
+        if denominator == 0.0 {
+            "undef".to_owned()
+        } else {
+            (numerator / denominator).to_string()
+        }
+    }
+
+    match value {
+        Value::Unsigned(values) => join(values.iter()),
+        Value::Signed(values) => join(values.iter()),
+        Value::Float(values) => join(values.iter()),
+        Value::Rational(values) => join(values.iter().map(|&(n, d)| ratio(n as f64, d as f64))),
+        Value::SignedRational(values) => {
+            join(values.iter().map(|&(n, d)| ratio(n as f64, d as f64)))
+        }
+        Value::Ascii(_) | Value::Undefined(_) => None,
+    }
+}
+
+/// UserComment 형식(8바이트 문자셋 헤더 + 본문)을 디코딩하고, 쓴 인코딩을 함께 돌려줍니다.
+fn decode_comment(data: &[u8], big_endian: bool) -> (String, &'static str) {
+    if data.len() < 8 {
+        return text::decode_legacy(data);
+    }
+    let (header, body) = data.split_at(8);
+    match header {
+        b"UNICODE\0" => {
+            let big_endian = utf16_big_endian(body, big_endian);
+            let encoding = if big_endian {
+                text::UTF16BE
+            } else {
+                text::UTF16LE
+            };
+            (text::utf16(body, big_endian), encoding)
+        }
+        b"JIS\0\0\0\0\0" => decode_jis(body),
+        // ASCII 및 미정의(0으로 채운 헤더). 오래된 국내 기기는 CP949로 기록합니다.
+        _ => text::decode_legacy(body),
+    }
+}
+
+// 규격상 UNICODE 본문은 TIFF 바이트 순서를 따르지만, piexif(A1111, Forge)는
+// 리틀 엔디언 파일에도 UTF-16BE로 기록합니다. ASCII 문자의 0 바이트 위치로 판별합니다.
+fn utf16_big_endian(body: &[u8], tiff_big_endian: bool) -> bool {
+    let zeros_at = |parity: usize| {
+        body.iter()
+            .enumerate()
+            .filter(|&(i, &b)| i % 2 == parity && b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    if even > odd {
+        true
+    } else if odd > even {
+        false
+    } else {
+        tiff_big_endian
+    }
+}
+
+// 규격은 JIS X 0208을 지정하지만 실제로는 Shift_JIS로 기록하는 기기가 대부분입니다.
+fn decode_jis(body: &[u8]) -> (String, &'static str) {
+    let encoding = if body.contains(&0x1b) {
+        encoding_rs::ISO_2022_JP
+    } else {
+        encoding_rs::SHIFT_JIS
+    };
+    let text = encoding.decode_without_bom_handling(body).0.into_owned();
+    (text, encoding.name())
+}
+
+fn tag_name(ifd: Ifd, tag: u16) -> Option<&'static str> {
+    let table = match ifd {
+        Ifd::Ifd0 | Ifd::Ifd1 => IFD0_TAGS,
+        Ifd::Exif => EXIF_TAGS,
+        Ifd::Gps => GPS_TAGS,
+    };
+    table
+        .iter()
+        .find(|&&(id, _)| id == tag)
+        .map(|&(_, name)| name)
+}
+
+const IFD0_TAGS: &[(u16, &str)] = &[
+    (0x0100, "ImageWidth"),
+    (0x0101, "ImageHeight"),
+    (0x0102, "BitsPerSample"),
+    (0x0103, "Compression"),
+    (0x0106, "PhotometricInterpretation"),
+    (0x010d, "DocumentName"),
+    (0x010e, "ImageDescription"),
+    (0x010f, "Make"),
+    (0x0110, "Model"),
+    (0x0112, "Orientation"),
+    (0x0115, "SamplesPerPixel"),
+    (0x011a, "XResolution"),
+    (0x011b, "YResolution"),
+    (0x011d, "PageName"),
+    (0x0128, "ResolutionUnit"),
+    (0x0131, "Software"),
+    (0x0132, "ModifyDate"),
+    (0x013b, "Artist"),
+    (0x013c, "HostComputer"),
+    (0x0201, "ThumbnailOffset"),
+    (0x0202, "ThumbnailLength"),
+    (0x0213, "YCbCrPositioning"),
+    (0x4746, "Rating"),
+    (0x8298, "Copyright"),
+    (0x9c9b, "XPTitle"),
+    (0x9c9c, "XPComment"),
+    (0x9c9d, "XPAuthor"),
+    (0x9c9e, "XPKeywords"),
+    (0x9c9f, "XPSubject"),
+];
+
+const EXIF_TAGS: &[(u16, &str)] = &[
+    (0x829a, "ExposureTime"),
+    (0x829d, "FNumber"),
+    (0x8822, "ExposureProgram"),
+    (0x8827, "ISO"),
+    (0x8830, "SensitivityType"),
+    (0x9000, "ExifVersion"),
+    (0x9003, "DateTimeOriginal"),
+    (0x9004, "CreateDate"),
+    (0x9010, "OffsetTime"),
+    (0x9011, "OffsetTimeOriginal"),
+    (0x9012, "OffsetTimeDigitized"),
+    (0x9201, "ShutterSpeedValue"),
+    (0x9202, "ApertureValue"),
+    (0x9203, "BrightnessValue"),
+    (0x9204, "ExposureCompensation"),
+    (0x9205, "MaxApertureValue"),
+    (0x9206, "SubjectDistance"),
+    (0x9207, "MeteringMode"),
+    (0x9208, "LightSource"),
+    (0x9209, "Flash"),
+    (0x920a, "FocalLength"),
+    (0x9286, "UserComment"),
+    (0x9290, "SubSecTime"),
+    (0x9291, "SubSecTimeOriginal"),
+    (0x9292, "SubSecTimeDigitized"),
+    (0xa000, "FlashpixVersion"),
+    (0xa001, "ColorSpace"),
+    (0xa002, "ExifImageWidth"),
+    (0xa003, "ExifImageHeight"),
+    (0xa004, "RelatedSoundFile"),
+    (0xa20e, "FocalPlaneXResolution"),
+    (0xa20f, "FocalPlaneYResolution"),
+    (0xa210, "FocalPlaneResolutionUnit"),
+    (0xa217, "SensingMethod"),
+    (0xa401, "CustomRendered"),
+    (0xa402, "ExposureMode"),
+    (0xa403, "WhiteBalance"),
+    (0xa404, "DigitalZoomRatio"),
+    (0xa405, "FocalLengthIn35mmFormat"),
+    (0xa406, "SceneCaptureType"),
+    (0xa408, "Contrast"),
+    (0xa409, "Saturation"),
+    (0xa40a, "Sharpness"),
+    (0xa420, "ImageUniqueID"),
+    (0xa430, "OwnerName"),
+    (0xa431, "SerialNumber"),
+    (0xa432, "LensInfo"),
+    (0xa433, "LensMake"),
+    (0xa434, "LensModel"),
+    (0xa435, "LensSerialNumber"),
+];
+
+const GPS_TAGS: &[(u16, &str)] = &[
+    (0x0000, "GPSVersionID"),
+    (0x0001, "GPSLatitudeRef"),
+    (0x0002, "GPSLatitude"),
+    (0x0003, "GPSLongitudeRef"),
+    (0x0004, "GPSLongitude"),
+    (0x0005, "GPSAltitudeRef"),
+    (0x0006, "GPSAltitude"),
+    (0x0007, "GPSTimeStamp"),
+    (0x0008, "GPSSatellites"),
+    (0x0009, "GPSStatus"),
+    (0x000a, "GPSMeasureMode"),
+    (0x0010, "GPSImgDirectionRef"),
+    (0x0011, "GPSImgDirection"),
+    (0x0012, "GPSMapDatum"),
+    (0x001b, "GPSProcessingMethod"),
+    (0x001c, "GPSAreaInformation"),
+    (0x001d, "GPSDateStamp"),
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::testing::{ifd, rational, segment, tiff};
+    use crate::metadata::{extract, Issue, MetadataValue, Recovery};
+
+    const ASCII: u16 = 2;
+    const BYTE: u16 = 1;
+    const LONG: u16 = 4;
+    const RATIONAL: u16 = 5;
+    const UNDEFINED: u16 = 7;
+
+    // IFD0(8) → Exif IFD(200), IFD1(300). IFD1의 다음 IFD는 다시 IFD0을 가리킵니다.
+    fn looping_tiff() -> Vec<u8> {
+        let mut user_comment = b"UNICODE\0".to_vec();
+        for unit in "masterpiece, 1girl".encode_utf16() {
+            user_comment.extend(unit.to_be_bytes());
+        }
+        let mut xp_comment = Vec::new();
+        for unit in "고양이".encode_utf16() {
+            xp_comment.extend(unit.to_le_bytes());
+        }
+        xp_comment.extend([0, 0]);
+
+        let mut out = b"II*\0".to_vec();
+        out.extend(8u32.to_le_bytes());
+        out.extend(ifd(
+            8,
+            &[
+                (0x010e, ASCII, 6, b"hello\0".to_vec()),
+                (0x9c9c, BYTE, xp_comment.len() as u32, xp_comment),
+                (0x8769, LONG, 1, 200u32.to_le_bytes().to_vec()),
+            ],
+            300,
+        ));
+        out.resize(200, 0);
+        out.extend(ifd(
+            200,
+            &[
+                (0x829d, RATIONAL, 1, rational(28, 10)),
+                // MakerNote는 제조사 형식이라 읽지 않습니다.
+                (0x927c, UNDEFINED, 4, b"junk".to_vec()),
+                (0x9286, UNDEFINED, user_comment.len() as u32, user_comment),
+            ],
+            0,
+        ));
+        out.resize(300, 0);
+        out.extend(ifd(300, &[(0x011a, RATIONAL, 1, rational(72, 1))], 8));
+        out
+    }
+
+    #[test]
+    fn exif_in_jpeg() {
+        let tiff = looping_tiff();
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend(&tiff);
+        let mut jpeg = vec![0xff, 0xd8];
+        segment(&mut jpeg, 0xe1, &app1);
+        jpeg.extend([0xff, 0xda, 0, 2, 0xff, 0xd9]);
+
+        let metadata = extract(&jpeg);
+        assert_eq!(metadata.get("EXIF:ImageDescription"), Some("hello"));
+        assert_eq!(metadata.get("EXIF:UserComment"), Some("masterpiece, 1girl"));
+        assert_eq!(metadata.get("EXIF:XPComment"), Some("고양이"));
+        assert_eq!(metadata.get("EXIF:FNumber"), Some("2.8"));
+        assert_eq!(metadata.get("EXIF:IFD1:XResolution"), Some("72"));
+        assert!(metadata.iter().all(|field| !field.key.contains("0x927C")));
+
+        let f_number = metadata.field("EXIF:FNumber").unwrap();
+        assert_eq!(
+            f_number.value,
+            MetadataValue::Rational {
+                numerator: 28,
+                denominator: 10
+            }
+        );
+        assert_eq!(f_number.value.as_number(), Some(2.8));
+        assert_eq!(f_number.source.as_deref(), Some("JPEG:APP1"));
+        // APP1 본문(마커와 길이 뒤)부터 TIFF 끝까지
+        assert_eq!(f_number.byte_range, Some(6..6 + 6 + tiff.len()));
+        assert_eq!(
+            metadata
+                .field("EXIF:UserComment")
+                .unwrap()
+                .encoding
+                .as_deref(),
+            Some("UTF-16BE")
+        );
+        assert_eq!(
+            metadata
+                .field("EXIF:XPComment")
+                .unwrap()
+                .encoding
+                .as_deref(),
+            Some("UTF-16LE")
+        );
+    }
+
+    #[test]
+    fn tiff_file() {
+        let metadata = extract(&looping_tiff());
+        assert_eq!(metadata.get("EXIF:UserComment"), Some("masterpiece, 1girl"));
+        assert_eq!(metadata.get("EXIF:IFD1:XResolution"), Some("72"));
+
+        let metadata = extract(&tiff(&[(0x0131, ASCII, 5, b"Forge\0".to_vec())]));
+        assert_eq!(metadata.get("EXIF:Software"), Some("Forge"));
+    }
+
+    #[test]
+    fn value_outside_file_is_skipped() {
+        // 64바이트 값의 위치가 파일 끝 너머인 4096입니다.
+        let data = tiff(&[
+            (0x010e, ASCII, 64, 4096u32.to_le_bytes().to_vec()),
+            (0x0131, ASCII, 3, b"ok\0".to_vec()),
+        ]);
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("EXIF:ImageDescription"), None);
+        assert_eq!(metadata.get("EXIF:Software"), Some("ok"));
+    }
+
+    #[test]
+    fn truncated_ifd() {
+        let mut data = tiff(&[
+            (0x0131, ASCII, 3, b"ok\0".to_vec()),
+            (0x010e, ASCII, 3, b"hi\0".to_vec()),
+        ]);
+        // 두 번째 항목과 다음 IFD 오프셋이 잘립니다.
+        data.truncate(8 + 2 + 12 + 6);
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("EXIF:Software"), Some("ok"));
+        assert_eq!(metadata.get("EXIF:ImageDescription"), None);
+        let block = &metadata.report().blocks[0];
+        assert_eq!(block.name, "TIFF:IFD0");
+        assert_eq!(block.byte_range, 8..data.len());
+        assert_eq!(
+            block.issue,
+            Some(Issue::Truncated {
+                expected: 30,
+                available: 20
+            })
+        );
+        assert_eq!(block.recovery, Some(Recovery::Partial { fields: 1 }));
+
+        assert!(extract(&tiff(&[(0x0131, ASCII, 3, b"ok\0".to_vec())]))
+            .report()
+            .is_clean());
+    }
+}
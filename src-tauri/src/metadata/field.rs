@@ -0,0 +1,309 @@
+//! 형식 있는 메타데이터 필드
+//!
+//! 필드마다 값의 형식(텍스트, 숫자, 유리수, 목록, JSON)과 출처(청크나 세그먼트,
+//! 파일 안의 바이트 범위, 문자 인코딩)를 함께 기록합니다.
+//! 텍스트 청크에 담긴 객체나 배열 JSON(ComfyUI 그래프 등)은 읽을 때 한 번 해석해 `Json`으로 둡니다.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use serde::Serialize;
+
+/// 필드 값
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum MetadataValue {
+    Text(String),
+    Number(f64),
+    /// EXIF RATIONAL/SRATIONAL (예: FNumber 28/10)
+    Rational {
+        numerator: i64,
+        denominator: i64,
+    },
+    List(Vec<MetadataValue>),
+    Json(serde_json::Value),
+}
+
+impl MetadataValue {
+    /// 숫자로 비교할 수 있는 값. 분모가 0인 유리수는 `None`입니다.
+    pub fn as_number(&self) -> Option<f64> {
+        match *self {
+            Self::Number(number) => Some(number),
+            Self::Rational {
+                numerator,
+                denominator,
+            } if denominator != 0 => Some(numerator as f64 / denominator as f64),
+            _ => None,
+        }
+    }
+
+    /// 객체나 배열 JSON 텍스트면 `Json`, 아니면 `Text`
+    pub fn from_text(text: &str) -> Self {
+        let trimmed = text.trim_start();
+        if trimmed.starts_with(['{', '[']) {
+            if let Ok(json) = serde_json::from_str(trimmed) {
+                return Self::Json(json);
+            }
+        }
+        Self::Text(text.to_owned())
+    }
+
+    /// 텍스트 목록. 표시 문자열은 `, `로 이어 붙입니다.
+    pub fn list(items: Vec<String>) -> (String, Self) {
+        let text = items.join(", ");
+        (
+            text,
+            Self::List(items.into_iter().map(Self::Text).collect()),
+        )
+    }
+
+    // 텍스트 값은 `text`와 같고 JSON 값은 원문이나 안의 문자열이 `text`에 있으므로
+    // IPC로 두 번 보내지 않습니다. (워크플로 JSON은 수백 KB가 되기도 합니다)
+    fn is_in_text(&self) -> bool {
+        matches!(self, Self::Text(_) | Self::Json(_))
+    }
+}
+
+/// 필드를 읽은 위치
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance {
+    /// 청크, 세그먼트 또는 원본 필드 (`PNG:tEXt`, `JPEG:APP1`, `PNG:parameters` 등)
+    pub source: Option<String>,
+    /// 파일 안에서 내용이 차지하는 바이트 범위
+    pub byte_range: Option<Range<usize>>,
+    /// 텍스트를 디코딩한 문자 인코딩
+    pub encoding: Option<String>,
+}
+
+impl Provenance {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: Some(source.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn range(mut self, byte_range: Range<usize>) -> Self {
+        self.byte_range = Some(byte_range);
+        self
+    }
+
+    pub fn encoding(mut self, encoding: impl Into<String>) -> Self {
+        self.encoding = Some(encoding.into());
+        self
+    }
+
+    // 비어 있는 항목은 바깥 출처(컨테이너)를 따릅니다.
+    pub(crate) fn or(self, outer: &Self) -> Self {
+        Self {
+            source: self.source.or_else(|| outer.source.clone()),
+            byte_range: self.byte_range.or_else(|| outer.byte_range.clone()),
+            encoding: self.encoding.or_else(|| outer.encoding.clone()),
+        }
+    }
+}
+
+/// 메타데이터 필드 하나
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataField {
+    /// `PNG:parameters`의 `PNG`
+    pub namespace: String,
+    /// `PNG:parameters`의 `parameters`
+    pub key: String,
+    /// 텍스트와 JSON 값은 `text`에 있으므로 직렬화하지 않습니다.
+    #[serde(skip_serializing_if = "MetadataValue::is_in_text")]
+    pub value: MetadataValue,
+    /// 키워드 검색과 표시에 쓰는 문자열
+    pub text: String,
+    pub source: Option<String>,
+    pub byte_range: Option<Range<usize>>,
+    pub encoding: Option<String>,
+}
+
+impl MetadataField {
+    pub fn new(name: &str, text: String, value: MetadataValue, provenance: Provenance) -> Self {
+        let (namespace, key) = name.split_once(':').unwrap_or(("", name));
+        Self {
+            namespace: namespace.to_owned(),
+            key: key.to_owned(),
+            value,
+            text,
+            source: provenance.source,
+            byte_range: provenance.byte_range,
+            encoding: provenance.encoding,
+        }
+    }
+
+    /// `네임스페이스:이름` 형식의 전체 키
+    pub fn name(&self) -> String {
+        if self.namespace.is_empty() {
+            self.key.clone()
+        } else {
+            format!("{}:{}", self.namespace, self.key)
+        }
+    }
+
+    pub fn is(&self, name: &str) -> bool {
+        match name.split_once(':') {
+            Some((namespace, key)) => self.namespace == namespace && self.key == key,
+            None => self.namespace.is_empty() && self.key == name,
+        }
+    }
+
+    /// 값이 JSON이면 그 값, 텍스트가 객체나 배열 JSON이면 해석한 값
+    pub fn json(&self) -> Option<Cow<'_, serde_json::Value>> {
+        match &self.value {
+            MetadataValue::Json(json) => Some(Cow::Borrowed(json)),
+            MetadataValue::Text(text) if text.trim_start().starts_with(['{', '[']) => {
+                serde_json::from_str(text).ok().map(Cow::Owned)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn provenance(&self) -> Provenance {
+        Provenance {
+            source: self.source.clone(),
+            byte_range: self.byte_range.clone(),
+            encoding: self.encoding.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::testing::{chunk, png};
+    use crate::metadata::{extract, Metadata};
+
+    #[test]
+    fn png_provenance() {
+        let parameters = b"parameters\0a cat\nNegative prompt: blurry\nSteps: 20, Sampler: Euler a, CFG scale: 7, Seed: 1, Size: 512x512";
+        let data = png(&[
+            chunk(b"tEXt", parameters),
+            chunk(
+                b"tEXt",
+                &[
+                    b"prompt\0",
+                    &br#"{"1": {"class_type": "Note", "inputs": {}}}"#[..],
+                ]
+                .concat(),
+            ),
+            chunk(b"tEXt", b"Author\0Jos\xe9"),
+        ]);
+        let metadata = extract(&data);
+
+        let field = metadata.field("PNG:parameters").unwrap();
+        assert_eq!(field.source.as_deref(), Some("PNG:tEXt"));
+        assert_eq!(&data[field.byte_range.clone().unwrap()], parameters);
+        assert_eq!(field.encoding.as_deref(), Some("ISO-8859-1"));
+        assert!(matches!(field.value, MetadataValue::Text(_)));
+        // JSON 텍스트 청크는 읽을 때 해석해 두고, 표시 문자열은 원문 그대로입니다.
+        let prompt = metadata.field("PNG:prompt").unwrap();
+        assert!(
+            matches!(&prompt.value, MetadataValue::Json(json) if json["1"]["class_type"] == "Note")
+        );
+        assert!(matches!(prompt.json(), Some(Cow::Borrowed(_))));
+        assert!(prompt.text.starts_with(r#"{"1": {"class_type""#));
+        assert_eq!(metadata.get("PNG:Author"), Some("José"));
+
+        // 생성 도구 필드는 원본 필드의 위치를 이어받습니다.
+        let prompt = metadata.field("Generation:Prompt").unwrap();
+        assert_eq!(prompt.text, "a cat");
+        assert_eq!(prompt.source.as_deref(), Some("PNG:parameters"));
+        assert_eq!(prompt.byte_range, field.byte_range);
+        assert_eq!(
+            metadata.field("SD:Steps").unwrap().source.as_deref(),
+            Some("PNG:parameters")
+        );
+    }
+
+    #[test]
+    fn insert_overwrites_in_place() {
+        let mut metadata = Metadata::new();
+        metadata.insert("A:x", "1");
+        metadata.insert("A:y", "2");
+        metadata.insert("A:x", "3");
+        metadata.insert("A:z", "  ");
+        assert_eq!(
+            metadata.iter().map(MetadataField::name).collect::<Vec<_>>(),
+            ["A:x", "A:y"]
+        );
+        assert_eq!(metadata.get("A:x"), Some("3"));
+        assert!(metadata.field("A:x").unwrap().source.is_none());
+    }
+
+    #[test]
+    fn scoped_provenance() {
+        let mut metadata = Metadata::new();
+        metadata.scoped(Provenance::new("JPEG:APP1").range(10..20), |metadata| {
+            metadata.scoped(Provenance::default().encoding("UTF-8"), |metadata| {
+                metadata.insert("EXIF:Artist", "me");
+            });
+        });
+        metadata.insert("File:Name", "a.jpg");
+        let artist = metadata.field("EXIF:Artist").unwrap();
+        assert_eq!(
+            artist.provenance(),
+            Provenance::new("JPEG:APP1").range(10..20).encoding("UTF-8")
+        );
+        assert_eq!(
+            metadata.field("File:Name").unwrap().provenance(),
+            Provenance::default()
+        );
+    }
+
+    #[test]
+    fn values() {
+        let text = |text: &str| {
+            MetadataField::new(
+                "A:x",
+                text.into(),
+                MetadataValue::Text(text.into()),
+                Provenance::default(),
+            )
+        };
+        assert_eq!(
+            text(" [1, 2]").json().as_deref(),
+            Some(&serde_json::json!([1, 2]))
+        );
+        assert_eq!(text("{not json").json(), None);
+        assert_eq!(text("12").json(), None);
+        assert_eq!(
+            MetadataValue::from_text(" [1, 2]"),
+            MetadataValue::Json(serde_json::json!([1, 2]))
+        );
+        assert_eq!(
+            MetadataValue::from_text("{not json"),
+            MetadataValue::Text("{not json".into())
+        );
+        // 텍스트 값은 `text`로만 보냅니다.
+        let json = serde_json::to_value(text("cat")).unwrap();
+        assert_eq!(json["text"], "cat");
+        assert!(json.get("value").is_none());
+        // 목록 값은 형식과 함께 보냅니다.
+        let (list_text, list) = MetadataValue::list(vec!["cat".into(), "dog".into()]);
+        assert_eq!(list_text, "cat, dog");
+        let field = MetadataField::new("A:x", list_text, list, Provenance::default());
+        let json = serde_json::to_value(field).unwrap();
+        assert_eq!(json["value"]["type"], "list");
+        assert_eq!(json["value"]["value"][1]["value"], "dog");
+        let rational = |numerator, denominator| MetadataValue::Rational {
+            numerator,
+            denominator,
+        };
+        assert_eq!(rational(28, 10).as_number(), Some(2.8));
+        assert_eq!(rational(1, 0).as_number(), None);
+
+        let field = MetadataField::new(
+            "NoNamespace",
+            "x".into(),
+            MetadataValue::Number(1.0),
+            Provenance::default(),
+        );
+        assert!(field.is("NoNamespace"));
+        assert!(!field.is(":NoNamespace:"));
+        assert_eq!(field.name(), "NoNamespace");
+    }
+}
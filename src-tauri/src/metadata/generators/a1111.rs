@@ -0,0 +1,268 @@
+//! AUTOMATIC1111 / Forge `parameters` 텍스트 파서
+//!
+//! ```text
+//! 프롬프트 (여러 줄 가능)
+//! Negative prompt: 네거티브 프롬프트 (여러 줄 가능)
+//! Steps: 20, Sampler: Euler a, CFG scale: 7, Seed: 1, Size: 512x768, Lora hashes: "a: 1, b: 2"
+//! ```
+//!
+//! 마지막 줄이 `키: 값` 쌍을 3개 이상 가지면 설정 줄로 봅니다 (webui와 같은 기준).
+
+use super::Generation;
+use crate::metadata::Metadata;
+
+const NEGATIVE_PROMPT: &str = "Negative prompt:";
+
+// 설정 줄로 인정하는 최소 `키: 값` 쌍 수
+const MIN_SETTINGS: usize = 3;
+
+const FIELDS: &[(&str, &str)] = &[
+    ("Steps", "SD:Steps"),
+    ("Sampler", "SD:Sampler"),
+    ("Schedule type", "SD:Schedule"),
+    ("CFG scale", "SD:CFG"),
+    ("Seed", "SD:Seed"),
+    ("Size", "SD:Size"),
+    ("Model hash", "SD:ModelHash"),
+    ("Model", "SD:Model"),
+    ("Lora hashes", "SD:LoraHashes"),
+];
+
+// 이 단어로 시작하는 설정은 `SD:HiresUpscaler`처럼 이어 붙인 이름으로 추가합니다.
+const PREFIXED_FIELDS: &[&str] = &["Hires", "ADetailer"];
+
+/// 해석된 생성 파라미터
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Parameters {
+    pub prompt: String,
+    pub negative_prompt: String,
+    /// 설정 줄의 `키: 값` 쌍 (따옴표는 벗긴 값)
+    pub settings: Vec<(String, String)>,
+}
+
+impl Parameters {
+    pub fn setting(&self, key: &str) -> Option<&str> {
+        self.settings
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// `SD:*` 필드를 `metadata`에 추가합니다.
+    pub fn write(&self, metadata: &mut Metadata) {
+        metadata.insert("SD:Prompt", self.prompt.as_str());
+        metadata.insert("SD:NegativePrompt", self.negative_prompt.as_str());
+        for (key, value) in &self.settings {
+            if let Some(&(_, field)) = FIELDS.iter().find(|(name, _)| name == key) {
+                metadata.insert(field, value.as_str());
+            } else if PREFIXED_FIELDS
+                .iter()
+                .any(|prefix| key.split_whitespace().next() == Some(prefix))
+            {
+                metadata.insert(format!("SD:{}", pascal_case(key)), value.as_str());
+            }
+        }
+    }
+
+    pub fn generation(&self) -> Generation {
+        let setting = |key| self.setting(key).unwrap_or_default().to_owned();
+        Generation {
+            prompt: self.prompt.clone(),
+            negative: self.negative_prompt.clone(),
+            model: setting("Model"),
+            seed: setting("Seed"),
+            sampler: setting("Sampler"),
+            steps: setting("Steps"),
+            cfg: setting("CFG scale"),
+        }
+    }
+}
+
+/// A1111 형식이면 프롬프트, 네거티브 프롬프트, 설정으로 나눕니다.
+/// 설정 줄이 없으면 다른 도구의 텍스트로 보고 `None`을 돌려줍니다.
+pub fn parse(text: &str) -> Option<Parameters> {
+    let text = text.trim();
+    let (body, last_line) = match text.rsplit_once('\n') {
+        Some((body, last_line)) => (body, last_line),
+        None => ("", text),
+    };
+    let settings = parse_settings(last_line);
+    if settings.len() < MIN_SETTINGS {
+        return None;
+    }
+
+    let mut parameters = Parameters {
+        settings,
+        ..Parameters::default()
+    };
+    let mut in_negative = false;
+    for line in body.lines().map(str::trim) {
+        let line = match line.strip_prefix(NEGATIVE_PROMPT) {
+            Some(rest) if !in_negative => {
+                in_negative = true;
+                rest.trim()
+            }
+            _ => line,
+        };
+        let target = if in_negative {
+            &mut parameters.negative_prompt
+        } else {
+            &mut parameters.prompt
+        };
+        if !target.is_empty() {
+            target.push('\n');
+        }
+        target.push_str(line);
+    }
+    Some(parameters)
+}
+
+/// 설정 줄을 `키: 값` 쌍으로 나눕니다. 큰따옴표로 감싼 값은 쉼표를 포함할 수 있습니다.
+pub fn parse_settings(line: &str) -> Vec<(String, String)> {
+    let mut settings = Vec::new();
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        let Some((key, after)) = rest.split_once(':') else {
+            break;
+        };
+        if !is_key(key.trim_start()) {
+            // 키가 아닌 조각은 다음 쉼표까지 건너뜁니다.
+            match rest.split_once(',') {
+                Some((_, next)) => rest = next.trim_start(),
+                None => break,
+            }
+            continue;
+        }
+        let after = after.trim_start();
+        let (value, next) = match after.strip_prefix('"') {
+            Some(quoted) => split_quoted(quoted),
+            None => {
+                let (value, next) = after.split_once(',').unwrap_or((after, ""));
+                (value.to_owned(), next)
+            }
+        };
+        settings.push((key.trim().to_owned(), value.trim().to_owned()));
+        rest = next.trim_start();
+    }
+    settings
+}
+
+// webui의 `\w[\w \-/]+`와 같은 규칙
+fn is_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    chars.next().is_some_and(is_word)
+        && key.chars().count() >= 2
+        && chars.all(|c| is_word(c) || matches!(c, ' ' | '-' | '/'))
+}
+
+/// 여는 따옴표 다음부터 닫는 따옴표까지를 값으로 돌려주고, 뒤의 쉼표 다음부터를 나머지로 돌려줍니다.
+fn split_quoted(quoted: &str) -> (String, &str) {
+    let mut escaped = false;
+    let end = quoted.char_indices().find_map(|(i, c)| {
+        let found = c == '"' && !escaped;
+        escaped = c == '\\' && !escaped;
+        found.then_some(i)
+    });
+    let Some(end) = end else {
+        return (quoted.to_owned(), "");
+    };
+    // webui는 JSON 문자열 규칙으로 따옴표를 씌웁니다.
+    let literal = &quoted[..end];
+    let value = serde_json::from_str::<String>(&format!("\"{literal}\""))
+        .unwrap_or_else(|_| literal.to_owned());
+    let rest = &quoted[end + 1..];
+    let rest = rest.split_once(',').map_or("", |(_, next)| next);
+    (value, rest)
+}
+
+// "Hires upscaler" -> "HiresUpscaler"
+fn pascal_case(key: &str) -> String {
+    key.split_whitespace()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::generators;
+
+    const TEXT: &str = "masterpiece, 1girl,\n(red hair:1.2), <lora:foo:0.8>\nNegative prompt: lowres, bad hands\nworst quality\nSteps: 28, Sampler: DPM++ 2M, Schedule type: Karras, CFG scale: 7, Seed: 12345, Size: 832x1216, Model hash: abcdef12, Model: animagine, Denoising strength: 0.4, Hires upscale: 1.5, Hires upscaler: R-ESRGAN 4x+, ADetailer model: face_yolov8n.pt, ADetailer prompt: \"smile, \\\"blue\\\" eyes\", Lora hashes: \"foo: 1234, bar: 5678\", Version: v1.9.0";
+
+    #[test]
+    fn prompts_and_settings() {
+        let parameters = parse(TEXT).unwrap();
+        assert_eq!(
+            parameters.prompt,
+            "masterpiece, 1girl,\n(red hair:1.2), <lora:foo:0.8>"
+        );
+        assert_eq!(
+            parameters.negative_prompt,
+            "lowres, bad hands\nworst quality"
+        );
+        assert_eq!(parameters.setting("Sampler"), Some("DPM++ 2M"));
+        assert_eq!(
+            parameters.setting("Lora hashes"),
+            Some("foo: 1234, bar: 5678")
+        );
+        assert_eq!(
+            parameters.setting("ADetailer prompt"),
+            Some("smile, \"blue\" eyes")
+        );
+        assert_eq!(parameters.setting("Version"), Some("v1.9.0"));
+    }
+
+    #[test]
+    fn sd_fields() {
+        let mut metadata = Metadata::new();
+        metadata.insert("PNG:parameters", TEXT);
+        generators::read(&mut metadata);
+        assert_eq!(metadata.get("SD:Steps"), Some("28"));
+        assert_eq!(metadata.get("SD:Schedule"), Some("Karras"));
+        assert_eq!(metadata.get("SD:CFG"), Some("7"));
+        assert_eq!(metadata.get("SD:Size"), Some("832x1216"));
+        assert_eq!(metadata.get("SD:ModelHash"), Some("abcdef12"));
+        assert_eq!(metadata.get("SD:Model"), Some("animagine"));
+        assert_eq!(metadata.get("SD:HiresUpscaler"), Some("R-ESRGAN 4x+"));
+        assert_eq!(metadata.get("SD:ADetailerModel"), Some("face_yolov8n.pt"));
+        assert_eq!(metadata.get("SD:LoraHashes"), Some("foo: 1234, bar: 5678"));
+        assert_eq!(metadata.get("SD:Version"), None);
+    }
+
+    #[test]
+    fn missing_parts() {
+        let parameters = parse("a cat\nSteps: 20, Sampler: Euler, Seed: 1").unwrap();
+        assert_eq!(parameters.prompt, "a cat");
+        assert_eq!(parameters.negative_prompt, "");
+
+        let parameters = parse("Steps: 20, Sampler: Euler, Seed: 1").unwrap();
+        assert_eq!(parameters.prompt, "");
+        assert_eq!(parameters.setting("Seed"), Some("1"));
+    }
+
+    #[test]
+    fn not_a1111() {
+        assert!(parse(r#"{"prompt": "x", "steps": 20}"#).is_none());
+        assert!(parse("just a caption").is_none());
+    }
+
+    #[test]
+    fn settings_line() {
+        assert_eq!(
+            parse_settings("Steps: 20, (weird: 1.2), Size: 512x512, Note: \"a, b\""),
+            [
+                ("Steps".to_owned(), "20".to_owned()),
+                ("Size".to_owned(), "512x512".to_owned()),
+                ("Note".to_owned(), "a, b".to_owned()),
+            ]
+        );
+        assert_eq!(pascal_case("Hires upscaler"), "HiresUpscaler");
+    }
+}
@@ -0,0 +1,337 @@
+//! ComfyUI `prompt`(API 형식 그래프) 해석
+//!
+//! ```json
+//! { "3": { "class_type": "KSampler",
+//!          "inputs": { "seed": 1, "positive": ["6", 0], "negative": ["7", 0], ... } },
+//!   "6": { "class_type": "CLIPTextEncode", "inputs": { "text": "...", "clip": ["4", 1] } } }
+//! ```
+//!
+//! 샘플러 노드의 `positive`/`negative` 링크를 텍스트 인코더까지 따라가고,
+//! 그 사이의 문자열 노드(프리미티브, 문자열 연결, 와일드카드 처리)를 최종 텍스트로 풉니다.
+//! 노드 제목이나 `workflow`의 위젯 기본값은 보지 않습니다.
+
+use std::cell::Cell;
+
+use serde_json::{Map, Value};
+
+use super::Generation;
+use crate::metadata::Metadata;
+
+// 링크를 따라가는 최대 깊이와 전체 방문 횟수 (순환 그래프 방지)
+const MAX_DEPTH: usize = 32;
+const MAX_VISITS: usize = 10_000;
+
+// 텍스트를 담는 입력 이름 (앞쪽 우선)
+const TEXT_INPUTS: &[&str] = &[
+    "text", "text_g", "t5xxl", "clip_l", "text_l", "prompt", "string", "value",
+];
+// 와일드카드 노드는 채워진 결과를 먼저 봅니다.
+const WILDCARD_INPUTS: &[&str] = &["populated_text", "wildcard_text", "text"];
+const DELIMITER_INPUTS: &[&str] = &["delimiter", "separator"];
+const SEED_INPUTS: &[&str] = &["seed", "noise_seed", "value"];
+const SAMPLER_INPUTS: &[&str] = &["sampler_name"];
+const CHECKPOINT_INPUTS: &[&str] = &["ckpt_name", "unet_name", "model_name"];
+
+/// 해석된 생성 파라미터
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Parameters {
+    pub positive: String,
+    pub negative: String,
+    pub checkpoint: String,
+    pub seed: String,
+    pub sampler: String,
+}
+
+impl Parameters {
+    /// `Comfy:*` 필드를 `metadata`에 추가합니다.
+    pub fn write(&self, metadata: &mut Metadata) {
+        metadata.insert("Comfy:Positive", self.positive.as_str());
+        metadata.insert("Comfy:Negative", self.negative.as_str());
+        metadata.insert("Comfy:Checkpoint", self.checkpoint.as_str());
+        metadata.insert("Comfy:Seed", self.seed.as_str());
+        metadata.insert("Comfy:Sampler", self.sampler.as_str());
+    }
+
+    pub fn generation(&self) -> Generation {
+        Generation {
+            prompt: self.positive.clone(),
+            negative: self.negative.clone(),
+            model: self.checkpoint.clone(),
+            seed: self.seed.clone(),
+            sampler: self.sampler.clone(),
+            ..Generation::default()
+        }
+    }
+}
+
+/// API 형식 그래프를 해석합니다. 샘플러가 여럿이면 노드 번호가 가장 작은
+/// (보통 첫 패스의) 샘플러를 씁니다.
+pub fn parse(text: &str) -> Option<Parameters> {
+    let Value::Object(nodes) = serde_json::from_str(text).ok()? else {
+        return None;
+    };
+    let graph = Graph {
+        nodes: &nodes,
+        visits: Cell::new(0),
+    };
+    let mut ids: Vec<&str> = nodes.keys().map(String::as_str).collect();
+    ids.sort_by_key(|id| node_order(id));
+    let mut parameters = ids
+        .iter()
+        .filter_map(|id| graph.sampler(id))
+        .find(|parameters| !parameters.positive.is_empty())?;
+    if parameters.checkpoint.is_empty() {
+        // 모델 링크를 따라가지 못하면 그래프의 첫 체크포인트 로더를 씁니다.
+        parameters.checkpoint = ids
+            .iter()
+            .filter_map(|id| graph.inputs(id))
+            .find_map(|inputs| graph.scalar(inputs.get("ckpt_name")?, CHECKPOINT_INPUTS, 0))
+            .unwrap_or_default();
+    }
+    Some(parameters)
+}
+
+struct Graph<'a> {
+    nodes: &'a Map<String, Value>,
+    visits: Cell<usize>,
+}
+
+impl<'a> Graph<'a> {
+    fn inputs(&self, id: &str) -> Option<&'a Map<String, Value>> {
+        self.nodes.get(id)?.get("inputs")?.as_object()
+    }
+
+    /// 링크를 따라갈 수 있으면 (노드 id, 출력 번호)를 돌려주고 방문 횟수를 셉니다.
+    fn follow<'v>(&self, value: &'v Value, depth: usize) -> Option<(&'v str, u64)> {
+        let visits = self.visits.get();
+        if depth >= MAX_DEPTH || visits >= MAX_VISITS {
+            return None;
+        }
+        let target = link(value)?;
+        self.visits.set(visits + 1);
+        Some(target)
+    }
+
+    fn class_type(&self, id: &str) -> &'a str {
+        self.nodes
+            .get(id)
+            .and_then(|node| node.get("class_type"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+
+    /// 샘플러 노드(KSampler 계열, SamplerCustom, SamplerCustomAdvanced)에서 파라미터를 읽습니다.
+    fn sampler(&self, id: &str) -> Option<Parameters> {
+        let inputs = self.inputs(id)?;
+        // SamplerCustomAdvanced는 프롬프트와 모델을 가이더 노드(CFGGuider 등)로 받습니다.
+        let guider = match inputs.get("guider").and_then(link) {
+            Some((guider, _)) => self.inputs(guider)?,
+            None if ["seed", "noise_seed"]
+                .iter()
+                .any(|k| inputs.contains_key(*k)) =>
+            {
+                inputs
+            }
+            None => return None,
+        };
+        let positive = guider
+            .get("positive")
+            .or_else(|| guider.get("conditioning"))?;
+        let negative = guider.get("negative");
+
+        Some(Parameters {
+            positive: self.conditioning(positive, 0).join("\n"),
+            negative: negative
+                .map(|value| self.conditioning(value, 0).join("\n"))
+                .unwrap_or_default(),
+            checkpoint: guider
+                .get("model")
+                .and_then(|value| self.checkpoint(value, 0))
+                .unwrap_or_default(),
+            seed: ["seed", "noise_seed", "noise"]
+                .iter()
+                .find_map(|k| self.scalar(inputs.get(*k)?, SEED_INPUTS, 0))
+                .unwrap_or_default(),
+            sampler: ["sampler_name", "sampler"]
+                .iter()
+                .find_map(|k| self.scalar(inputs.get(*k)?, SAMPLER_INPUTS, 0))
+                .unwrap_or_default(),
+        })
+    }
+
+    /// 컨디셔닝 링크를 텍스트 인코더까지 따라가 프롬프트 텍스트를 모읍니다.
+    fn conditioning(&self, value: &Value, depth: usize) -> Vec<String> {
+        let Some((id, slot)) = self.follow(value, depth) else {
+            return Vec::new();
+        };
+        let Some(inputs) = self.inputs(id) else {
+            return Vec::new();
+        };
+        // ControlNetApplyAdvanced 등: 출력 0은 positive, 1은 negative를 그대로 넘깁니다.
+        if inputs.contains_key("positive") && inputs.contains_key("negative") {
+            let key = if slot == 0 { "positive" } else { "negative" };
+            return self.conditioning(&inputs[key], depth + 1);
+        }
+        if let Some(text) = self.text_node(id, depth) {
+            return vec![text];
+        }
+        // ConditioningCombine, ConditioningSetArea 등
+        let mut texts: Vec<String> = Vec::new();
+        for (_, value) in inputs.iter().filter(|(k, _)| k.starts_with("conditioning")) {
+            for text in self.conditioning(value, depth + 1) {
+                if !texts.contains(&text) {
+                    texts.push(text);
+                }
+            }
+        }
+        texts
+    }
+
+    /// 입력값을 텍스트로 풉니다. 링크면 연결된 노드의 출력 텍스트입니다.
+    fn text(&self, value: &Value, depth: usize) -> Option<String> {
+        match value {
+            Value::String(text) => Some(text.clone()),
+            Value::Number(number) => Some(number.to_string()),
+            _ => {
+                let (id, _) = self.follow(value, depth)?;
+                self.text_node(id, depth + 1)
+            }
+        }
+    }
+
+    fn text_node(&self, id: &str, depth: usize) -> Option<String> {
+        let inputs = self.inputs(id)?;
+        let class_type = self.class_type(id);
+
+        // ImpactWildcardProcessor, ImpactWildcardEncode
+        if class_type.contains("Wildcard") {
+            return WILDCARD_INPUTS
+                .iter()
+                .filter_map(|k| self.text(inputs.get(*k)?, depth))
+                .find(|text| !text.trim().is_empty());
+        }
+        // StringConcatenate, Text Concatenate, JoinStrings, CR Text Concatenate 등
+        if class_type.to_ascii_lowercase().contains("concat")
+            && !class_type.contains("Conditioning")
+        {
+            let delimiter = DELIMITER_INPUTS
+                .iter()
+                .find_map(|k| inputs.get(*k)?.as_str())
+                .unwrap_or_default();
+            let parts: Vec<String> = inputs
+                .iter()
+                .filter(|(k, _)| k.starts_with("text") || k.starts_with("string"))
+                .filter_map(|(_, value)| self.text(value, depth))
+                .filter(|text| !text.is_empty())
+                .collect();
+            return (!parts.is_empty()).then(|| parts.join(delimiter));
+        }
+        TEXT_INPUTS
+            .iter()
+            .find_map(|k| self.text(inputs.get(*k)?, depth))
+    }
+
+    /// 시드, 샘플러 이름처럼 값 하나를 담는 입력을 풉니다.
+    /// 링크면 연결된 노드에서 `keys` 중 처음 있는 입력을 따라갑니다.
+    fn scalar(&self, value: &Value, keys: &[&str], depth: usize) -> Option<String> {
+        match value {
+            Value::String(text) => Some(text.clone()),
+            Value::Number(number) => Some(number.to_string()),
+            _ => {
+                let (id, _) = self.follow(value, depth)?;
+                let inputs = self.inputs(id)?;
+                keys.iter()
+                    .find_map(|k| self.scalar(inputs.get(*k)?, keys, depth + 1))
+            }
+        }
+    }
+
+    /// 모델 링크를 LoRA 로더 등을 거슬러 체크포인트 로더까지 따라갑니다.
+    fn checkpoint(&self, value: &Value, depth: usize) -> Option<String> {
+        let (id, _) = self.follow(value, depth)?;
+        let inputs = self.inputs(id)?;
+        CHECKPOINT_INPUTS
+            .iter()
+            .find_map(|k| self.scalar(inputs.get(*k)?, CHECKPOINT_INPUTS, depth + 1))
+            .or_else(|| self.checkpoint(inputs.get("model")?, depth + 1))
+    }
+}
+
+/// 링크 입력 `["노드 id", 출력 번호]`
+fn link(value: &Value) -> Option<(&str, u64)> {
+    let [id, slot] = value.as_array()?.as_slice() else {
+        return None;
+    };
+    Some((id.as_str()?, slot.as_u64()?))
+}
+
+// 노드 id는 보통 숫자 문자열입니다 ("3", "10", 그룹 노드는 "12:5").
+fn node_order(id: &str) -> (u64, &str) {
+    let digits = id.find(|c: char| !c.is_ascii_digit()).unwrap_or(id.len());
+    (id[..digits].parse().unwrap_or(u64::MAX), id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::generators;
+
+    #[test]
+    fn ksampler_with_string_nodes() {
+        let graph = r#"{
+          "3": {"class_type":"KSampler","inputs":{"seed":123456789,"steps":20,"cfg":7,"sampler_name":"euler_ancestral","scheduler":"normal","denoise":1,"model":["10",0],"positive":["6",0],"negative":["7",0],"latent_image":["5",0]}},
+          "4": {"class_type":"CheckpointLoaderSimple","inputs":{"ckpt_name":"animagine-xl.safetensors"}},
+          "10": {"class_type":"LoraLoader","inputs":{"lora_name":"x.safetensors","model":["4",0],"clip":["4",1]}},
+          "6": {"class_type":"CLIPTextEncode","inputs":{"text":["12",0],"clip":["10",1]}},
+          "7": {"class_type":"CLIPTextEncode","inputs":{"text":"lowres, bad anatomy","clip":["10",1]}},
+          "11": {"class_type":"PrimitiveStringMultiline","inputs":{"value":"1girl, white background"}},
+          "13": {"class_type":"ImpactWildcardProcessor","inputs":{"wildcard_text":"__hair__","populated_text":"red hair","mode":"populate","seed":1}},
+          "12": {"class_type":"StringConcatenate","inputs":{"string_a":["11",0],"string_b":["13",0],"delimiter":", "}}
+        }"#;
+        let parameters = parse(graph).unwrap();
+        assert_eq!(parameters.positive, "1girl, white background, red hair");
+        assert_eq!(parameters.negative, "lowres, bad anatomy");
+        assert_eq!(parameters.checkpoint, "animagine-xl.safetensors");
+        assert_eq!(parameters.seed, "123456789");
+        assert_eq!(parameters.sampler, "euler_ancestral");
+
+        let mut metadata = Metadata::new();
+        metadata.insert("PNG:prompt", graph);
+        generators::read(&mut metadata);
+        assert_eq!(
+            metadata.get("Comfy:Positive"),
+            Some("1girl, white background, red hair")
+        );
+        assert_eq!(metadata.get("Comfy:Seed"), Some("123456789"));
+    }
+
+    #[test]
+    fn custom_advanced_and_controlnet() {
+        let graph = r#"{
+          "1": {"class_type":"UNETLoader","inputs":{"unet_name":"flux1-dev.safetensors"}},
+          "2": {"class_type":"CLIPTextEncode","inputs":{"text":"a cat","clip":["9",0]}},
+          "3": {"class_type":"CLIPTextEncode","inputs":{"text":"blurry","clip":["9",0]}},
+          "4": {"class_type":"ControlNetApplyAdvanced","inputs":{"positive":["2",0],"negative":["3",0],"strength":1}},
+          "5": {"class_type":"CFGGuider","inputs":{"model":["1",0],"positive":["4",0],"negative":["4",1],"cfg":3.5}},
+          "6": {"class_type":"RandomNoise","inputs":{"noise_seed":42}},
+          "7": {"class_type":"KSamplerSelect","inputs":{"sampler_name":"dpmpp_2m"}},
+          "8": {"class_type":"SamplerCustomAdvanced","inputs":{"noise":["6",0],"guider":["5",0],"sampler":["7",0],"sigmas":["x",0],"latent_image":["y",0]}}
+        }"#;
+        let parameters = parse(graph).unwrap();
+        assert_eq!(parameters.positive, "a cat");
+        assert_eq!(parameters.negative, "blurry");
+        assert_eq!(parameters.checkpoint, "flux1-dev.safetensors");
+        assert_eq!(parameters.seed, "42");
+        assert_eq!(parameters.sampler, "dpmpp_2m");
+    }
+
+    #[test]
+    fn cycles_and_invalid_json() {
+        assert!(parse("not json").is_none());
+        assert!(parse("[1,2]").is_none());
+        // 순환하는 링크는 끝까지 따라가지 않습니다.
+        let graph = r#"{"1":{"class_type":"KSampler","inputs":{"seed":1,"positive":["2",0],"negative":["2",0]}},
+                    "2":{"class_type":"ConditioningCombine","inputs":{"conditioning_1":["2",0],"conditioning_2":["1",0]}}}"#;
+        assert!(parse(graph).is_none());
+    }
+}
@@ -0,0 +1,91 @@
+//! Easy Diffusion 생성 정보 해석
+//!
+//! PNG는 필드마다 텍스트 청크를 하나씩 쓰고(`prompt`, `seed`, ...),
+//! JPEG/WebP는 같은 필드를 JSON으로 묶어 EXIF UserComment에 씁니다.
+//! 구버전은 `Prompt`, `Stable Diffusion model`처럼 사람이 읽는 이름을 씁니다.
+
+use serde_json::Value;
+
+use super::{string, Generation};
+
+// (현재 이름, 구버전 이름)
+const PROMPT: (&str, &str) = ("prompt", "Prompt");
+const NEGATIVE: (&str, &str) = ("negative_prompt", "Negative Prompt");
+pub const MODEL: (&str, &str) = ("use_stable_diffusion_model", "Stable Diffusion model");
+const SEED: (&str, &str) = ("seed", "Seed");
+const SAMPLER: (&str, &str) = ("sampler_name", "Sampler");
+const STEPS: (&str, &str) = ("num_inference_steps", "Steps");
+const CFG: (&str, &str) = ("guidance_scale", "Guidance Scale");
+
+/// 필드 이름으로 값을 찾는 `get`으로 해석합니다. 모델 필드가 없으면 `None`입니다.
+pub fn parse(get: impl Fn(&str) -> Option<String>) -> Option<Generation> {
+    let field = |(name, legacy): (&str, &str)| get(name).or_else(|| get(legacy));
+    let model = field(MODEL)?;
+    Some(Generation {
+        prompt: field(PROMPT).unwrap_or_default(),
+        negative: field(NEGATIVE).unwrap_or_default(),
+        model,
+        seed: field(SEED).unwrap_or_default(),
+        sampler: field(SAMPLER).unwrap_or_default(),
+        steps: field(STEPS).unwrap_or_default(),
+        cfg: field(CFG).unwrap_or_default(),
+    })
+}
+
+/// EXIF UserComment의 JSON을 해석합니다.
+pub fn parse_json(text: &str) -> Option<Generation> {
+    let Value::Object(fields) = serde_json::from_str(text).ok()? else {
+        return None;
+    };
+    parse(|name| fields.get(name).map(|value| string(Some(value))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup<'a>(fields: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        |name| {
+            fields
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn field_names() {
+        let generation = parse(lookup(&[
+            ("prompt", "a boat"),
+            ("negative_prompt", "fog"),
+            ("use_stable_diffusion_model", "sd-v1-4"),
+            ("seed", "99"),
+        ]))
+        .unwrap();
+        assert_eq!(generation.prompt, "a boat");
+        assert_eq!(generation.negative, "fog");
+        assert_eq!(generation.model, "sd-v1-4");
+        assert_eq!(generation.seed, "99");
+
+        let legacy = parse(lookup(&[
+            ("Prompt", "old"),
+            ("Stable Diffusion model", "sd14"),
+        ]));
+        assert_eq!(
+            legacy.map(|generation| generation.model),
+            Some("sd14".to_owned())
+        );
+
+        // 모델 필드가 없으면 다른 도구의 `prompt` 청크일 수 있습니다.
+        assert!(parse(lookup(&[("prompt", "a boat")])).is_none());
+    }
+
+    #[test]
+    fn user_comment_json() {
+        let generation =
+            parse_json(r#"{"prompt":"a boat","use_stable_diffusion_model":"sd-v1-4","num_inference_steps":25}"#)
+                .unwrap();
+        assert_eq!(generation.steps, "25");
+        assert!(parse_json("[1]").is_none());
+    }
+}
@@ -0,0 +1,59 @@
+//! Fooocus 생성 정보 해석
+//!
+//! `fooocus_scheme` 청크가 `fooocus`이면 `parameters` 청크(JPEG/WebP는 EXIF UserComment)에
+//! JSON이 들어 있습니다. `a1111`이면 A1111 형식 텍스트라 A1111 파서가 읽습니다.
+
+use serde_json::Value;
+
+use super::{string, Generation};
+
+const SCHEME: &str = "fooocus";
+
+/// `parameters` JSON을 해석합니다. `scheme`은 같은 파일의 `fooocus_scheme` 값입니다.
+pub fn parse(text: &str, scheme: Option<&str>) -> Option<Generation> {
+    let Value::Object(fields) = serde_json::from_str(text).ok()? else {
+        return None;
+    };
+    // 스킴 청크가 없는 파일(JPEG 등)은 Fooocus만 쓰는 필드로 판별합니다.
+    let is_fooocus = scheme.is_some_and(|scheme| scheme.trim() == SCHEME)
+        || fields.contains_key("full_prompt")
+        || fields
+            .get("version")
+            .and_then(Value::as_str)
+            .is_some_and(|version| version.starts_with("Fooocus"));
+    if !is_fooocus {
+        return None;
+    }
+    Some(Generation {
+        prompt: string(fields.get("prompt")),
+        negative: string(fields.get("negative_prompt")),
+        model: string(fields.get("base_model")),
+        seed: string(fields.get("seed")),
+        sampler: string(fields.get("sampler")),
+        steps: string(fields.get("steps")),
+        cfg: string(fields.get("guidance_scale")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMETERS: &str = r#"{"prompt":"castle","negative_prompt":"bad","base_model":"juggernautXL","seed":"123","sampler":"dpmpp_2m_sde_gpu","steps":30,"guidance_scale":4,"version":"Fooocus v2.5.5"}"#;
+
+    #[test]
+    fn scheme_or_version() {
+        let generation = parse(PARAMETERS, Some("fooocus")).unwrap();
+        assert_eq!(generation.prompt, "castle");
+        assert_eq!(generation.model, "juggernautXL");
+        assert_eq!(generation.seed, "123");
+        // 스킴 청크가 없어도 version 필드로 판별합니다.
+        assert_eq!(parse(PARAMETERS, None), Some(generation));
+    }
+
+    #[test]
+    fn not_fooocus() {
+        assert!(parse(r#"{"prompt":"castle"}"#, Some("a1111")).is_none());
+        assert!(parse("castle\nSteps: 20", Some("fooocus")).is_none());
+    }
+}
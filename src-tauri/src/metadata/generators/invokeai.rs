@@ -0,0 +1,207 @@
+//! InvokeAI 생성 정보 해석
+//!
+//! - `invokeai_metadata` (v3 이상): 생성 파라미터 JSON
+//! - `invokeai_graph`: 노드 그래프 JSON (`invokeai_metadata`가 없을 때만 사용)
+//! - `sd-metadata` (v2): `{"model_weights": ..., "image": {"prompt": ..., "seed": ...}}`
+
+use serde_json::{Map, Value};
+
+use super::{string, Generation};
+
+/// `invokeai_metadata` JSON을 해석합니다.
+pub fn parse_metadata(text: &str) -> Option<Generation> {
+    let Value::Object(fields) = serde_json::from_str(text).ok()? else {
+        return None;
+    };
+    fields.contains_key("positive_prompt").then(|| Generation {
+        prompt: string(fields.get("positive_prompt")),
+        negative: string(fields.get("negative_prompt")),
+        model: model_name(fields.get("model")),
+        seed: string(fields.get("seed")),
+        sampler: string(fields.get("scheduler")),
+        steps: string(fields.get("steps")),
+        cfg: string(fields.get("cfg_scale")),
+    })
+}
+
+/// `invokeai_graph` JSON을 해석합니다. 디노이즈 노드의 컨디셔닝 입력으로
+/// 연결된 프롬프트 노드를 찾습니다.
+pub fn parse_graph(text: &str) -> Option<Generation> {
+    let graph: Value = serde_json::from_str(text).ok()?;
+    // 버전에 따라 노드가 id를 키로 하는 객체이거나 배열입니다.
+    let nodes: Vec<&Map<String, Value>> = match graph.get("nodes")? {
+        Value::Object(nodes) => nodes.values().filter_map(Value::as_object).collect(),
+        Value::Array(nodes) => nodes.iter().filter_map(Value::as_object).collect(),
+        _ => return None,
+    };
+    let edges = graph.get("edges").and_then(Value::as_array)?;
+
+    let node = |id: &str| {
+        nodes
+            .iter()
+            .find(|node| node.get("id").and_then(Value::as_str) == Some(id))
+            .copied()
+    };
+    let node_of_type = |matches: fn(&str) -> bool| {
+        nodes
+            .iter()
+            .find(|node| {
+                node.get("type")
+                    .and_then(Value::as_str)
+                    .is_some_and(matches)
+            })
+            .copied()
+    };
+    // `field` 입력으로 연결된 노드의 프롬프트
+    let prompt = |field: &str| {
+        edges
+            .iter()
+            .filter(|edge| {
+                edge.pointer("/destination/field").and_then(Value::as_str) == Some(field)
+            })
+            .filter_map(|edge| node(edge.pointer("/source/node_id")?.as_str()?))
+            .find_map(|source| source.get("prompt").and_then(Value::as_str))
+            .unwrap_or_default()
+            .to_owned()
+    };
+
+    let prompt_text = prompt("positive_conditioning");
+    if prompt_text.is_empty() {
+        return None;
+    }
+    let denoise = node_of_type(|kind| kind.contains("denoise_latents"));
+    Some(Generation {
+        prompt: prompt_text,
+        negative: prompt("negative_conditioning"),
+        model: model_name(
+            node_of_type(|kind| kind.ends_with("model_loader")).and_then(|n| n.get("model")),
+        ),
+        seed: string(node_of_type(|kind| kind == "noise").and_then(|n| n.get("seed"))),
+        sampler: string(denoise.and_then(|n| n.get("scheduler"))),
+        steps: string(denoise.and_then(|n| n.get("steps"))),
+        cfg: string(denoise.and_then(|n| n.get("cfg_scale"))),
+    })
+}
+
+/// v2의 `sd-metadata` JSON을 해석합니다. 프롬프트 안의 `[...]`는 네거티브 프롬프트입니다.
+pub fn parse_legacy(text: &str) -> Option<Generation> {
+    let fields: Value = serde_json::from_str(text).ok()?;
+    let image = fields.get("image")?.as_object()?;
+    let prompt = match image.get("prompt")? {
+        Value::String(prompt) => prompt.clone(),
+        // [{"prompt": "...", "weight": 1.0}, ...]
+        Value::Array(prompts) => prompts
+            .iter()
+            .filter_map(|p| p.get("prompt").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => return None,
+    };
+    let (prompt, negative) = split_negative(&prompt);
+    Some(Generation {
+        prompt,
+        negative,
+        model: string(fields.get("model_weights")),
+        seed: string(image.get("seed")),
+        sampler: string(image.get("sampler").or_else(|| image.get("sampler_name"))),
+        steps: string(image.get("steps")),
+        cfg: string(image.get("cfg_scale")),
+    })
+}
+
+// v3는 `{"model_name": ...}`, v4 이상은 `{"name": ...}`, 그 전에는 문자열입니다.
+fn model_name(model: Option<&Value>) -> String {
+    match model {
+        Some(Value::Object(model)) => string(model.get("name").or_else(|| model.get("model_name"))),
+        other => string(other),
+    }
+}
+
+// "a cat [blurry, lowres]" -> ("a cat", "blurry, lowres")
+fn split_negative(prompt: &str) -> (String, String) {
+    let mut positive = String::new();
+    let mut negatives = Vec::new();
+    let mut depth = 0usize;
+    let mut current = String::new();
+    for c in prompt.chars() {
+        match c {
+            '[' => {
+                if depth > 0 {
+                    current.push(c);
+                }
+                depth += 1;
+            }
+            ']' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    negatives.push(std::mem::take(&mut current).trim().to_owned());
+                } else {
+                    current.push(c);
+                }
+            }
+            _ if depth > 0 => current.push(c),
+            _ => positive.push(c),
+        }
+    }
+    let positive = positive.split_whitespace().collect::<Vec<_>>().join(" ");
+    negatives.retain(|n| !n.is_empty());
+    (positive, negatives.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_json() {
+        let generation = parse_metadata(
+            r#"{"positive_prompt":"a cat","negative_prompt":"blurry","seed":42,"model":{"name":"Juggernaut XL","base":"sdxl"},"scheduler":"euler","steps":30,"cfg_scale":6.5}"#,
+        )
+        .unwrap();
+        assert_eq!(generation.prompt, "a cat");
+        assert_eq!(generation.negative, "blurry");
+        assert_eq!(generation.model, "Juggernaut XL");
+        assert_eq!(generation.seed, "42");
+        assert_eq!(generation.sampler, "euler");
+        assert_eq!(generation.cfg, "6.5");
+    }
+
+    #[test]
+    fn workflow_graph() {
+        // 프롬프트는 denoise 노드의 positive/negative 입력으로 이어진 노드에서 찾습니다.
+        let graph = r#"{"id":"g","nodes":{
+            "a":{"id":"a","type":"compel","prompt":"a dog"},
+            "b":{"id":"b","type":"compel","prompt":"ugly"},
+            "n":{"id":"n","type":"noise","seed":7},
+            "m":{"id":"m","type":"main_model_loader","model":{"model_name":"sd15"}},
+            "d":{"id":"d","type":"denoise_latents","steps":20,"cfg_scale":7,"scheduler":"dpmpp"}},
+          "edges":[
+            {"source":{"node_id":"b","field":"conditioning"},"destination":{"node_id":"d","field":"negative_conditioning"}},
+            {"source":{"node_id":"a","field":"conditioning"},"destination":{"node_id":"d","field":"positive_conditioning"}}]}"#;
+        let generation = parse_graph(graph).unwrap();
+        assert_eq!(generation.prompt, "a dog");
+        assert_eq!(generation.negative, "ugly");
+        assert_eq!(generation.model, "sd15");
+        assert_eq!(generation.seed, "7");
+        assert_eq!(generation.sampler, "dpmpp");
+        assert_eq!(generation.steps, "20");
+    }
+
+    #[test]
+    fn legacy_sd_metadata() {
+        // 2.x 이전에는 네거티브 프롬프트를 대괄호로 프롬프트 안에 썼습니다.
+        let legacy = r#"{"model_weights":"stable-diffusion-1.5","image":{"prompt":[{"prompt":"a fox [lowres] in snow [blurry]","weight":1}],"seed":9,"sampler":"k_lms","steps":50,"cfg_scale":7.5}}"#;
+        let generation = parse_legacy(legacy).unwrap();
+        assert_eq!(generation.prompt, "a fox in snow");
+        assert_eq!(generation.negative, "lowres, blurry");
+        assert_eq!(generation.model, "stable-diffusion-1.5");
+        assert_eq!(generation.cfg, "7.5");
+    }
+
+    #[test]
+    fn not_invokeai() {
+        assert!(parse_metadata("a cat").is_none());
+        assert!(parse_graph(r#"{"nodes":{}}"#).is_none());
+        assert!(parse_legacy(r#"{"image":{}}"#).is_none());
+    }
+}
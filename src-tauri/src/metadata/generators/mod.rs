@@ -0,0 +1,291 @@
+//! 이미지 생성 도구가 남긴 파라미터 해석
+//!
+//! 형식별 파서가 읽은 원본 필드(`PNG:parameters` 등)를 도구별 형식에 맞춰
+//! 프롬프트, 시드 같은 개별 필드로 나눕니다. 원본 필드는 그대로 둡니다.
+//!
+//! 도구별 필드(`SD:Prompt`, `NAI:Prompt` 등)와 함께, 어떤 도구로 만들었든 같은 이름으로
+//! 검색할 수 있도록 `Generation:Prompt`, `Generation:Negative` 등의 공통 필드를 추가합니다.
+
+pub mod a1111;
+pub mod comfy;
+pub mod easydiffusion;
+pub mod fooocus;
+pub mod invokeai;
+pub mod novelai;
+pub mod swarmui;
+
+use serde_json::Value;
+
+use super::{Metadata, Provenance};
+
+// A1111 형식 텍스트(또는 Fooocus, SwarmUI JSON)가 들어 있을 수 있는 원본 필드 (앞쪽 우선)
+// AnimateDiff는 동영상 주석에 A1111 형식 텍스트를 씁니다.
+const PARAMETER_KEYS: &[&str] = &[
+    "PNG:parameters",
+    "Stealth:parameters",
+    "EXIF:UserComment",
+    "QuickTime:Comment",
+    "Matroska:Comment",
+    // webui의 "생성 정보를 텍스트 파일로 저장" 옵션
+    "Sidecar:Text",
+];
+// ComfyUI API 형식 그래프 (WebP는 EXIF에 `prompt:{...}`로, 동영상은 주석 JSON에 저장됩니다)
+const COMFY_PROMPT_KEYS: &[&str] = &[
+    "PNG:prompt",
+    "WebP:prompt",
+    "QuickTime:prompt",
+    "Matroska:prompt",
+];
+
+/// 생성 도구 파서가 쓰는 필드의 네임스페이스
+pub const NAMESPACES: &[&str] = &[
+    "Generation",
+    "SD",
+    "Comfy",
+    "NAI",
+    "Invoke",
+    "Fooocus",
+    "Swarm",
+    "EasyDiffusion",
+];
+
+/// 생성 도구와 관계없이 같은 이름으로 쓰는 필드
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Generation {
+    pub prompt: String,
+    pub negative: String,
+    pub model: String,
+    pub seed: String,
+    pub sampler: String,
+    pub steps: String,
+    pub cfg: String,
+}
+
+impl Generation {
+    /// `{namespace}:Prompt` 형식의 필드로 `metadata`에 추가합니다.
+    pub fn write(&self, namespace: &str, metadata: &mut Metadata) {
+        let fields = [
+            ("Prompt", &self.prompt),
+            ("Negative", &self.negative),
+            ("Model", &self.model),
+            ("Seed", &self.seed),
+            ("Sampler", &self.sampler),
+            ("Steps", &self.steps),
+            ("CFG", &self.cfg),
+        ];
+        for (name, value) in fields {
+            metadata.insert(format!("{namespace}:{name}"), value.as_str());
+        }
+    }
+}
+
+/// 원본 필드를 해석해 생성 도구별 필드와 공통 필드를 `metadata`에 추가합니다.
+/// 여러 도구의 흔적이 있으면 공통 필드는 먼저 찾은 도구의 값을 씁니다.
+/// 추가한 필드의 출처는 해석한 원본 필드(`PNG:parameters` 등)입니다.
+pub fn read(metadata: &mut Metadata) {
+    let mut tools: Vec<(&str, Generation, Provenance)> = Vec::new();
+
+    let novelai = metadata
+        .get("PNG:Comment")
+        .and_then(|comment| {
+            novelai::parse(
+                comment,
+                metadata.get("PNG:Software"),
+                metadata.get("PNG:Source"),
+            )
+        })
+        .map(|parameters| ("PNG:Comment", parameters))
+        .or_else(|| {
+            let parameters = novelai::parse_stealth(metadata.get("Stealth:parameters")?)?;
+            Some(("Stealth:parameters", parameters))
+        });
+    if let Some((key, parameters)) = novelai {
+        let origin = origin(metadata, key);
+        metadata.scoped(origin.clone(), |metadata| parameters.write(metadata));
+        tools.push(("NovelAI", parameters.generation(), origin));
+    }
+
+    let comfy = find_map(metadata, COMFY_PROMPT_KEYS, comfy::parse);
+    if let Some((key, parameters)) = comfy {
+        let origin = origin(metadata, key);
+        metadata.scoped(origin.clone(), |metadata| parameters.write(metadata));
+        tools.push(("ComfyUI", parameters.generation(), origin));
+    }
+
+    let invokeai = find_map(
+        metadata,
+        &["PNG:invokeai_metadata"],
+        invokeai::parse_metadata,
+    )
+    .or_else(|| find_map(metadata, &["PNG:invokeai_graph"], invokeai::parse_graph))
+    .or_else(|| find_map(metadata, &["PNG:sd-metadata"], invokeai::parse_legacy));
+    if let Some((key, generation)) = invokeai {
+        let origin = origin(metadata, key);
+        metadata.scoped(origin.clone(), |metadata| {
+            generation.write("Invoke", metadata)
+        });
+        tools.push(("InvokeAI", generation, origin));
+    }
+
+    let scheme = metadata.get("PNG:fooocus_scheme");
+    let fooocus = find_map(metadata, PARAMETER_KEYS, |text| {
+        fooocus::parse(text, scheme)
+    });
+    if let Some((key, generation)) = fooocus {
+        let origin = origin(metadata, key);
+        metadata.scoped(origin.clone(), |metadata| {
+            generation.write("Fooocus", metadata)
+        });
+        tools.push(("Fooocus", generation, origin));
+    }
+
+    let swarmui = find_map(metadata, PARAMETER_KEYS, swarmui::parse);
+    if let Some((key, generation)) = swarmui {
+        let origin = origin(metadata, key);
+        metadata.scoped(origin.clone(), |metadata| {
+            generation.write("Swarm", metadata)
+        });
+        tools.push(("SwarmUI", generation, origin));
+    }
+
+    // PNG는 필드마다 청크가 따로 있으므로 모델 필드를 출처로 씁니다.
+    let (model, legacy_model) = easydiffusion::MODEL;
+    let model_key = [model, legacy_model]
+        .map(|name| format!("PNG:{name}"))
+        .into_iter()
+        .find(|key| metadata.get(key).is_some())
+        .unwrap_or_default();
+    let easydiffusion =
+        easydiffusion::parse(|name| metadata.get(&format!("PNG:{name}")).map(str::to_owned))
+            .map(|generation| (model_key.as_str(), generation))
+            .or_else(|| find_map(metadata, PARAMETER_KEYS, easydiffusion::parse_json));
+    if let Some((key, generation)) = easydiffusion {
+        let origin = origin(metadata, key);
+        metadata.scoped(origin.clone(), |metadata| {
+            generation.write("EasyDiffusion", metadata)
+        });
+        tools.push(("Easy Diffusion", generation, origin));
+    }
+
+    let a1111 = find_map(metadata, PARAMETER_KEYS, a1111::parse);
+    if let Some((key, parameters)) = a1111 {
+        let origin = origin(metadata, key);
+        metadata.scoped(origin.clone(), |metadata| parameters.write(metadata));
+        tools.push(("A1111", parameters.generation(), origin));
+    }
+
+    if let Some((tool, generation, origin)) = tools.into_iter().next() {
+        metadata.scoped(origin, |metadata| {
+            metadata.insert("Generation:Tool", tool);
+            generation.write("Generation", metadata);
+        });
+    }
+}
+
+/// `keys` 중 `parse`가 처음으로 해석한 원본 필드의 키와 결과
+fn find_map<'k, T>(
+    metadata: &Metadata,
+    keys: &[&'k str],
+    parse: impl Fn(&str) -> Option<T>,
+) -> Option<(&'k str, T)> {
+    keys.iter()
+        .find_map(|&key| Some((key, parse(metadata.get(key)?)?)))
+}
+
+// 원본 필드의 위치와 인코딩을 이어받고, 출처는 원본 필드 키로 기록합니다.
+fn origin(metadata: &Metadata, key: &str) -> Provenance {
+    let provenance = metadata
+        .field(key)
+        .map(|field| field.provenance())
+        .unwrap_or_default();
+    Provenance {
+        source: Some(key.to_owned()),
+        ..provenance
+    }
+}
+
+/// JSON 값을 필드 문자열로 바꿉니다. 문자열, 숫자, 불리언이 아니면 빈 문자열입니다.
+fn string(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Number(number)) => number.to_string(),
+        Some(Value::Bool(flag)) => flag.to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(fields: &[(&str, &str)]) -> Metadata {
+        let mut metadata = Metadata::new();
+        for (key, value) in fields {
+            metadata.insert(*key, *value);
+        }
+        read(&mut metadata);
+        metadata
+    }
+
+    #[test]
+    fn common_fields() {
+        let metadata = run(&[(
+            "PNG:parameters",
+            "cat\nNegative prompt: dog\nSteps: 20, Sampler: Euler, CFG scale: 7, Seed: 1, Model: m",
+        )]);
+        assert_eq!(metadata.get("Generation:Tool"), Some("A1111"));
+        assert_eq!(metadata.get("Generation:Prompt"), Some("cat"));
+        assert_eq!(metadata.get("Generation:Negative"), Some("dog"));
+        assert_eq!(metadata.get("Generation:Model"), Some("m"));
+        assert_eq!(metadata.get("Generation:CFG"), Some("7"));
+        // 출처가 없는 원본 필드는 그 키를 출처로 씁니다.
+        assert_eq!(
+            metadata
+                .field("Generation:Prompt")
+                .unwrap()
+                .source
+                .as_deref(),
+            Some("PNG:parameters")
+        );
+    }
+
+    #[test]
+    fn tool_detection() {
+        let metadata = run(&[(
+            "PNG:invokeai_metadata",
+            r#"{"positive_prompt":"a cat","negative_prompt":"blurry","model":{"name":"Juggernaut XL"},"cfg_scale":6.5}"#,
+        )]);
+        assert_eq!(metadata.get("Invoke:Prompt"), Some("a cat"));
+        assert_eq!(metadata.get("Generation:Tool"), Some("InvokeAI"));
+        assert_eq!(metadata.get("Generation:CFG"), Some("6.5"));
+
+        // Fooocus JSON은 A1111 텍스트로 해석하지 않습니다.
+        let metadata = run(&[
+            (
+                "PNG:parameters",
+                r#"{"prompt":"castle","base_model":"juggernautXL","version":"Fooocus v2.5.5"}"#,
+            ),
+            ("PNG:fooocus_scheme", "fooocus"),
+        ]);
+        assert_eq!(metadata.get("Fooocus:Model"), Some("juggernautXL"));
+        assert_eq!(metadata.get("Generation:Tool"), Some("Fooocus"));
+        assert_eq!(metadata.get("SD:Prompt"), None);
+
+        let metadata = run(&[(
+            "EXIF:UserComment",
+            r#"{"sui_image_params":{"prompt":"a tree","model":"flux"},"sui_extra_data":{}}"#,
+        )]);
+        assert_eq!(metadata.get("Swarm:Prompt"), Some("a tree"));
+        assert_eq!(metadata.get("Generation:Model"), Some("flux"));
+
+        // Easy Diffusion의 `prompt` 청크는 ComfyUI 그래프가 아닙니다.
+        let metadata = run(&[
+            ("PNG:prompt", "a boat"),
+            ("PNG:use_stable_diffusion_model", "sd-v1-4"),
+            ("PNG:seed", "99"),
+        ]);
+        assert_eq!(metadata.get("EasyDiffusion:Prompt"), Some("a boat"));
+        assert_eq!(metadata.get("Generation:Seed"), Some("99"));
+        assert_eq!(metadata.get("Comfy:Positive"), None);
+    }
+}
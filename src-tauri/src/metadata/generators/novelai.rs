@@ -0,0 +1,269 @@
+//! NovelAI 생성 정보 해석
+//!
+//! `Comment` 텍스트 청크에 JSON 문자열로 저장됩니다.
+//!
+//! ```json
+//! { "prompt": "...", "uc": "...", "seed": 1, "sampler": "k_euler_ancestral", "scale": 5.0,
+//!   "v4_prompt": { "caption": { "base_caption": "...",
+//!                               "char_captions": [{ "char_caption": "...", "centers": [{ "x": 0.5, "y": 0.5 }] }] } },
+//!   "v4_negative_prompt": { "caption": { "base_caption": "...", "char_captions": [...] } } }
+//! ```
+//!
+//! 모델 버전은 `Source` 청크(`NovelAI Diffusion V4.5 1229B44F` 등)로 판별합니다.
+//! 스텔스 PNG 정보에는 청크들이 `{"Comment": "...", "Source": "...", ...}` 하나로 묶여 있습니다.
+
+use serde_json::{Map, Value};
+
+use super::{string, Generation};
+use crate::metadata::Metadata;
+
+const SOFTWARE: &str = "NovelAI";
+
+/// 캐릭터 프롬프트 (V4 이상)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Character {
+    pub prompt: String,
+    pub negative: String,
+    /// 배치 위치 (`x,y`, 여러 개면 `;`로 구분)
+    pub center: String,
+}
+
+/// 해석된 생성 파라미터
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Parameters {
+    pub prompt: String,
+    pub negative: String,
+    pub characters: Vec<Character>,
+    pub seed: String,
+    pub sampler: String,
+    pub scale: String,
+    pub steps: String,
+    pub size: String,
+    /// `Source`에서 해시를 뺀 모델 이름
+    pub model: String,
+    pub version: String,
+}
+
+impl Parameters {
+    /// `NAI:*` 필드를 `metadata`에 추가합니다.
+    pub fn write(&self, metadata: &mut Metadata) {
+        metadata.insert("NAI:Prompt", self.prompt.as_str());
+        metadata.insert("NAI:Negative", self.negative.as_str());
+        for (index, character) in self.characters.iter().enumerate() {
+            let key = format!("NAI:Character[{}]", index + 1);
+            metadata.insert(format!("{key}:Negative"), character.negative.as_str());
+            metadata.insert(format!("{key}:Center"), character.center.as_str());
+            metadata.insert(key, character.prompt.as_str());
+        }
+        metadata.insert("NAI:Seed", self.seed.as_str());
+        metadata.insert("NAI:Sampler", self.sampler.as_str());
+        metadata.insert("NAI:Scale", self.scale.as_str());
+        metadata.insert("NAI:Steps", self.steps.as_str());
+        metadata.insert("NAI:Size", self.size.as_str());
+        metadata.insert("NAI:Model", self.model.as_str());
+        metadata.insert("NAI:Version", self.version.as_str());
+    }
+
+    pub fn generation(&self) -> Generation {
+        Generation {
+            prompt: self.prompt.clone(),
+            negative: self.negative.clone(),
+            model: self.model.clone(),
+            seed: self.seed.clone(),
+            sampler: self.sampler.clone(),
+            steps: self.steps.clone(),
+            cfg: self.scale.clone(),
+        }
+    }
+}
+
+/// `Comment` JSON을 해석합니다. `software`/`source`는 같은 파일의 `Software`, `Source` 값입니다.
+/// NovelAI가 만든 이미지로 보이지 않으면 `None`을 돌려줍니다.
+pub fn parse(comment: &str, software: Option<&str>, source: Option<&str>) -> Option<Parameters> {
+    let Value::Object(comment) = serde_json::from_str(comment).ok()? else {
+        return None;
+    };
+    parse_comment(&comment, software, source)
+}
+
+/// 스텔스 PNG 정보(`Stealth:parameters`)의 JSON을 해석합니다.
+pub fn parse_stealth(text: &str) -> Option<Parameters> {
+    let Value::Object(fields) = serde_json::from_str(text).ok()? else {
+        return None;
+    };
+    let software = fields.get("Software").and_then(Value::as_str);
+    let source = fields.get("Source").and_then(Value::as_str);
+    match fields.get("Comment")? {
+        Value::String(comment) => parse(comment, software, source),
+        Value::Object(comment) => parse_comment(comment, software, source),
+        _ => None,
+    }
+}
+
+fn parse_comment(
+    comment: &Map<String, Value>,
+    software: Option<&str>,
+    source: Option<&str>,
+) -> Option<Parameters> {
+    let is_novelai = software.is_some_and(|software| software.trim() == SOFTWARE)
+        || comment.contains_key("uc")
+        || comment.contains_key("v4_prompt");
+    if !is_novelai {
+        return None;
+    }
+
+    let v4_prompt = comment.get("v4_prompt").and_then(|v| v.get("caption"));
+    let v4_negative = comment
+        .get("v4_negative_prompt")
+        .and_then(|v| v.get("caption"));
+    let base_caption = |caption: Option<&Value>| {
+        caption
+            .and_then(|c| c.get("base_caption"))
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+    };
+
+    let characters = char_captions(v4_prompt)
+        .iter()
+        .enumerate()
+        .map(|(index, caption)| Character {
+            prompt: string(caption.get("char_caption")),
+            negative: char_captions(v4_negative)
+                .get(index)
+                .map(|negative| string(negative.get("char_caption")))
+                .unwrap_or_default(),
+            center: centers(caption.get("centers")),
+        })
+        .collect();
+
+    let size = match (comment.get("width"), comment.get("height")) {
+        (Some(width), Some(height)) => format!("{}x{}", string(Some(width)), string(Some(height))),
+        _ => String::new(),
+    };
+
+    Some(Parameters {
+        prompt: base_caption(v4_prompt).unwrap_or_else(|| string(comment.get("prompt"))),
+        negative: base_caption(v4_negative).unwrap_or_else(|| string(comment.get("uc"))),
+        characters,
+        seed: string(comment.get("seed")),
+        sampler: string(comment.get("sampler")),
+        scale: string(comment.get("scale")),
+        steps: string(comment.get("steps")),
+        size,
+        model: source.map(model_name).unwrap_or_default(),
+        version: version(source, v4_prompt.is_some()),
+    })
+}
+
+fn char_captions(caption: Option<&Value>) -> &[Value] {
+    caption
+        .and_then(|c| c.get("char_captions"))
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+// [{ "x": 0.5, "y": 0.5 }, ...] -> "0.5,0.5"
+fn centers(centers: Option<&Value>) -> String {
+    let Some(centers) = centers.and_then(Value::as_array) else {
+        return String::new();
+    };
+    centers
+        .iter()
+        .map(|center| format!("{},{}", string(center.get("x")), string(center.get("y"))))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+// "NovelAI Diffusion V4.5 1229B44F" -> "NovelAI Diffusion V4.5"
+fn model_name(source: &str) -> String {
+    let source = source.trim();
+    match source.rsplit_once(' ') {
+        Some((name, hash)) if hash.len() == 8 && hash.chars().all(|c| c.is_ascii_hexdigit()) => {
+            name.to_owned()
+        }
+        _ => source.to_owned(),
+    }
+}
+
+fn version(source: Option<&str>, has_v4_prompt: bool) -> String {
+    let source = source.unwrap_or_default();
+    let version = if source.contains("V4.5") {
+        "V4.5"
+    } else if source.contains("V4") || has_v4_prompt {
+        "V4"
+    } else if source.starts_with("Stable Diffusion XL") {
+        // NAI Diffusion V3는 SDXL 기반이라 `Stable Diffusion XL <해시>`로 기록됩니다.
+        "V3"
+    } else {
+        ""
+    };
+    version.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::generators;
+
+    const V4: &str = r#"{"prompt":"old prompt","steps":28,"height":1216,"width":832,"scale":5.0,"uncond_scale":0.0,"seed":3417089346,"sampler":"k_euler_ancestral","noise_schedule":"karras","uc":"lowres, {bad}","v4_prompt":{"caption":{"base_caption":"2girls, -1::production_art::, 1::halo::","char_captions":[{"char_caption":"girl, red hair","centers":[{"x":0.3,"y":0.5}]},{"char_caption":"girl, blue hair","centers":[{"x":0.7,"y":0.5}]}]},"use_coords":true,"use_order":true},"v4_negative_prompt":{"caption":{"base_caption":"lowres, bad anatomy","char_captions":[{"char_caption":"hat","centers":[{"x":0.3,"y":0.5}]},{"char_caption":"","centers":[{"x":0.7,"y":0.5}]}]}}}"#;
+
+    #[test]
+    fn v4_fields() {
+        let mut metadata = Metadata::new();
+        metadata.insert("PNG:Software", "NovelAI");
+        metadata.insert("PNG:Source", "NovelAI Diffusion V4.5 1229B44F");
+        metadata.insert("PNG:Comment", V4);
+        generators::read(&mut metadata);
+
+        // V4는 `prompt`, `uc` 대신 v4_prompt의 기본 캡션을 씁니다.
+        assert_eq!(
+            metadata.get("NAI:Prompt"),
+            Some("2girls, -1::production_art::, 1::halo::")
+        );
+        assert_eq!(metadata.get("NAI:Negative"), Some("lowres, bad anatomy"));
+        assert_eq!(metadata.get("NAI:Character[1]"), Some("girl, red hair"));
+        assert_eq!(metadata.get("NAI:Character[1]:Negative"), Some("hat"));
+        assert_eq!(metadata.get("NAI:Character[1]:Center"), Some("0.3,0.5"));
+        assert_eq!(metadata.get("NAI:Character[2]"), Some("girl, blue hair"));
+        assert_eq!(metadata.get("NAI:Character[2]:Negative"), None);
+        assert_eq!(metadata.get("NAI:Seed"), Some("3417089346"));
+        assert_eq!(metadata.get("NAI:Scale"), Some("5.0"));
+        assert_eq!(metadata.get("NAI:Size"), Some("832x1216"));
+        assert_eq!(metadata.get("NAI:Model"), Some("NovelAI Diffusion V4.5"));
+        assert_eq!(metadata.get("NAI:Version"), Some("V4.5"));
+    }
+
+    #[test]
+    fn v3() {
+        let parameters = parse(
+            r#"{"prompt":"1girl","uc":"bad","seed":1,"sampler":"k_euler"}"#,
+            Some("NovelAI"),
+            Some("Stable Diffusion XL C1E1DE52"),
+        )
+        .unwrap();
+        assert_eq!(parameters.prompt, "1girl");
+        assert_eq!(parameters.negative, "bad");
+        assert_eq!(parameters.version, "V3");
+        assert!(parameters.characters.is_empty());
+    }
+
+    #[test]
+    fn stealth_json() {
+        // 스텔스 PNG에는 텍스트 청크 전체가 JSON 하나로 들어 있습니다.
+        let stealth = serde_json::json!({
+            "Software": "NovelAI",
+            "Source": "NovelAI Diffusion V4 4BDE2A90",
+            "Comment": V4,
+        })
+        .to_string();
+        let parameters = parse_stealth(&stealth).unwrap();
+        assert_eq!(parameters.version, "V4");
+        assert_eq!(parameters.characters.len(), 2);
+    }
+
+    #[test]
+    fn not_novelai() {
+        assert!(parse(r#"{"foo":1}"#, None, None).is_none());
+        assert!(parse("a normal comment", Some("NovelAI"), None).is_none());
+    }
+}
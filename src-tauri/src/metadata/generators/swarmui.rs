@@ -0,0 +1,39 @@
+//! SwarmUI 생성 정보 해석
+//!
+//! `parameters` 청크(JPEG/WebP는 EXIF UserComment)에
+//! `{"sui_image_params": {...}, "sui_extra_data": {...}}` JSON으로 저장됩니다.
+
+use serde_json::Value;
+
+use super::{string, Generation};
+
+/// `parameters` JSON을 해석합니다.
+pub fn parse(text: &str) -> Option<Generation> {
+    let fields: Value = serde_json::from_str(text).ok()?;
+    let params = fields.get("sui_image_params")?.as_object()?;
+    Some(Generation {
+        prompt: string(params.get("prompt")),
+        negative: string(params.get("negativeprompt")),
+        model: string(params.get("model")),
+        seed: string(params.get("seed")),
+        sampler: string(params.get("sampler")),
+        steps: string(params.get("steps")),
+        cfg: string(params.get("cfgscale")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_params() {
+        let generation = parse(r#"{"sui_image_params":{"prompt":"a tree","negativeprompt":"bad","model":"flux","seed":5,"steps":20,"cfgscale":1,"sampler":"euler"},"sui_extra_data":{}}"#).unwrap();
+        assert_eq!(generation.prompt, "a tree");
+        assert_eq!(generation.negative, "bad");
+        assert_eq!(generation.model, "flux");
+        assert_eq!(generation.seed, "5");
+        assert_eq!(generation.cfg, "1");
+        assert!(parse(r#"{"prompt":"a tree"}"#).is_none());
+    }
+}
@@ -0,0 +1,258 @@
+//! GIF 블록 파서
+//!
+//! Comment Extension은 `GIF:Comment`로, XMP Application Extension은 XMP 파서로 넘깁니다.
+//! 방문한 블록과 잘린 블록은 진단 보고서에 기록합니다.
+
+use super::{text, xmp, Block, Issue, Metadata};
+
+const EXTENSION: u8 = 0x21;
+const IMAGE_DESCRIPTOR: u8 = 0x2c;
+
+const TRAILER: u8 = 0x3b;
+
+const LABEL_PLAIN_TEXT: u8 = 0x01;
+const LABEL_GRAPHIC_CONTROL: u8 = 0xf9;
+const LABEL_COMMENT: u8 = 0xfe;
+const LABEL_APPLICATION: u8 = 0xff;
+
+// Application Extension 식별자(8) + 인증 코드(3)
+const XMP_APPLICATION: &[u8] = b"XMP DataXMP";
+const NETSCAPE_APPLICATION: &[u8] = b"NETSCAPE2.0";
+
+// XMP는 서브 블록 구조 없이 그대로 기록되고, 끝에 0x01, 0xFF, 0xFE, ..., 0x00의
+// "매직 트레일러"가 붙어 일반 GIF 디코더가 서브 블록처럼 건너뛸 수 있게 합니다.
+const XMP_TRAILER_START: &[u8] = b"\x01\xff\xfe\xfd";
+
+pub fn is_gif(data: &[u8]) -> bool {
+    data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")
+}
+
+/// 확장 블록을 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    // 헤더(6) + 논리 화면 기술자(7) [+ 전역 색상표]
+    let Some(&packed) = data.get(10) else {
+        return;
+    };
+    let mut offset = 13 + color_table_size(packed);
+    let mut comments = Vec::new();
+    let mut frames = 0u32;
+
+    let mut finished = false;
+    while let Some(&introducer) = data.get(offset) {
+        let (name, start) = match introducer {
+            EXTENSION => {
+                let label = data.get(offset + 1).copied();
+                (extension_name(label), offset + 2)
+            }
+            IMAGE_DESCRIPTOR => {
+                frames += 1;
+                // 기술자(9) [+ 지역 색상표] + LZW 최소 코드 크기(1) + 이미지 데이터 서브 블록
+                let start = match data.get(offset + 9) {
+                    Some(&packed) => offset + 10 + color_table_size(packed) + 1,
+                    None => offset + 10,
+                };
+                ("GIF:Image", start)
+            }
+            TRAILER => {
+                finished = true;
+                break;
+            }
+            _ => {
+                metadata.record(Block::new("GIF", offset..data.len()).issue(Issue::Corrupt));
+                break;
+            }
+        };
+        let end = match skip_sub_blocks(data, start) {
+            Ok(end) => end,
+            Err(expected) => {
+                metadata.record(
+                    Block::new(name, offset..data.len()).issue(Issue::Truncated {
+                        expected: expected - offset,
+                        available: data.len() - offset,
+                    }),
+                );
+                break;
+            }
+        };
+        if introducer == EXTENSION {
+            match data[offset + 1] {
+                LABEL_COMMENT => {
+                    comments.push(text::utf8_or_latin1(&join_sub_blocks(&data[start..end])))
+                }
+                LABEL_APPLICATION => read_application(&data[start..end], metadata),
+                _ => {}
+            }
+        }
+        metadata.record(Block::new(name, offset..end));
+        offset = end;
+    }
+    if !finished && offset >= data.len() {
+        // 잘린 블록은 이미 기록했습니다.
+        let end = data.len();
+        metadata.record(Block::new("GIF:Trailer", end..end).issue(Issue::Missing));
+    }
+
+    metadata.insert("GIF:Comment", comments.join("\n"));
+    if frames > 1 {
+        metadata.insert("GIF:FrameCount", frames.to_string());
+    }
+}
+
+// blocks: 첫 서브 블록(식별자) + 이후 서브 블록들 + 종료 블록
+fn read_application(blocks: &[u8], metadata: &mut Metadata) {
+    let Some((&11, rest)) = blocks.split_first() else {
+        return;
+    };
+    let Some((identifier, payload)) = rest.split_at_checked(11) else {
+        return;
+    };
+    if identifier == XMP_APPLICATION {
+        let end = payload
+            .windows(XMP_TRAILER_START.len())
+            .position(|w| w == XMP_TRAILER_START)
+            .unwrap_or(payload.len());
+        xmp::read(&payload[..end], metadata);
+    } else if identifier == NETSCAPE_APPLICATION {
+        // 서브 블록: 크기(3) + 1 + 반복 횟수(2, 리틀 엔디언)
+        if let Some(&[3, 1, low, high]) = payload.get(..4) {
+            metadata.insert("GIF:LoopCount", u16::from_le_bytes([low, high]).to_string());
+        }
+    }
+}
+
+fn extension_name(label: Option<u8>) -> &'static str {
+    match label {
+        Some(LABEL_PLAIN_TEXT) => "GIF:PlainText",
+        Some(LABEL_GRAPHIC_CONTROL) => "GIF:GraphicControl",
+        Some(LABEL_COMMENT) => "GIF:Comment",
+        Some(LABEL_APPLICATION) => "GIF:Application",
+        _ => "GIF:Extension",
+    }
+}
+
+fn color_table_size(packed: u8) -> usize {
+    if packed & 0x80 != 0 {
+        3 << ((packed & 0x07) + 1)
+    } else {
+        0
+    }
+}
+
+/// `start`부터 이어지는 서브 블록을 건너뛰고 종료 블록 다음 위치를 돌려줍니다.
+/// 데이터가 중간에 끝나면 적어도 필요한 끝 위치를 `Err`로 돌려줍니다.
+fn skip_sub_blocks(data: &[u8], start: usize) -> Result<usize, usize> {
+    let mut offset = start;
+    loop {
+        let Some(&size) = data.get(offset) else {
+            return Err(offset + 1);
+        };
+        offset += 1 + size as usize;
+        if offset > data.len() {
+            return Err(offset);
+        }
+        if size == 0 {
+            return Ok(offset);
+        }
+    }
+}
+
+fn join_sub_blocks(blocks: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    while let Some(&size) = blocks.get(offset) {
+        let Some(block) = blocks.get(offset + 1..offset + 1 + size as usize) else {
+            break;
+        };
+        out.extend_from_slice(block);
+        offset += 1 + size as usize;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::xmp;
+
+    // 1×1, 전역 색상표 2색
+    fn header() -> Vec<u8> {
+        let mut out = b"GIF89a".to_vec();
+        out.extend([1, 0, 1, 0, 0x80, 0, 0]);
+        out.extend([0; 6]);
+        out
+    }
+
+    // 이미지 설명자와 LZW 데이터
+    fn frame(out: &mut Vec<u8>) {
+        out.extend([0x2c, 0, 0, 0, 0, 1, 0, 1, 0, 0]);
+        out.extend([2, 2, 0x4c, 0x01, 0]);
+    }
+
+    #[test]
+    fn comment_xmp_and_frames() {
+        let mut data = header();
+        // 하위 블록 둘로 나뉜 주석
+        data.extend([0x21, 0xfe, 5]);
+        data.extend(b"hello");
+        data.push(6);
+        data.extend(b" world");
+        data.push(0);
+        data.extend([0x21, 0xff, 11]);
+        data.extend(b"NETSCAPE2.0");
+        data.extend([3, 1, 0, 0, 0]);
+        // XMP는 하위 블록으로 나누지 않고 그대로 쓴 뒤 258바이트 "매직 트레일러"로 끝납니다.
+        data.extend([0x21, 0xff, 11]);
+        data.extend(b"XMP DataXMP");
+        data.extend(xmp("<dc:title>xmp title</dc:title>").as_bytes());
+        data.push(1);
+        data.extend((0..=255u8).rev());
+        data.push(0);
+        frame(&mut data);
+        frame(&mut data);
+        data.push(0x3b);
+
+        assert!(is_gif(&data));
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("GIF:Comment"), Some("hello world"));
+        assert_eq!(metadata.get("XMP:dc:title"), Some("xmp title"));
+        assert_eq!(metadata.get("GIF:FrameCount"), Some("2"));
+    }
+
+    #[test]
+    fn truncated_extension() {
+        let mut data = header();
+        data.extend([0x21, 0xfe, 5]);
+        data.extend(b"hel");
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("GIF:Comment"), None);
+        let block = &metadata.report().blocks[0];
+        assert_eq!(block.name, "GIF:Comment");
+        assert_eq!(block.byte_range, 19..data.len());
+        assert_eq!(
+            block.issue,
+            Some(Issue::Truncated {
+                expected: 8,
+                available: 6
+            })
+        );
+    }
+
+    #[test]
+    fn missing_trailer() {
+        let mut data = header();
+        frame(&mut data);
+        let metadata = extract(&data);
+        let names: Vec<_> = metadata
+            .report()
+            .blocks
+            .iter()
+            .map(|block| block.name.as_str())
+            .collect();
+        assert_eq!(names, ["GIF:Image", "GIF:Trailer"]);
+        assert_eq!(metadata.report().blocks[1].issue, Some(Issue::Missing));
+
+        data.push(0x3b);
+        assert!(extract(&data).report().is_clean());
+    }
+}
@@ -0,0 +1,121 @@
+//! ICC 색상 프로파일 파서
+//!
+//! 프로파일 설명(`desc`)과 저작권(`cprt`) 문자열만 읽습니다.
+
+use super::{text, Metadata};
+
+// 헤더(128) + 태그 수(4)
+const HEADER_SIZE: usize = 128;
+
+const TAGS: &[(&[u8; 4], &str)] = &[
+    (b"desc", "ICC:ProfileDescription"),
+    (b"cprt", "ICC:ProfileCopyright"),
+];
+
+/// ICC 프로파일의 문자열 태그를 읽어 `metadata`에 추가합니다.
+pub fn read(profile: &[u8], metadata: &mut Metadata) {
+    let Some(count) = be_u32(profile, HEADER_SIZE) else {
+        return;
+    };
+    for index in 0..count as usize {
+        // 태그 테이블: signature(4) + offset(4) + size(4)
+        let entry = HEADER_SIZE + 4 + index * 12;
+        let (Some(signature), Some(offset), Some(size)) = (
+            profile.get(entry..entry + 4),
+            be_u32(profile, entry + 4),
+            be_u32(profile, entry + 8),
+        ) else {
+            break;
+        };
+        let Some(&(_, key)) = TAGS.iter().find(|(tag, _)| tag.as_slice() == signature) else {
+            continue;
+        };
+        let (offset, size) = (offset as usize, size as usize);
+        if let Some(value) = profile
+            .get(offset..offset.saturating_add(size))
+            .and_then(decode_text)
+        {
+            metadata.insert(key, text::trim_nul(&value).trim());
+        }
+    }
+}
+
+fn decode_text(data: &[u8]) -> Option<String> {
+    match data.get(..4)? {
+        // textDescriptionType (v2): ASCII 길이(4) + ASCII
+        b"desc" => {
+            let length = be_u32(data, 8)? as usize;
+            Some(text::latin1(data.get(12..12 + length)?))
+        }
+        // textType (v2): ASCII
+        b"text" => Some(text::latin1(data.get(8..)?)),
+        // multiLocalizedUnicodeType (v4): 첫 번째 레코드의 UTF-16BE 문자열
+        b"mluc" => {
+            let length = be_u32(data, 20)? as usize;
+            let offset = be_u32(data, 24)? as usize;
+            Some(text::utf16(data.get(offset..offset + length)?, true))
+        }
+        _ => None,
+    }
+}
+
+fn be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 헤더 뒤 태그 테이블에 (서명, 값)을 차례로 넣은 프로파일
+    fn profile(tags: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut out = vec![0; HEADER_SIZE];
+        out.extend((tags.len() as u32).to_be_bytes());
+        let mut offset = out.len() + tags.len() * 12;
+        let mut values = Vec::new();
+        for (signature, value) in tags {
+            out.extend(*signature);
+            out.extend((offset as u32).to_be_bytes());
+            out.extend((value.len() as u32).to_be_bytes());
+            offset += value.len();
+            values.extend_from_slice(value);
+        }
+        out.extend(values);
+        out
+    }
+
+    #[test]
+    fn v2_text() {
+        let mut description = b"desc\0\0\0\0".to_vec();
+        description.extend(5u32.to_be_bytes());
+        description.extend(b"sRGB\0");
+        let data = profile(&[
+            (b"desc", description),
+            (b"cprt", b"text\0\0\0\0No copyright\0".to_vec()),
+        ]);
+
+        let mut metadata = Metadata::new();
+        read(&data, &mut metadata);
+        assert_eq!(metadata.get("ICC:ProfileDescription"), Some("sRGB"));
+        assert_eq!(metadata.get("ICC:ProfileCopyright"), Some("No copyright"));
+    }
+
+    #[test]
+    fn v4_mluc() {
+        // 레코드 하나 (언어, 국가, 길이, 오프셋 28)
+        let mut description = b"mluc\0\0\0\0".to_vec();
+        description.extend(1u32.to_be_bytes());
+        description.extend(12u32.to_be_bytes());
+        description.extend(b"enUS");
+        description.extend(14u32.to_be_bytes());
+        description.extend(28u32.to_be_bytes());
+        for unit in "Wide P3".encode_utf16() {
+            description.extend(unit.to_be_bytes());
+        }
+
+        let mut metadata = Metadata::new();
+        read(&profile(&[(b"desc", description)]), &mut metadata);
+        assert_eq!(metadata.get("ICC:ProfileDescription"), Some("Wide P3"));
+    }
+}
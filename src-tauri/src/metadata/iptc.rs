@@ -0,0 +1,230 @@
+//! IPTC-IIM 파서
+//!
+//! JPEG APP13(`Photoshop 3.0`)의 8BIM 리소스 블록에서 IPTC 데이터셋을 찾아
+//! `IPTC:데이터셋이름` 필드로 변환합니다. 반복 가능한 데이터셋(Keywords 등)은
+//! 값을 합치지 않고 `IPTC:Keywords[1]`, `IPTC:Keywords[2]`처럼 따로 두고,
+//! 나온 순서대로 모은 목록 값(`IPTC:Keywords`)도 함께 둡니다.
+
+use super::{text, Metadata, MetadataValue, Provenance};
+
+// Photoshop 이미지 리소스 ID: IPTC-NAA 레코드
+const RESOURCE_IPTC: u16 = 0x0404;
+// 데이터셋 시작 표시
+const TAG_MARKER: u8 = 0x1c;
+// 1:90 CodedCharacterSet 값 중 UTF-8을 나타내는 ISO 2022 이스케이프 시퀀스
+const UTF8_ESCAPE: &[u8] = b"\x1b%G";
+
+/// 애플리케이션 레코드(2번) 데이터셋 이름과 반복 가능 여부
+const DATASETS: &[(u8, &str, bool)] = &[
+    (5, "ObjectName", false),
+    (7, "EditStatus", false),
+    (10, "Urgency", false),
+    (15, "Category", false),
+    (20, "SupplementalCategories", true),
+    (25, "Keywords", true),
+    (40, "SpecialInstructions", false),
+    (55, "DateCreated", false),
+    (60, "TimeCreated", false),
+    (65, "OriginatingProgram", false),
+    (70, "ProgramVersion", false),
+    (80, "By-line", true),
+    (85, "By-lineTitle", true),
+    (90, "City", false),
+    (92, "Sub-location", false),
+    (95, "Province-State", false),
+    (100, "Country-PrimaryLocationCode", false),
+    (101, "Country-PrimaryLocationName", false),
+    (103, "OriginalTransmissionReference", false),
+    (105, "Headline", false),
+    (110, "Credit", false),
+    (115, "Source", false),
+    (116, "CopyrightNotice", false),
+    (118, "Contact", true),
+    (120, "Caption-Abstract", false),
+    (122, "Writer-Editor", true),
+];
+
+/// Photoshop 이미지 리소스 블록(8BIM)을 읽어 IPTC 레코드를 `metadata`에 추가합니다.
+pub fn read_photoshop(data: &[u8], metadata: &mut Metadata) {
+    for (id, resource) in resources(data) {
+        if id == RESOURCE_IPTC {
+            read(resource, metadata);
+        }
+    }
+}
+
+/// 이미지 리소스 블록 목록. 알 수 없는 시그니처를 만나면 멈춥니다.
+pub fn resources(data: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    while let Some(signature) = data.get(offset..offset + 4) {
+        if !matches!(signature, b"8BIM" | b"PHUT" | b"AgHg" | b"DCSR") {
+            break;
+        }
+        let Some(&[high, low, name_len]) = data.get(offset + 4..offset + 7) else {
+            break;
+        };
+        let id = u16::from_be_bytes([high, low]);
+        // 파스칼 문자열 이름: 길이 바이트를 포함해 짝수 길이로 맞춤
+        let name_size = (1 + name_len as usize + 1) & !1;
+        let size_at = offset + 6 + name_size;
+        let Some(size) = data.get(size_at..size_at + 4) else {
+            break;
+        };
+        let size = u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize;
+        let Some(resource) = data.get(size_at + 4..size_at + 4 + size) else {
+            break;
+        };
+        out.push((id, resource));
+        offset = size_at + 4 + ((size + 1) & !1);
+    }
+    out
+}
+
+/// IPTC-IIM 데이터셋을 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    let datasets = datasets(data);
+    let utf8 = datasets
+        .iter()
+        .any(|&(record, number, value)| record == 1 && number == 90 && value == UTF8_ESCAPE);
+
+    // 반복 가능한 데이터셋: (이름, 첫 값의 인코딩, 값). 처음 나온 순서대로
+    let mut lists: Vec<(&str, &str, Vec<String>)> = Vec::new();
+    for (record, number, value) in datasets {
+        if record != 2 {
+            continue;
+        }
+        let Some(&(_, name, repeatable)) = DATASETS.iter().find(|&&(n, _, _)| n == number) else {
+            continue;
+        };
+        // 문자셋 선언이 없으면 기록한 프로그램의 코드 페이지(CP949 등)를 추정합니다.
+        let (value, encoding) = if utf8 {
+            (String::from_utf8_lossy(value).into_owned(), text::UTF8)
+        } else {
+            text::decode_legacy(value)
+        };
+        let value = text::trim_nul(&value).trim();
+        let key = if repeatable {
+            let index = match lists.iter().position(|&(list, _, _)| list == name) {
+                Some(index) => index,
+                None => {
+                    lists.push((name, encoding, Vec::new()));
+                    lists.len() - 1
+                }
+            };
+            let values = &mut lists[index].2;
+            values.push(value.to_owned());
+            format!("IPTC:{name}[{}]", values.len())
+        } else {
+            format!("IPTC:{name}")
+        };
+        metadata.scoped(Provenance::default().encoding(encoding), |metadata| {
+            metadata.insert(key, value)
+        });
+    }
+
+    // 모은 값은 항목 필드와 함께 목록 값 하나로도 둡니다.
+    for (name, encoding, values) in lists {
+        let (text, value) = MetadataValue::list(values);
+        metadata.scoped(Provenance::default().encoding(encoding), |metadata| {
+            metadata.insert_typed(format!("IPTC:{name}"), text, value)
+        });
+    }
+}
+
+// 데이터셋: 0x1C + 레코드 번호 + 데이터셋 번호 + 길이(2) + 값
+// 길이의 최상위 비트가 켜져 있으면 나머지 비트가 실제 길이를 담은 바이트 수입니다.
+fn datasets(data: &[u8]) -> Vec<(u8, u8, &[u8])> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    while let Some(&[marker, record, number, high, low]) = data.get(offset..offset + 5) {
+        if marker != TAG_MARKER {
+            break;
+        }
+        offset += 5;
+        let mut size = u16::from_be_bytes([high, low]) as usize;
+        if size & 0x8000 != 0 {
+            let length_size = size & 0x7fff;
+            let Some(bytes) = data
+                .get(offset..offset + length_size)
+                .filter(|b| b.len() <= 4)
+            else {
+                break;
+            };
+            size = bytes.iter().fold(0, |acc, &b| (acc << 8) | b as usize);
+            offset += length_size;
+        }
+        let Some(value) = data.get(offset..offset + size) else {
+            break;
+        };
+        out.push((record, number, value));
+        offset += size;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::testing::segment;
+    use crate::metadata::{extract, MetadataValue};
+
+    fn dataset(record: u8, number: u8, value: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x1c, record, number];
+        out.extend((value.len() as u16).to_be_bytes());
+        out.extend(value);
+        out
+    }
+
+    // IPTC-IIM을 담은 Photoshop 이미지 리소스(0x0404) APP13
+    fn jpeg(iim: &[u8]) -> Vec<u8> {
+        let mut payload = b"Photoshop 3.0\08BIM\x04\x04\0\0".to_vec();
+        payload.extend((iim.len() as u32).to_be_bytes());
+        payload.extend(iim);
+        if iim.len() % 2 == 1 {
+            payload.push(0);
+        }
+        let mut out = vec![0xff, 0xd8];
+        segment(&mut out, 0xed, &payload);
+        out.extend([0xff, 0xd9]);
+        out
+    }
+
+    #[test]
+    fn utf8_datasets() {
+        // 1:90 CodedCharacterSet = UTF-8
+        let mut iim = dataset(1, 90, b"\x1b%G");
+        iim.extend(dataset(2, 25, "고양이".as_bytes()));
+        iim.extend(dataset(2, 25, b"white background"));
+        iim.extend(dataset(2, 120, b"caption here"));
+        iim.extend(dataset(2, 105, b"Head"));
+
+        let metadata = extract(&jpeg(&iim));
+        assert_eq!(metadata.get("IPTC:Keywords[1]"), Some("고양이"));
+        assert_eq!(metadata.get("IPTC:Keywords[2]"), Some("white background"));
+        let keywords = metadata.field("IPTC:Keywords").unwrap();
+        assert_eq!(keywords.text, "고양이, white background");
+        assert_eq!(
+            keywords.value,
+            MetadataValue::List(vec![
+                MetadataValue::Text("고양이".into()),
+                MetadataValue::Text("white background".into())
+            ])
+        );
+        assert_eq!(
+            metadata.field("IPTC:Headline").unwrap().value,
+            MetadataValue::Text("Head".into())
+        );
+        assert_eq!(metadata.get("IPTC:Caption-Abstract"), Some("caption here"));
+        assert_eq!(metadata.get("IPTC:Headline"), Some("Head"));
+    }
+
+    #[test]
+    fn truncated_dataset() {
+        let mut iim = dataset(2, 105, b"Head");
+        iim.extend(&dataset(2, 120, b"caption here")[..8]);
+
+        let metadata = extract(&jpeg(&iim));
+        assert_eq!(metadata.get("IPTC:Headline"), Some("Head"));
+        assert_eq!(metadata.get("IPTC:Caption-Abstract"), None);
+    }
+}
@@ -0,0 +1,579 @@
+//! ISOBMFF(AVIF/HEIF) 컨테이너 파서
+//!
+//! `meta` 박스의 `iinf`/`iloc`/`idat`으로 아이템 위치를 찾아 `Exif` 아이템은
+//! EXIF 파서로, `mime`(application/rdf+xml) 아이템은 XMP 파서로 넘깁니다.
+//! 최상위 박스와 읽지 못한 아이템은 진단 보고서에 기록합니다.
+
+use std::ops::Range;
+
+use super::{exif, text, xmp, Block, Issue, Metadata, Provenance, Recovery};
+
+// HEIF 계열 브랜드 (AVIF, HEIC, 일반 이미지 컬렉션)
+const IMAGE_BRANDS: &[&[u8; 4]] = &[
+    b"avif", b"avis", b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1", b"msf1",
+];
+
+// 아이템 하나의 크기 상한. 익스텐트마다 파일 끝까지를 가리키는 아이템 방지
+const MAX_ITEM_SIZE: usize = 64 * 1024 * 1024;
+
+/// ftyp 박스의 주 브랜드나 호환 브랜드가 HEIF 계열인지 확인합니다.
+pub fn is_heif(data: &[u8]) -> bool {
+    let Some(ftyp) = boxes(data).next().filter(|b| &b.kind == b"ftyp") else {
+        return false;
+    };
+    // major brand(4) + minor version(4) + compatible brands(4 * n)
+    let compatible = ftyp.data.get(8..).unwrap_or(&[]).chunks_exact(4);
+    let mut brands = ftyp.data.get(..4).into_iter().chain(compatible);
+    brands.any(|brand| IMAGE_BRANDS.iter().any(|b| b.as_slice() == brand))
+}
+
+/// 박스 하나
+#[derive(Debug, Clone, Copy)]
+pub struct Atom<'a> {
+    pub kind: [u8; 4],
+    /// 헤더 이후의 내용
+    pub data: &'a [u8],
+    /// 상위 데이터 안에서 `data`가 시작하는 위치
+    pub offset: usize,
+    /// 데이터가 잘려 `data`가 박스의 앞부분뿐이면 헤더를 뺀 원래 길이
+    pub truncated: Option<usize>,
+    header_size: usize,
+}
+
+impl Atom<'_> {
+    /// 헤더를 포함한 박스 전체의 위치
+    pub fn range(&self) -> Range<usize> {
+        self.offset - self.header_size..self.offset + self.data.len()
+    }
+
+    /// 진단 보고서에 남길 `{prefix}:{type}` 블록. 잘린 박스에서 읽은 필드 수를 `fields`로 받습니다.
+    pub fn block(&self, prefix: &str, fields: usize) -> Block {
+        let name = format!("{prefix}:{}", text::latin1(&self.kind).trim_end());
+        let mut block = Block::new(name, self.range());
+        if let Some(expected) = self.truncated {
+            block = block.issue(Issue::Truncated {
+                expected,
+                available: self.data.len(),
+            });
+            if fields > 0 {
+                block = block.recovery(Recovery::Partial { fields });
+            }
+        }
+        block
+    }
+}
+
+/// 같은 수준의 박스를 순서대로 돌려줍니다.
+/// 데이터 끝을 넘는 박스는 남은 부분만 `truncated`로 돌려준 뒤 멈춥니다.
+pub fn boxes(data: &[u8]) -> impl Iterator<Item = Atom<'_>> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        // size(4) + type(4) [+ largesize(8)]
+        let header = data.get(offset..offset + 8)?;
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (header_size, size) =
+            match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                0 => (8, data.len() - offset),
+                1 => {
+                    let large = data.get(offset + 8..offset + 16)?;
+                    let large = u64::from_be_bytes(large.try_into().ok()?);
+                    (16, usize::try_from(large).ok()?)
+                }
+                size => (8, size as usize),
+            };
+        if size < header_size {
+            return None;
+        }
+        let end = offset.checked_add(size)?;
+        let atom = Atom {
+            kind,
+            data: data.get(offset + header_size..end.min(data.len()))?,
+            offset: offset + header_size,
+            truncated: (end > data.len()).then_some(size - header_size),
+            header_size,
+        };
+        offset = end.min(data.len());
+        Some(atom)
+    })
+}
+
+/// 마지막 박스가 `end`에서 끝난 뒤 박스 헤더로 읽을 수 없는 바이트가 남았으면
+/// 그 범위를 `name` 블록으로 돌려줍니다.
+pub fn trailing_block(data: &[u8], end: usize, name: &str) -> Option<Block> {
+    (end < data.len()).then(|| Block::new(name, end..data.len()).issue(Issue::Corrupt))
+}
+
+/// 주어진 종류의 첫 번째 자식 박스를 찾습니다.
+pub fn find<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<Atom<'a>> {
+    boxes(data).find(|b| &b.kind == kind)
+}
+
+/// `meta` 박스의 메타데이터 아이템을 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    let mut end = 0;
+    let mut read_meta = false;
+    for atom in boxes(data) {
+        let before = metadata.len();
+        if &atom.kind == b"meta" && !read_meta {
+            read_meta = true;
+            read_items(data, atom, metadata);
+        }
+        metadata.record(atom.block("HEIF", metadata.len() - before));
+        end = atom.range().end;
+    }
+    if let Some(block) = trailing_block(data, end, "HEIF") {
+        metadata.record(block);
+    }
+}
+
+fn read_items(data: &[u8], meta: Atom, metadata: &mut Metadata) {
+    for item in items(data, meta, metadata) {
+        // 아이템은 여러 익스텐트로 나뉠 수 있어 바이트 범위는 기록하지 않습니다.
+        let provenance = Provenance::new(item.kind.name());
+        match item.kind {
+            ItemKind::Exif => metadata.scoped(provenance, |metadata| {
+                exif::read_with_offset(&item.data, metadata)
+            }),
+            ItemKind::Xmp => {
+                metadata.scoped(provenance, |metadata| xmp::read(&item.data, metadata))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    Exif,
+    Xmp,
+}
+
+impl ItemKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Exif => "HEIF:Exif",
+            Self::Xmp => "HEIF:mime",
+        }
+    }
+}
+
+struct Item {
+    kind: ItemKind,
+    data: Vec<u8>,
+}
+
+fn items(file: &[u8], meta: Atom, metadata: &mut Metadata) -> Vec<Item> {
+    // meta는 FullBox: version(1) + flags(3) 이후에 자식 박스
+    let Some(children) = meta.data.get(4..) else {
+        return Vec::new();
+    };
+    let (Some(iinf), Some(iloc)) = (find(children, b"iinf"), find(children, b"iloc")) else {
+        return Vec::new();
+    };
+    // idat 내용과 파일 안에서의 시작 위치
+    let idat = find(children, b"idat").map_or((&[][..], 0), |idat| {
+        (idat.data, meta.offset + 4 + idat.offset)
+    });
+    let locations = locations(iloc.data);
+
+    item_infos(iinf.data)
+        .into_iter()
+        .filter_map(|(id, kind)| {
+            let location = locations.iter().find(|l| l.id == id)?;
+            let data = location.read(file, idat, kind, metadata)?;
+            Some(Item { kind, data })
+        })
+        .collect()
+}
+
+// iinf: FullBox + entry_count + infe 박스들
+fn item_infos(iinf: &[u8]) -> Vec<(u32, ItemKind)> {
+    let Some(&version) = iinf.first() else {
+        return Vec::new();
+    };
+    let entries = if version == 0 {
+        iinf.get(6..)
+    } else {
+        iinf.get(8..)
+    };
+    boxes(entries.unwrap_or(&[]))
+        .filter(|b| &b.kind == b"infe")
+        .filter_map(|infe| item_info(infe.data))
+        .collect()
+}
+
+// infe v2/v3: item_ID + protection_index(2) + item_type(4) + item_name\0 [+ content_type\0]
+fn item_info(infe: &[u8]) -> Option<(u32, ItemKind)> {
+    let mut reader = Reader::new(infe);
+    let version = reader.uint(1)?;
+    reader.skip(3)?;
+    let id = match version {
+        2 => reader.uint(2)?,
+        3 => reader.uint(4)?,
+        _ => return None,
+    };
+    reader.skip(2)?;
+    let item_type = reader.bytes(4)?;
+    let _name = reader.c_str()?;
+    let kind = match item_type {
+        b"Exif" => ItemKind::Exif,
+        b"mime" if reader.c_str()? == b"application/rdf+xml" => ItemKind::Xmp,
+        _ => return None,
+    };
+    Some((id as u32, kind))
+}
+
+struct Location {
+    id: u32,
+    construction_method: u64,
+    base_offset: u64,
+    extents: Vec<(u64, u64)>,
+}
+
+impl Location {
+    // 익스텐트를 이어 붙입니다. 데이터 밖을 가리키거나 상한을 넘는 아이템은 읽지 않고
+    // 진단 보고서에 남깁니다.
+    fn read(
+        &self,
+        file: &[u8],
+        (idat, idat_start): (&[u8], usize),
+        kind: ItemKind,
+        metadata: &mut Metadata,
+    ) -> Option<Vec<u8>> {
+        let (source, source_start) = match self.construction_method {
+            0 => (file, 0),
+            1 => (idat, idat_start),
+            _ => return None,
+        };
+        // 원본 안의 위치를 파일 위치로 바꿉니다.
+        let position = |at: u64| source_start + at.min(source.len() as u64) as usize;
+        let mut out = Vec::new();
+        for &(offset, length) in &self.extents {
+            let start = self.base_offset.saturating_add(offset);
+            // 길이 0은 원본 끝까지를 의미합니다.
+            let end = if length == 0 {
+                (source.len() as u64).max(start)
+            } else {
+                start.saturating_add(length)
+            };
+            let range = position(start)..position(end);
+            let Some(extent) = source
+                .get(range.start - source_start..range.end - source_start)
+                .filter(|extent| extent.len() as u64 == end - start)
+            else {
+                metadata.record(
+                    Block::new(kind.name(), range.clone()).issue(Issue::Truncated {
+                        expected: usize::try_from(end - start).unwrap_or(usize::MAX),
+                        available: range.len(),
+                    }),
+                );
+                return None;
+            };
+            if out.len() + extent.len() > MAX_ITEM_SIZE {
+                metadata.record(Block::new(kind.name(), range).issue(Issue::TooLarge {
+                    limit: MAX_ITEM_SIZE,
+                }));
+                return None;
+            }
+            out.extend_from_slice(extent);
+        }
+        Some(out)
+    }
+}
+
+// iloc: FullBox + 필드 크기(offset/length/base_offset/index, 각 4비트) + item_count + 아이템들
+fn locations(iloc: &[u8]) -> Vec<Location> {
+    let mut out = Vec::new();
+    let mut reader = Reader::new(iloc);
+    let Some(version) = reader.uint(1) else {
+        return out;
+    };
+    let (Some(_), Some(sizes)) = (reader.skip(3), reader.uint(2)) else {
+        return out;
+    };
+    let layout = IlocLayout {
+        version,
+        offset_size: (sizes >> 12) as usize & 0xf,
+        length_size: (sizes >> 8) as usize & 0xf,
+        base_offset_size: (sizes >> 4) as usize & 0xf,
+        index_size: if version >= 1 {
+            sizes as usize & 0xf
+        } else {
+            0
+        },
+    };
+    let count = if version < 2 {
+        reader.uint(2)
+    } else {
+        reader.uint(4)
+    };
+    for _ in 0..count.unwrap_or(0) {
+        match location(&mut reader, &layout) {
+            Some(location) => out.push(location),
+            None => break,
+        }
+    }
+    out
+}
+
+struct IlocLayout {
+    version: u64,
+    offset_size: usize,
+    length_size: usize,
+    base_offset_size: usize,
+    index_size: usize,
+}
+
+fn location(reader: &mut Reader, layout: &IlocLayout) -> Option<Location> {
+    let id = if layout.version < 2 {
+        reader.uint(2)?
+    } else {
+        reader.uint(4)?
+    };
+    let construction_method = if layout.version >= 1 {
+        reader.uint(2)? & 0xf
+    } else {
+        0
+    };
+    reader.skip(2)?; // data_reference_index
+    let base_offset = reader.uint(layout.base_offset_size)?;
+    let extent_count = reader.uint(2)?;
+    let mut extents = Vec::new();
+    for _ in 0..extent_count {
+        reader.uint(layout.index_size)?;
+        extents.push((
+            reader.uint(layout.offset_size)?,
+            reader.uint(layout.length_size)?,
+        ));
+    }
+    Some(Location {
+        id: id as u32,
+        construction_method,
+        base_offset,
+        extents,
+    })
+}
+
+/// 빅 엔디언 순차 읽기 도우미
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn bytes(&mut self, size: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset.checked_add(size)?)?;
+        self.offset += size;
+        Some(bytes)
+    }
+
+    fn skip(&mut self, size: usize) -> Option<()> {
+        self.bytes(size).map(|_| ())
+    }
+
+    /// `size`바이트(0~8) 부호 없는 정수. 0바이트면 0입니다.
+    fn uint(&mut self, size: usize) -> Option<u64> {
+        if size > 8 {
+            return None;
+        }
+        Some(
+            self.bytes(size)?
+                .iter()
+                .fold(0, |acc, &b| (acc << 8) | b as u64),
+        )
+    }
+
+    fn c_str(&mut self) -> Option<&'a [u8]> {
+        let rest = self.data.get(self.offset..)?;
+        let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+        self.offset += (end + 1).min(rest.len());
+        Some(&rest[..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::{bmff_box, full_box, tiff, xmp};
+
+    // infe v2: 항목 ID, 보호 인덱스, 항목 형식, 이름, (mime이면) 콘텐츠 형식
+    fn infe(id: u16, kind: &[u8; 4], content_type: &str) -> Vec<u8> {
+        let mut body = id.to_be_bytes().to_vec();
+        body.extend([0, 0]);
+        body.extend(kind);
+        body.push(0);
+        if !content_type.is_empty() {
+            body.extend(content_type.as_bytes());
+            body.push(0);
+        }
+        full_box(b"infe", 2, &body)
+    }
+
+    // iloc v1 (오프셋·길이 4바이트, 기준 오프셋 없음). 항목마다 (ID, 구성 방식, 오프셋, 길이)
+    fn iloc(items: &[(u16, u16, u32, u32)]) -> Vec<u8> {
+        let mut body = vec![0x44, 0x00];
+        body.extend((items.len() as u16).to_be_bytes());
+        for &(id, construction, offset, length) in items {
+            body.extend(id.to_be_bytes());
+            body.extend(construction.to_be_bytes());
+            body.extend(0u16.to_be_bytes());
+            body.extend(1u16.to_be_bytes());
+            body.extend(offset.to_be_bytes());
+            body.extend(length.to_be_bytes());
+        }
+        full_box(b"iloc", 1, &body)
+    }
+
+    /// EXIF 항목은 mdat에, XMP 항목은 meta 안의 idat에 둔 AVIF
+    fn avif(exif: &[u8], packet: &[u8]) -> Vec<u8> {
+        let mut iinf = 2u16.to_be_bytes().to_vec();
+        iinf.extend(infe(1, b"Exif", ""));
+        iinf.extend(infe(2, b"mime", "application/rdf+xml"));
+        let build = |exif_offset: u32| {
+            let mut meta = full_box(b"hdlr", 0, b"\0\0\0\0pict\0\0\0\0\0\0\0\0\0\0\0\0\0");
+            meta.extend(full_box(b"iinf", 0, &iinf));
+            meta.extend(iloc(&[
+                (1, 0, exif_offset, exif.len() as u32),
+                (2, 1, 0, packet.len() as u32),
+            ]));
+            meta.extend(bmff_box(b"idat", packet));
+            let mut out = bmff_box(b"ftyp", b"avif\0\0\0\0mif1avif");
+            out.extend(full_box(b"meta", 0, &meta));
+            out
+        };
+        // mdat 본문은 meta 뒤, mdat 헤더(8바이트) 다음에 옵니다.
+        let mdat_start = build(0).len() as u32 + 8;
+        let mut out = build(mdat_start);
+        out.extend(bmff_box(b"mdat", exif));
+        out
+    }
+
+    // EXIF 항목은 TIFF 헤더까지의 오프셋(4바이트) 뒤에 `Exif\0\0`와 TIFF가 옵니다.
+    fn exif_item() -> Vec<u8> {
+        let mut out = 6u32.to_be_bytes().to_vec();
+        out.extend(b"Exif\0\0");
+        out.extend(tiff(&[(0x010e, 2, 11, b"hello heif\0".to_vec())]));
+        out
+    }
+
+    #[test]
+    fn avif_items() {
+        let packet = xmp("<dc:title>avif xmp</dc:title>");
+        let data = avif(&exif_item(), packet.as_bytes());
+        assert!(is_heif(&data));
+
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("EXIF:ImageDescription"), Some("hello heif"));
+        assert_eq!(metadata.get("XMP:dc:title"), Some("avif xmp"));
+    }
+
+    #[test]
+    fn item_outside_file_is_skipped() {
+        let packet = xmp("<dc:title>avif xmp</dc:title>");
+        let mut data = avif(&exif_item(), packet.as_bytes());
+        // mdat을 잘라 EXIF 항목이 파일 밖을 가리키게 합니다.
+        data.truncate(data.len() - 10);
+
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("EXIF:ImageDescription"), None);
+        assert_eq!(metadata.get("XMP:dc:title"), Some("avif xmp"));
+
+        let blocks = &metadata.report().blocks;
+        let exif = blocks.iter().find(|b| b.name == "HEIF:Exif").unwrap();
+        let expected = exif_item().len();
+        assert_eq!(
+            exif.issue,
+            Some(Issue::Truncated {
+                expected,
+                available: expected - 10
+            })
+        );
+        let mdat = blocks.last().unwrap();
+        assert_eq!(mdat.name, "HEIF:mdat");
+        assert!(matches!(mdat.issue, Some(Issue::Truncated { .. })));
+    }
+
+    #[test]
+    fn truncated_meta_recovers() {
+        let item = exif_item();
+        let mut iinf = 1u16.to_be_bytes().to_vec();
+        iinf.extend(infe(1, b"Exif", ""));
+        let mut meta = full_box(b"iinf", 0, &iinf);
+        meta.extend(iloc(&[(1, 1, 0, item.len() as u32)]));
+        meta.extend(bmff_box(b"idat", &item));
+        meta.extend(bmff_box(b"free", &[0; 16]));
+        let mut data = bmff_box(b"ftyp", b"avif\0\0\0\0mif1avif");
+        data.extend(full_box(b"meta", 0, &meta));
+        // meta 끝의 free 박스가 잘려도 idat 안의 EXIF 항목은 읽습니다.
+        data.truncate(data.len() - 10);
+
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("EXIF:ImageDescription"), Some("hello heif"));
+        let block = metadata.report().blocks.last().unwrap();
+        assert_eq!(block.name, "HEIF:meta");
+        assert!(matches!(block.issue, Some(Issue::Truncated { .. })));
+        assert_eq!(block.recovery, Some(Recovery::Partial { fields: 1 }));
+    }
+
+    #[test]
+    fn oversized_item_is_skipped() {
+        let mut iinf = 1u16.to_be_bytes().to_vec();
+        iinf.extend(infe(1, b"Exif", ""));
+        // 길이 0(파일 끝까지) 익스텐트 65535개
+        let mut iloc = vec![0x44, 0x00];
+        iloc.extend(1u16.to_be_bytes());
+        iloc.extend([0, 1, 0, 0, 0, 0]);
+        iloc.extend(u16::MAX.to_be_bytes());
+        for _ in 0..u16::MAX {
+            iloc.extend([0; 8]);
+        }
+        let mut meta = full_box(b"iinf", 0, &iinf);
+        meta.extend(full_box(b"iloc", 1, &iloc));
+        let mut data = bmff_box(b"ftyp", b"avif\0\0\0\0mif1avif");
+        data.extend(full_box(b"meta", 0, &meta));
+
+        let metadata = extract(&data);
+        assert!(metadata.is_empty());
+        let names: Vec<_> = metadata
+            .report()
+            .blocks
+            .iter()
+            .map(|block| block.name.as_str())
+            .collect();
+        assert_eq!(names, ["HEIF:ftyp", "HEIF:Exif", "HEIF:meta"]);
+        let block = &metadata.report().blocks[1];
+        assert_eq!(block.byte_range, 0..data.len());
+        assert_eq!(
+            block.issue,
+            Some(Issue::TooLarge {
+                limit: MAX_ITEM_SIZE
+            })
+        );
+    }
+
+    #[test]
+    fn box_sizes() {
+        let mut data = bmff_box(b"ftyp", b"heic\0\0\0\0mif1heic");
+        // 64비트 길이
+        data.extend(1u32.to_be_bytes());
+        data.extend(b"free");
+        data.extend(20u64.to_be_bytes());
+        data.extend([0; 4]);
+        // 길이 0은 파일 끝까지
+        data.extend(0u32.to_be_bytes());
+        data.extend(b"mdat");
+        data.extend([1, 2, 3]);
+
+        let atoms: Vec<_> = boxes(&data).collect();
+        let kinds: Vec<_> = atoms.iter().map(|atom| &atom.kind).collect();
+        assert_eq!(kinds, [b"ftyp", b"free", b"mdat"]);
+        assert_eq!(atoms[1].data, [0; 4]);
+        assert_eq!(atoms[2].data, [1, 2, 3]);
+        assert!(is_heif(&data));
+        assert!(find(&data, b"meta").is_none());
+    }
+}
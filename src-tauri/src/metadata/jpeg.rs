@@ -0,0 +1,560 @@
+//! JPEG 마커 세그먼트 파서
+//!
+//! 모든 APPn 세그먼트를 식별자로 구분해 `JPEG:Segments`에 기록하고, 알려진 형식은
+//! 각 파서로 넘깁니다. COM 세그먼트와 식별자를 모르는 APPn의 텍스트 내용도
+//! 키워드 규칙이 찾을 수 있도록 필드로 추가합니다.
+//! 방문한 세그먼트와 잘리거나 손상된 곳은 진단 보고서에 기록합니다.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use super::{c2pa, exif, icc, iptc, mpf, text, xmp, Block, Issue, Metadata, Provenance, Recovery};
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const EXTENDED_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+const ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+const MPF_HEADER: &[u8] = b"MPF\0";
+const DUCKY_HEADER: &[u8] = b"Ducky";
+const PHOTOSHOP_HEADER: &[u8] = b"Photoshop 3.0\0";
+// APP11 JUMBF (C2PA): 공통 식별자 "JP"(2) + 인스턴스 번호(2) + 패킷 순번(4) + 박스
+const JUMBF_HEADER: &[u8] = b"JP";
+
+// 텍스트 없이 구조 정보만 담는 세그먼트
+const STRUCTURAL_SEGMENTS: &[&str] = &["JFIF", "JFXX", "Adobe"];
+
+const MARKER_SOS: u8 = 0xda;
+const MARKER_EOI: u8 = 0xd9;
+const MARKER_COM: u8 = 0xfe;
+const MARKER_APP0: u8 = 0xe0;
+const MARKER_APP11: u8 = 0xeb;
+const MARKER_APP15: u8 = 0xef;
+
+pub fn is_jpeg(data: &[u8]) -> bool {
+    data.starts_with(&[0xff, 0xd8, 0xff])
+}
+
+/// 길이 필드가 있는 마커 세그먼트 하나
+#[derive(Debug, Clone, Copy)]
+pub struct Segment<'a> {
+    pub marker: u8,
+    /// 길이 필드 이후의 내용
+    pub data: &'a [u8],
+    /// 파일 안에서 `data`가 시작하는 위치
+    pub offset: usize,
+    /// 파일이 잘려 `data`가 세그먼트의 앞부분뿐이면 원래 내용 길이
+    pub truncated: Option<usize>,
+    /// 앞 세그먼트와 이 세그먼트 사이에서 건너뛴 손상된 바이트 수
+    pub skipped: usize,
+}
+
+impl Segment<'_> {
+    pub fn is_app(&self) -> bool {
+        (MARKER_APP0..=MARKER_APP15).contains(&self.marker)
+    }
+
+    /// APPn 세그먼트 앞의 식별자 문자열 (`JFIF`, `Exif`, `ICC_PROFILE` 등)
+    pub fn identifier(&self) -> &str {
+        let end = self
+            .data
+            .iter()
+            .take(64)
+            .position(|&b| b == 0 || !(0x20..0x7f).contains(&b))
+            .unwrap_or(self.data.len().min(64));
+        // 출력 가능한 ASCII만 포함하므로 항상 UTF-8입니다.
+        std::str::from_utf8(&self.data[..end]).unwrap_or_default()
+    }
+
+    /// `JPEG:APP1` 등
+    pub fn name(&self) -> String {
+        format!("JPEG:{}", marker_name(self.marker))
+    }
+
+    /// 마커와 길이 필드를 포함한 세그먼트 전체의 위치
+    pub fn range(&self) -> Range<usize> {
+        self.offset - 4..self.offset + self.data.len()
+    }
+
+    /// 이 세그먼트에서 읽은 필드의 출처 (`JPEG:APP1` 등)
+    pub fn provenance(&self) -> Provenance {
+        Provenance::new(self.name()).range(self.offset..self.offset + self.data.len())
+    }
+}
+
+fn marker_name(marker: u8) -> String {
+    match marker {
+        MARKER_COM => "COM".to_owned(),
+        MARKER_APP0..=MARKER_APP15 => format!("APP{}", marker - MARKER_APP0),
+        MARKER_SOS => "SOS".to_owned(),
+        0xc4 => "DHT".to_owned(),
+        0xcc => "DAC".to_owned(),
+        0xdb => "DQT".to_owned(),
+        0xdd => "DRI".to_owned(),
+        0xc0..=0xcf => format!("SOF{}", marker - 0xc0),
+        _ => format!("0x{marker:02X}"),
+    }
+}
+
+/// SOI 이후의 세그먼트를 EOI까지 순서대로 돌려줍니다.
+/// SOS 이후의 엔트로피 부호화 데이터는 다음 마커가 나올 때까지 건너뜁니다.
+/// 마커가 있어야 할 곳이 손상되었으면 다음 마커를 찾아 건너뛰고, 파일 끝을 넘는 세그먼트는
+/// 남은 부분만 `truncated`로 돌려준 뒤 멈춥니다.
+pub fn segments(data: &[u8]) -> impl Iterator<Item = Segment<'_>> {
+    let mut offset = if is_jpeg(data) { 2 } else { data.len() };
+    std::iter::from_fn(move || {
+        let mut skipped = 0;
+        loop {
+            if *data.get(offset)? != 0xff {
+                let next = resync(data, offset)?;
+                skipped += next - offset;
+                offset = next;
+            }
+            // 마커 앞의 채움 바이트(0xFF) 건너뛰기
+            while data.get(offset + 1) == Some(&0xff) {
+                offset += 1;
+            }
+            let marker = *data.get(offset + 1)?;
+            match marker {
+                // 길이 필드가 없는 단독 마커 (TEM, RSTn)
+                0x01 | 0xd0..=0xd7 => {
+                    offset += 2;
+                    continue;
+                }
+                MARKER_EOI => return None,
+                _ => {}
+            }
+            let &[high, low] = data.get(offset + 2..offset + 4)? else {
+                return None;
+            };
+            let length = u16::from_be_bytes([high, low]) as usize;
+            if length < 2 {
+                // 길이 필드가 손상된 세그먼트는 다음 마커부터 다시 읽습니다.
+                let next = resync(data, offset + 2)?;
+                skipped += next - offset;
+                offset = next;
+                continue;
+            }
+            let start = offset + 4;
+            let end = offset + 2 + length;
+            let mut segment = Segment {
+                marker,
+                data: &data[start.min(data.len())..end.min(data.len())],
+                offset: start,
+                truncated: None,
+                skipped,
+            };
+            if end > data.len() {
+                segment.truncated = Some(length - 2);
+                offset = data.len();
+            } else if marker == MARKER_SOS {
+                offset = skip_scan(data, end);
+            } else {
+                offset = end;
+            }
+            return Some(segment);
+        }
+    })
+}
+
+// `start` 이후에서 세그먼트를 시작하는 마커의 위치
+fn resync(data: &[u8], start: usize) -> Option<usize> {
+    (start..data.len().saturating_sub(1)).find(|&offset| {
+        data[offset] == 0xff && matches!(data[offset + 1], 0xc0..=0xcf | 0xd9..=0xfe)
+    })
+}
+
+// 스캔 데이터 안의 0xFF는 0x00(바이트 스터핑)이나 RSTn 마커만 뒤따릅니다.
+fn skip_scan(data: &[u8], start: usize) -> usize {
+    let mut offset = start;
+    while offset + 1 < data.len() {
+        if data[offset] == 0xff && !matches!(data[offset + 1], 0x00 | 0xd0..=0xd7 | 0xff) {
+            return offset;
+        }
+        offset += 1;
+    }
+    data.len()
+}
+
+/// 세그먼트의 메타데이터를 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    let mut segment_names = Vec::new();
+    let mut comments = Vec::new();
+    let mut extended_xmp = BTreeMap::new();
+    let mut photoshop = Vec::new();
+    let mut icc_parts = Vec::new();
+    let mut jumbf_parts: BTreeMap<u16, Vec<(u32, &[u8])>> = BTreeMap::new();
+
+    let mut last = None;
+    for segment in segments(data) {
+        let range = segment.range();
+        if segment.skipped > 0 {
+            let skipped = range.start - segment.skipped..range.start;
+            let issue = header_issue(&data[skipped.clone()]);
+            metadata.record(
+                Block::new("JPEG", skipped)
+                    .issue(issue)
+                    .recovery(Recovery::Resynced { next: range.start }),
+            );
+        }
+        let before = metadata.len();
+        'segment: {
+            if segment.marker == MARKER_COM {
+                segment_names.push("COM".to_owned());
+                comments.push(text::decode_legacy(segment.data));
+                break 'segment;
+            }
+            if !segment.is_app() {
+                break 'segment;
+            }
+            let app = segment.marker - MARKER_APP0;
+            let identifier = segment.identifier();
+            let is_jumbf = segment.marker == MARKER_APP11 && segment.data.starts_with(JUMBF_HEADER);
+            segment_names.push(match segment_name(segment.data) {
+                _ if is_jumbf => format!("APP{app}:JUMBF"),
+                Some(name) => format!("APP{app}:{name}"),
+                None if identifier.is_empty() => format!("APP{app}"),
+                None => format!("APP{app}:{identifier}"),
+            });
+
+            let payload = segment.data;
+            metadata.scoped(segment.provenance(), |metadata| {
+                if let Some(tiff) = payload.strip_prefix(EXIF_HEADER) {
+                    exif::read(tiff, metadata);
+                } else if let Some(packet) = payload.strip_prefix(XMP_HEADER) {
+                    xmp::read(packet, metadata);
+                } else if let Some(part) = payload.strip_prefix(EXTENDED_XMP_HEADER) {
+                    add_extended_xmp(part, &mut extended_xmp);
+                } else if let Some(part) = payload.strip_prefix(ICC_HEADER) {
+                    // 순번(1) + 전체 개수(1) + 조각
+                    if let Some((&sequence, rest)) = part.split_first() {
+                        icc_parts.push((sequence, rest.get(1..).unwrap_or(&[])));
+                    }
+                } else if let Some(tiff) = payload.strip_prefix(MPF_HEADER) {
+                    mpf::read(tiff, data, segment.offset + MPF_HEADER.len(), metadata);
+                } else if let Some(records) = payload.strip_prefix(DUCKY_HEADER) {
+                    read_ducky(records, metadata);
+                } else if let Some(resources) = payload.strip_prefix(PHOTOSHOP_HEADER) {
+                    // Photoshop 이미지 리소스 (IPTC). 여러 세그먼트로 나뉘면 이어 붙입니다.
+                    photoshop.extend_from_slice(resources);
+                } else if is_jumbf {
+                    if let Some(&[instance_high, instance_low, a, b, c, d]) = payload.get(2..8) {
+                        let instance = u16::from_be_bytes([instance_high, instance_low]);
+                        let sequence = u32::from_be_bytes([a, b, c, d]);
+                        jumbf_parts
+                            .entry(instance)
+                            .or_default()
+                            .push((sequence, &payload[8..]));
+                    }
+                } else if !identifier.is_empty() && !STRUCTURAL_SEGMENTS.contains(&identifier) {
+                    read_unknown(app, identifier, payload, metadata);
+                }
+            });
+        }
+        let mut block = Block::new(segment.name(), range);
+        if let Some(expected) = segment.truncated {
+            block = block.issue(Issue::Truncated {
+                expected,
+                available: segment.data.len(),
+            });
+            if metadata.len() > before {
+                block = block.recovery(Recovery::Partial {
+                    fields: metadata.len() - before,
+                });
+            }
+        }
+        metadata.record(block);
+        last = Some(segment);
+    }
+    if let Some(last) = last.filter(|segment| segment.truncated.is_none()) {
+        record_end(data, &last, metadata);
+    }
+
+    metadata.insert("JPEG:Segments", segment_names.join(", "));
+    // 여러 세그먼트를 합친 필드는 바이트 범위 없이 세그먼트 종류만 기록합니다.
+    let joined = |marker: u8| Provenance::new(format!("JPEG:{}", marker_name(marker)));
+    // 주석마다 인코딩이 다르면 인코딩은 기록하지 않습니다.
+    let mut comment = joined(MARKER_COM);
+    if let Some((_, encoding)) = comments.first() {
+        if comments.iter().all(|(_, e)| e == encoding) {
+            comment = comment.encoding(*encoding);
+        }
+    }
+    let comments: Vec<String> = comments.into_iter().map(|(text, _)| text).collect();
+    metadata.scoped(comment, |metadata| {
+        metadata.insert("JPEG:Comment", comments.join("\n"))
+    });
+    metadata.scoped(joined(MARKER_APP0 + 13), |metadata| {
+        iptc::read_photoshop(&photoshop, metadata)
+    });
+    metadata.scoped(joined(MARKER_APP0 + 1), |metadata| {
+        for extended in extended_xmp.into_values() {
+            if let Some(packet) = extended.assemble() {
+                xmp::read(&packet, metadata);
+            }
+        }
+    });
+    if !icc_parts.is_empty() {
+        icc_parts.sort_by_key(|&(sequence, _)| sequence);
+        let profile: Vec<u8> = icc_parts
+            .iter()
+            .flat_map(|(_, part)| part.iter().copied())
+            .collect();
+        metadata.scoped(joined(MARKER_APP0 + 2), |metadata| {
+            icc::read(&profile, metadata)
+        });
+    }
+    for parts in jumbf_parts.into_values() {
+        if let Some(jumbf) = assemble_jumbf(parts) {
+            metadata.scoped(joined(MARKER_APP11), |metadata| {
+                c2pa::read(&jumbf, metadata)
+            });
+        }
+    }
+}
+
+// 마커로 시작하는데 건너뛰었다면 길이 필드가 손상된 것입니다.
+fn header_issue(header: &[u8]) -> Issue {
+    match *header {
+        [0xff, marker, high, low, ..] if marker != 0xff => Issue::InvalidLength {
+            length: u16::from_be_bytes([high, low]) as usize,
+        },
+        _ => Issue::Corrupt,
+    }
+}
+
+// 마지막 세그먼트(보통 SOS의 스캔 데이터) 뒤에 EOI가 없으면 잘렸거나 손상된 파일입니다.
+fn record_end(data: &[u8], last: &Segment, metadata: &mut Metadata) {
+    let mut end = last.range().end;
+    if last.marker == MARKER_SOS {
+        end = skip_scan(data, end);
+    }
+    while data.get(end + 1) == Some(&0xff) {
+        end += 1;
+    }
+    if data.get(end..end + 2) == Some(&[0xff, MARKER_EOI]) {
+        return;
+    }
+    if end < data.len() {
+        metadata.record(Block::new("JPEG", end..data.len()).issue(Issue::Corrupt));
+    } else {
+        metadata.record(Block::new("JPEG:EOI", end..end).issue(Issue::Missing));
+    }
+}
+
+// 첫 패킷은 박스 헤더부터 시작하고, 이후 패킷은 같은 박스 헤더(LBox + TBox [+ XLBox])를
+// 반복한 뒤 이어지는 내용을 담습니다.
+fn assemble_jumbf(mut parts: Vec<(u32, &[u8])>) -> Option<Vec<u8>> {
+    parts.sort_by_key(|&(sequence, _)| sequence);
+    parts.dedup_by_key(|&mut (sequence, _)| sequence);
+    let ((_, first), rest) = parts.split_first()?;
+    let header_size = match first.get(..4)? {
+        [0, 0, 0, 1] => 16,
+        _ => 8,
+    };
+    let mut jumbf = first.to_vec();
+    for (_, part) in rest {
+        jumbf.extend_from_slice(part.get(header_size..)?);
+    }
+    Some(jumbf)
+}
+
+// 잘 알려진 APPn 세그먼트의 표시 이름
+fn segment_name(payload: &[u8]) -> Option<&'static str> {
+    const NAMES: &[(&[u8], &str)] = &[
+        (b"JFIF\0", "JFIF"),
+        (b"JFXX\0", "JFXX"),
+        (EXIF_HEADER, "Exif"),
+        (XMP_HEADER, "XMP"),
+        (EXTENDED_XMP_HEADER, "ExtendedXMP"),
+        (ICC_HEADER, "ICC"),
+        (MPF_HEADER, "MPF"),
+        (DUCKY_HEADER, "Ducky"),
+        (PHOTOSHOP_HEADER, "Photoshop"),
+        (b"Adobe", "Adobe"),
+    ];
+    NAMES
+        .iter()
+        .find(|(header, _)| payload.starts_with(header))
+        .map(|&(_, name)| name)
+}
+
+// Ducky(Photoshop "웹용으로 저장"): 태그(2) + 길이(2) + 데이터, 태그 0에서 끝납니다.
+fn read_ducky(records: &[u8], metadata: &mut Metadata) {
+    let mut offset = 0;
+    while let Some(&[tag_high, tag_low, len_high, len_low]) = records.get(offset..offset + 4) {
+        let tag = u16::from_be_bytes([tag_high, tag_low]);
+        let length = u16::from_be_bytes([len_high, len_low]) as usize;
+        let Some(value) = records.get(offset + 4..offset + 4 + length) else {
+            break;
+        };
+        match tag {
+            0 => break,
+            1 => {
+                if let Some(&[a, b, c, d]) = value.get(..4) {
+                    metadata.insert(
+                        "Ducky:Quality",
+                        u32::from_be_bytes([a, b, c, d]).to_string(),
+                    );
+                }
+            }
+            // 문자 수(4) + UTF-16BE 문자열
+            2 | 3 => {
+                let key = if tag == 2 {
+                    "Ducky:Comment"
+                } else {
+                    "Ducky:Copyright"
+                };
+                let value = text::utf16(value.get(4..).unwrap_or(&[]), true);
+                metadata.insert(key, text::trim_nul(&value));
+            }
+            _ => {}
+        }
+        offset += 4 + length;
+    }
+}
+
+// 식별자를 모르는 APPn: 식별자 뒤의 내용이 텍스트이면 `JPEG:APPn:식별자` 필드로 추가합니다.
+fn read_unknown(app: u8, identifier: &str, payload: &[u8], metadata: &mut Metadata) {
+    let body = payload.get(identifier.len()..).unwrap_or(&[]);
+    let body = body.strip_prefix(b"\0").unwrap_or(body);
+    let Ok(body) = std::str::from_utf8(body) else {
+        return;
+    };
+    let body = text::trim_nul(body);
+    if body
+        .chars()
+        .all(|c| !c.is_control() || c.is_ascii_whitespace())
+    {
+        metadata.insert(format!("JPEG:APP{app}:{identifier}"), body.trim());
+    }
+}
+
+/// 64KB를 넘어 여러 APP1 세그먼트로 나뉜 Extended XMP
+struct ExtendedXmp<'a> {
+    length: usize,
+    parts: Vec<(usize, &'a [u8])>,
+}
+
+impl ExtendedXmp<'_> {
+    /// 모든 조각이 모였으면 하나의 패킷으로 합칩니다.
+    fn assemble(&self) -> Option<Vec<u8>> {
+        let filled: usize = self.parts.iter().map(|(_, part)| part.len()).sum();
+        if filled != self.length {
+            return None;
+        }
+        let mut packet = vec![0; self.length];
+        for &(offset, part) in &self.parts {
+            packet
+                .get_mut(offset..offset + part.len())?
+                .copy_from_slice(part);
+        }
+        Some(packet)
+    }
+}
+
+// GUID(32) + 전체 길이(4) + 오프셋(4) + 조각
+fn add_extended_xmp<'a>(part: &'a [u8], extended: &mut BTreeMap<&'a [u8], ExtendedXmp<'a>>) {
+    if part.len() < 40 {
+        return;
+    }
+    let (guid, rest) = part.split_at(32);
+    let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+    let offset = u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+    let entry = extended.entry(guid).or_insert_with(|| ExtendedXmp {
+        length,
+        parts: Vec::new(),
+    });
+    if entry.length == length && !entry.parts.iter().any(|&(o, _)| o == offset) {
+        entry.parts.push((offset, &rest[8..]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::segment;
+
+    // Ducky 레코드: 품질(1), 주석(2, UTF-16BE)
+    fn ducky() -> Vec<u8> {
+        let mut out = b"Ducky".to_vec();
+        out.extend([0, 1, 0, 4, 0, 0, 0, 80]);
+        out.extend([0, 2, 0, 10, 0, 0, 0, 3]);
+        for unit in "hi!".encode_utf16() {
+            out.extend(unit.to_be_bytes());
+        }
+        out.extend([0, 0]);
+        out
+    }
+
+    fn sample() -> Vec<u8> {
+        let mut out = vec![0xff, 0xd8];
+        segment(&mut out, 0xe0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        segment(&mut out, MARKER_COM, b"NovelAI prompt: 1girl");
+        segment(&mut out, 0xec, &ducky());
+        segment(&mut out, 0xe5, b"MyTool\0white background, cat");
+        segment(&mut out, 0xe9, b"\x01\x02\x03\xff");
+        segment(&mut out, MARKER_SOS, &[1, 1, 0, 0, 0x3f, 0]);
+        // 바이트 스터핑(FF 00)과 RST 마커는 엔트로피 부호화 데이터의 일부입니다.
+        out.extend([0x12, 0xff, 0x00, 0x34, 0xff, 0xd0, 0x56]);
+        segment(&mut out, MARKER_COM, b"after scan");
+        out.extend([0xff, 0xd9]);
+        out
+    }
+
+    #[test]
+    fn segment_order() {
+        let data = sample();
+        let markers: Vec<_> = segments(&data).map(|segment| segment.name()).collect();
+        assert_eq!(
+            markers,
+            [
+                "JPEG:APP0",
+                "JPEG:COM",
+                "JPEG:APP12",
+                "JPEG:APP5",
+                "JPEG:APP9",
+                "JPEG:SOS",
+                "JPEG:COM"
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_and_app_segments() {
+        let metadata = extract(&sample());
+        // 여러 COM 세그먼트는 줄바꿈으로 합칩니다.
+        assert_eq!(
+            metadata.get("JPEG:Comment"),
+            Some("NovelAI prompt: 1girl\nafter scan")
+        );
+        assert_eq!(metadata.get("Ducky:Quality"), Some("80"));
+        assert_eq!(metadata.get("Ducky:Comment"), Some("hi!"));
+        // 알 수 없는 APPn은 식별자 뒤의 텍스트만 읽습니다.
+        assert_eq!(
+            metadata.get("JPEG:APP5:MyTool"),
+            Some("white background, cat")
+        );
+        assert!(metadata.iter().all(|field| !field.key.contains("APP9")));
+    }
+
+    #[test]
+    fn truncated_segment() {
+        let mut data = vec![0xff, 0xd8];
+        segment(&mut data, MARKER_COM, b"first");
+        segment(&mut data, MARKER_COM, b"second comment");
+        data.truncate(data.len() - 4);
+
+        let segments: Vec<_> = segments(&data).collect();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[1].truncated, Some(14));
+        // 잘린 세그먼트도 남은 부분은 읽고 진단 보고서에 남깁니다.
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("JPEG:Comment"), Some("first\nsecond com"));
+        assert!(metadata
+            .report()
+            .blocks
+            .iter()
+            .any(|block| matches!(block.issue, Some(Issue::Truncated { .. }))));
+    }
+}
@@ -0,0 +1,174 @@
+//! JPEG XL 컨테이너 파서
+//!
+//! ISOBMFF 형식의 컨테이너에서 `Exif`, `xml `(XMP) 박스와 Brotli로 압축된
+//! `brob` 박스를 읽습니다. 컨테이너 없이 코드스트림만 있는 파일에는 메타데이터가 없습니다.
+//! 방문한 박스와 잘리거나 압축을 풀 수 없는 박스는 진단 보고서에 기록합니다.
+
+use std::io::Read;
+
+use super::{exif, isobmff, text, xmp, Issue, Metadata, Provenance};
+
+// 컨테이너 시그니처 박스 (크기 12, 타입 `JXL `)
+const CONTAINER_SIGNATURE: &[u8] = b"\0\0\0\x0cJXL \r\n\x87\n";
+// 코드스트림 시그니처
+const CODESTREAM_SIGNATURE: &[u8] = b"\xff\x0a";
+
+// 압축 해제 결과 상한 (압축 폭탄 방지)
+const MAX_DECOMPRESSED_SIZE: u64 = 64 * 1024 * 1024;
+
+pub fn is_jxl(data: &[u8]) -> bool {
+    data.starts_with(CONTAINER_SIGNATURE) || data.starts_with(CODESTREAM_SIGNATURE)
+}
+
+/// 메타데이터 박스를 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    if !data.starts_with(CONTAINER_SIGNATURE) {
+        return;
+    }
+    let mut end = 0;
+    for atom in isobmff::boxes(data) {
+        let before = metadata.len();
+        let result = if &atom.kind == b"brob" {
+            read_brotli(atom.data, metadata)
+        } else {
+            read_box(&atom.kind, atom.data, metadata);
+            Ok(())
+        };
+        let mut block = atom.block("JXL", metadata.len() - before);
+        // 잘린 박스는 압축도 풀 수 없으므로 잘린 것을 우선 기록합니다.
+        if let (Err(issue), None) = (result, atom.truncated) {
+            block = block.issue(issue);
+        }
+        metadata.record(block);
+        end = atom.range().end;
+    }
+    if let Some(block) = isobmff::trailing_block(data, end, "JXL") {
+        metadata.record(block);
+    }
+}
+
+// brob: 원래 박스 타입(4) + Brotli 스트림
+fn read_brotli(data: &[u8], metadata: &mut Metadata) -> Result<(), Issue> {
+    let (kind, compressed) = data.split_first_chunk::<4>().ok_or(Issue::Malformed)?;
+    let payload = decompress(compressed)?;
+    read_box(kind, &payload, metadata);
+    Ok(())
+}
+
+fn read_box(kind: &[u8; 4], payload: &[u8], metadata: &mut Metadata) {
+    let provenance = Provenance::new(format!("JXL:{}", text::latin1(kind).trim_end()));
+    match kind {
+        b"Exif" => metadata.scoped(provenance, |metadata| {
+            exif::read_with_offset(payload, metadata)
+        }),
+        b"xml " => metadata.scoped(provenance, |metadata| xmp::read(payload, metadata)),
+        _ => {}
+    }
+}
+
+fn decompress(compressed: &[u8]) -> Result<Vec<u8>, Issue> {
+    let mut out = Vec::new();
+    brotli_decompressor::Decompressor::new(compressed, 4096)
+        .take(MAX_DECOMPRESSED_SIZE + 1)
+        .read_to_end(&mut out)
+        .map_err(|_| Issue::Decompress)?;
+    if out.len() as u64 > MAX_DECOMPRESSED_SIZE {
+        return Err(Issue::TooLarge {
+            limit: MAX_DECOMPRESSED_SIZE as usize,
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::{bmff_box, xmp};
+
+    fn container(boxes: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = b"\0\0\0\x0cJXL \r\n\x87\n".to_vec();
+        out.extend(bmff_box(b"ftyp", b"jxl \0\0\0\0jxl "));
+        for b in boxes {
+            out.extend_from_slice(b);
+        }
+        out.extend(bmff_box(b"jxlc", b"\xff\x0a"));
+        out
+    }
+
+    #[test]
+    fn xml_box() {
+        let packet = xmp("<dc:title>xmp title</dc:title>");
+        let data = container(&[bmff_box(b"xml ", packet.as_bytes())]);
+        assert!(is_jxl(&data));
+        assert_eq!(extract(&data).get("XMP:dc:title"), Some("xmp title"));
+    }
+
+    #[test]
+    fn brotli_box() {
+        let packet = xmp("<dc:title>xmp title</dc:title>");
+        let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        encoder.write_all(packet.as_bytes()).unwrap();
+        // brob: 원래 박스 형식 뒤에 brotli 스트림
+        let mut body = b"xml ".to_vec();
+        body.extend(encoder.into_inner());
+
+        let data = container(&[bmff_box(b"brob", &body)]);
+        assert_eq!(extract(&data).get("XMP:dc:title"), Some("xmp title"));
+    }
+
+    #[test]
+    fn broken_boxes() {
+        let packet = xmp("<dc:title>xmp title</dc:title>");
+        let data = container(&[
+            bmff_box(b"brob", b"xml \xff\xff\xff\xff"),
+            bmff_box(b"brob", b"xm"),
+        ]);
+        let metadata = extract(&data);
+        let issues: Vec<_> = metadata
+            .report()
+            .issues()
+            .map(|block| (block.name.as_str(), block.issue.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                ("JXL:brob", Issue::Decompress),
+                ("JXL:brob", Issue::Malformed)
+            ]
+        );
+
+        // 파일이 xml 박스 중간에서 끝남
+        let mut data = container(&[bmff_box(b"xml ", packet.as_bytes())]);
+        let jxlc = bmff_box(b"jxlc", b"\xff\x0a").len();
+        data.truncate(data.len() - jxlc - 10);
+        let metadata = extract(&data);
+        let block = metadata.report().blocks.last().unwrap();
+        assert_eq!(block.name, "JXL:xml");
+        assert_eq!(
+            block.issue,
+            Some(Issue::Truncated {
+                expected: packet.len(),
+                available: packet.len() - 10
+            })
+        );
+
+        // 박스 헤더로 읽을 수 없는 나머지
+        let mut data = container(&[]);
+        data.extend([0, 0, 0, 4]);
+        let end = data.len();
+        let block = extract(&data).report().blocks.last().unwrap().clone();
+        assert_eq!(block.name, "JXL");
+        assert_eq!(block.byte_range, end - 4..end);
+        assert_eq!(block.issue, Some(Issue::Corrupt));
+    }
+
+    #[test]
+    fn bare_codestream() {
+        // 컨테이너 없는 코드스트림에는 메타데이터를 담을 곳이 없습니다.
+        assert!(is_jxl(b"\xff\x0a\0\0"));
+        assert!(extract(b"\xff\x0a\0\0").is_empty());
+    }
+}
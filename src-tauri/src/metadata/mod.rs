@@ -0,0 +1,308 @@
+//! 이미지(와 생성 도구가 만든 동영상) 파일에서 메타데이터를 추출합니다.
+//!
+//! 결과는 프론트엔드의 `ImageFile.metadata`와 같은 `네임스페이스:이름` 형식의
+//! 키(`PNG:parameters` 등)를 사용합니다. 필드마다 형식 있는 값과 출처(청크, 바이트 범위,
+//! 인코딩)를 함께 기록합니다. 손상된 청크나 세그먼트는 건너뛰고 계속 읽으며,
+//! 방문한 블록과 발견한 문제는 파일마다 [`Report`]로 돌려줍니다.
+//! 경로로 읽을 때는 이미지 옆의 사이드카 파일도 `Sidecar:` 필드로 합칩니다.
+
+pub mod archive;
+pub mod c2pa;
+mod error;
+pub mod exif;
+mod field;
+pub mod generators;
+pub mod gif;
+pub mod icc;
+pub mod iptc;
+pub mod isobmff;
+pub mod jpeg;
+pub mod jxl;
+pub mod mpf;
+pub mod png;
+mod report;
+pub mod sidecar;
+pub mod stealth;
+pub mod svg;
+#[cfg(test)]
+mod testing;
+mod text;
+pub mod video;
+pub mod webp;
+pub mod xmp;
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use serde::{Serialize, Serializer};
+
+pub use error::Error;
+pub use field::{MetadataField, MetadataValue, Provenance};
+pub use report::{Block, Issue, Recovery, Report};
+pub use sidecar::SidecarOptions;
+
+// 형식을 판별할 때 읽는 파일 앞부분의 크기
+const HEAD_SIZE: u64 = 4096;
+
+/// 추출된 메타데이터 필드 목록. 삽입 순서를 유지합니다.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Metadata {
+    fields: Vec<MetadataField>,
+    // 지금 읽고 있는 청크나 세그먼트. 새 필드의 출처가 됩니다.
+    provenance: Provenance,
+    report: Report,
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 텍스트 필드를 추가합니다. JSON 텍스트도 해석하지 않고 텍스트로 기록합니다.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let text = value.into();
+        let value = MetadataValue::Text(text.clone());
+        self.insert_typed(key, text, value);
+    }
+
+    /// 표시 문자열 `text`와 형식 있는 값 `value`로 필드를 추가합니다.
+    pub fn insert_typed(
+        &mut self,
+        key: impl Into<String>,
+        text: impl Into<String>,
+        value: MetadataValue,
+    ) {
+        let field = MetadataField::new(&key.into(), text.into(), value, self.provenance.clone());
+        self.insert_field(field);
+    }
+
+    /// 필드를 그대로 추가합니다. 공백뿐인 값은 무시하고, 같은 키가 있으면 덮어씁니다.
+    pub fn insert_field(&mut self, field: MetadataField) {
+        if field.text.trim().is_empty() {
+            return;
+        }
+        match self
+            .fields
+            .iter_mut()
+            .find(|f| f.namespace == field.namespace && f.key == field.key)
+        {
+            Some(existing) => *existing = field,
+            None => self.fields.push(field),
+        }
+    }
+
+    /// `read`가 추가하는 필드의 출처를 `provenance`로 기록합니다.
+    /// 비어 있는 항목은 바깥 출처를 따릅니다.
+    pub fn scoped<R>(&mut self, provenance: Provenance, read: impl FnOnce(&mut Self) -> R) -> R {
+        let inner = provenance.or(&self.provenance);
+        let outer = std::mem::replace(&mut self.provenance, inner);
+        let result = read(self);
+        self.provenance = outer;
+        result
+    }
+
+    /// 방문한 청크나 세그먼트를 진단 보고서에 기록합니다.
+    pub fn record(&mut self, block: Block) {
+        self.report.blocks.push(block);
+    }
+
+    pub fn report(&self) -> &Report {
+        &self.report
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.field(key).map(|field| field.text.as_str())
+    }
+
+    pub fn field(&self, key: &str) -> Option<&MetadataField> {
+        self.fields.iter().find(|field| field.is(key))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MetadataField> {
+        self.fields.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+// `MetadataField` 배열로 직렬화합니다.
+impl Serialize for Metadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.fields.serialize(serializer)
+    }
+}
+
+/// 파일 하나의 추출 결과
+#[derive(Debug, Clone, Serialize)]
+pub struct Extraction {
+    pub metadata: Metadata,
+    pub report: Report,
+}
+
+impl From<Metadata> for Extraction {
+    fn from(mut metadata: Metadata) -> Self {
+        let report = std::mem::take(&mut metadata.report);
+        Self { metadata, report }
+    }
+}
+
+/// 파일과 옆의 사이드카를 읽어 메타데이터와 진단 보고서를 추출합니다.
+pub fn read_metadata(
+    path: impl AsRef<Path>,
+    sidecars: &SidecarOptions,
+) -> Result<Extraction, Error> {
+    let path = path.as_ref();
+    let mut metadata = read_file(path)?;
+    sidecar::read(path, sidecars, &mut metadata);
+    generators::read(&mut metadata);
+    Ok(Extraction::from(metadata))
+}
+
+// 동영상은 메타데이터를 담은 부분만 읽고, 나머지 형식은 파일 전체를 읽습니다.
+fn read_file(path: &Path) -> Result<Metadata, Error> {
+    let mut file = File::open(path)?;
+    let mut head = Vec::new();
+    file.by_ref().take(HEAD_SIZE).read_to_end(&mut head)?;
+    match Format::detect(&head) {
+        Some(format @ (Format::QuickTime | Format::Matroska)) => {
+            let mut metadata = Metadata::new();
+            metadata.report.format = Some(format);
+            video::read_file(&mut BufReader::new(file), format, &mut metadata)?;
+            Ok(metadata)
+        }
+        _ => {
+            let mut data = head;
+            file.read_to_end(&mut data)?;
+            Ok(read_container(&data))
+        }
+    }
+}
+
+/// 파일 시그니처로 판별한 파일 형식
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Png,
+    Jpeg,
+    WebP,
+    Heif,
+    JpegXl,
+    Gif,
+    Tiff,
+    Svg,
+    /// MP4, MOV
+    QuickTime,
+    /// WebM, MKV
+    Matroska,
+    /// 메타데이터를 담을 곳이 없는 형식
+    Bmp,
+    Ico,
+}
+
+impl Format {
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if png::is_png(data) {
+            Some(Self::Png)
+        } else if jpeg::is_jpeg(data) {
+            Some(Self::Jpeg)
+        } else if webp::is_webp(data) {
+            Some(Self::WebP)
+        } else if isobmff::is_heif(data) {
+            Some(Self::Heif)
+        } else if video::quicktime::is_quicktime(data) {
+            Some(Self::QuickTime)
+        } else if video::matroska::is_matroska(data) {
+            Some(Self::Matroska)
+        } else if jxl::is_jxl(data) {
+            Some(Self::JpegXl)
+        } else if gif::is_gif(data) {
+            Some(Self::Gif)
+        } else if exif::is_tiff(data) {
+            Some(Self::Tiff)
+        } else if data.starts_with(b"BM") {
+            Some(Self::Bmp)
+        } else if data.starts_with(b"\0\0\x01\0") {
+            Some(Self::Ico)
+        } else if svg::is_svg(data) {
+            Some(Self::Svg)
+        } else {
+            None
+        }
+    }
+}
+
+/// 메모리에 있는 파일 내용에서 메타데이터를 추출합니다.
+/// 형식은 확장자가 아니라 파일 시그니처로 판별합니다.
+/// 손상된 블록은 건너뛰고 계속 읽으며, 그 내용은 [`Metadata::report`]에 기록합니다.
+pub fn extract(data: &[u8]) -> Metadata {
+    let mut metadata = read_container(data);
+    generators::read(&mut metadata);
+    metadata
+}
+
+// 생성 도구 해석 전의 원본 필드
+fn read_container(data: &[u8]) -> Metadata {
+    let mut metadata = Metadata::new();
+    let format = Format::detect(data);
+    metadata.report.format = format;
+    match format {
+        Some(Format::Png) => {
+            png::read(data, &mut metadata);
+            stealth::read(data, &mut metadata);
+        }
+        Some(Format::Jpeg) => jpeg::read(data, &mut metadata),
+        Some(Format::WebP) => webp::read(data, &mut metadata),
+        Some(Format::Heif) => isobmff::read(data, &mut metadata),
+        Some(Format::JpegXl) => jxl::read(data, &mut metadata),
+        Some(Format::Gif) => gif::read(data, &mut metadata),
+        Some(Format::Tiff) => exif::read_file(data, &mut metadata),
+        Some(Format::Svg) => svg::read(data, &mut metadata),
+        Some(Format::QuickTime) => video::quicktime::read(data, &mut metadata),
+        Some(Format::Matroska) => video::matroska::read(data, &mut metadata),
+        Some(Format::Bmp | Format::Ico) | None => {}
+    }
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::testing::{bmff_box, png as png_file};
+
+    #[test]
+    fn detect_format() {
+        let cases: &[(&[u8], Option<Format>)] = &[
+            (&png_file(&[]), Some(Format::Png)),
+            (b"\xff\xd8\xff\xe0", Some(Format::Jpeg)),
+            (b"RIFF\0\0\0\0WEBPVP8 ", Some(Format::WebP)),
+            (&bmff_box(b"ftyp", b"avif\0\0\0\0mif1"), Some(Format::Heif)),
+            (
+                &bmff_box(b"ftyp", b"isom\0\0\0\0mp41"),
+                Some(Format::QuickTime),
+            ),
+            (b"\x1a\x45\xdf\xa3", Some(Format::Matroska)),
+            (b"\xff\x0a", Some(Format::JpegXl)),
+            (b"GIF89a", Some(Format::Gif)),
+            (b"II*\0\x08\0\0\0", Some(Format::Tiff)),
+            (b"MM\0*\0\0\0\x08", Some(Format::Tiff)),
+            (b"BM", Some(Format::Bmp)),
+            (b"\0\0\x01\0", Some(Format::Ico)),
+            (
+                br#"<svg xmlns="http://www.w3.org/2000/svg"/>"#,
+                Some(Format::Svg),
+            ),
+            (b"plain text", None),
+        ];
+        for (data, format) in cases {
+            assert_eq!(Format::detect(data), *format, "{data:?}");
+            assert_eq!(extract(data).report().format, *format);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! MPF(Multi-Picture Format) 인덱스 파서
+//!
+//! JPEG APP2 `MPF` 세그먼트의 MP 인덱스 IFD에서 파일 안에 함께 저장된
+//! 보조 이미지(썸네일, 파노라마, 게인 맵 등)의 종류와 크기를 읽습니다.
+
+use super::{jpeg, Metadata};
+
+const TAG_NUMBER_OF_IMAGES: u16 = 0xb001;
+const TAG_MP_ENTRY: u16 = 0xb002;
+
+// MP 엔트리: 속성(4) + 크기(4) + 오프셋(4) + 종속 이미지(2 + 2)
+const ENTRY_SIZE: usize = 16;
+
+/// `tiff`는 MPF 세그먼트의 TIFF 헤더, `file`은 JPEG 파일 전체,
+/// `base`는 파일 안에서 TIFF 헤더가 시작하는 위치입니다 (MP 엔트리 오프셋의 기준).
+pub fn read(tiff: &[u8], file: &[u8], base: usize, metadata: &mut Metadata) {
+    let big_endian = match tiff.get(..4) {
+        Some(b"MM\0*") => true,
+        Some(b"II*\0") => false,
+        _ => return,
+    };
+    let u16_at = |offset: usize| {
+        let bytes = tiff.get(offset..offset + 2)?;
+        let bytes = [bytes[0], bytes[1]];
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |data: &[u8], offset: usize| {
+        let bytes = data.get(offset..offset + 4)?;
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    let Some(ifd) = u32_at(tiff, 4).map(|o| o as usize) else {
+        return;
+    };
+    let Some(count) = u16_at(ifd) else {
+        return;
+    };
+    for index in 0..count as usize {
+        let entry = ifd + 2 + index * 12;
+        let (Some(tag), Some(size), Some(value)) = (
+            u16_at(entry),
+            u32_at(tiff, entry + 4),
+            u32_at(tiff, entry + 8),
+        ) else {
+            break;
+        };
+        match tag {
+            TAG_NUMBER_OF_IMAGES => metadata.insert("MPF:NumberOfImages", value.to_string()),
+            TAG_MP_ENTRY => {
+                let (offset, size) = (value as usize, size as usize);
+                let Some(entries) = tiff.get(offset..offset.saturating_add(size)) else {
+                    continue;
+                };
+                for (number, entry) in entries.chunks_exact(ENTRY_SIZE).enumerate() {
+                    let (Some(attribute), Some(size), Some(offset)) =
+                        (u32_at(entry, 0), u32_at(entry, 4), u32_at(entry, 8))
+                    else {
+                        continue;
+                    };
+                    let key = format!("MPF:Image{}", number + 1);
+                    metadata.insert(format!("{key}:Type"), image_type(attribute & 0x00ff_ffff));
+                    metadata.insert(format!("{key}:Size"), size.to_string());
+                    // 첫 번째(대표) 이미지의 오프셋은 0입니다. 보조 이미지는
+                    // 오프셋이 실제 JPEG를 가리킬 때만 파일 안의 위치를 기록합니다.
+                    let start = base.saturating_add(offset as usize);
+                    if number > 0 && file.get(start..).is_some_and(jpeg::is_jpeg) {
+                        metadata.insert(format!("{key}:Offset"), start.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn image_type(code: u32) -> String {
+    match code {
+        0x030000 => "Baseline MP Primary Image".to_owned(),
+        0x010001 => "Large Thumbnail (VGA)".to_owned(),
+        0x010002 => "Large Thumbnail (Full HD)".to_owned(),
+        0x020001 => "Multi-frame Panorama".to_owned(),
+        0x020002 => "Multi-frame Disparity".to_owned(),
+        0x020003 => "Multi-frame Multi-angle".to_owned(),
+        0x000000 => "Undefined".to_owned(),
+        _ => format!("0x{code:06x}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 빅 엔디언 MP 인덱스 IFD: NumberOfImages, MPEntry(IFD 뒤 38바이트 위치에 엔트리 둘)
+    fn index(second_offset: u32) -> Vec<u8> {
+        let mut out = b"MM\0*".to_vec();
+        out.extend(8u32.to_be_bytes());
+        out.extend(2u16.to_be_bytes());
+        out.extend(TAG_NUMBER_OF_IMAGES.to_be_bytes());
+        out.extend([0, 4, 0, 0, 0, 1, 0, 0, 0, 2]);
+        out.extend(TAG_MP_ENTRY.to_be_bytes());
+        out.extend([0, 7, 0, 0, 0, 32, 0, 0, 0, 38]);
+        out.extend(0u32.to_be_bytes());
+        for (attribute, size, offset) in
+            [(0x030000u32, 1000u32, 0u32), (0x010001, 100, second_offset)]
+        {
+            out.extend(attribute.to_be_bytes());
+            out.extend(size.to_be_bytes());
+            out.extend(offset.to_be_bytes());
+            out.extend([0; 4]);
+        }
+        out
+    }
+
+    #[test]
+    fn entries() {
+        // 파일 안 200 위치에 TIFF 헤더, 300 위치에 보조 JPEG
+        let mut file = vec![0; 300];
+        file.extend([0xff, 0xd8, 0xff, 0xd9]);
+        let mut metadata = Metadata::new();
+        read(&index(100), &file, 200, &mut metadata);
+
+        assert_eq!(metadata.get("MPF:NumberOfImages"), Some("2"));
+        assert_eq!(
+            metadata.get("MPF:Image1:Type"),
+            Some("Baseline MP Primary Image")
+        );
+        assert_eq!(metadata.get("MPF:Image1:Offset"), None);
+        assert_eq!(
+            metadata.get("MPF:Image2:Type"),
+            Some("Large Thumbnail (VGA)")
+        );
+        assert_eq!(metadata.get("MPF:Image2:Size"), Some("100"));
+        assert_eq!(metadata.get("MPF:Image2:Offset"), Some("300"));
+    }
+
+    #[test]
+    fn offset_not_pointing_at_jpeg() {
+        let file = vec![0; 400];
+        let mut metadata = Metadata::new();
+        read(&index(100), &file, 200, &mut metadata);
+        assert_eq!(
+            metadata.get("MPF:Image2:Type"),
+            Some("Large Thumbnail (VGA)")
+        );
+        assert_eq!(metadata.get("MPF:Image2:Offset"), None);
+    }
+}
@@ -0,0 +1,519 @@
+//! PNG 청크 파서
+//!
+//! 텍스트 청크(tEXt, iTXt, zTXt)를 읽어 `PNG:키워드` 필드로 변환하고,
+//! eXIf, iCCP 청크와 XMP(`XML:com.adobe.xmp` iTXt)는 각각 EXIF, ICC, XMP 파서로,
+//! C2PA 매니페스트(`caBX`)는 C2PA 파서로 넘깁니다.
+//! tEXt, zTXt는 규격대로 Latin-1로 읽고, UTF-8로 읽은 경우는 필드 출처의 인코딩으로 남깁니다.
+//! CRC가 맞지 않는 청크는 신뢰할 수 없으므로 건너뜁니다. 잘린 청크는 남은 부분에서
+//! 읽을 수 있는 만큼 읽고, 방문한 청크와 문제는 모두 진단 보고서에 기록합니다.
+
+use std::io::Read;
+use std::ops::Range;
+
+use flate2::read::ZlibDecoder;
+
+use super::{
+    c2pa, exif, icc, text, xmp, Block, Issue, Metadata, MetadataValue, Provenance, Recovery,
+};
+
+// PNG 시그니처: 89 50 4E 47 0D 0A 1A 0A
+pub const SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+
+// XMP 패킷을 담는 iTXt 키워드
+const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+
+// 규격상 청크 길이 상한 (2^31 - 1)
+const MAX_CHUNK_LENGTH: usize = 0x7fff_ffff;
+
+// 손상된 부분 뒤에서 다음 청크를 찾을 때 인정하는 청크 종류
+const KNOWN_CHUNKS: &[&[u8; 4]] = &[
+    b"IHDR", b"PLTE", b"IDAT", b"IEND", b"tEXt", b"zTXt", b"iTXt", b"eXIf", b"iCCP", b"caBX",
+    b"gAMA", b"cHRM", b"sRGB", b"sBIT", b"pHYs", b"tIME", b"bKGD", b"tRNS", b"hIST", b"sPLT",
+    b"acTL", b"fcTL", b"fdAT",
+];
+
+// 압축 해제 결과 상한 (압축 폭탄 방지)
+const MAX_INFLATED_SIZE: u64 = 64 * 1024 * 1024;
+
+pub fn is_png(data: &[u8]) -> bool {
+    data.starts_with(&SIGNATURE)
+}
+
+/// PNG 청크 하나
+#[derive(Debug, Clone, Copy)]
+pub struct Chunk<'a> {
+    pub kind: [u8; 4],
+    pub data: &'a [u8],
+    /// 파일 안에서 `data`가 시작하는 위치
+    pub offset: usize,
+    pub crc_ok: bool,
+    /// 파일이 잘려 `data`가 청크의 앞부분뿐이면 원래 길이
+    pub truncated: Option<usize>,
+    /// 앞 청크와 이 청크 사이에서 건너뛴 손상된 바이트 수
+    pub skipped: usize,
+}
+
+impl Chunk<'_> {
+    pub fn is(&self, kind: &[u8; 4]) -> bool {
+        &self.kind == kind
+    }
+
+    /// `PNG:tEXt` 등
+    pub fn name(&self) -> String {
+        format!("PNG:{}", text::latin1(&self.kind))
+    }
+
+    /// 길이, 종류, 데이터, CRC를 포함한 청크 전체의 위치
+    pub fn range(&self) -> Range<usize> {
+        let crc = if self.truncated.is_some() { 0 } else { 4 };
+        self.offset - 8..self.offset + self.data.len() + crc
+    }
+
+    /// 이 청크에서 읽은 필드의 출처 (`PNG:tEXt` 등)
+    pub fn provenance(&self) -> Provenance {
+        Provenance::new(self.name()).range(self.offset..self.offset + self.data.len())
+    }
+}
+
+/// 시그니처 이후의 청크를 순서대로 돌려줍니다. IEND에서 멈춥니다.
+/// 헤더가 손상된 청크는 CRC가 맞는 다음 청크를 찾아 건너뛰고, 파일 끝을 넘는 청크는
+/// 남은 부분만 `truncated`로 돌려준 뒤 멈춥니다.
+pub fn chunks(data: &[u8]) -> impl Iterator<Item = Chunk<'_>> {
+    let mut offset = if is_png(data) {
+        SIGNATURE.len()
+    } else {
+        data.len()
+    };
+    std::iter::from_fn(move || {
+        let start = offset;
+        // length(4) + type(4) + data(length) + CRC(4)
+        let header = data.get(offset..offset + 8)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = [header[4], header[5], header[6], header[7]];
+        let is_valid = length <= MAX_CHUNK_LENGTH && kind.iter().all(u8::is_ascii_alphabetic);
+        // 더하다 넘치는 길이는 파일 끝을 넘는 것과 같이 잘린 청크로 다룹니다.
+        let fits = offset
+            .checked_add(12)
+            .and_then(|end| end.checked_add(length))
+            .is_some_and(|end| end <= data.len());
+        if !is_valid || !fits {
+            // 길이가 파일 끝을 넘어도 손상일 수 있으므로 뒤에 온전한 청크가 있는지 먼저 찾습니다.
+            if let Some(next) = resync(data, offset + 1) {
+                let mut chunk = chunk_at(data, next)?;
+                chunk.skipped = next - start;
+                offset = next_offset(data, &chunk);
+                return Some(chunk);
+            }
+            offset = data.len();
+            if !is_valid {
+                return None;
+            }
+            let body = &data[start + 8..(start + 8).saturating_add(length).min(data.len())];
+            return Some(Chunk {
+                kind,
+                data: body,
+                offset: start + 8,
+                crc_ok: false,
+                truncated: Some(length),
+                skipped: 0,
+            });
+        }
+        let chunk = chunk_at(data, offset)?;
+        offset = next_offset(data, &chunk);
+        Some(chunk)
+    })
+}
+
+// 파일 안에 온전히 들어 있는 청크
+fn chunk_at(data: &[u8], offset: usize) -> Option<Chunk<'_>> {
+    let header = data.get(offset..offset + 8)?;
+    let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let kind = [header[4], header[5], header[6], header[7]];
+    let body_end = (offset + 8).checked_add(length)?;
+    let body = data.get(offset + 8..body_end)?;
+    let crc = data.get(body_end..body_end + 4)?;
+
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&kind);
+    hasher.update(body);
+    let crc_ok = hasher.finalize() == u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]);
+    Some(Chunk {
+        kind,
+        data: body,
+        offset: offset + 8,
+        crc_ok,
+        truncated: None,
+        skipped: 0,
+    })
+}
+
+fn next_offset(data: &[u8], chunk: &Chunk) -> usize {
+    if chunk.is(b"IEND") {
+        data.len()
+    } else {
+        chunk.offset + chunk.data.len() + 4
+    }
+}
+
+// `start` 이후에서 종류가 알려져 있고 CRC가 맞는 첫 청크의 위치
+fn resync(data: &[u8], start: usize) -> Option<usize> {
+    (start..data.len().saturating_sub(11)).find(|&offset| {
+        let kind = &data[offset + 4..offset + 8];
+        KNOWN_CHUNKS.iter().any(|known| known[..] == *kind)
+            && chunk_at(data, offset).is_some_and(|chunk| chunk.crc_ok)
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextChunkKind {
+    Text,
+    International,
+    Compressed,
+}
+
+/// 디코딩된 텍스트 청크
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextChunk {
+    pub kind: TextChunkKind,
+    pub keyword: String,
+    /// iTXt 언어 태그 (예: `ko-KR`). 다른 청크는 빈 문자열입니다.
+    pub language: String,
+    /// iTXt 번역된 키워드. 다른 청크는 빈 문자열입니다.
+    pub translated_keyword: String,
+    pub text: String,
+    /// `text`를 디코딩한 인코딩
+    pub encoding: &'static str,
+}
+
+impl TextChunk {
+    /// 메타데이터 키. 언어 태그가 있으면 ExifTool처럼 `PNG:Title-ko-KR` 형식으로 붙입니다.
+    pub fn key(&self) -> String {
+        if self.language.is_empty() {
+            format!("PNG:{}", self.keyword)
+        } else {
+            format!("PNG:{}-{}", self.keyword, self.language)
+        }
+    }
+}
+
+/// 텍스트 청크, eXIf, iCCP, caBX 청크를 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    let mut end = SIGNATURE.len();
+    let mut finished = false;
+    for chunk in chunks(data) {
+        let range = chunk.range();
+        if chunk.skipped > 0 {
+            let skipped = range.start - chunk.skipped..range.start;
+            let issue = header_issue(&data[skipped.clone()]);
+            metadata.record(
+                Block::new("PNG", skipped)
+                    .issue(issue)
+                    .recovery(Recovery::Resynced { next: range.start }),
+            );
+        }
+        let mut block = Block::new(chunk.name(), range.clone());
+        if let Some(expected) = chunk.truncated {
+            block = block.issue(Issue::Truncated {
+                expected,
+                available: chunk.data.len(),
+            });
+            let before = metadata.len();
+            let _ = metadata.scoped(chunk.provenance(), |metadata| read_chunk(&chunk, metadata));
+            if metadata.len() > before {
+                block = block.recovery(Recovery::Partial {
+                    fields: metadata.len() - before,
+                });
+            }
+        } else if !chunk.crc_ok {
+            block = block.issue(Issue::CrcMismatch);
+        } else if let Err(issue) =
+            metadata.scoped(chunk.provenance(), |metadata| read_chunk(&chunk, metadata))
+        {
+            block = block.issue(issue);
+        }
+        metadata.record(block);
+        end = range.end;
+        finished = chunk.is(b"IEND");
+    }
+    if !finished && is_png(data) {
+        // 잘린 청크는 이미 기록했습니다.
+        let truncated = metadata
+            .report()
+            .blocks
+            .last()
+            .is_some_and(|block| matches!(block.issue, Some(Issue::Truncated { .. })));
+        if end < data.len() {
+            metadata.record(Block::new("PNG", end..data.len()).issue(Issue::Corrupt));
+        } else if !truncated {
+            metadata.record(Block::new("PNG:IEND", end..end).issue(Issue::Missing));
+        }
+    }
+}
+
+// 청크 종류는 읽을 수 있는데 건너뛰었다면 길이 필드가 손상된 것입니다.
+fn header_issue(header: &[u8]) -> Issue {
+    match header.get(..8) {
+        Some(&[a, b, c, d, ref kind @ ..]) if kind.iter().all(u8::is_ascii_alphabetic) => {
+            Issue::InvalidLength {
+                length: u32::from_be_bytes([a, b, c, d]) as usize,
+            }
+        }
+        _ => Issue::Corrupt,
+    }
+}
+
+fn read_chunk(chunk: &Chunk, metadata: &mut Metadata) -> Result<(), Issue> {
+    match &chunk.kind {
+        b"eXIf" => exif::read(chunk.data, metadata),
+        b"caBX" => c2pa::read(chunk.data, metadata),
+        b"iCCP" => {
+            // 프로파일 이름 + null + 압축 방식 + zlib 압축된 프로파일
+            let compressed = split_null(chunk.data)
+                .and_then(|(_, rest)| rest.get(1..))
+                .ok_or(Issue::Malformed)?;
+            let profile = inflate(compressed)?;
+            icc::read(&profile, metadata);
+        }
+        b"tEXt" | b"zTXt" | b"iTXt" => {
+            let chunk = parse_text_chunk(chunk)?;
+            let encoding = chunk.encoding;
+            metadata.scoped(Provenance::default().encoding(encoding), |metadata| {
+                if chunk.keyword == XMP_KEYWORD {
+                    xmp::read(chunk.text.as_bytes(), metadata);
+                    return;
+                }
+                let key = chunk.key();
+                if !chunk.translated_keyword.is_empty() {
+                    metadata.insert(format!("{key}:TranslatedKeyword"), chunk.translated_keyword);
+                }
+                let value = MetadataValue::from_text(&chunk.text);
+                metadata.insert_typed(key, chunk.text, value);
+            });
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// CRC가 올바른 텍스트 청크를 모두 디코딩합니다.
+pub fn text_chunks(data: &[u8]) -> Vec<TextChunk> {
+    chunks(data)
+        .filter(|chunk| chunk.crc_ok)
+        .filter_map(|chunk| parse_text_chunk(&chunk).ok())
+        .collect()
+}
+
+/// 텍스트 청크를 디코딩합니다. 압축을 풀지 못하거나 상한을 넘으면 그 문제를, 구조가
+/// 맞지 않으면 [`Issue::Malformed`]를 돌려줍니다.
+pub fn parse_text_chunk(chunk: &Chunk) -> Result<TextChunk, Issue> {
+    match &chunk.kind {
+        b"tEXt" => parse_text(chunk.data),
+        b"zTXt" => parse_compressed(chunk.data),
+        b"iTXt" => parse_international(chunk.data),
+        _ => Err(Issue::Malformed),
+    }
+}
+
+// tEXt: keyword + null + text
+fn parse_text(data: &[u8]) -> Result<TextChunk, Issue> {
+    let (keyword, text) = split_null(data).ok_or(Issue::Malformed)?;
+    let (text, encoding) = decode_latin1(text);
+    Ok(TextChunk {
+        kind: TextChunkKind::Text,
+        keyword: text::latin1(keyword),
+        language: String::new(),
+        translated_keyword: String::new(),
+        text,
+        encoding,
+    })
+}
+
+// zTXt: keyword + null + compression method + compressed text
+fn parse_compressed(data: &[u8]) -> Result<TextChunk, Issue> {
+    let (keyword, rest) = split_null(data).ok_or(Issue::Malformed)?;
+    let (&method, compressed) = rest.split_first().ok_or(Issue::Malformed)?;
+    if method != 0 {
+        return Err(Issue::Malformed);
+    }
+    let (text, encoding) = decode_latin1(&inflate(compressed)?);
+    Ok(TextChunk {
+        kind: TextChunkKind::Compressed,
+        keyword: text::latin1(keyword),
+        language: String::new(),
+        translated_keyword: String::new(),
+        text,
+        encoding,
+    })
+}
+
+// iTXt: keyword + null + compression flag + compression method
+//       + language tag + null + translated keyword + null + text
+fn parse_international(data: &[u8]) -> Result<TextChunk, Issue> {
+    let parse = || {
+        let (keyword, rest) = split_null(data)?;
+        let (&flag, rest) = rest.split_first()?;
+        let (&method, rest) = rest.split_first()?;
+        let (language, rest) = split_null(rest)?;
+        let (translated_keyword, text) = split_null(rest)?;
+        Some((keyword, flag, method, language, translated_keyword, text))
+    };
+    let (keyword, flag, method, language, translated_keyword, text) =
+        parse().ok_or(Issue::Malformed)?;
+
+    let text = match (flag, method) {
+        (0, _) => String::from_utf8_lossy(text).into_owned(),
+        (1, 0) => String::from_utf8_lossy(&inflate(text)?).into_owned(),
+        _ => return Err(Issue::Malformed),
+    };
+    Ok(TextChunk {
+        kind: TextChunkKind::International,
+        keyword: text::latin1(keyword),
+        language: text::latin1(language),
+        translated_keyword: String::from_utf8_lossy(translated_keyword).into_owned(),
+        text,
+        encoding: text::UTF8,
+    })
+}
+
+// tEXt, zTXt 본문은 규격상 Latin-1입니다. UTF-8을 그대로 써 넣는 도구가 있어
+// ASCII가 아닌 바이트가 올바른 UTF-8을 이룰 때만 UTF-8로 읽고 그 인코딩을 돌려줍니다.
+fn decode_latin1(data: &[u8]) -> (String, &'static str) {
+    match std::str::from_utf8(data) {
+        Ok(text) if !data.is_ascii() => (text.to_owned(), text::UTF8),
+        _ => (text::latin1(data), text::LATIN1),
+    }
+}
+
+/// zlib 스트림을 해제합니다. 결과가 상한을 넘으면 잘라 쓰지 않고 [`Issue::TooLarge`]입니다.
+pub(crate) fn inflate(compressed: &[u8]) -> Result<Vec<u8>, Issue> {
+    inflate_limited(compressed, MAX_INFLATED_SIZE)
+}
+
+fn inflate_limited(compressed: &[u8], limit: u64) -> Result<Vec<u8>, Issue> {
+    let mut out = Vec::new();
+    ZlibDecoder::new(compressed)
+        .take(limit + 1)
+        .read_to_end(&mut out)
+        .map_err(|_| Issue::Decompress)?;
+    if out.len() as u64 > limit {
+        return Err(Issue::TooLarge {
+            limit: limit as usize,
+        });
+    }
+    Ok(out)
+}
+
+fn split_null(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let pos = data.iter().position(|&b| b == 0)?;
+    Some((&data[..pos], &data[pos + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::{chunk, png, zlib};
+
+    #[test]
+    fn text_chunks() {
+        let mut compressed = b"Comment\0\0".to_vec();
+        compressed.extend(zlib("압축된 프롬프트".as_bytes()));
+        let mut international = b"Title\0\x01\0ko-KR\0\xec\xa0\x9c\xeb\xaa\xa9\0".to_vec();
+        international.extend(zlib("제목".as_bytes()));
+        let data = png(&[
+            chunk(b"tEXt", b"parameters\0a cat, \xe9"),
+            chunk(b"zTXt", &compressed),
+            chunk(b"iTXt", &international),
+        ]);
+
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("PNG:parameters"), Some("a cat, é"));
+        assert_eq!(metadata.get("PNG:Comment"), Some("압축된 프롬프트"));
+        assert_eq!(metadata.get("PNG:Title-ko-KR"), Some("제목"));
+        assert_eq!(
+            metadata.get("PNG:Title-ko-KR:TranslatedKeyword"),
+            Some("제목")
+        );
+    }
+
+    #[test]
+    fn crc_mismatch_is_skipped() {
+        let mut bad = chunk(b"tEXt", b"Bad\0value");
+        let last = bad.len() - 1;
+        bad[last] ^= 1;
+        let data = png(&[bad, chunk(b"tEXt", b"Good\0value")]);
+
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("PNG:Bad"), None);
+        assert_eq!(metadata.get("PNG:Good"), Some("value"));
+    }
+
+    #[test]
+    fn latin1_and_utf8_text() {
+        assert_eq!(decode_latin1(b"caf\xe9"), ("café".to_owned(), text::LATIN1));
+        assert_eq!(decode_latin1(b"cat"), ("cat".to_owned(), text::LATIN1));
+        assert_eq!(
+            decode_latin1("제목".as_bytes()),
+            ("제목".to_owned(), text::UTF8)
+        );
+
+        // UTF-8로 써 넣은 tEXt는 문제가 아니라 필드 출처의 인코딩으로 남깁니다.
+        let data = png(&[chunk(b"tEXt", "Title\0제목".as_bytes())]);
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("PNG:Title"), Some("제목"));
+        assert_eq!(
+            metadata.field("PNG:Title").unwrap().encoding.as_deref(),
+            Some(text::UTF8)
+        );
+        let block = metadata
+            .report()
+            .blocks
+            .iter()
+            .find(|block| block.name == "PNG:tEXt")
+            .unwrap();
+        assert_eq!(block.issue, None);
+    }
+
+    #[test]
+    fn inflate_limit() {
+        let compressed = zlib(&[b'a'; 100]);
+        assert_eq!(inflate_limited(&compressed, 100), Ok(vec![b'a'; 100]));
+        // 상한을 넘으면 잘라 쓰지 않고 보고합니다.
+        assert_eq!(
+            inflate_limited(&compressed, 99),
+            Err(Issue::TooLarge { limit: 99 })
+        );
+        assert_eq!(inflate(b"not zlib"), Err(Issue::Decompress));
+
+        let mut bomb = b"Comment\0\0".to_vec();
+        bomb.extend(zlib(&vec![0; MAX_INFLATED_SIZE as usize + 1]));
+        let metadata = extract(&png(&[chunk(b"zTXt", &bomb)]));
+        assert_eq!(metadata.get("PNG:Comment"), None);
+        let block = metadata
+            .report()
+            .blocks
+            .iter()
+            .find(|block| block.name == "PNG:zTXt")
+            .unwrap();
+        assert_eq!(
+            block.issue,
+            Some(Issue::TooLarge {
+                limit: MAX_INFLATED_SIZE as usize
+            })
+        );
+    }
+
+    #[test]
+    fn overflowing_length_is_truncated() {
+        let mut data = SIGNATURE.to_vec();
+        data.extend_from_slice(&(MAX_CHUNK_LENGTH as u32).to_be_bytes());
+        data.extend_from_slice(b"tEXt");
+        data.extend_from_slice(b"Key\0value");
+
+        let chunks: Vec<_> = chunks(&data).collect();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is(b"tEXt"));
+        assert_eq!(chunks[0].data, b"Key\0value");
+        assert_eq!(chunks[0].truncated, Some(MAX_CHUNK_LENGTH));
+    }
+}
@@ -0,0 +1,228 @@
+//! 파일별 추출 진단 보고서
+//!
+//! 파서가 방문한 청크와 세그먼트, CRC나 길이 검사에 실패한 곳, 잘린 곳과
+//! 손상을 건너뛰어 복구한 내용을 기록합니다. 이미지가 규칙에 맞지 않은 이유를
+//! 메타데이터가 비어 있는 것만으로는 알 수 없기 때문입니다.
+
+use std::ops::Range;
+
+use serde::Serialize;
+
+use super::Format;
+
+/// 블록에서 발견한 문제
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Issue {
+    #[error("CRC가 맞지 않습니다")]
+    CrcMismatch,
+    /// 파일이 블록 중간에서 끝남
+    #[error("{expected}바이트 중 {available}바이트만 남아 있습니다")]
+    Truncated { expected: usize, available: usize },
+    #[error("길이 필드가 올바르지 않습니다: {length}")]
+    InvalidLength { length: usize },
+    /// 블록 헤더로 읽을 수 없는 바이트
+    #[error("손상된 데이터입니다")]
+    Corrupt,
+    #[error("압축을 풀 수 없습니다")]
+    Decompress,
+    /// 구조는 맞지만 내용을 해석할 수 없음
+    #[error("내용을 해석할 수 없습니다")]
+    Malformed,
+    /// 끝을 나타내는 블록(IEND, EOI)이 없음
+    #[error("종료 블록이 없습니다")]
+    Missing,
+    /// 처리 상한을 넘어 읽지 않음 (압축 폭탄 방지)
+    #[error("크기 상한 {limit}바이트를 넘어 읽지 않았습니다")]
+    TooLarge { limit: usize },
+}
+
+/// 문제가 있던 블록에서 건진 것
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Recovery {
+    /// 손상된 바이트를 건너뛰고 `next` 위치에서 다음 블록을 찾음
+    Resynced { next: usize },
+    /// 잘린 블록의 남은 부분에서 필드를 읽음
+    Partial { fields: usize },
+}
+
+/// 파서가 방문한 청크나 세그먼트 하나
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Block {
+    /// `PNG:tEXt`, `JPEG:APP1` 등
+    pub name: String,
+    /// 파일 안에서 헤더를 포함한 블록 전체의 위치
+    pub byte_range: Range<usize>,
+    pub issue: Option<Issue>,
+    pub recovery: Option<Recovery>,
+}
+
+impl Block {
+    pub fn new(name: impl Into<String>, byte_range: Range<usize>) -> Self {
+        Self {
+            name: name.into(),
+            byte_range,
+            issue: None,
+            recovery: None,
+        }
+    }
+
+    pub fn issue(mut self, issue: Issue) -> Self {
+        self.issue = Some(issue);
+        self
+    }
+
+    pub fn recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = Some(recovery);
+        self
+    }
+}
+
+/// 파일 하나의 진단 보고서
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    /// 시그니처로 판별한 형식. 알 수 없으면 `None`입니다.
+    pub format: Option<Format>,
+    /// 방문한 순서대로의 블록
+    pub blocks: Vec<Block>,
+}
+
+impl Report {
+    /// 문제가 있던 블록
+    pub fn issues(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.iter().filter(|block| block.issue.is_some())
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.issues().next().is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::{chunk, png};
+
+    // IEND 청크의 길이
+    const IEND_LEN: usize = 12;
+
+    #[test]
+    fn clean_png() {
+        let metadata = extract(&png(&[chunk(b"tEXt", b"a\0b")]));
+        let report = metadata.report();
+        assert!(report.is_clean(), "{report:?}");
+        assert_eq!(report.format, Some(Format::Png));
+        let names: Vec<_> = report
+            .blocks
+            .iter()
+            .map(|block| block.name.as_str())
+            .collect();
+        assert_eq!(names, ["PNG:IHDR", "PNG:tEXt", "PNG:IEND"]);
+        assert_eq!(report.blocks[1].byte_range, 33..48);
+    }
+
+    #[test]
+    fn truncated_text_recovers() {
+        let mut data = png(&[
+            chunk(b"tEXt", b"a\0b"),
+            chunk(b"tEXt", b"parameters\0cat, dog, more"),
+        ]);
+        data.truncate(data.len() - IEND_LEN - 10);
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("PNG:a"), Some("b"));
+        assert_eq!(metadata.get("PNG:parameters"), Some("cat, dog"));
+        let report = metadata.report();
+        assert_eq!(report.blocks.len(), 3);
+        let last = report.blocks.last().unwrap();
+        assert_eq!(
+            last.issue,
+            Some(Issue::Truncated {
+                expected: 25,
+                available: 19
+            })
+        );
+        assert_eq!(last.recovery, Some(Recovery::Partial { fields: 1 }));
+    }
+
+    #[test]
+    fn png_resync() {
+        let mut bad_crc = chunk(b"tEXt", b"x\0y");
+        *bad_crc.last_mut().unwrap() ^= 1;
+        let mut bad_length = chunk(b"tEXt", b"q\0r");
+        bad_length[0] = 0x7f;
+        let data = png(&[bad_crc, bad_length, chunk(b"tEXt", b"a\0b")]);
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("PNG:x"), None);
+        assert_eq!(metadata.get("PNG:a"), Some("b"));
+
+        let blocks = &metadata.report().blocks;
+        assert_eq!(blocks[1].issue, Some(Issue::CrcMismatch));
+        assert!(matches!(blocks[2].issue, Some(Issue::InvalidLength { .. })));
+        assert!(matches!(
+            blocks[2].recovery,
+            Some(Recovery::Resynced { .. })
+        ));
+        assert_eq!(blocks[3].name, "PNG:tEXt");
+        assert!(blocks[3].issue.is_none());
+        assert_eq!(metadata.report().issues().count(), 2);
+    }
+
+    #[test]
+    fn missing_iend() {
+        let mut data = png(&[chunk(b"tEXt", b"a\0b")]);
+        data.truncate(data.len() - IEND_LEN);
+        let metadata = extract(&data);
+        assert_eq!(
+            metadata.report().blocks.last().unwrap().issue,
+            Some(Issue::Missing)
+        );
+    }
+
+    #[test]
+    fn jpeg_garbage_and_missing_eoi() {
+        let mut data = vec![0xff, 0xd8];
+        data.extend_from_slice(&[0xff, 0xfe, 0, 5, b'h', b'i', b'!']);
+        // 세그먼트 사이의 쓰레기 바이트
+        data.extend_from_slice(&[1, 2, 3]);
+        // 스캔 데이터 뒤에 EOI가 없음
+        data.extend_from_slice(&[0xff, 0xda, 0, 2, 9, 9, 9]);
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("JPEG:Comment"), Some("hi!"));
+        let blocks = &metadata.report().blocks;
+        assert_eq!(blocks[0].name, "JPEG:COM");
+        assert_eq!(blocks[1].issue, Some(Issue::Corrupt));
+        assert_eq!(blocks[2].name, "JPEG:SOS");
+        assert_eq!(blocks[3].issue, Some(Issue::Missing));
+    }
+
+    #[test]
+    fn serialize() {
+        let data = [0xff, 0xd8, 0xff, 0xfe, 0, 40, b'a', b'b'];
+        let metadata = extract(&data);
+        let report = metadata.report();
+        assert_eq!(
+            report.blocks[0].issue,
+            Some(Issue::Truncated {
+                expected: 38,
+                available: 2
+            })
+        );
+        let json = serde_json::to_value(report).unwrap();
+        assert_eq!(json["format"], "jpeg");
+        assert_eq!(json["blocks"][0]["byteRange"]["start"], 2);
+        assert_eq!(json["blocks"][0]["issue"]["kind"], "truncated");
+        assert_eq!(json["blocks"][0]["issue"]["expected"], 38);
+    }
+}
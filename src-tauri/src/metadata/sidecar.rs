@@ -0,0 +1,345 @@
+//! 사이드카 파일 파서
+//!
+//! 이미지 옆에 같은 이름으로 놓인 설명 파일을 찾아 `Sidecar:` 필드로 추가합니다.
+//!
+//! - `.xmp` (Lightroom 등) → `Sidecar:XMP:dc:subject[1]` 등
+//! - `.txt`, `.caption` (kohya 학습용 태그·캡션, webui 생성 정보) → `Sidecar:Text`, `Sidecar:Caption`
+//! - `.json` (gallery-dl, Google Takeout) → `Sidecar:{name}.json:title`,
+//!   `Sidecar:{name}.json:photoTakenTime/formatted` 등. JSON 필드는 이름이 정해져 있지 않아
+//!   다른 사이드카 필드나 다른 JSON 파일과 겹치지 않도록 파일을 찾은 패턴 아래에 둡니다.
+//!   패턴은 이미지마다 같으므로 규칙과 검색에 같은 키를 쓸 수 있습니다.
+//!
+//! 파일 이름 패턴의 `{name}`은 이미지 파일 이름 전체(`photo.jpg`), `{stem}`은 확장자를 뺀
+//! 이름(`photo`)으로 바뀝니다.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use super::{text, xmp, Block, Issue, Metadata, MetadataValue, Provenance};
+
+/// 기본 사이드카 파일 이름 패턴
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    "{stem}.xmp",
+    "{name}.xmp",
+    "{stem}.txt",
+    "{stem}.caption",
+    "{name}.json",
+    "{stem}.json",
+    // Google Takeout (2024년 이후)
+    "{name}.supplemental-metadata.json",
+];
+
+// 이보다 큰 파일은 사이드카로 보지 않습니다.
+const MAX_SIDECAR_SIZE: u64 = 16 * 1024 * 1024;
+
+// 중첩된 JSON 객체를 평탄화하는 깊이 상한
+const MAX_JSON_DEPTH: usize = 8;
+
+/// 사이드카를 찾을 파일 이름 패턴
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SidecarOptions {
+    pub patterns: Vec<String>,
+}
+
+impl Default for SidecarOptions {
+    fn default() -> Self {
+        Self {
+            patterns: DEFAULT_PATTERNS.iter().map(|&p| p.to_owned()).collect(),
+        }
+    }
+}
+
+impl SidecarOptions {
+    /// `image`와 같은 폴더에서 찾을 (패턴, 사이드카 경로). 다른 폴더를 가리키는 패턴은 무시합니다.
+    pub fn candidates(&self, image: &Path) -> Vec<(&str, PathBuf)> {
+        let (Some(name), Some(stem)) = (image.file_name(), image.file_stem()) else {
+            return Vec::new();
+        };
+        let (name, stem) = (name.to_string_lossy(), stem.to_string_lossy());
+        let mut paths: Vec<(&str, PathBuf)> = Vec::new();
+        for pattern in &self.patterns {
+            let file_name = pattern.replace("{name}", &name).replace("{stem}", &stem);
+            if file_name.contains(['/', '\\']) || file_name == name || file_name.starts_with('.') {
+                continue;
+            }
+            let path = image.with_file_name(file_name);
+            if !paths.iter().any(|(_, found)| *found == path) {
+                paths.push((pattern, path));
+            }
+        }
+        paths
+    }
+}
+
+/// `image` 옆의 사이드카를 읽어 `metadata`에 추가합니다. 읽은 파일은 진단 보고서에 기록합니다.
+pub fn read(image: &Path, options: &SidecarOptions, metadata: &mut Metadata) {
+    for (pattern, path) in options.candidates(image) {
+        // 사이드카가 없는 것이 보통이므로 열 수 없는 파일은 기록하지 않습니다.
+        let Ok(size) = std::fs::metadata(&path).map(|m| m.len()) else {
+            continue;
+        };
+        let name = format!(
+            "Sidecar:{}",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        let block = Block::new(name.clone(), 0..size as usize);
+        if size > MAX_SIDECAR_SIZE {
+            metadata.record(block.issue(Issue::InvalidLength {
+                length: size as usize,
+            }));
+            continue;
+        }
+        let Ok(data) = std::fs::read(&path) else {
+            continue;
+        };
+        let result = metadata.scoped(Provenance::new(name.clone()), |metadata| {
+            read_sidecar(&path, &format!("Sidecar:{pattern}"), &data, metadata)
+        });
+        metadata.record(match result {
+            Ok(()) => block,
+            Err(issue) => block.issue(issue),
+        });
+    }
+}
+
+// `scope`는 `Sidecar:{찾은 패턴}`입니다. JSON 필드를 이 아래에 둡니다.
+fn read_sidecar(
+    path: &Path,
+    scope: &str,
+    data: &[u8],
+    metadata: &mut Metadata,
+) -> Result<(), Issue> {
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("xmp") => {
+            let fields = xmp::flatten(data);
+            if fields.is_empty() {
+                return Err(Issue::Malformed);
+            }
+            for (key, text, value) in fields {
+                metadata.insert_typed(format!("Sidecar:{key}"), text, value);
+            }
+        }
+        Some("json") => {
+            let (json, encoding) = decode(data);
+            let value: Value = serde_json::from_str(&json).map_err(|_| Issue::Malformed)?;
+            metadata.scoped(
+                Provenance::default().encoding(encoding),
+                |metadata| match value {
+                    Value::Object(_) => flatten_json(&value, scope, "", 0, metadata),
+                    _ => metadata.insert_typed(scope, json.trim(), MetadataValue::Json(value)),
+                },
+            );
+        }
+        Some("caption") => read_text(data, "Sidecar:Caption", metadata),
+        _ => read_text(data, "Sidecar:Text", metadata),
+    }
+    Ok(())
+}
+
+// BOM이 있으면 그 인코딩으로, 없으면 UTF-8(실패하면 Latin-1)로 읽습니다.
+fn decode(data: &[u8]) -> (String, &'static str) {
+    if let Some(rest) = data.strip_prefix(b"\xef\xbb\xbf") {
+        text::decode_utf8_or_latin1(rest)
+    } else if let Some(rest) = data.strip_prefix(b"\xff\xfe") {
+        (text::utf16(rest, false), text::UTF16LE)
+    } else if let Some(rest) = data.strip_prefix(b"\xfe\xff") {
+        (text::utf16(rest, true), text::UTF16BE)
+    } else {
+        text::decode_utf8_or_latin1(data)
+    }
+}
+
+// 한 줄짜리 쉼표 구분 태그(`1girl, solo, smile`)는 목록 값으로도 기록합니다.
+fn read_text(data: &[u8], key: &str, metadata: &mut Metadata) {
+    let (text, encoding) = decode(data);
+    let text = text.trim();
+    let tags: Vec<&str> = text
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    metadata.scoped(Provenance::default().encoding(encoding), |metadata| {
+        if tags.len() > 1 && !text.contains('\n') {
+            let list = tags
+                .iter()
+                .map(|&tag| MetadataValue::Text(tag.to_owned()))
+                .collect();
+            metadata.insert_typed(key, text, MetadataValue::List(list));
+        } else {
+            metadata.insert(key, text);
+        }
+    });
+}
+
+// 객체는 `{prefix}:부모/자식` 키로 펼치고, 값 배열은 목록으로, 객체 배열은 JSON 그대로
+// 기록합니다.
+fn flatten_json(value: &Value, prefix: &str, path: &str, depth: usize, metadata: &mut Metadata) {
+    let key = format!("{prefix}:{path}");
+    match value {
+        Value::Object(fields) if depth < MAX_JSON_DEPTH => {
+            for (name, field) in fields {
+                let path = if path.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{path}/{name}")
+                };
+                flatten_json(field, prefix, &path, depth + 1, metadata);
+            }
+        }
+        Value::Array(items) if items.iter().all(is_scalar) => {
+            let values: Vec<MetadataValue> = items.iter().filter_map(scalar).collect();
+            let text = items
+                .iter()
+                .filter_map(scalar_text)
+                .collect::<Vec<_>>()
+                .join(", ");
+            metadata.insert_typed(key, text, MetadataValue::List(values));
+        }
+        Value::Object(_) | Value::Array(_) => {
+            // 키워드 규칙이 찾을 수 있도록 안의 문자열을 모두 이어 붙입니다.
+            let mut strings = Vec::new();
+            collect_strings(value, &mut strings);
+            metadata.insert_typed(key, strings.join(", "), MetadataValue::Json(value.clone()));
+        }
+        _ => {
+            if let (Some(value), Some(text)) = (scalar(value), scalar_text(value)) {
+                metadata.insert_typed(key, text, value);
+            }
+        }
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Object(_) | Value::Array(_))
+}
+
+fn scalar(value: &Value) -> Option<MetadataValue> {
+    match value {
+        Value::String(text) => Some(MetadataValue::Text(text.clone())),
+        Value::Number(number) => number.as_f64().map(MetadataValue::Number),
+        Value::Bool(flag) => Some(MetadataValue::Text(flag.to_string())),
+        _ => None,
+    }
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+fn collect_strings(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::String(text) => out.push(text.clone()),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, out)),
+        Value::Object(fields) => fields
+            .values()
+            .for_each(|field| collect_strings(field, out)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::read_metadata;
+    use crate::metadata::testing::{xmp, TempDir};
+
+    #[test]
+    fn candidates() {
+        let options = SidecarOptions {
+            patterns: [
+                "{stem}.txt",
+                "{name}.json",
+                "{stem}.txt",
+                "../{stem}.txt",
+                "{name}",
+                ".{stem}",
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+        };
+        assert_eq!(
+            options.candidates(Path::new("dir/photo.png")),
+            [
+                ("{stem}.txt", PathBuf::from("dir/photo.txt")),
+                ("{name}.json", PathBuf::from("dir/photo.png.json"))
+            ]
+        );
+    }
+
+    #[test]
+    fn sidecars() {
+        let dir = TempDir::new();
+        let image = dir.file("photo.png", b"not really");
+        dir.file("photo.txt", "\u{feff}1girl, solo, smile\n");
+        dir.file("photo.caption", "a cat on a mat");
+        dir.file(
+            "photo.png.json",
+            r#"{"title": "T", "tags": ["a", "b"], "photoTakenTime": {"formatted": "Jan 1"}, "people": [{"name": "Kim"}], "views": 3}"#,
+        );
+        dir.file("photo.json", "{broken");
+        dir.file(
+            "photo.xmp",
+            xmp("<dc:subject><rdf:Bag><rdf:li>sunset</rdf:li></rdf:Bag></dc:subject>"),
+        );
+
+        let extraction = read_metadata(&image, &SidecarOptions::default()).unwrap();
+        let metadata = &extraction.metadata;
+        let text = metadata.field("Sidecar:Text").unwrap();
+        assert_eq!(text.text, "1girl, solo, smile");
+        assert!(matches!(&text.value, MetadataValue::List(tags) if tags.len() == 3));
+        assert_eq!(text.source.as_deref(), Some("Sidecar:photo.txt"));
+        assert_eq!(metadata.get("Sidecar:Caption"), Some("a cat on a mat"));
+        assert_eq!(metadata.get("Sidecar:{name}.json:title"), Some("T"));
+        assert_eq!(metadata.get("Sidecar:{name}.json:tags"), Some("a, b"));
+        assert_eq!(
+            metadata.get("Sidecar:{name}.json:photoTakenTime/formatted"),
+            Some("Jan 1")
+        );
+        assert_eq!(metadata.get("Sidecar:{name}.json:people"), Some("Kim"));
+        assert_eq!(metadata.get("Sidecar:{name}.json:views"), Some("3"));
+        assert_eq!(metadata.get("Sidecar:XMP:dc:subject[1]"), Some("sunset"));
+        let broken: Vec<_> = extraction
+            .report
+            .issues()
+            .map(|block| block.name.as_str())
+            .collect();
+        assert_eq!(broken, ["Sidecar:photo.json"]);
+
+        let options = SidecarOptions {
+            patterns: vec!["{stem}.txt".into()],
+        };
+        let extraction = read_metadata(&image, &options).unwrap();
+        assert!(extraction.metadata.get("Sidecar:Caption").is_none());
+        assert!(extraction.metadata.get("Sidecar:Text").is_some());
+    }
+
+    #[test]
+    fn json_sidecars_do_not_collide() {
+        let dir = TempDir::new();
+        let image = dir.file("photo.png", b"not really");
+        dir.file("photo.txt", "a cat");
+        dir.file(
+            "photo.png.json",
+            r#"{"title": "gallery", "Text": "json text"}"#,
+        );
+        dir.file("photo.json", r#"{"title": "takeout"}"#);
+
+        let extraction = read_metadata(&image, &SidecarOptions::default()).unwrap();
+        let metadata = &extraction.metadata;
+        assert_eq!(metadata.get("Sidecar:Text"), Some("a cat"));
+        assert_eq!(metadata.get("Sidecar:{name}.json:title"), Some("gallery"));
+        assert_eq!(metadata.get("Sidecar:{name}.json:Text"), Some("json text"));
+        assert_eq!(metadata.get("Sidecar:{stem}.json:title"), Some("takeout"));
+    }
+}
@@ -0,0 +1,490 @@
+//! 스텔스 PNG 정보 디코더
+//!
+//! A1111 `stealth_pnginfo` 확장과 NovelAI는 생성 정보를 알파 채널(또는 RGB 채널)의
+//! 최하위 비트에 숨겨 저장합니다. 텍스트 청크가 제거된 이미지에서도 남아 있으므로
+//! 텍스트 청크에 생성 정보(A1111 `parameters`나 NovelAI `Comment` JSON)가 없을 때만
+//! 픽셀을 디코딩해 시그니처를 확인하고
+//! 내용을 `Stealth:parameters`로 읽습니다. 시그니처는 첫 열의 위쪽 행에 있어
+//! 그 행까지만 먼저 디코딩하고, 시그니처가 없으면 나머지 행은 디코딩하지 않습니다.
+//! 16비트 이미지는 표본을 8비트로 줄이지 않고 16비트 표본의 최하위 비트를 읽습니다.
+
+use std::io::{Cursor, Read};
+use std::ops::Range;
+
+use flate2::read::GzDecoder;
+use png::{BitDepth, ColorType, Transformations};
+
+use super::generators::novelai;
+use super::{png as chunks, text, Block, Issue, Metadata, Provenance};
+
+// 시그니처는 모두 15바이트이고, 뒤에 비트 단위 길이(32비트)와 내용이 이어집니다.
+const SIGNATURE_LENGTH: usize = 15;
+const ALPHA_SIGNATURES: (&[u8], &[u8]) = (b"stealth_pnginfo", b"stealth_pngcomp");
+const RGB_SIGNATURES: (&[u8], &[u8]) = (b"stealth_rgbinfo", b"stealth_rgbcomp");
+
+// 디코딩한 픽셀 버퍼 상한
+const MAX_PIXELS_SIZE: usize = 256 * 1024 * 1024;
+
+// 압축 해제 결과 상한 (압축 폭탄 방지)
+const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+/// 텍스트 청크에 생성 정보가 없으면 PNG 픽셀에 숨겨진 생성 정보를 읽어 `metadata`에 추가합니다.
+/// 찾은 내용과 디코딩하지 못한 이유는 진단 보고서에 `PNG:Stealth` 블록으로 남깁니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    if has_text_parameters(metadata) {
+        return;
+    }
+    // 픽셀 데이터가 없으면 숨길 곳도 없습니다.
+    let Some(range) = pixel_range(data) else {
+        return;
+    };
+    let block = Block::new("PNG:Stealth", range);
+    match decode(data) {
+        Ok(None) => {}
+        Ok(Some(parameters)) => {
+            // 픽셀에 흩어져 있으므로 바이트 범위는 없습니다.
+            let provenance = Provenance::new("PNG:IDAT").encoding(text::UTF8);
+            metadata.scoped(provenance, |metadata| {
+                metadata.insert("Stealth:parameters", parameters)
+            });
+            metadata.record(block);
+        }
+        Err(issue) => metadata.record(block.issue(issue)),
+    }
+}
+
+// 텍스트 청크에 A1111 `parameters`가 있거나 `Comment`가 NovelAI JSON인지.
+// 다른 프로그램이 남긴 평범한 `Comment`만 있으면 픽셀을 디코딩합니다.
+fn has_text_parameters(metadata: &Metadata) -> bool {
+    metadata.get("PNG:parameters").is_some()
+        || metadata.get("PNG:Comment").is_some_and(|comment| {
+            novelai::parse(
+                comment,
+                metadata.get("PNG:Software"),
+                metadata.get("PNG:Source"),
+            )
+            .is_some()
+        })
+}
+
+// 첫 IDAT부터 마지막 IDAT까지의 위치
+fn pixel_range(data: &[u8]) -> Option<Range<usize>> {
+    chunks::chunks(data)
+        .filter(|chunk| chunk.is(b"IDAT"))
+        .map(|chunk| chunk.range())
+        .reduce(|first, last| first.start..last.end)
+}
+
+/// 픽셀 데이터를 디코딩해 숨겨진 내용을 돌려줍니다. 시그니처가 없으면 `None`이고,
+/// 픽셀을 디코딩할 수 없거나 시그니처 뒤의 내용을 읽을 수 없으면 그 문제를 돌려줍니다.
+pub fn decode(data: &[u8]) -> Result<Option<String>, Issue> {
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    // 8비트 미만은 8비트로 펼치고, 16비트 표본은 그대로 둡니다.
+    decoder.set_transformations(Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(|_| Issue::Corrupt)?;
+
+    // (채널 수, 알파 채널 위치, RGB 채널을 읽을지). 팔레트는 RGB(A)로 펼쳐집니다.
+    let (color_type, bit_depth) = reader.output_color_type();
+    let sample_size = if bit_depth == BitDepth::Sixteen { 2 } else { 1 };
+    let (channels, alpha, rgb) = match color_type {
+        ColorType::Rgba => (4, Some(3), true),
+        ColorType::Rgb => (3, None, true),
+        // 회색조 픽셀은 세 채널이 같아 채널마다 다른 비트를 쓰는 RGB 모드를 담을 수 없습니다.
+        ColorType::GrayscaleAlpha => (2, Some(1), false),
+        ColorType::Grayscale | ColorType::Indexed => return Ok(None),
+    };
+    let size = reader.output_buffer_size();
+    if size > MAX_PIXELS_SIZE {
+        return Err(Issue::TooLarge {
+            limit: MAX_PIXELS_SIZE,
+        });
+    }
+    let info = reader.info();
+    let (width, height) = (info.width as usize, info.height as usize);
+    let interlaced = info.interlaced;
+    let line_size = reader.output_line_size(info.width);
+
+    let mut buffer = vec![0; size];
+    let mut rows = 0;
+    if interlaced {
+        // 인터레이스 이미지는 행이 순서대로 나오지 않아 한 번에 디코딩합니다.
+        reader.next_frame(&mut buffer).map_err(|_| Issue::Corrupt)?;
+        rows = height;
+    } else {
+        // 시그니처 비트(알파 모드는 픽셀당 1비트, RGB 모드는 3비트)가 들어 있는 행
+        let per_pixel = if alpha.is_some() { 1 } else { 3 };
+        let signature_rows = height.min((SIGNATURE_LENGTH * 8).div_ceil(per_pixel));
+        while rows < signature_rows {
+            read_row(&mut reader, &mut buffer, line_size, &mut rows)?;
+        }
+    }
+
+    let layout = Layout {
+        width,
+        height,
+        line_size,
+        channels,
+        sample_size,
+    };
+    let alpha_signature = alpha
+        .and_then(|alpha| read_signature(&mut layout.alpha_bits(&buffer, alpha), ALPHA_SIGNATURES));
+    let rgb_signature = match alpha_signature {
+        None if rgb => read_signature(&mut layout.rgb_bits(&buffer), RGB_SIGNATURES),
+        _ => None,
+    };
+
+    let payload = match (alpha, alpha_signature, rgb_signature) {
+        (Some(alpha), Some(compressed), _) => {
+            read_rest(&mut reader, &mut buffer, layout, &mut rows)?;
+            read_payload(&mut layout.alpha_bits(&buffer, alpha), compressed)
+        }
+        (_, _, Some(compressed)) => {
+            read_rest(&mut reader, &mut buffer, layout, &mut rows)?;
+            read_payload(&mut layout.rgb_bits(&buffer), compressed)
+        }
+        _ => return Ok(None),
+    }?;
+    Ok(Some(payload))
+}
+
+// 남은 행을 모두 디코딩합니다.
+fn read_rest<R: Read>(
+    reader: &mut png::Reader<R>,
+    buffer: &mut [u8],
+    layout: Layout,
+    rows: &mut usize,
+) -> Result<(), Issue> {
+    while *rows < layout.height {
+        read_row(reader, buffer, layout.line_size, rows)?;
+    }
+    Ok(())
+}
+
+// 다음 행을 디코딩해 `buffer`의 `rows`번째 행에 씁니다.
+fn read_row<R: Read>(
+    reader: &mut png::Reader<R>,
+    buffer: &mut [u8],
+    line_size: usize,
+    rows: &mut usize,
+) -> Result<(), Issue> {
+    let row = reader
+        .next_row()
+        .map_err(|_| Issue::Corrupt)?
+        .ok_or(Issue::Corrupt)?;
+    let start = *rows * line_size;
+    buffer[start..start + row.data().len()].copy_from_slice(row.data());
+    *rows += 1;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    width: usize,
+    height: usize,
+    line_size: usize,
+    channels: usize,
+    /// 표본 하나의 바이트 수 (16비트면 2, 빅 엔디언)
+    sample_size: usize,
+}
+
+impl Layout {
+    /// 확장과 같은 순서(열 우선: x마다 위에서 아래로)로 픽셀을 순회합니다.
+    fn column_major(self, data: &[u8]) -> impl Iterator<Item = &[u8]> {
+        let pixel_size = self.channels * self.sample_size;
+        (0..self.width).flat_map(move |x| {
+            (0..self.height).filter_map(move |y| {
+                let start = y * self.line_size + x * pixel_size;
+                data.get(start..start + pixel_size)
+            })
+        })
+    }
+
+    // 표본의 최하위 비트는 마지막 바이트에 있습니다.
+    fn bit(self, pixel: &[u8], channel: usize) -> u8 {
+        pixel[(channel + 1) * self.sample_size - 1] & 1
+    }
+
+    fn alpha_bits(self, data: &[u8], alpha: usize) -> impl Iterator<Item = u8> + '_ {
+        self.column_major(data)
+            .map(move |pixel| self.bit(pixel, alpha))
+    }
+
+    fn rgb_bits(self, data: &[u8]) -> impl Iterator<Item = u8> + '_ {
+        self.column_major(data)
+            .flat_map(move |pixel| [0, 1, 2].map(|channel| self.bit(pixel, channel)))
+    }
+}
+
+/// 비트 스트림 앞의 시그니처를 확인합니다. `signatures`는 (비압축, gzip 압축) 시그니처 쌍이고,
+/// 맞으면 압축 여부를 돌려줍니다.
+fn read_signature(bits: &mut impl Iterator<Item = u8>, signatures: (&[u8], &[u8])) -> Option<bool> {
+    let signature = take_bytes(bits, SIGNATURE_LENGTH).ok()?;
+    if signature == signatures.0 {
+        Some(false)
+    } else if signature == signatures.1 {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// 시그니처 뒤의 길이와 내용을 읽습니다.
+fn read_payload(bits: &mut impl Iterator<Item = u8>, compressed: bool) -> Result<String, Issue> {
+    let _ = take_bytes(bits, SIGNATURE_LENGTH)?;
+    let length = take_bytes(bits, 4)?;
+    let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+    let payload = take_bytes(bits, length / 8)?;
+    if !compressed {
+        return Ok(String::from_utf8_lossy(&payload).into_owned());
+    }
+    let mut out = Vec::new();
+    GzDecoder::new(payload.as_slice())
+        .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|_| Issue::Decompress)?;
+    if out.len() > MAX_DECOMPRESSED_SIZE {
+        return Err(Issue::TooLarge {
+            limit: MAX_DECOMPRESSED_SIZE,
+        });
+    }
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// 상위 비트부터 8비트씩 묶어 `count`바이트를 읽습니다. 비트가 모자라면 잘린 것입니다.
+fn take_bytes(bits: &mut impl Iterator<Item = u8>, count: usize) -> Result<Vec<u8>, Issue> {
+    let mut out = Vec::new();
+    for _ in 0..count {
+        let mut byte = 0u8;
+        for _ in 0..8 {
+            let bit = bits.next().ok_or(Issue::Truncated {
+                expected: count,
+                available: out.len(),
+            })?;
+            byte = (byte << 1) | bit;
+        }
+        out.push(byte);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::chunk;
+
+    fn bits_of(bytes: &[u8]) -> impl Iterator<Item = u8> + '_ {
+        bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+    }
+
+    fn encode(
+        width: usize,
+        height: usize,
+        color: ColorType,
+        signature: &[u8],
+        payload: &[u8],
+    ) -> Vec<u8> {
+        encode_with_depth(width, height, color, BitDepth::Eight, signature, payload)
+    }
+
+    /// 시그니처, 비트 단위 길이, 내용을 열 우선으로 최하위 비트에 숨긴 PNG.
+    /// RGB 시그니처면 RGB 채널에, 아니면 마지막(알파) 채널에 씁니다.
+    /// 16비트 표본의 상위 바이트는 최하위 비트가 1이어서, 8비트로 줄이면 내용이 사라집니다.
+    fn encode_with_depth(
+        width: usize,
+        height: usize,
+        color: ColorType,
+        depth: BitDepth,
+        signature: &[u8],
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let channels = color.samples();
+        let targets = if signature.starts_with(b"stealth_rgb") {
+            0..3
+        } else {
+            channels - 1..channels
+        };
+        let length = ((payload.len() * 8) as u32).to_be_bytes();
+        let mut bits = bits_of(signature)
+            .chain(bits_of(&length))
+            .chain(bits_of(payload));
+
+        let mut pixels = vec![0x8180u16; width * height * channels];
+        'pixels: for x in 0..width {
+            for y in 0..height {
+                let pixel = (y * width + x) * channels;
+                for channel in targets.clone() {
+                    let Some(bit) = bits.next() else {
+                        break 'pixels;
+                    };
+                    pixels[pixel + channel] = (pixels[pixel + channel] & !1) | u16::from(bit);
+                }
+            }
+        }
+
+        let samples: Vec<u8> = match depth {
+            BitDepth::Sixteen => pixels
+                .iter()
+                .flat_map(|sample| sample.to_be_bytes())
+                .collect(),
+            _ => pixels.iter().map(|&sample| sample as u8).collect(),
+        };
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, width as u32, height as u32);
+        encoder.set_color(color);
+        encoder.set_depth(depth);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&samples)
+            .unwrap();
+        out
+    }
+
+    fn block(metadata: &Metadata) -> Option<&Block> {
+        metadata
+            .report()
+            .blocks
+            .iter()
+            .find(|block| block.name == "PNG:Stealth")
+    }
+
+    #[test]
+    fn alpha() {
+        let text = "a cat, masterpiece\nSteps: 20";
+        let data = encode(64, 64, ColorType::Rgba, b"stealth_pnginfo", text.as_bytes());
+        assert_eq!(decode(&data), Ok(Some(text.to_owned())));
+
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("Stealth:parameters"), Some(text));
+        assert_eq!(block(&metadata).unwrap().issue, None);
+    }
+
+    #[test]
+    fn alpha_gzip() {
+        let text = r#"{"prompt":"한글 프롬프트"}"#;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        let payload = encoder.finish().unwrap();
+
+        let data = encode(50, 40, ColorType::Rgba, b"stealth_pngcomp", &payload);
+        assert_eq!(extract(&data).get("Stealth:parameters"), Some(text));
+    }
+
+    #[test]
+    fn rgb() {
+        let data = encode(32, 20, ColorType::Rgb, b"stealth_rgbinfo", b"hello rgb");
+        assert_eq!(extract(&data).get("Stealth:parameters"), Some("hello rgb"));
+    }
+
+    #[test]
+    fn grayscale_alpha() {
+        let data = encode(
+            40,
+            40,
+            ColorType::GrayscaleAlpha,
+            b"stealth_pnginfo",
+            b"gray",
+        );
+        assert_eq!(extract(&data).get("Stealth:parameters"), Some("gray"));
+    }
+
+    #[test]
+    fn sixteen_bit() {
+        let data = encode_with_depth(
+            40,
+            40,
+            ColorType::Rgba,
+            BitDepth::Sixteen,
+            b"stealth_pnginfo",
+            b"deep",
+        );
+        assert_eq!(extract(&data).get("Stealth:parameters"), Some("deep"));
+        let data = encode_with_depth(
+            32,
+            20,
+            ColorType::Rgb,
+            BitDepth::Sixteen,
+            b"stealth_rgbinfo",
+            b"deep rgb",
+        );
+        assert_eq!(extract(&data).get("Stealth:parameters"), Some("deep rgb"));
+    }
+
+    #[test]
+    fn no_signature() {
+        let data = encode(8, 8, ColorType::Rgba, b"nothing_here___", b"x");
+        assert_eq!(decode(&data), Ok(None));
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("Stealth:parameters"), None);
+        assert!(block(&metadata).is_none());
+    }
+
+    #[test]
+    fn truncated() {
+        // 내용이 픽셀 수(144비트)보다 깁니다.
+        let data = encode(12, 12, ColorType::Rgba, b"stealth_pnginfo", &[b'a'; 100]);
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("Stealth:parameters"), None);
+        assert!(matches!(
+            block(&metadata).unwrap().issue,
+            Some(Issue::Truncated { .. })
+        ));
+    }
+
+    // 시그니처(8)와 IHDR(25) 다음에 `chunks`를 넣습니다.
+    fn with_chunks(data: &[u8], chunks: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = data[..33].to_vec();
+        chunks.iter().for_each(|chunk| out.extend_from_slice(chunk));
+        out.extend_from_slice(&data[33..]);
+        out
+    }
+
+    #[test]
+    fn skipped_when_text_has_parameters() {
+        let data = encode(64, 64, ColorType::Rgba, b"stealth_pnginfo", b"hidden");
+        let metadata = extract(&with_chunks(
+            &data,
+            &[chunk(b"tEXt", b"parameters\0visible")],
+        ));
+        assert_eq!(metadata.get("PNG:parameters"), Some("visible"));
+        assert_eq!(metadata.get("Stealth:parameters"), None);
+        assert!(block(&metadata).is_none());
+    }
+
+    #[test]
+    fn decoded_when_comment_is_not_novelai() {
+        let data = encode(64, 64, ColorType::Rgba, b"stealth_pnginfo", b"hidden");
+        let metadata = extract(&with_chunks(
+            &data,
+            &[chunk(b"tEXt", b"Comment\0Created with GIMP")],
+        ));
+        assert_eq!(metadata.get("PNG:Comment"), Some("Created with GIMP"));
+        assert_eq!(metadata.get("Stealth:parameters"), Some("hidden"));
+
+        // NovelAI Comment JSON이 있으면 디코딩하지 않습니다.
+        let metadata = extract(&with_chunks(
+            &data,
+            &[
+                chunk(b"tEXt", b"Software\0NovelAI"),
+                chunk(
+                    b"tEXt",
+                    &[
+                        b"Comment\0",
+                        &br#"{"prompt": "a cat", "uc": "", "seed": 1}"#[..],
+                    ]
+                    .concat(),
+                ),
+            ],
+        ));
+        assert_eq!(metadata.get("NAI:Prompt"), Some("a cat"));
+        assert_eq!(metadata.get("Stealth:parameters"), None);
+    }
+}
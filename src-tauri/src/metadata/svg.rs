@@ -0,0 +1,73 @@
+//! SVG 문서 파서
+//!
+//! 최상위 `<title>`, `<desc>`와 `<metadata>` 안의 RDF(XMP와 같은 방식으로 평탄화)를 읽습니다.
+
+use roxmltree::{Document, ParsingOptions};
+
+use super::{xmp, Metadata};
+
+const SVG: &str = "http://www.w3.org/2000/svg";
+
+// 시그니처를 찾을 앞부분 길이 (XML 선언, 주석, DOCTYPE 이후에 `<svg`가 옵니다)
+const SNIFF_LENGTH: usize = 4096;
+
+pub fn is_svg(data: &[u8]) -> bool {
+    let head = &data[..data.len().min(SNIFF_LENGTH)];
+    let head = head.strip_prefix(b"\xef\xbb\xbf").unwrap_or(head);
+    let head = head.trim_ascii_start();
+    head.starts_with(b"<")
+        && head.windows(4).any(|w| w == b"<svg")
+        && (head.starts_with(b"<?xml") || head.starts_with(b"<svg") || head.starts_with(b"<!"))
+}
+
+/// 제목, 설명, RDF 메타데이터를 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    let text = String::from_utf8_lossy(data);
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let Ok(doc) = Document::parse_with_options(&text, options) else {
+        return;
+    };
+    for child in doc.root_element().children().filter(|n| n.is_element()) {
+        if child.tag_name().namespace() != Some(SVG) {
+            continue;
+        }
+        let key = match child.tag_name().name() {
+            "title" => "SVG:Title",
+            "desc" => "SVG:Description",
+            _ => continue,
+        };
+        let value: String = child
+            .descendants()
+            .filter(|n| n.is_text())
+            .filter_map(|n| n.text())
+            .collect();
+        metadata.insert(key, value.trim());
+    }
+    xmp::read(data, metadata);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::extract;
+
+    #[test]
+    fn title_and_rdf_metadata() {
+        let data = r#"<?xml version="1.0"?><!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd"><svg xmlns="http://www.w3.org/2000/svg"><title>My cat</title><desc>a white cat</desc><metadata><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:cc="http://creativecommons.org/ns#" xmlns:dc="http://purl.org/dc/elements/1.1/"><cc:Work rdf:about=""><dc:creator>me</dc:creator></cc:Work></rdf:RDF></metadata></svg>"#;
+        assert!(is_svg(data.as_bytes()));
+
+        let metadata = extract(data.as_bytes());
+        assert_eq!(metadata.get("SVG:Title"), Some("My cat"));
+        assert_eq!(metadata.get("SVG:Description"), Some("a white cat"));
+        assert_eq!(metadata.get("XMP:dc:creator"), Some("me"));
+    }
+
+    #[test]
+    fn not_svg() {
+        assert!(!is_svg(b"<html><body></body></html>"));
+        assert!(!is_svg(b"plain text"));
+    }
+}
@@ -0,0 +1,143 @@
+//! 테스트용 바이트 픽스처 생성기
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+/// CRC까지 붙인 PNG 청크
+pub fn chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut out = (data.len() as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(kind);
+    hasher.update(data);
+    out.extend_from_slice(&hasher.finalize().to_be_bytes());
+    out
+}
+
+/// 1×1 RGBA IHDR과 IEND 사이에 `chunks`를 넣은 PNG
+pub fn png(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = super::png::SIGNATURE.to_vec();
+    out.extend(chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]));
+    for chunk in chunks {
+        out.extend_from_slice(chunk);
+    }
+    out.extend(chunk(b"IEND", &[]));
+    out
+}
+
+/// `parameters` tEXt 청크 하나뿐인 PNG
+pub fn parameters_png(text: &str) -> Vec<u8> {
+    png(&[chunk(b"tEXt", format!("parameters\0{text}").as_bytes())])
+}
+
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// IFD 항목 (태그, 형식, 개수, 값)
+pub type IfdEntry = (u16, u16, u32, Vec<u8>);
+
+/// 파일의 `base` 위치에 놓일 리틀 엔디언 IFD. 4바이트가 넘는 값은 IFD 바로 뒤에 둡니다.
+pub fn ifd(base: usize, entries: &[IfdEntry], next: u32) -> Vec<u8> {
+    let values_start = base + 2 + entries.len() * 12 + 4;
+    let mut values = Vec::new();
+    let mut out = (entries.len() as u16).to_le_bytes().to_vec();
+    for (tag, kind, count, value) in entries {
+        out.extend_from_slice(&tag.to_le_bytes());
+        out.extend_from_slice(&kind.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        if value.len() <= 4 {
+            let mut inline = value.clone();
+            inline.resize(4, 0);
+            out.extend(inline);
+        } else {
+            out.extend_from_slice(&((values_start + values.len()) as u32).to_le_bytes());
+            values.extend_from_slice(value);
+        }
+    }
+    out.extend_from_slice(&next.to_le_bytes());
+    out.extend(values);
+    out
+}
+
+/// IFD0 하나뿐인 리틀 엔디언 TIFF
+pub fn tiff(entries: &[IfdEntry]) -> Vec<u8> {
+    let mut out = b"II*\0".to_vec();
+    out.extend_from_slice(&8u32.to_le_bytes());
+    out.extend(ifd(8, entries, 0));
+    out
+}
+
+/// 분자와 분모로 된 RATIONAL 값
+pub fn rational(numerator: u32, denominator: u32) -> Vec<u8> {
+    [numerator.to_le_bytes(), denominator.to_le_bytes()].concat()
+}
+
+/// JPEG 마커 세그먼트
+pub fn segment(out: &mut Vec<u8>, marker: u8, payload: &[u8]) {
+    out.extend_from_slice(&[0xff, marker]);
+    out.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+    out.extend_from_slice(payload);
+}
+
+/// `rdf:Description` 하나에 `properties`를 넣은 XMP 패킷 (dc, xmp 네임스페이스)
+pub fn xmp(properties: &str) -> String {
+    format!(
+        r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"><rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:xmp="http://ns.adobe.com/xap/1.0/">{properties}</rdf:Description></rdf:RDF></x:xmpmeta>"#
+    )
+}
+
+/// ISOBMFF 박스
+pub fn bmff_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    out
+}
+
+/// 버전과 플래그(0)가 본문 앞에 붙는 ISOBMFF 전체 박스
+pub fn full_box(kind: &[u8; 4], version: u8, body: &[u8]) -> Vec<u8> {
+    let mut data = vec![version, 0, 0, 0];
+    data.extend_from_slice(body);
+    bmff_box(kind, &data)
+}
+
+/// VideoHelperSuite가 동영상 주석에 쓰는 ComfyUI 프롬프트와 워크플로
+pub const VIDEO_COMMENT: &str = r#"{"prompt": {"3": {"class_type": "KSampler", "inputs": {"seed": 42, "steps": 20, "cfg": 7, "sampler_name": "euler", "positive": ["6", 0], "negative": ["7", 0], "model": ["4", 0]}}, "4": {"class_type": "CheckpointLoaderSimple", "inputs": {"ckpt_name": "sd15.safetensors"}}, "6": {"class_type": "CLIPTextEncode", "inputs": {"text": "a dancing cat"}}, "7": {"class_type": "CLIPTextEncode", "inputs": {"text": "blurry"}}}, "workflow": {"nodes": []}}"#;
+
+/// 테스트가 끝나면 지워지는 임시 폴더
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "metadata-test-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    /// 폴더 안에 `name` 파일을 만들고 경로를 돌려줍니다.
+    pub fn file(&self, name: &str, data: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.0.join(name);
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,161 @@
+//! 메타데이터 문자열 디코딩 도우미
+
+// 필드 출처에 기록하는 인코딩 이름
+pub const UTF8: &str = "UTF-8";
+pub const LATIN1: &str = "ISO-8859-1";
+pub const UTF16LE: &str = "UTF-16LE";
+pub const UTF16BE: &str = "UTF-16BE";
+
+pub fn latin1(data: &[u8]) -> String {
+    data.iter().map(|&b| b as char).collect()
+}
+
+/// 규격상 Latin-1인 필드도 UTF-8을 그대로 써 넣는 도구가 많아 UTF-8을 먼저 시도합니다.
+pub fn utf8_or_latin1(data: &[u8]) -> String {
+    decode_utf8_or_latin1(data).0
+}
+
+/// `utf8_or_latin1`과 같고, 실제로 쓴 인코딩 이름을 함께 돌려줍니다.
+pub fn decode_utf8_or_latin1(data: &[u8]) -> (String, &'static str) {
+    match std::str::from_utf8(data) {
+        Ok(text) => (text.to_owned(), UTF8),
+        Err(_) => (latin1(data), LATIN1),
+    }
+}
+
+/// 문자셋 정보가 없는 레거시 문자열(EXIF ASCII, IPTC, JPEG 주석)을 디코딩하고,
+/// 고른 인코딩을 함께 돌려줍니다. UTF-8이 아니면 CP949(EUC-KR)와 Shift_JIS 중
+/// 자주 쓰는 글자가 더 많이 나오는 쪽을 고르고, 둘 다 아니면 Latin-1로 읽습니다.
+pub fn decode_legacy(data: &[u8]) -> (String, &'static str) {
+    if let Ok(text) = std::str::from_utf8(data) {
+        return (text.to_owned(), UTF8);
+    }
+    // 점수가 같으면 뒤쪽(한국어)을 고릅니다.
+    let candidates = [
+        (encoding_rs::SHIFT_JIS, japanese_score(data)),
+        (encoding_rs::EUC_KR, korean_score(data)),
+    ];
+    candidates
+        .into_iter()
+        .filter(|&(_, score)| score > 0)
+        .filter_map(|(encoding, score)| {
+            let text = encoding.decode_without_bom_handling_and_without_replacement(data)?;
+            Some((text.into_owned(), encoding.name(), score))
+        })
+        .max_by_key(|&(_, _, score)| score)
+        .map(|(text, name, _)| (text, name))
+        .unwrap_or_else(|| (latin1(data), LATIN1))
+}
+
+// KS X 1001 한글 음절(B0A1–C8FE) 수. CP949 확장 영역은 드물게 쓰는 음절이라 세지 않습니다.
+fn korean_score(data: &[u8]) -> usize {
+    let mut score = 0;
+    let mut i = 0;
+    while let Some(&lead) = data.get(i) {
+        if lead < 0x80 {
+            i += 1;
+            continue;
+        }
+        let trail = data.get(i + 1).copied().unwrap_or(0);
+        if (0xb0..=0xc8).contains(&lead) && (0xa1..=0xfe).contains(&trail) {
+            score += 2;
+        }
+        i += 2;
+    }
+    score
+}
+
+// 히라가나(82xx), 가타카나(83xx)는 2점, 한자(889F–9FFC, E040–EAA4)는 1점
+fn japanese_score(data: &[u8]) -> usize {
+    let mut score = 0;
+    let mut i = 0;
+    while let Some(&lead) = data.get(i) {
+        match lead {
+            // ASCII, 반각 가타카나
+            0x00..=0x80 | 0xa0..=0xdf => i += 1,
+            _ => {
+                score += match lead {
+                    0x82 | 0x83 => 2,
+                    0x88..=0x9f | 0xe0..=0xea => 1,
+                    _ => 0,
+                };
+                i += 2;
+            }
+        }
+    }
+    score
+}
+
+/// UTF-16 바이트열을 디코딩합니다. BOM이 있으면 BOM의 바이트 순서를 따릅니다.
+pub fn utf16(data: &[u8], big_endian: bool) -> String {
+    let (data, big_endian) = match data {
+        [0xfe, 0xff, rest @ ..] => (rest, true),
+        [0xff, 0xfe, rest @ ..] => (rest, false),
+        _ => (data, big_endian),
+    };
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|pair| {
+            if big_endian {
+                u16::from_be_bytes([pair[0], pair[1]])
+            } else {
+                u16::from_le_bytes([pair[0], pair[1]])
+            }
+        })
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// C 문자열처럼 끝에 붙은 NUL 문자를 제거합니다.
+pub fn trim_nul(text: &str) -> &str {
+    text.trim_end_matches('\0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::tiff;
+
+    #[test]
+    fn legacy_encodings() {
+        let (korean, _, _) = encoding_rs::EUC_KR.encode("고양이 사진, 안녕하세요");
+        assert_eq!(
+            decode_legacy(&korean),
+            ("고양이 사진, 안녕하세요".into(), "EUC-KR")
+        );
+        let (japanese, _, _) = encoding_rs::SHIFT_JIS.encode("こんにちは、猫の写真");
+        assert_eq!(
+            decode_legacy(&japanese),
+            ("こんにちは、猫の写真".into(), "Shift_JIS")
+        );
+        let (katakana, _, _) = encoding_rs::SHIFT_JIS.encode("カタカナ");
+        assert_eq!(decode_legacy(&katakana).1, "Shift_JIS");
+        assert_eq!(
+            decode_legacy(b"Caf\xe9 Zo\xeb"),
+            ("Café Zoë".into(), LATIN1)
+        );
+        assert_eq!(decode_legacy("고양이".as_bytes()), ("고양이".into(), UTF8));
+    }
+
+    #[test]
+    fn exif_ascii_encoding() {
+        let (korean, _, _) = encoding_rs::EUC_KR.encode("고양이 사진\0");
+        let data = tiff(&[(0x010e, 2, korean.len() as u32, korean.into_owned())]);
+        let field = extract(&data)
+            .field("EXIF:ImageDescription")
+            .cloned()
+            .unwrap();
+        assert_eq!(field.text, "고양이 사진");
+        assert_eq!(field.encoding.as_deref(), Some("EUC-KR"));
+    }
+
+    #[test]
+    fn utf16_bom() {
+        assert_eq!(utf16(b"\xfe\xff\0a\0b", false), "ab");
+        assert_eq!(utf16(b"\xff\xfea\0b\0", true), "ab");
+        assert_eq!(utf16(b"a\0b\0", false), "ab");
+        assert_eq!(utf8_or_latin1(b"\xe9t\xe9"), "été");
+        assert_eq!(trim_nul("abc\0\0"), "abc");
+    }
+}
@@ -0,0 +1,362 @@
+//! Matroska(WebM/MKV) 메타데이터 파서
+//!
+//! EBML 요소 트리에서 `Segment/Info`의 제목과 먹싱 도구, `Segment/Tags`의 전역 태그를
+//! `Matroska:이름` 필드로 만듭니다. 트랙에 붙은 태그(ffmpeg의 `DURATION`, `ENCODER` 등)는 읽지 않습니다.
+//!
+//! ```text
+//! 요소: ID(가변 길이, 1-4바이트) + 크기(가변 길이, 1-8바이트) + 내용
+//! ```
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::metadata::{text, Metadata, Provenance};
+
+const EBML: u32 = 0x1a45_dfa3;
+const DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_a966;
+const TITLE: u32 = 0x7ba9;
+const MUXING_APP: u32 = 0x4d80;
+const WRITING_APP: u32 = 0x5741;
+const TAGS: u32 = 0x1254_c367;
+const TAG: u32 = 0x7373;
+const TARGETS: u32 = 0x63c0;
+const SIMPLE_TAG: u32 = 0x67c8;
+const TAG_NAME: u32 = 0x45a3;
+const TAG_STRING: u32 = 0x4487;
+// 트랙, 에디션, 챕터, 첨부 파일을 가리키는 Targets 하위 요소
+const TARGET_UIDS: &[u32] = &[0x63c5, 0x63c9, 0x63c4, 0x63c6];
+
+// 중첩된 SimpleTag 깊이 상한
+const MAX_TAG_DEPTH: usize = 8;
+
+pub fn is_matroska(data: &[u8]) -> bool {
+    data.starts_with(&EBML.to_be_bytes())
+}
+
+/// EBML 요소 하나
+#[derive(Debug, Clone, Copy)]
+struct Element<'a> {
+    id: u32,
+    data: &'a [u8],
+}
+
+/// 같은 수준의 요소를 순서대로 돌려줍니다. 크기를 모르는 요소(실시간 녹화의 클러스터 등)는
+/// 남은 데이터 전체로 봅니다.
+fn elements(data: &[u8]) -> impl Iterator<Item = Element<'_>> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let (id, id_length) = vint(data.get(offset..)?, true)?;
+        let (size, size_length) = vint(data.get(offset + id_length..)?, false)?;
+        let start = offset + id_length + size_length;
+        let end = match size {
+            Some(size) => start.checked_add(usize::try_from(size).ok()?)?,
+            None => data.len(),
+        };
+        // 잘린 파일이면 남은 만큼만 읽습니다.
+        let body = data.get(start..end.min(data.len()))?;
+        offset = end;
+        Some(Element {
+            id: id? as u32,
+            data: body,
+        })
+    })
+}
+
+// 가변 길이 정수: 첫 바이트의 선행 0 비트 수 + 1이 길이입니다.
+// ID는 표시 비트를 유지하고, 크기는 표시 비트를 떼며 모든 비트가 1이면 "크기 모름"(None)입니다.
+fn vint(data: &[u8], keep_marker: bool) -> Option<(Option<u64>, usize)> {
+    let &first = data.first()?;
+    let length = first.leading_zeros() as usize + 1;
+    if length > 8 || (keep_marker && length > 4) {
+        return None;
+    }
+    let bytes = data.get(..length)?;
+    let mut value = bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    if keep_marker {
+        return Some((Some(value), length));
+    }
+    let marker = 1u64 << (7 * length);
+    value &= marker - 1;
+    let unknown = value == marker - 1;
+    Some(((!unknown).then_some(value), length))
+}
+
+/// 파일에서 EBML 헤더와 `Segment`의 `Info`, `Tags` 요소만 읽어 [`read`]로 넘깁니다.
+/// 다른 요소는 헤더만 읽고 건너뛰며, 크기를 모르는 요소(클러스터 등)는 그 안으로 들어가
+/// 하위 요소를 같은 방식으로 건너뜁니다.
+pub fn read_file<R: Read + Seek>(reader: &mut R, metadata: &mut Metadata) -> io::Result<()> {
+    let length = reader.seek(SeekFrom::End(0))?;
+    let Some((EBML, Some(size), header_length)) = element_header(reader, 0)? else {
+        return Ok(());
+    };
+    let mut offset = header_length.saturating_add(size);
+    let Some(mut copy) = super::read_element(reader, "Matroska:EBML", 0..offset, metadata)? else {
+        return Ok(());
+    };
+
+    // 최상위에서 Segment를 찾습니다.
+    let segment_end = loop {
+        match element_header(reader, offset)? {
+            Some((SEGMENT, size, header_length)) => {
+                offset += header_length;
+                break size.map_or(length, |size| offset.saturating_add(size).min(length));
+            }
+            Some((_, Some(size), header_length)) => {
+                offset = offset.saturating_add(header_length + size)
+            }
+            _ => return Ok(()),
+        }
+    };
+    // 읽은 요소는 크기를 모르는 Segment 안에 모읍니다.
+    copy.extend_from_slice(&[
+        0x18, 0x53, 0x80, 0x67, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    ]);
+    while offset < segment_end {
+        let Some((id, size, header_length)) = element_header(reader, offset)? else {
+            break;
+        };
+        let Some(size) = size else {
+            offset += header_length;
+            continue;
+        };
+        let end = offset.saturating_add(header_length + size);
+        let name = match id {
+            INFO => "Matroska:Info",
+            TAGS => "Matroska:Tags",
+            _ => {
+                offset = end;
+                continue;
+            }
+        };
+        if let Some(element) = super::read_element(reader, name, offset..end, metadata)? {
+            copy.extend(element);
+        }
+        offset = end;
+    }
+    read(&copy, metadata);
+    Ok(())
+}
+
+// `offset`의 요소 헤더: ID, 크기(모르면 `None`), 헤더 길이
+fn element_header<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+) -> io::Result<Option<(u32, Option<u64>, u64)>> {
+    let header = super::read_at(reader, offset, 12)?;
+    let Some((Some(id), id_length)) = vint(&header, true) else {
+        return Ok(None);
+    };
+    let Some((size, size_length)) = header.get(id_length..).and_then(|rest| vint(rest, false))
+    else {
+        return Ok(None);
+    };
+    Ok(Some((id as u32, size, (id_length + size_length) as u64)))
+}
+
+fn find(data: &[u8], id: u32) -> Option<Element<'_>> {
+    elements(data).find(|element| element.id == id)
+}
+
+fn string(element: Element) -> String {
+    text::trim_nul(&String::from_utf8_lossy(element.data)).to_owned()
+}
+
+/// `Info`와 `Tags` 요소를 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    let mut top = elements(data);
+    if let Some(doc_type) = top
+        .next()
+        .filter(|header| header.id == EBML)
+        .and_then(|header| find(header.data, DOC_TYPE))
+    {
+        metadata.insert("Matroska:DocType", string(doc_type));
+    }
+    let Some(segment) = top.find(|element| element.id == SEGMENT) else {
+        return;
+    };
+    for element in elements(segment.data) {
+        match element.id {
+            INFO => metadata.scoped(Provenance::new("Matroska:Info"), |metadata| {
+                read_info(element.data, metadata)
+            }),
+            TAGS => metadata.scoped(Provenance::new("Matroska:Tags"), |metadata| {
+                read_tags(element.data, metadata)
+            }),
+            _ => {}
+        }
+    }
+    super::split_comment("Matroska", metadata);
+}
+
+fn read_info(info: &[u8], metadata: &mut Metadata) {
+    for element in elements(info) {
+        let name = match element.id {
+            TITLE => "Title",
+            MUXING_APP => "MuxingApp",
+            WRITING_APP => "WritingApp",
+            _ => continue,
+        };
+        metadata.insert(format!("Matroska:{name}"), string(element));
+    }
+}
+
+fn read_tags(tags: &[u8], metadata: &mut Metadata) {
+    for tag in elements(tags).filter(|element| element.id == TAG) {
+        let is_global = find(tag.data, TARGETS).is_none_or(|targets| {
+            !elements(targets.data).any(|target| TARGET_UIDS.contains(&target.id))
+        });
+        if !is_global {
+            continue;
+        }
+        for simple in elements(tag.data).filter(|element| element.id == SIMPLE_TAG) {
+            read_simple_tag(simple, "", 0, metadata);
+        }
+    }
+}
+
+// 중첩된 SimpleTag는 `Matroska:Parent/Child`로 씁니다.
+fn read_simple_tag(simple: Element, parent: &str, depth: usize, metadata: &mut Metadata) {
+    if depth >= MAX_TAG_DEPTH {
+        return;
+    }
+    let Some(name) = find(simple.data, TAG_NAME).map(string) else {
+        return;
+    };
+    let name = format!("{parent}{}", field_name(&name));
+    if let Some(value) = find(simple.data, TAG_STRING) {
+        metadata.insert(format!("Matroska:{name}"), string(value));
+    }
+    for child in elements(simple.data).filter(|element| element.id == SIMPLE_TAG) {
+        read_simple_tag(child, &format!("{name}/"), depth + 1, metadata);
+    }
+}
+
+// 규격의 대문자 태그 이름은 "DATE_RECORDED" -> "DateRecorded"로 바꾸고, 나머지는 그대로 둡니다.
+fn field_name(name: &str) -> String {
+    let is_official = name
+        .chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    if !is_official {
+        return name.to_owned();
+    }
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_string() + &chars.as_str().to_ascii_lowercase(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::VIDEO_COMMENT;
+
+    // 크기는 8바이트 가변 길이 정수로 씁니다.
+    fn element(id: u32, body: &[u8]) -> Vec<u8> {
+        let id = id.to_be_bytes();
+        let skip = id.iter().position(|&b| b != 0).unwrap();
+        let mut out = id[skip..].to_vec();
+        out.push(0x01);
+        out.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn simple_tag(name: &str, value: &str) -> Vec<u8> {
+        element(
+            SIMPLE_TAG,
+            &[
+                element(TAG_NAME, name.as_bytes()),
+                element(TAG_STRING, value.as_bytes()),
+            ]
+            .concat(),
+        )
+    }
+
+    fn webm() -> Vec<u8> {
+        let header = element(EBML, &element(DOC_TYPE, b"webm"));
+        let info = element(
+            INFO,
+            &[element(MUXING_APP, b"Lavf60"), element(TITLE, b"clip")].concat(),
+        );
+        let global = element(
+            TAG,
+            &[
+                element(TARGETS, &[]),
+                simple_tag("COMMENT", VIDEO_COMMENT),
+                simple_tag("DATE_RECORDED", "2024"),
+            ]
+            .concat(),
+        );
+        let track = element(
+            TAG,
+            &[
+                element(TARGETS, &element(TARGET_UIDS[0], &[1])),
+                simple_tag("ENCODER", "libvpx"),
+            ]
+            .concat(),
+        );
+        let tags = element(TAGS, &[global, track].concat());
+        let cluster = element(0x1f43_b675, &[0; 50]);
+        // 크기를 모르는 Segment
+        let mut segment = vec![
+            0x18, 0x53, 0x80, 0x67, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        ];
+        segment.extend([info, cluster, tags].concat());
+        [header, segment].concat()
+    }
+
+    #[test]
+    fn webm_tags() {
+        let file = webm();
+        assert!(is_matroska(&file));
+        let metadata = extract(&file);
+        assert_eq!(metadata.get("Matroska:DocType"), Some("webm"));
+        assert_eq!(metadata.get("Matroska:Title"), Some("clip"));
+        assert_eq!(metadata.get("Matroska:MuxingApp"), Some("Lavf60"));
+        assert_eq!(metadata.get("Matroska:DateRecorded"), Some("2024"));
+        assert_eq!(metadata.get("Matroska:Encoder"), None);
+        assert_eq!(metadata.get("Comfy:Checkpoint"), Some("sd15.safetensors"));
+    }
+
+    #[test]
+    fn read_file_skips_clusters() {
+        let mut metadata = Metadata::new();
+        read_file(&mut Cursor::new(webm()), &mut metadata).unwrap();
+        assert_eq!(metadata.get("Matroska:Title"), Some("clip"));
+        assert!(metadata.get("Matroska:prompt").is_some());
+
+        // 크기를 모르는 클러스터(실시간 녹화) 뒤의 Tags도 찾습니다.
+        let header = element(EBML, &element(DOC_TYPE, b"webm"));
+        let mut cluster = vec![
+            0x1f, 0x43, 0xb6, 0x75, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        ];
+        cluster.extend(element(0xa3, &[0; 500]));
+        let tags = element(TAGS, &element(TAG, &simple_tag("COMMENT", "late")));
+        let segment = element(SEGMENT, &[cluster, tags].concat());
+        let mut metadata = Metadata::new();
+        read_file(&mut Cursor::new([header, segment].concat()), &mut metadata).unwrap();
+        assert_eq!(metadata.get("Matroska:Comment"), Some("late"));
+    }
+
+    #[test]
+    fn truncated() {
+        let file = webm();
+        for end in [5, 20, file.len() - 10] {
+            extract(&file[..end]);
+        }
+        assert_eq!(
+            vint(&[0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], false),
+            Some((None, 8))
+        );
+        assert_eq!(vint(&[0x40], false), None);
+    }
+}
@@ -0,0 +1,95 @@
+//! 동영상 컨테이너 파서
+//!
+//! ComfyUI VideoHelperSuite, AnimateDiff 등은 생성 정보를 동영상의 주석 태그에 기록합니다.
+//!
+//! - [`quicktime`]: MP4/MOV의 `moov/udta`, `moov/meta` 아톰 → `QuickTime:Comment` 등
+//! - [`matroska`]: WebM/MKV의 `Info`, `Tags` 요소 → `Matroska:Comment` 등
+//!
+//! VideoHelperSuite는 주석에 `{"prompt": {...}, "workflow": {...}}` JSON을 쓰므로
+//! PNG의 `PNG:prompt`, `PNG:workflow`처럼 키별 필드로도 나눕니다.
+//!
+//! 경로로 읽을 때는 파일 전체를 메모리에 올리지 않고 [`read_file`]로 메타데이터를 담은
+//! 아톰과 요소만 읽습니다. 영상 데이터(`mdat`, `Cluster`)는 건너뜁니다.
+
+pub mod matroska;
+pub mod quicktime;
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use serde_json::Value;
+
+use super::{Block, Format, Issue, Metadata, MetadataValue};
+
+// 주석 JSON에서 따로 꺼내는 키
+const COMMENT_KEYS: &[&str] = &["prompt", "workflow"];
+
+// 메타데이터를 담은 아톰이나 요소(moov, Info, Tags) 하나의 크기 상한
+const MAX_ELEMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// 파일에서 `format` 동영상의 메타데이터를 담은 부분만 읽어 `metadata`에 추가합니다.
+pub fn read_file<R: Read + Seek>(
+    reader: &mut R,
+    format: Format,
+    metadata: &mut Metadata,
+) -> io::Result<()> {
+    match format {
+        Format::QuickTime => quicktime::read_file(reader, metadata),
+        Format::Matroska => matroska::read_file(reader, metadata),
+        _ => Ok(()),
+    }
+}
+
+// `offset`부터 `length`바이트를 읽습니다. 파일이 먼저 끝나면 남은 만큼만 돌려줍니다.
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, length: u64) -> io::Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::new();
+    reader.take(length).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+// 상한을 넘는 요소는 읽지 않고 진단 보고서에 남깁니다.
+fn read_element<R: Read + Seek>(
+    reader: &mut R,
+    name: &str,
+    range: std::ops::Range<u64>,
+    metadata: &mut Metadata,
+) -> io::Result<Option<Vec<u8>>> {
+    let length = range.end - range.start;
+    if length > MAX_ELEMENT_SIZE {
+        let range = range.start as usize..range.end as usize;
+        metadata.record(Block::new(name, range).issue(Issue::TooLarge {
+            limit: MAX_ELEMENT_SIZE as usize,
+        }));
+        return Ok(None);
+    }
+    read_at(reader, range.start, length).map(Some)
+}
+
+/// `{namespace}:Comment`가 ComfyUI 메타데이터 JSON이면 `{namespace}:prompt` 등으로 나눠 추가합니다.
+pub fn split_comment(namespace: &str, metadata: &mut Metadata) {
+    let Some(comment) = metadata.field(&format!("{namespace}:Comment")) else {
+        return;
+    };
+    let split: Vec<(&str, String, MetadataValue)> = match comment.json().as_deref() {
+        Some(Value::Object(fields)) => COMMENT_KEYS
+            .iter()
+            .filter_map(|&key| {
+                // 그래프가 객체 그대로 들어 있거나 JSON 문자열로 한 번 더 감싸져 있습니다.
+                let (text, value) = match fields.get(key)? {
+                    Value::String(text) => (text.clone(), MetadataValue::from_text(text)),
+                    value @ (Value::Object(_) | Value::Array(_)) => {
+                        (value.to_string(), MetadataValue::Json(value.clone()))
+                    }
+                    _ => return None,
+                };
+                Some((key, text, value))
+            })
+            .collect(),
+        _ => return,
+    };
+    metadata.scoped(comment.provenance(), |metadata| {
+        for (key, text, value) in split {
+            metadata.insert_typed(format!("{namespace}:{key}"), text, value);
+        }
+    });
+}
@@ -0,0 +1,336 @@
+//! QuickTime(MOV)/MP4 메타데이터 파서
+//!
+//! `moov` 아래의 세 가지 위치에서 텍스트 태그를 읽어 `QuickTime:이름` 필드로 만듭니다.
+//!
+//! ```text
+//! moov
+//! ├ udta
+//! │ ├ ©cmt, ©nam, ...     QuickTime 문자열: (크기(2) + 언어(2) + 텍스트)*
+//! │ └ meta
+//! │   └ ilst
+//! │     └ ©cmt, desc, ... iTunes 항목: data 박스 (ffmpeg MP4)
+//! └ meta
+//!   ├ keys                 항목 번호 → `com.apple.quicktime.comment` 등 키 이름
+//!   └ ilst                 항목: data 박스 (ffmpeg `-movflags use_metadata_tags`)
+//! ```
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::metadata::isobmff::{self, Atom};
+use crate::metadata::{text, Metadata, Provenance};
+
+// ISOBMFF 계열이지만 HEIF가 아닌 동영상 브랜드는 다양하므로 첫 박스 종류로 판별합니다.
+// 구형 MOV는 ftyp 없이 바로 moov나 mdat로 시작합니다.
+const TOP_LEVEL_KINDS: &[&[u8; 4]] = &[
+    b"ftyp", b"moov", b"mdat", b"wide", b"free", b"skip", b"pnot",
+];
+
+// `moov/meta/keys`의 Apple 키 접두사
+const APPLE_KEY_PREFIX: &str = "com.apple.quicktime.";
+
+// data 박스의 값 형식
+const DATA_UTF8: u32 = 1;
+const DATA_UTF16: u32 = 2;
+
+/// 아톰 이름과 필드 이름. 표에 없는 © 아톰은 ©를 뺀 이름을 씁니다.
+const ATOM_NAMES: &[(&[u8; 4], &str)] = &[
+    (b"\xa9cmt", "Comment"),
+    (b"\xa9nam", "Title"),
+    (b"\xa9des", "Description"),
+    (b"desc", "Description"),
+    (b"ldes", "LongDescription"),
+    (b"\xa9too", "Encoder"),
+    (b"\xa9swr", "SoftwareVersion"),
+    (b"\xa9day", "CreateDate"),
+    (b"\xa9ART", "Artist"),
+    (b"\xa9aut", "Author"),
+    (b"\xa9cpy", "Copyright"),
+    (b"cprt", "Copyright"),
+    (b"\xa9inf", "Information"),
+    (b"\xa9key", "Keywords"),
+    (b"keyw", "Keywords"),
+    (b"\xa9gen", "Genre"),
+];
+
+/// 파일이 QuickTime/MP4 컨테이너로 보이는지 확인합니다. HEIF는 먼저 걸러야 합니다.
+/// 첫 아톰이 `mdat`처럼 클 수 있으므로 헤더의 종류만 봅니다.
+pub fn is_quicktime(data: &[u8]) -> bool {
+    data.get(4..8)
+        .is_some_and(|kind| TOP_LEVEL_KINDS.iter().any(|known| known[..] == *kind))
+}
+
+/// 파일에서 최상위 `moov` 아톰만 읽어 [`read`]로 넘깁니다. 다른 아톰은 헤더만 읽고 건너뜁니다.
+pub fn read_file<R: Read + Seek>(reader: &mut R, metadata: &mut Metadata) -> io::Result<()> {
+    let length = reader.seek(SeekFrom::End(0))?;
+    let mut offset = 0u64;
+    while offset < length {
+        // size(4) + type(4) [+ largesize(8)]
+        let header = super::read_at(reader, offset, 16)?;
+        let Some(&[a, b, c, d, e, f, g, h]) = header.get(..8) else {
+            break;
+        };
+        let (header_size, size) = match u32::from_be_bytes([a, b, c, d]) {
+            0 => (8, length - offset),
+            1 => {
+                let Some(large) = header.get(8..16).and_then(|large| large.try_into().ok()) else {
+                    break;
+                };
+                (16, u64::from_be_bytes(large))
+            }
+            size => (8, size as u64),
+        };
+        if size < header_size {
+            break;
+        }
+        let end = offset.saturating_add(size);
+        if [e, f, g, h] == *b"moov" {
+            if let Some(moov) =
+                super::read_element(reader, "QuickTime:moov", offset..end, metadata)?
+            {
+                read(&moov, metadata);
+            }
+            break;
+        }
+        offset = end;
+    }
+    Ok(())
+}
+
+/// `moov`의 텍스트 태그를 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    // mdat가 moov보다 앞에 있어도 크기만큼 건너뜁니다.
+    let Some(moov) = isobmff::find(data, b"moov") else {
+        return;
+    };
+    if let Some(udta) = isobmff::find(moov.data, b"udta") {
+        metadata.scoped(Provenance::new("QuickTime:udta"), |metadata| {
+            read_user_data(udta, metadata)
+        });
+    }
+    if let Some(meta) = isobmff::find(moov.data, b"meta") {
+        metadata.scoped(Provenance::new("QuickTime:meta"), |metadata| {
+            read_meta(meta, metadata)
+        });
+    }
+    super::split_comment("QuickTime", metadata);
+}
+
+fn read_user_data(udta: Atom, metadata: &mut Metadata) {
+    for atom in isobmff::boxes(udta.data) {
+        if &atom.kind == b"meta" {
+            read_meta(atom, metadata);
+        } else if atom.kind[0] == 0xa9 {
+            // 언어별로 여러 문자열이 있을 수 있지만 첫 번째만 씁니다.
+            if let Some(value) = user_data_text(atom.data) {
+                metadata.insert(format!("QuickTime:{}", atom_name(&atom.kind)), value);
+            }
+        }
+    }
+}
+
+// 크기(2) + 언어 코드(2) + 텍스트. 언어 코드가 0x400 이상이면 Mac 언어가 아닌 ISO 639 코드이고
+// 텍스트는 UTF-8입니다. 그 전에는 Mac Roman이지만 실제로는 대부분 ASCII입니다.
+fn user_data_text(data: &[u8]) -> Option<String> {
+    let &[high, low, ..] = data else {
+        return None;
+    };
+    let size = u16::from_be_bytes([high, low]) as usize;
+    let value = data.get(4..4 + size)?;
+    Some(text::trim_nul(&text::utf8_or_latin1(value)).to_owned())
+}
+
+// QuickTime 파일의 meta는 일반 박스이고, MP4의 meta는 FullBox(버전 + 플래그 4바이트)입니다.
+fn read_meta(meta: Atom, metadata: &mut Metadata) {
+    let children = match meta.data.get(4..8) {
+        Some(b"hdlr") => meta.data,
+        _ => meta.data.get(4..).unwrap_or(&[]),
+    };
+    let keys = isobmff::find(children, b"keys")
+        .map(|keys| key_names(keys.data))
+        .unwrap_or_default();
+    let Some(ilst) = isobmff::find(children, b"ilst") else {
+        return;
+    };
+    for item in isobmff::boxes(ilst.data) {
+        // keys가 있으면 항목 종류는 1부터 시작하는 키 번호입니다.
+        let name = if keys.is_empty() {
+            atom_name(&item.kind)
+        } else {
+            let index = u32::from_be_bytes(item.kind) as usize;
+            match index.checked_sub(1).and_then(|i| keys.get(i)) {
+                Some(key) => key_field_name(key),
+                None => continue,
+            }
+        };
+        if let Some(value) = item_text(item.data) {
+            metadata.insert(format!("QuickTime:{name}"), value);
+        }
+    }
+}
+
+// keys: FullBox(4) + 개수(4) + (크기(4) + 네임스페이스(4) + 키 이름)*
+fn key_names(keys: &[u8]) -> Vec<String> {
+    isobmff::boxes(keys.get(8..).unwrap_or(&[]))
+        .map(|entry| String::from_utf8_lossy(entry.data).into_owned())
+        .collect()
+}
+
+// data 박스: 형식(4) + 로캘(4) + 값. 텍스트 형식만 읽습니다.
+fn item_text(item: &[u8]) -> Option<String> {
+    let data = isobmff::find(item, b"data")?;
+    let (kind, value) = data.data.split_first_chunk::<4>()?;
+    let value = value.get(4..)?;
+    match u32::from_be_bytes(*kind) & 0x00ff_ffff {
+        DATA_UTF8 => Some(String::from_utf8_lossy(value).into_owned()),
+        DATA_UTF16 => Some(text::utf16(value, true)),
+        _ => None,
+    }
+}
+
+fn atom_name(kind: &[u8; 4]) -> String {
+    if let Some(&(_, name)) = ATOM_NAMES.iter().find(|(atom, _)| *atom == kind) {
+        return name.to_owned();
+    }
+    let name = kind.strip_prefix(b"\xa9").unwrap_or(kind);
+    text::latin1(name)
+}
+
+// "com.apple.quicktime.comment" -> "Comment". 다른 키(ffmpeg가 쓴 "comment" 등)도 같은 규칙을 따릅니다.
+fn key_field_name(key: &str) -> String {
+    let name = key.strip_prefix(APPLE_KEY_PREFIX).unwrap_or(key);
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::testing::{bmff_box as atom, TempDir, VIDEO_COMMENT};
+    use crate::metadata::{extract, read_metadata, Format, SidecarOptions};
+
+    // UTF-8 iTunes 항목
+    fn data(text: &str) -> Vec<u8> {
+        let mut body = vec![0, 0, 0, 1, 0, 0, 0, 0];
+        body.extend_from_slice(text.as_bytes());
+        atom(b"data", &body)
+    }
+
+    fn handler(kind: &[u8; 4]) -> Vec<u8> {
+        let mut body = vec![0; 8];
+        body.extend_from_slice(kind);
+        body.extend_from_slice(&[0; 12]);
+        atom(b"hdlr", &body)
+    }
+
+    #[test]
+    fn mp4_ilst_comment() {
+        let ilst = atom(
+            b"ilst",
+            &[
+                atom(b"\xa9cmt", &data(VIDEO_COMMENT)),
+                atom(b"\xa9too", &data("Lavf60.3.100")),
+            ]
+            .concat(),
+        );
+        let meta = atom(b"meta", &[&[0; 4][..], &handler(b"mdir"), &ilst].concat());
+        let moov = atom(
+            b"moov",
+            &[atom(b"mvhd", &[0; 100]), atom(b"udta", &meta)].concat(),
+        );
+        let file = [
+            atom(b"ftyp", b"isom\0\0\x02\0isomiso2avc1mp41"),
+            atom(b"mdat", &[1; 1000]),
+            moov,
+        ]
+        .concat();
+        assert_eq!(Format::detect(&file), Some(Format::QuickTime));
+
+        let metadata = extract(&file);
+        assert_eq!(metadata.get("QuickTime:Encoder"), Some("Lavf60.3.100"));
+        assert!(metadata
+            .get("QuickTime:prompt")
+            .unwrap()
+            .contains("KSampler"));
+        assert_eq!(metadata.get("QuickTime:workflow"), Some(r#"{"nodes":[]}"#));
+        assert_eq!(
+            metadata
+                .field("QuickTime:prompt")
+                .unwrap()
+                .source
+                .as_deref(),
+            Some("QuickTime:udta")
+        );
+        assert_eq!(metadata.get("Comfy:Positive"), Some("a dancing cat"));
+        assert_eq!(metadata.get("Generation:Tool"), Some("ComfyUI"));
+        assert_eq!(metadata.get("Generation:Seed"), Some("42"));
+        assert_eq!(
+            metadata
+                .field("Generation:Prompt")
+                .unwrap()
+                .source
+                .as_deref(),
+            Some("QuickTime:prompt")
+        );
+    }
+
+    #[test]
+    fn read_file_skips_media() {
+        let udta = atom(
+            b"udta",
+            &atom(b"\xa9cmt", &[&[0, 5, 0x55, 0xc4][..], b"hello"].concat()),
+        );
+        // mdat가 앞에 있는 구형 MOV. 첫 아톰이 파일 앞부분보다 커도 형식을 판별합니다.
+        let file = [atom(b"mdat", &[1; 10_000]), atom(b"moov", &udta)].concat();
+        assert_eq!(Format::detect(&file[..4096]), Some(Format::QuickTime));
+
+        let dir = TempDir::new();
+        let path = dir.file("a.mov", &file);
+        let extraction = read_metadata(&path, &SidecarOptions::default()).unwrap();
+        assert_eq!(extraction.metadata.get("QuickTime:Comment"), Some("hello"));
+        assert_eq!(extraction.report.format, Some(Format::QuickTime));
+
+        // 64비트 크기(largesize) 아톰도 건너뜁니다.
+        let mut large = vec![0, 0, 0, 1];
+        large.extend_from_slice(b"mdat");
+        large.extend_from_slice(&(16u64 + 100).to_be_bytes());
+        large.extend_from_slice(&[0; 100]);
+        let path = dir.file("b.mov", [large, atom(b"moov", &udta)].concat());
+        let extraction = read_metadata(&path, &SidecarOptions::default()).unwrap();
+        assert_eq!(extraction.metadata.get("QuickTime:Comment"), Some("hello"));
+    }
+
+    #[test]
+    fn mov_user_data_and_keys() {
+        let parameters = "a cat\nNegative prompt: blurry\nSteps: 20, Sampler: Euler a, CFG scale: 7, Seed: 5, Size: 512x512";
+        let mut comment = (parameters.len() as u16).to_be_bytes().to_vec();
+        comment.extend_from_slice(&[0x55, 0xc4]);
+        comment.extend_from_slice(parameters.as_bytes());
+        let udta = atom(b"udta", &atom(b"\xa9cmt", &comment));
+
+        let mut keys = vec![0, 0, 0, 0, 0, 0, 0, 2];
+        keys.extend(atom(b"mdta", b"com.apple.quicktime.title"));
+        keys.extend(atom(b"mdta", b"prompt_note"));
+        let ilst = atom(
+            b"ilst",
+            &[
+                atom(&1u32.to_be_bytes(), &data("My clip")),
+                atom(&2u32.to_be_bytes(), &data("hello")),
+            ]
+            .concat(),
+        );
+        let meta = atom(
+            b"meta",
+            &[handler(b"mdta"), atom(b"keys", &keys), ilst].concat(),
+        );
+        let file = [atom(b"wide", &[]), atom(b"moov", &[udta, meta].concat())].concat();
+
+        let metadata = extract(&file);
+        assert_eq!(metadata.get("QuickTime:Comment"), Some(parameters));
+        assert_eq!(metadata.get("QuickTime:Title"), Some("My clip"));
+        assert_eq!(metadata.get("QuickTime:Prompt_note"), Some("hello"));
+        assert_eq!(metadata.get("SD:Seed"), Some("5"));
+        assert_eq!(metadata.get("Generation:Tool"), Some("A1111"));
+    }
+}
@@ -0,0 +1,219 @@
+//! WebP(RIFF) 컨테이너 파서
+//!
+//! VP8X 확장 형식의 `EXIF`, `XMP `, `C2PA` 청크와 애니메이션 정보(ANIM/ANMF)를 읽습니다.
+//! 방문한 청크와 잘린 청크는 진단 보고서에 기록합니다.
+
+use std::ops::Range;
+
+use super::{c2pa, exif, text, xmp, Block, Issue, Metadata, Provenance, Recovery};
+
+pub fn is_webp(data: &[u8]) -> bool {
+    data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP"
+}
+
+/// RIFF 청크 하나
+#[derive(Debug, Clone, Copy)]
+pub struct Chunk<'a> {
+    pub fourcc: [u8; 4],
+    pub data: &'a [u8],
+    /// 파일 안에서 `data`가 시작하는 위치
+    pub offset: usize,
+    /// 파일이 잘려 `data`가 청크의 앞부분뿐이면 원래 길이
+    pub truncated: Option<usize>,
+}
+
+impl Chunk<'_> {
+    /// `WebP:EXIF` 등
+    pub fn name(&self) -> String {
+        format!("WebP:{}", text::latin1(&self.fourcc).trim_end())
+    }
+
+    /// fourcc와 크기를 포함한 청크 전체의 위치
+    pub fn range(&self) -> Range<usize> {
+        self.offset - 8..self.offset + self.data.len()
+    }
+
+    /// 이 청크에서 읽은 필드의 출처 (`WebP:EXIF` 등)
+    pub fn provenance(&self) -> Provenance {
+        Provenance::new(self.name()).range(self.offset..self.offset + self.data.len())
+    }
+}
+
+/// `WEBP` 폼 타입 이후의 청크를 순서대로 돌려줍니다.
+/// 파일 끝을 넘는 청크는 남은 부분만 `truncated`로 돌려준 뒤 멈춥니다.
+pub fn chunks(data: &[u8]) -> impl Iterator<Item = Chunk<'_>> {
+    let mut offset = if is_webp(data) { 12 } else { data.len() };
+    std::iter::from_fn(move || {
+        // fourcc(4) + size(4, 리틀 엔디언) + data(size) + 짝수 맞춤 패딩
+        let header = data.get(offset..offset + 8)?;
+        let fourcc = [header[0], header[1], header[2], header[3]];
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let end = (offset + 8).saturating_add(size);
+        let chunk = Chunk {
+            fourcc,
+            data: &data[offset + 8..end.min(data.len())],
+            offset: offset + 8,
+            truncated: (end > data.len()).then_some(size),
+        };
+        offset = if end > data.len() {
+            data.len()
+        } else {
+            end + (size & 1)
+        };
+        Some(chunk)
+    })
+}
+
+/// 메타데이터 청크를 읽어 `metadata`에 추가합니다.
+pub fn read(data: &[u8], metadata: &mut Metadata) {
+    let mut frames = 0u32;
+    let mut duration = 0u32;
+    for chunk in chunks(data) {
+        let before = metadata.len();
+        read_chunk(&chunk, &mut frames, &mut duration, metadata);
+        let mut block = Block::new(chunk.name(), chunk.range());
+        if let Some(expected) = chunk.truncated {
+            block = block.issue(Issue::Truncated {
+                expected,
+                available: chunk.data.len(),
+            });
+            if metadata.len() > before {
+                block = block.recovery(Recovery::Partial {
+                    fields: metadata.len() - before,
+                });
+            }
+        }
+        metadata.record(block);
+    }
+    if frames > 0 {
+        metadata.insert("WebP:FrameCount", frames.to_string());
+        metadata.insert("WebP:Duration", duration.to_string());
+    }
+}
+
+fn read_chunk(chunk: &Chunk, frames: &mut u32, duration: &mut u32, metadata: &mut Metadata) {
+    match &chunk.fourcc {
+        b"VP8X" => {
+            // flags(1) + reserved(3) + 캔버스 너비-1(3) + 높이-1(3)
+            if let (Some(width), Some(height)) = (u24(chunk.data, 4), u24(chunk.data, 7)) {
+                metadata.insert("WebP:ImageWidth", (width + 1).to_string());
+                metadata.insert("WebP:ImageHeight", (height + 1).to_string());
+            }
+        }
+        b"ANIM" => {
+            // 배경색(4) + 반복 횟수(2, 0이면 무한)
+            if let Some(&[low, high]) = chunk.data.get(4..6) {
+                metadata.insert(
+                    "WebP:LoopCount",
+                    u16::from_le_bytes([low, high]).to_string(),
+                );
+            }
+        }
+        b"ANMF" => {
+            // X(3) + Y(3) + 너비-1(3) + 높이-1(3) + 표시 시간(3, ms)
+            *frames += 1;
+            *duration = duration.saturating_add(u24(chunk.data, 12).unwrap_or(0));
+        }
+        b"EXIF" => read_exif(chunk, metadata),
+        b"XMP " => metadata.scoped(chunk.provenance(), |metadata| {
+            xmp::read(chunk.data, metadata)
+        }),
+        b"C2PA" => metadata.scoped(chunk.provenance(), |metadata| {
+            c2pa::read(chunk.data, metadata)
+        }),
+        _ => {}
+    }
+}
+
+// 일부 도구는 JPEG처럼 `Exif\0\0` 헤더를 붙여 기록합니다.
+fn read_exif(chunk: &Chunk, metadata: &mut Metadata) {
+    let tiff = chunk.data.strip_prefix(b"Exif\0\0").unwrap_or(chunk.data);
+    let mut fields = Metadata::new();
+    fields.scoped(chunk.provenance(), |fields| exif::read(tiff, fields));
+    for field in fields.iter() {
+        // ComfyUI는 IFD0 문자열 태그(Model, Make, ...)에 `prompt:{...}`, `workflow:{...}`를
+        // 기록하므로 PNG의 `PNG:prompt`, `PNG:workflow`처럼 키워드별 필드로 분리합니다.
+        if let Some((name, json)) = field.text.split_once(':') {
+            let is_keyword =
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if is_keyword && json.trim_start().starts_with(['{', '[']) {
+                metadata.scoped(field.provenance(), |metadata| {
+                    metadata.insert(format!("WebP:{name}"), json)
+                });
+            }
+        }
+        metadata.insert_field(field.clone());
+    }
+}
+
+fn u24(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 3)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::extract;
+    use crate::metadata::testing::{tiff, xmp};
+
+    fn riff_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        out.extend(kind);
+        out.extend((data.len() as u32).to_le_bytes());
+        out.extend(data);
+        if data.len() % 2 == 1 {
+            out.push(0);
+        }
+    }
+
+    fn webp(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut out = b"RIFF\0\0\0\0WEBP".to_vec();
+        for (kind, data) in chunks {
+            riff_chunk(&mut out, kind, data);
+        }
+        out
+    }
+
+    // 100×50 캔버스, EXIF·XMP·애니메이션 플래그
+    fn vp8x() -> Vec<u8> {
+        vec![0x0e, 0, 0, 0, 99, 0, 0, 49, 0, 0]
+    }
+
+    // 위치와 크기(12바이트) 뒤에 길이 100ms, 플래그
+    fn frame() -> Vec<u8> {
+        let mut out = vec![0; 12];
+        out.extend([100, 0, 0, 0]);
+        out
+    }
+
+    #[test]
+    fn animation_and_exif() {
+        // ComfyUI는 EXIF Make/Model에 `prompt:{...}`처럼 이름을 붙여 저장합니다.
+        let value = b"prompt:{\"3\": {\"class_type\": \"KSampler\"}}\0";
+        let exif = tiff(&[(0x0110, 2, value.len() as u32, value.to_vec())]);
+        let data = webp(&[
+            (b"VP8X", vp8x()),
+            (b"ANIM", vec![0; 6]),
+            (b"ANMF", frame()),
+            (b"ANMF", frame()),
+            (b"ANMF", frame()),
+            (b"EXIF", exif),
+        ]);
+
+        let metadata = extract(&data);
+        assert_eq!(metadata.get("WebP:ImageWidth"), Some("100"));
+        assert_eq!(metadata.get("WebP:ImageHeight"), Some("50"));
+        assert_eq!(metadata.get("WebP:FrameCount"), Some("3"));
+        assert_eq!(metadata.get("WebP:Duration"), Some("300"));
+        assert_eq!(
+            metadata.get("WebP:prompt"),
+            Some("{\"3\": {\"class_type\": \"KSampler\"}}")
+        );
+    }
+
+    #[test]
+    fn xmp_chunk() {
+        let packet = xmp("<dc:subject><rdf:Bag><rdf:li>cat</rdf:li></rdf:Bag></dc:subject>");
+        let data = webp(&[(b"VP8X", vp8x()), (b"XMP ", packet.into_bytes())]);
+        assert_eq!(extract(&data).get("XMP:dc:subject[1]"), Some("cat"));
+    }
+}
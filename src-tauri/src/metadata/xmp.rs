@@ -0,0 +1,327 @@
+//! XMP 패킷 파서
+//!
+//! RDF 트리를 `XMP:접두사:이름` 필드로 평탄화합니다. 접두사는 파일마다 다르게
+//! 선언될 수 있으므로 잘 알려진 네임스페이스는 표준 접두사로 통일합니다.
+//!
+//! - `rdf:Bag`/`rdf:Seq` → `XMP:dc:subject[1]`, `XMP:dc:subject[2]`, ...와 목록 값 `XMP:dc:subject`.
+//!   항목이 구조체면 `XMP:xmpMM:History[1]/...`로 펼치고 목록 값은 두지 않습니다.
+//! - `rdf:Alt` → 모든 언어의 목록 값 `XMP:dc:description`. 표시 문자열은 기본 언어(`x-default`)이고,
+//!   나머지 언어는 `XMP:dc:description-ko-KR`로도 둡니다.
+//! - 구조체 → `XMP:Iptc4xmpCore:CreatorContactInfo/Iptc4xmpCore:CiAdrCity`
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+use super::{Metadata, MetadataValue};
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// 잘 알려진 네임스페이스와 표준 접두사
+const PREFIXES: &[(&str, &str)] = &[
+    ("http://purl.org/dc/elements/1.1/", "dc"),
+    ("http://ns.adobe.com/xap/1.0/", "xmp"),
+    ("http://ns.adobe.com/xap/1.0/mm/", "xmpMM"),
+    ("http://ns.adobe.com/xap/1.0/rights/", "xmpRights"),
+    ("http://ns.adobe.com/xmp/note/", "xmpNote"),
+    ("http://ns.adobe.com/xap/1.0/sType/ResourceEvent#", "stEvt"),
+    ("http://ns.adobe.com/xap/1.0/sType/ResourceRef#", "stRef"),
+    ("http://ns.adobe.com/photoshop/1.0/", "photoshop"),
+    ("http://ns.adobe.com/tiff/1.0/", "tiff"),
+    ("http://ns.adobe.com/exif/1.0/", "exif"),
+    ("http://ns.adobe.com/exif/1.0/aux/", "aux"),
+    ("http://cipa.jp/exif/1.0/", "exifEX"),
+    ("http://ns.adobe.com/camera-raw-settings/1.0/", "crs"),
+    ("http://ns.adobe.com/lightroom/1.0/", "lr"),
+    (
+        "http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/",
+        "Iptc4xmpCore",
+    ),
+    ("http://iptc.org/std/Iptc4xmpExt/2008-02-29/", "Iptc4xmpExt"),
+    ("http://ns.useplus.org/ldf/xmp/1.0/", "plus"),
+];
+
+/// XMP 패킷을 읽어 `metadata`에 추가합니다.
+pub fn read(packet: &[u8], metadata: &mut Metadata) {
+    for (key, text, value) in flatten(packet) {
+        metadata.insert_typed(key, text, value);
+    }
+}
+
+/// 평탄화한 속성 하나 (키, 표시 문자열, 값)
+pub type Property = (String, String, MetadataValue);
+
+/// XMP 패킷을 속성 목록으로 평탄화합니다. 잘못된 XML이면 빈 목록을 돌려줍니다.
+pub fn flatten(packet: &[u8]) -> Vec<Property> {
+    let text = String::from_utf8_lossy(packet);
+    // xpacket 앞뒤의 BOM, 패딩 제거
+    let (Some(start), Some(end)) = (text.find('<'), text.rfind('>')) else {
+        return Vec::new();
+    };
+    if end < start {
+        return Vec::new();
+    }
+    // SVG 등 DOCTYPE이 있는 문서 안의 RDF도 읽을 수 있도록 DTD를 허용합니다.
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let Ok(doc) = Document::parse_with_options(&text[start..=end], options) else {
+        return Vec::new();
+    };
+
+    // rdf:RDF의 자식은 rdf:Description 또는 타입 노드(cc:Work 등)입니다.
+    let mut out = Vec::new();
+    for description in doc.descendants().filter(|node| {
+        node.is_element() && node.parent_element().is_some_and(|p| is_rdf(&p, "RDF"))
+    }) {
+        flatten_struct(description, "XMP:", &mut out);
+    }
+    out
+}
+
+// 속성과 자식 요소를 `path` 아래의 필드로 추가합니다.
+fn flatten_struct(node: Node, path: &str, out: &mut Vec<Property>) {
+    for attribute in node.attributes() {
+        let Some(namespace) = attribute.namespace() else {
+            continue;
+        };
+        if namespace == RDF || namespace == XML {
+            continue;
+        }
+        let key = format!(
+            "{path}{}",
+            qualified_name(node, namespace, attribute.name())
+        );
+        push_text(out, key, attribute.value().to_owned());
+    }
+    for child in node.children().filter(Node::is_element) {
+        let key = format!("{path}{}", element_name(child));
+        flatten_property(child, key, out);
+    }
+}
+
+fn flatten_property(node: Node, key: String, out: &mut Vec<Property>) {
+    if let Some(resource) = node.attribute((RDF, "resource")) {
+        push_text(out, key, resource.to_owned());
+        return;
+    }
+    if node.attribute((RDF, "parseType")) == Some("Resource") {
+        flatten_struct(node, &format!("{key}/"), out);
+        return;
+    }
+
+    let Some(child) = node.children().find(Node::is_element) else {
+        // 단순 값. 속성으로 적힌 구조체 필드가 있으면 함께 추가합니다.
+        flatten_struct(node, &format!("{key}/"), out);
+        push_text(out, key, element_text(node));
+        return;
+    };
+
+    if is_rdf(&child, "Alt") {
+        let items: Vec<Node> = child.children().filter(|n| is_rdf(n, "li")).collect();
+        let default = items
+            .iter()
+            .position(|li| language(li) == Some("x-default"))
+            .unwrap_or(0);
+        let texts: Vec<String> = items.iter().map(|&li| element_text(li)).collect();
+        for (index, (li, text)) in items.iter().zip(&texts).enumerate() {
+            if let Some(lang) = language(li).filter(|_| index != default) {
+                push_text(out, format!("{key}-{lang}"), text.clone());
+            }
+        }
+        if let Some(text) = texts.get(default).cloned() {
+            let (_, value) = MetadataValue::list(texts);
+            out.push((key, text, value));
+        }
+    } else if is_rdf(&child, "Bag") || is_rdf(&child, "Seq") {
+        let items: Vec<Node> = child.children().filter(|n| is_rdf(n, "li")).collect();
+        let values: Option<Vec<String>> = items.iter().map(|&li| simple_value(li)).collect();
+        // 항목은 번호를 붙여 따로 펼치고, 모두 단순 값이면 목록 값도 함께 둡니다.
+        for (index, li) in items.into_iter().enumerate() {
+            flatten_property(li, format!("{key}[{}]", index + 1), out);
+        }
+        if let Some(values) = values {
+            let (text, value) = MetadataValue::list(values);
+            out.push((key, text, value));
+        }
+    } else if is_rdf(&child, "Description") {
+        flatten_struct(child, &format!("{key}/"), out);
+    } else {
+        // parseType 없이 바로 필드가 오는 구조체
+        flatten_struct(node, &format!("{key}/"), out);
+    }
+}
+
+fn push_text(out: &mut Vec<Property>, key: String, text: String) {
+    let value = MetadataValue::Text(text.clone());
+    out.push((key, text, value));
+}
+
+// 목록 항목이 구조체가 아닌 단순 값이면 그 텍스트
+fn simple_value(li: Node) -> Option<String> {
+    if let Some(resource) = li.attribute((RDF, "resource")) {
+        return Some(resource.to_owned());
+    }
+    let fields = li
+        .attributes()
+        .any(|a| a.namespace().is_some_and(|ns| ns != RDF && ns != XML));
+    if fields || li.attribute((RDF, "parseType")).is_some() || li.children().any(|n| n.is_element())
+    {
+        return None;
+    }
+    Some(element_text(li))
+}
+
+fn is_rdf(node: &Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().namespace() == Some(RDF) && node.tag_name().name() == name
+}
+
+fn language<'a>(node: &Node<'a, '_>) -> Option<&'a str> {
+    node.attribute((XML, "lang"))
+}
+
+fn element_name(node: Node) -> String {
+    let tag = node.tag_name();
+    match tag.namespace() {
+        Some(namespace) => qualified_name(node, namespace, tag.name()),
+        None => tag.name().to_owned(),
+    }
+}
+
+fn qualified_name(node: Node, namespace: &str, name: &str) -> String {
+    let prefix = PREFIXES
+        .iter()
+        .find(|&&(uri, _)| uri == namespace)
+        .map(|&(_, prefix)| prefix)
+        .or_else(|| node.lookup_prefix(namespace))
+        .unwrap_or("unknown");
+    format!("{prefix}:{name}")
+}
+
+fn element_text(node: Node) -> String {
+    node.children()
+        .filter(Node::is_text)
+        .filter_map(|n| n.text())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::extract;
+    use crate::metadata::testing::{chunk, png, segment, xmp};
+
+    const PACKET: &str = r#"<?xpacket begin="﻿" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+<rdf:Description rdf:about="" xmlns:d="http://purl.org/dc/elements/1.1/" xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:CreatorTool="Draw Things"
+ xmlns:Iptc4xmpCore="http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/" xmlns:foo="http://example.com/foo/">
+<d:description><rdf:Alt><rdf:li xml:lang="ko-KR">고양이</rdf:li><rdf:li xml:lang="x-default">a cat</rdf:li></rdf:Alt></d:description>
+<d:subject><rdf:Bag><rdf:li>cat</rdf:li><rdf:li>white background</rdf:li></rdf:Bag></d:subject>
+<Iptc4xmpCore:CreatorContactInfo rdf:parseType="Resource"><Iptc4xmpCore:CiAdrCity>Seoul</Iptc4xmpCore:CiAdrCity></Iptc4xmpCore:CreatorContactInfo>
+<foo:bar>baz</foo:bar>
+</rdf:Description></rdf:RDF></x:xmpmeta>
+<?xpacket end="w"?>   "#;
+
+    const GUID: &[u8] = b"0123456789ABCDEF0123456789ABCDEF";
+
+    #[test]
+    fn flatten_properties() {
+        let fields = flatten(PACKET.as_bytes());
+        let get = |key: &str| {
+            fields
+                .iter()
+                .find(|(name, _, _)| name == key)
+                .map(|(_, text, _)| text.as_str())
+        };
+        // 접두사는 파일의 것이 아니라 잘 알려진 접두사(dc)를 씁니다.
+        assert_eq!(get("XMP:dc:description"), Some("a cat"));
+        assert_eq!(get("XMP:dc:description-ko-KR"), Some("고양이"));
+        assert_eq!(get("XMP:dc:subject[2]"), Some("white background"));
+        assert_eq!(get("XMP:dc:subject"), Some("cat, white background"));
+        assert_eq!(get("XMP:xmp:CreatorTool"), Some("Draw Things"));
+        assert_eq!(
+            get("XMP:Iptc4xmpCore:CreatorContactInfo/Iptc4xmpCore:CiAdrCity"),
+            Some("Seoul")
+        );
+        assert_eq!(get("XMP:foo:bar"), Some("baz"));
+    }
+
+    #[test]
+    fn png_itxt() {
+        let mut data = b"XML:com.adobe.xmp\0\0\0\0\0".to_vec();
+        data.extend(PACKET.as_bytes());
+        let metadata = extract(&png(&[chunk(b"iTXt", &data)]));
+        assert_eq!(metadata.get("XMP:dc:subject[1]"), Some("cat"));
+        assert_eq!(metadata.get("PNG:XML:com.adobe.xmp"), None);
+
+        let text = |text: &str| MetadataValue::Text(text.into());
+        let subject = metadata.field("XMP:dc:subject").unwrap();
+        assert_eq!(
+            subject.value,
+            MetadataValue::List(vec![text("cat"), text("white background")])
+        );
+        let description = metadata.field("XMP:dc:description").unwrap();
+        assert_eq!(description.text, "a cat");
+        assert_eq!(
+            description.value,
+            MetadataValue::List(vec![text("고양이"), text("a cat")])
+        );
+        assert_eq!(
+            metadata.field("XMP:dc:description-ko-KR").unwrap().value,
+            text("고양이")
+        );
+    }
+
+    #[test]
+    fn structured_list_items() {
+        let packet = xmp(
+            r#"<xmpMM:History xmlns:xmpMM="http://ns.adobe.com/xap/1.0/mm/" xmlns:stEvt="http://ns.adobe.com/xap/1.0/sType/ResourceEvent#"><rdf:Seq><rdf:li stEvt:action="created"/><rdf:li rdf:parseType="Resource"><stEvt:action>saved</stEvt:action></rdf:li></rdf:Seq></xmpMM:History>"#,
+        );
+        let mut metadata = Metadata::new();
+        read(packet.as_bytes(), &mut metadata);
+        let keys: Vec<String> = metadata.iter().map(|field| field.name()).collect();
+        assert_eq!(
+            keys,
+            [
+                "XMP:xmpMM:History[1]/stEvt:action",
+                "XMP:xmpMM:History[2]/stEvt:action"
+            ]
+        );
+    }
+
+    #[test]
+    fn jpeg_extended_xmp() {
+        let main = format!(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"><rdf:Description rdf:about="" xmlns:xmpNote="http://ns.adobe.com/xmp/note/" xmpNote:HasExtendedXMP="{}"/></rdf:RDF></x:xmpmeta>"#,
+            std::str::from_utf8(GUID).unwrap()
+        );
+        let mut jpeg = vec![0xff, 0xd8];
+        let mut payload = b"http://ns.adobe.com/xap/1.0/\0".to_vec();
+        payload.extend(main.as_bytes());
+        segment(&mut jpeg, 0xe1, &payload);
+
+        // 확장 XMP 조각은 순서가 바뀌어 있어도 오프셋대로 합칩니다.
+        let extended = PACKET.as_bytes();
+        for (offset, part) in [(100, &extended[100..]), (0, &extended[..100])] {
+            let mut payload = b"http://ns.adobe.com/xmp/extension/\0".to_vec();
+            payload.extend(GUID);
+            payload.extend((extended.len() as u32).to_be_bytes());
+            payload.extend((offset as u32).to_be_bytes());
+            payload.extend(part);
+            segment(&mut jpeg, 0xe1, &payload);
+        }
+        jpeg.extend([0xff, 0xd9]);
+
+        let metadata = extract(&jpeg);
+        assert_eq!(metadata.get("XMP:dc:subject[2]"), Some("white background"));
+        assert_eq!(
+            metadata.get("XMP:xmpNote:HasExtendedXMP"),
+            std::str::from_utf8(GUID).ok()
+        );
+    }
+
+    #[test]
+    fn malformed_packet() {
+        assert!(flatten(b"<x:xmpmeta><rdf:RDF>").is_empty());
+    }
+}
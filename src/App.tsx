@@ -4,8 +4,9 @@ import { saveAs } from 'file-saver';
 import { DropZone } from './components/DropZone';
 import { RuleManager } from './components/RuleManager';
 import { ImageList } from './components/ImageList';
+import { SidecarSettings } from './components/SidecarSettings';
 import { useImageProcessor } from './hooks/useImageProcessor';
-import { KeywordRule, FilterMode, LIMITS, ProcessingProgress, PartialMatchSettings, DEFAULT_PARTIAL_MATCH_SETTINGS, MatchCandidate } from './types';
+import { KeywordRule, FilterMode, LIMITS, ProcessingProgress, PartialMatchSettings, DEFAULT_PARTIAL_MATCH_SETTINGS, DEFAULT_SIDECAR_PATTERNS, MatchCandidate } from './types';
 import { formatFileSize } from './utils/thumbnail';
 import { logger } from './utils/logger';
 
@@ -14,6 +15,7 @@ const isTauri = () => typeof window !== 'undefined' && '__TAURI__' in window;
 
 const STORAGE_KEY = 'image-renamer-rules-v2';
 const PARTIAL_MATCH_STORAGE_KEY = 'image-renamer-partial-match-settings';
+const SIDECAR_STORAGE_KEY = 'image-renamer-sidecar-patterns';
 
 export function App() {
   const [rules, setRules] = useState<KeywordRule[]>(() => {
@@ -32,6 +34,14 @@ export function App() {
       return DEFAULT_PARTIAL_MATCH_SETTINGS;
     }
   });
+  const [sidecarPatterns, setSidecarPatterns] = useState<string[]>(() => {
+    try {
+      const saved = localStorage.getItem(SIDECAR_STORAGE_KEY);
+      return saved ? JSON.parse(saved) : DEFAULT_SIDECAR_PATTERNS;
+    } catch {
+      return DEFAULT_SIDECAR_PATTERNS;
+    }
+  });
   const [filterMode, setFilterMode] = useState<FilterMode>('all');
   const [downloadProgress, setDownloadProgress] = useState<ProcessingProgress | null>(null);
 
@@ -40,6 +50,7 @@ export function App() {
     isProcessing,
     progress,
     processFiles,
+    processPaths,
     applyRules,
     removeImage,
     removeMultipleImages,
@@ -67,6 +78,20 @@ export function App() {
     }
   }, [partialMatchSettings]);
 
+  // 사이드카 패턴 변경 시 localStorage에 저장 (이후 추가하는 파일부터 적용)
+  useEffect(() => {
+    try {
+      localStorage.setItem(SIDECAR_STORAGE_KEY, JSON.stringify(sidecarPatterns));
+    } catch (e) {
+      console.warn('사이드카 설정 저장 실패:', e);
+    }
+  }, [sidecarPatterns]);
+
+  const handlePathsDropped = useCallback(
+    (paths: string[]) => processPaths(paths, sidecarPatterns),
+    [processPaths, sidecarPatterns]
+  );
+
   // 이미지 추가 시 규칙 적용
   useEffect(() => {
     if (images.length > 0) {
@@ -256,11 +281,17 @@ export function App() {
             {/* 파일 업로드 */}
             <DropZone 
               onFilesDropped={processFiles} 
+              onPathsDropped={handlePathsDropped}
               isProcessing={isProcessing}
               progress={progress}
               currentCount={images.length}
             />
 
+            {/* 사이드카 파일 (경로로 읽는 데스크톱 앱 전용) */}
+            {isTauri() && (
+              <SidecarSettings patterns={sidecarPatterns} onPatternsChange={setSidecarPatterns} />
+            )}
+
             {/* 통계 및 액션 */}
             {images.length > 0 && (
               <div className="bg-white rounded-xl border border-gray-200 p-4 shadow-sm">
@@ -1,19 +1,27 @@
 import { useCallback, useState, useEffect } from 'react';
 import { cn } from '../utils/cn';
-import { ProcessingProgress, LIMITS } from '../types';
+import { ProcessingProgress, LIMITS, IMAGE_MIME_TYPES, VIDEO_MIME_TYPES, ARCHIVE_EXTENSIONS } from '../types';
+import { isArchiveFile, isSupportedFile } from '../utils/files';
 import { logger } from '../utils/logger';
 
 // Check if running in Tauri environment
 const isTauri = () => typeof window !== 'undefined' && '__TAURI__' in window;
 
+// Rust 메타데이터 추출기가 읽는 확장자
+const SUPPORTED_EXTENSIONS = [...Object.keys(IMAGE_MIME_TYPES), ...Object.keys(VIDEO_MIME_TYPES)];
+
+// 파일 대화상자 필터는 마지막 확장자만 비교 ('tar.gz' → 'gz')
+const ARCHIVE_FILTER_EXTENSIONS = [...new Set(ARCHIVE_EXTENSIONS.map(ext => ext.split('.').pop() || ext))];
+
 interface DropZoneProps {
-  onFilesDropped: (files: FileList | File[]) => void;
+  onFilesDropped: (files: FileList | File[]) => void; // 웹 빌드
+  onPathsDropped: (paths: string[]) => void; // Tauri: 경로로 읽어 Rust 메타데이터 추출기 사용 (아카이브 포함)
   isProcessing: boolean;
   progress: ProcessingProgress | null;
   currentCount: number;
 }
 
-export function DropZone({ onFilesDropped, isProcessing, progress, currentCount }: DropZoneProps) {
+export function DropZone({ onFilesDropped, onPathsDropped, isProcessing, progress, currentCount }: DropZoneProps) {
   const [isDragging, setIsDragging] = useState(false);
 
   // Tauri drag-drop event listener
@@ -30,35 +38,11 @@ export function DropZone({ onFilesDropped, isProcessing, progress, currentCount
 
           const paths = event.payload.paths;
           if (paths && paths.length > 0) {
-            // Filter for image files
-            const imageExtensions = ['.jpg', '.jpeg', '.png', '.gif', '.webp', '.tiff', '.bmp'];
-            const imagePaths = paths.filter(path =>
-              imageExtensions.some(ext => path.toLowerCase().endsWith(ext))
-            );
+            // Filter for image files and archives
+            const imagePaths = paths.filter(path => isSupportedFile(path) || isArchiveFile(path));
 
             if (imagePaths.length > 0) {
-              // Convert paths to File objects
-              const files: File[] = [];
-              for (const filePath of imagePaths) {
-                try {
-                  const { readFile } = await import('@tauri-apps/plugin-fs');
-                  const contents = await readFile(filePath);
-                  const fileName = filePath.split(/[\\/]/).pop() || 'image';
-                  const ext = fileName.split('.').pop()?.toLowerCase() || 'png';
-                  const mimeType = ext === 'jpg' || ext === 'jpeg' ? 'image/jpeg' : `image/${ext}`;
-
-                  const blob = new Blob([contents], { type: mimeType });
-                  const file = new File([blob], fileName, { type: mimeType });
-                  files.push(file);
-                  logger.debug('DropZone', `Loaded file: ${fileName}`, { size: file.size });
-                } catch (err) {
-                  logger.error('DropZone', `Failed to read file: ${filePath}`, err);
-                }
-              }
-
-              if (files.length > 0) {
-                onFilesDropped(files);
-              }
+              onPathsDropped(imagePaths);
             }
           }
         });
@@ -88,7 +72,7 @@ export function DropZone({ onFilesDropped, isProcessing, progress, currentCount
     return () => {
       cleanup.then(fn => fn?.());
     };
-  }, [onFilesDropped]);
+  }, [onPathsDropped]);
 
   const handleDragOver = useCallback((e: React.DragEvent) => {
     e.preventDefault();
@@ -112,6 +96,32 @@ export function DropZone({ onFilesDropped, isProcessing, progress, currentCount
     [onFilesDropped]
   );
 
+  // Tauri: 클릭해서 고른 파일도 경로로 받아 Rust 추출기(EXIF 포함)로 읽음
+  const handleInputClick = useCallback(
+    async (e: React.MouseEvent<HTMLInputElement>) => {
+      if (!isTauri()) return;
+      e.preventDefault();
+      try {
+        const { open } = await import('@tauri-apps/plugin-dialog');
+        const paths = await open({
+          multiple: true,
+          filters: [
+            { name: '이미지, 아카이브', extensions: [...SUPPORTED_EXTENSIONS, ...ARCHIVE_FILTER_EXTENSIONS] },
+            { name: '이미지', extensions: SUPPORTED_EXTENSIONS },
+            { name: '아카이브', extensions: ARCHIVE_FILTER_EXTENSIONS },
+          ],
+        });
+        if (paths && paths.length > 0) {
+          logger.info('DropZone', `Dialog: ${paths.length} files`);
+          onPathsDropped(paths);
+        }
+      } catch (err) {
+        logger.error('DropZone', 'Failed to open file dialog', err);
+      }
+    },
+    [onPathsDropped]
+  );
+
   const handleFileInput = useCallback(
     (e: React.ChangeEvent<HTMLInputElement>) => {
       if (e.target.files && e.target.files.length > 0) {
@@ -141,7 +151,8 @@ export function DropZone({ onFilesDropped, isProcessing, progress, currentCount
       <input
         type="file"
         multiple
-        accept="image/*"
+        accept={['image/*', ...Object.keys(IMAGE_MIME_TYPES).map(ext => `.${ext}`)].join(',')}
+        onClick={handleInputClick}
         onChange={handleFileInput}
         className="absolute inset-0 w-full h-full opacity-0 cursor-pointer"
         disabled={isProcessing}
@@ -192,7 +203,7 @@ export function DropZone({ onFilesDropped, isProcessing, progress, currentCount
               이미지 파일을 드래그하거나 클릭하여 선택
             </p>
             <p className="text-xs text-gray-500 mt-1">
-              JPG, PNG, TIFF 등 · 단일 파일 최대 10MB
+              JPG, PNG, WebP, AVIF, HEIC, JXL, GIF, SVG 등{isTauri() && ', MP4, MOV, WebM, MKV, ZIP, TAR, TAR.GZ'} · 단일 파일 최대 10MB{isTauri() && ' (동영상 256MB)'}
             </p>
             <p className="text-xs text-gray-400 mt-0.5">
               {currentCount > 0 ? (
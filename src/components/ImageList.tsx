@@ -1,5 +1,5 @@
 import React, { useState, useMemo, useRef, useCallback } from 'react';
-import { ImageFile, FilterMode, LIMITS, MatchCandidate } from '../types';
+import { ImageFile, FilterMode, LIMITS, MatchCandidate, MetadataIssue, MetadataProvenance, MetadataRecovery, MetadataReport } from '../types';
 import { cn } from '../utils/cn';
 import { formatFileSize } from '../utils/thumbnail';
 
@@ -15,18 +15,25 @@ interface ImageListProps {
 // 페이지네이션 상수
 const ITEMS_PER_PAGE = 50;
 
+// 필드 출처 표시: 'PNG:tEXt · 33–120 · UTF-8'
+const describeProvenance = ({ source, byteRange, encoding }: MetadataProvenance): string =>
+  [source, byteRange && `${byteRange.start}–${byteRange.end}`, encoding].filter(Boolean).join(' · ');
+
 // 개별 메타데이터 필드 컴포넌트
 function MetadataField({
   fieldKey,
   value,
+  provenance,
   isMatched,
   matchedKeyword
 }: {
   fieldKey: string;
   value: string;
+  provenance?: MetadataProvenance;
   isMatched: boolean;
   matchedKeyword: string | null;
 }) {
+  const provenanceText = provenance ? describeProvenance(provenance) : '';
   const [isExpanded, setIsExpanded] = useState(false);
   const isLongText = value.length > 100;
 
@@ -110,6 +117,9 @@ function MetadataField({
       <div className="flex items-start justify-between gap-2">
         <span className="font-semibold text-gray-700 flex-shrink-0">
           {fieldKey}
+          {provenanceText && (
+            <span className="ml-1.5 font-normal text-gray-400">{provenanceText}</span>
+          )}
         </span>
         {isLongText && (
           <button
@@ -138,8 +148,9 @@ function MetadataField({
   );
 }
 
-function MetadataViewer({ metadata, matchedField, matchedKeyword }: { 
+function MetadataViewer({ metadata, provenance, matchedField, matchedKeyword }: { 
   metadata: Record<string, string>; 
+  provenance?: Record<string, MetadataProvenance>;
   matchedField: string | null;
   matchedKeyword: string | null;
 }) {
@@ -170,6 +181,7 @@ function MetadataViewer({ metadata, matchedField, matchedKeyword }: {
             key={key}
             fieldKey={key}
             value={value}
+            provenance={provenance?.[key]}
             isMatched={matchedField === key}
             matchedKeyword={matchedKeyword}
           />
@@ -206,6 +218,68 @@ function MetadataViewer({ metadata, matchedField, matchedKeyword }: {
   );
 }
 
+// 진단 보고서의 문제 설명 (src-tauri/src/metadata/report.rs의 `Issue`)
+const describeIssue = (issue: MetadataIssue): string => {
+  switch (issue.kind) {
+    case 'crcMismatch':
+      return 'CRC가 맞지 않습니다';
+    case 'truncated':
+      return `${issue.expected}바이트 중 ${issue.available}바이트만 남아 있습니다`;
+    case 'invalidLength':
+      return `길이 필드가 올바르지 않습니다: ${issue.length}`;
+    case 'corrupt':
+      return '손상된 데이터입니다';
+    case 'decompress':
+      return '압축을 풀 수 없습니다';
+    case 'malformed':
+      return '내용을 해석할 수 없습니다';
+    case 'missing':
+      return '종료 블록이 없습니다';
+    case 'tooLarge':
+      return `크기 상한 ${formatFileSize(issue.limit)}를 넘어 읽지 않았습니다`;
+  }
+};
+
+const describeRecovery = (recovery: MetadataRecovery): string =>
+  recovery.kind === 'resynced'
+    ? `${recovery.next}바이트 위치부터 다시 읽음`
+    : `남은 부분에서 필드 ${recovery.fields}개를 읽음`;
+
+// 메타데이터를 읽으며 발견한 손상. 문제가 있는 블록만 표시
+function MetadataReportViewer({ report }: { report: MetadataReport }) {
+  const [isExpanded, setIsExpanded] = useState(false);
+  const problems = report.blocks.filter((block) => block.issue);
+  if (problems.length === 0) return null;
+
+  return (
+    <div className="mt-1 text-xs rounded p-1.5 border bg-red-50 border-red-200">
+      <button
+        type="button"
+        onClick={(e) => {
+          e.preventDefault();
+          e.stopPropagation();
+          setIsExpanded(prev => !prev);
+        }}
+        className="text-red-700 font-semibold cursor-pointer select-none"
+      >
+        메타데이터 문제 {problems.length}개 {isExpanded ? '접기' : '보기'}
+      </button>
+      {isExpanded && (
+        <ul className="mt-1 space-y-0.5 text-red-800">
+          {problems.map((block, index) => (
+            <li key={index} className="break-all">
+              <span className="font-medium">{block.name}</span>
+              {' '}({block.byteRange.start}–{block.byteRange.end}):{' '}
+              {block.issue && describeIssue(block.issue)}
+              {block.recovery && ` → ${describeRecovery(block.recovery)}`}
+            </li>
+          ))}
+        </ul>
+      )}
+    </div>
+  );
+}
+
 function ImageItem({
   img,
   isSelected,
@@ -414,9 +488,11 @@ function ImageItem({
         {/* 메타데이터 */}
         <MetadataViewer 
           metadata={img.metadata} 
+          provenance={img.metadataProvenance}
           matchedField={img.matchedField}
           matchedKeyword={img.matchedRule?.keyword || null}
         />
+        {img.metadataReport && <MetadataReportViewer report={img.metadataReport} />}
       </div>
     </div>
   );
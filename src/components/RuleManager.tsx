@@ -1,6 +1,7 @@
 import { useState, useMemo, useRef } from 'react';
-import { KeywordRule, LIMITS, PartialMatchSettings, DEFAULT_PARTIAL_MATCH_SETTINGS } from '../types';
+import { KeywordRule, RuleKind, TagMatch, LIMITS, PartialMatchSettings, DEFAULT_PARTIAL_MATCH_SETTINGS } from '../types';
 import { cn } from '../utils/cn';
+import { formatKeywordPrefix, isNormalizedRule, isQueryRule, isRegexRule, isTagRule, parseKeywordPrefix, sanitizeFileName, TAG_MATCH_LABELS, validateRule } from '../utils/rules';
 
 interface RuleManagerProps {
   rules: KeywordRule[];
@@ -19,6 +20,10 @@ export function RuleManager({
 }: RuleManagerProps) {
   const [keyword, setKeyword] = useState('');
   const [newFileName, setNewFileName] = useState('');
+  const [ruleKind, setRuleKind] = useState<RuleKind>('literal');
+  const [caseInsensitive, setCaseInsensitive] = useState(false);
+  const [normalizeTags, setNormalizeTags] = useState(false);
+  const [tagMatch, setTagMatch] = useState<TagMatch | ''>('');
   const [searchTerm, setSearchTerm] = useState('');
   const [editingId, setEditingId] = useState<string | null>(null);
   const [editKeyword, setEditKeyword] = useState('');
@@ -27,22 +32,13 @@ export function RuleManager({
   const [showPartialMatchSettings, setShowPartialMatchSettings] = useState(false);
   const fileInputRef = useRef<HTMLInputElement>(null);
 
-  // 파일명 정규화: 띄어쓰기 → _, 위험 문자 제거
-  const sanitizeFileName = (name: string): string => {
-    return name
-      .replace(/\s+/g, '_')           // 띄어쓰기 → _
-      .replace(/[<>:"/\\|?*]/g, '_')  // Windows 금지 문자 → _
-      .replace(/_+/g, '_')            // 연속 _ → 단일 _
-      .replace(/^_|_$/g, '');         // 앞뒤 _ 제거
-  };
-
   // TXT 파일에서 규칙 Import (줄바꿈 무시, #태그 기준 분리)
   const handleImport = (e: React.ChangeEvent<HTMLInputElement>) => {
     const file = e.target.files?.[0];
     if (!file) return;
 
     const reader = new FileReader();
-    reader.onload = (event) => {
+    reader.onload = async (event) => {
       try {
         const text = event.target?.result as string;
         const lines = text.split('\n');
@@ -53,11 +49,13 @@ export function RuleManager({
         
         const saveCurrentRule = () => {
           if (currentFileName && currentKeywordParts.length > 0) {
-            // 모든 줄을 공백으로 연결하고 연속 공백 정리
-            const keyword = currentKeywordParts
-              .join(' ')
-              .replace(/\s+/g, ' ')
-              .trim();
+            // 모든 줄을 공백으로 연결하고 연속 공백 정리 (query:, regex:, tags:, match/word: 등 접두사는 규칙 종류)
+            const { keyword, ...options } = parseKeywordPrefix(
+              currentKeywordParts
+                .join(' ')
+                .replace(/\s+/g, ' ')
+                .trim()
+            );
             
             if (keyword) {
               // 중복 체크 (기존 규칙 + 새로 추가할 규칙)
@@ -73,6 +71,7 @@ export function RuleManager({
                   keyword,
                   newFileName: sanitizedFileName,
                   enabled: true,
+                  ...options,
                 });
               }
             }
@@ -98,9 +97,22 @@ export function RuleManager({
         
         // 마지막 규칙 저장
         saveCurrentRule();
+
+        // 잘못된 정규식, 쿼리 규칙은 제외하고 알림
+        const invalidRules: string[] = [];
+        for (const rule of [...newRules]) {
+          const error = await validateRule(rule, partialMatchSettings);
+          if (error) {
+            invalidRules.push(`#${rule.newFileName}: ${error}`);
+            newRules.splice(newRules.indexOf(rule), 1);
+          }
+        }
+        if (invalidRules.length > 0) {
+          alert(`${invalidRules.length}개의 규칙을 가져오지 못했습니다.\n\n${invalidRules.join('\n')}`);
+        }
         
         if (newRules.length === 0) {
-          alert('가져올 규칙이 없습니다. 형식을 확인해주세요.\n\n형식:\n#파일명\n키워드(프롬프트)');
+          alert('가져올 규칙이 없습니다. 형식을 확인해주세요.\n\n형식:\n#파일명\n키워드(프롬프트)\n\n쿼리, 정규식 규칙은 키워드 앞에 query:, regex: (대소문자 무시는 regex/i:), 태그 정규화는 tags:, 태그 단위 비교는 match/exact:, match/word: 등. 접두사로 시작하는 일반 키워드는 literal:');
           return;
         }
         
@@ -141,7 +153,7 @@ export function RuleManager({
       
       for (const rule of rules) {
         content += `#${rule.newFileName}\n`;
-        content += `${rule.keyword}\n\n`;
+        content += `${formatKeywordPrefix(rule)}\n\n`;
       }
       
       // BOM 추가하여 한글 인코딩 보장
@@ -171,7 +183,7 @@ export function RuleManager({
     }
   };
 
-  const addRule = () => {
+  const addRule = async () => {
     const trimmedKeyword = keyword.trim();
     const trimmedFileName = newFileName.trim();
     
@@ -201,8 +213,19 @@ export function RuleManager({
       keyword: trimmedKeyword,
       newFileName: sanitizedFileName,
       enabled: true,
+      ...(ruleKind === 'regex' ? { kind: ruleKind, caseInsensitive } : {}),
+      ...(ruleKind === 'query' ? { kind: ruleKind } : {}),
+      ...(ruleKind === 'literal' && normalizeTags ? { normalize: true } : {}),
+      ...(ruleKind === 'literal' && tagMatch ? { tagMatch } : {}),
     };
 
+    // 정규식, 쿼리, 태그 단위 규칙은 추가할 때 검사
+    const error = await validateRule(newRule, partialMatchSettings);
+    if (error) {
+      alert(error);
+      return;
+    }
+
     onRulesChange([...rules, newRule]);
     setKeyword('');
     setNewFileName('');
@@ -214,7 +237,7 @@ export function RuleManager({
     setEditFileName(rule.newFileName);
   };
 
-  const saveEdit = () => {
+  const saveEdit = async () => {
     if (!editingId) return;
     
     const trimmedKeyword = editKeyword.trim();
@@ -228,6 +251,15 @@ export function RuleManager({
     // 파일명 정규화 적용
     const sanitizedFileName = sanitizeFileName(trimmedFileName);
 
+    const rule = rules.find((r) => r.id === editingId);
+    if (rule) {
+      const error = await validateRule({ ...rule, keyword: trimmedKeyword, newFileName: sanitizedFileName }, partialMatchSettings);
+      if (error) {
+        alert(error);
+        return;
+      }
+    }
+
     onRulesChange(
       rules.map((r) =>
         r.id === editingId
@@ -524,6 +556,68 @@ export function RuleManager({
           rows={2}
           className="w-full px-3 py-2 text-sm border border-gray-300 rounded-lg focus:ring-2 focus:ring-purple-500 focus:border-transparent outline-none resize-none font-mono text-xs"
         />
+        {/* 규칙 종류 */}
+        <div className="flex items-center gap-3 text-xs text-gray-600">
+          <select
+            value={ruleKind}
+            onChange={(e) => setRuleKind(e.target.value as RuleKind)}
+            className="px-1.5 py-0.5 border border-gray-300 rounded outline-none focus:ring-1 focus:ring-purple-500"
+          >
+            <option value="literal">키워드</option>
+            <option value="regex">정규식</option>
+            <option value="query">쿼리</option>
+          </select>
+          {ruleKind === 'literal' && (
+            <>
+              <label className="flex items-center gap-1 cursor-pointer">
+                <input
+                  type="checkbox"
+                  checked={normalizeTags}
+                  onChange={(e) => {
+                    setNormalizeTags(e.target.checked);
+                    // 정규화 규칙의 기본 비교는 태그 전체
+                    if (e.target.checked && tagMatch === 'exact') setTagMatch('');
+                  }}
+                  className="accent-purple-600"
+                />
+                태그 정규화
+              </label>
+              <select
+                value={tagMatch}
+                onChange={(e) => setTagMatch(e.target.value as TagMatch | '')}
+                className="px-1.5 py-0.5 border border-gray-300 rounded outline-none focus:ring-1 focus:ring-purple-500"
+                title="구분자로 나눈 태그와 비교하는 방식"
+              >
+                <option value="">{normalizeTags ? TAG_MATCH_LABELS.exact : '필드 전체'}</option>
+                {(Object.keys(TAG_MATCH_LABELS) as TagMatch[])
+                  .filter((mode) => !normalizeTags || mode !== 'exact')
+                  .map((mode) => (
+                    <option key={mode} value={mode}>{TAG_MATCH_LABELS[mode]}</option>
+                  ))}
+              </select>
+              {normalizeTags && (
+                <span className="text-gray-400">(tag:1.2), {'{{tag}}'}, 1.3::tag:: 가중치 무시, tag@&gt;=1.2로 가중치 조건</span>
+              )}
+            </>
+          )}
+          {ruleKind === 'query' && (
+            <span className="text-gray-400">AND, OR, NOT, 괄호, "구절", prompt:/negative:/model: 한정자</span>
+          )}
+          {ruleKind === 'regex' && (
+            <>
+              <label className="flex items-center gap-1 cursor-pointer">
+                <input
+                  type="checkbox"
+                  checked={caseInsensitive}
+                  onChange={(e) => setCaseInsensitive(e.target.checked)}
+                  className="accent-purple-600"
+                />
+                대소문자 무시
+              </label>
+              <span className="text-gray-400">새 파일명에 {'{1}'}, {'{이름}'}으로 캡처 그룹 사용</span>
+            </>
+          )}
+        </div>
         <div className="flex gap-2">
           <input
             type="text"
@@ -654,22 +748,46 @@ export function RuleManager({
                             {matchCount}개 매칭
                           </span>
                         )}
-                        {/* 개별 부분 매칭 토글 */}
-                        <button
-                          onClick={(e) => {
-                            e.stopPropagation();
-                            toggleRulePartialMatch(rule.id);
-                          }}
-                          className={cn(
-                            'text-xs px-1.5 py-0.5 rounded-full transition-colors',
-                            rule.partialMatch
-                              ? 'bg-orange-100 text-orange-600 hover:bg-orange-200'
-                              : 'bg-gray-100 text-gray-400 hover:bg-gray-200'
-                          )}
-                          title={rule.partialMatch ? '부분 매칭 ON' : '부분 매칭 OFF'}
-                        >
-                          {rule.partialMatch ? '부분' : '전체'}
-                        </button>
+                        {/* 정규식, 쿼리 규칙은 부분 매칭 없음 */}
+                        {isQueryRule(rule) ? (
+                          <span className="text-xs px-1.5 py-0.5 rounded-full bg-teal-100 text-teal-600" title="쿼리">
+                            쿼리
+                          </span>
+                        ) : isRegexRule(rule) ? (
+                          <span
+                            className="text-xs px-1.5 py-0.5 rounded-full bg-blue-100 text-blue-600"
+                            title={rule.caseInsensitive ? '정규식 (대소문자 무시)' : '정규식'}
+                          >
+                            {rule.caseInsensitive ? '정규식/i' : '정규식'}
+                          </span>
+                        ) : (
+                          <>
+                            {isTagRule(rule) && (
+                              <span
+                                className="text-xs px-1.5 py-0.5 rounded-full bg-purple-100 text-purple-600"
+                                title={isNormalizedRule(rule) ? '태그 정규화' : '태그 단위 비교'}
+                              >
+                                {isNormalizedRule(rule) ? '정규화 ' : ''}{TAG_MATCH_LABELS[rule.tagMatch ?? 'exact']}
+                              </span>
+                            )}
+                            {/* 개별 부분 매칭 토글 */}
+                            <button
+                              onClick={(e) => {
+                                e.stopPropagation();
+                                toggleRulePartialMatch(rule.id);
+                              }}
+                              className={cn(
+                                'text-xs px-1.5 py-0.5 rounded-full transition-colors',
+                                rule.partialMatch
+                                  ? 'bg-orange-100 text-orange-600 hover:bg-orange-200'
+                                  : 'bg-gray-100 text-gray-400 hover:bg-gray-200'
+                              )}
+                              title={rule.partialMatch ? '부분 매칭 ON' : '부분 매칭 OFF'}
+                            >
+                              {rule.partialMatch ? '부분' : '전체'}
+                            </button>
+                          </>
+                        )}
                       </div>
                     </div>
 
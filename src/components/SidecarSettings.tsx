@@ -0,0 +1,73 @@
+import { useState } from 'react';
+import { cn } from '../utils/cn';
+import { DEFAULT_SIDECAR_PATTERNS } from '../types';
+
+interface SidecarSettingsProps {
+  patterns: string[];
+  onPatternsChange: (patterns: string[]) => void;
+}
+
+// 이미지 옆에서 찾을 사이드카 파일 이름 패턴 (`read_metadata`의 sidecarPatterns)
+export function SidecarSettings({ patterns, onPatternsChange }: SidecarSettingsProps) {
+  const [isOpen, setIsOpen] = useState(false);
+  // 입력 중인 빈 줄을 지우지 않도록 글자는 따로 보관
+  const [text, setText] = useState(() => patterns.join('\n'));
+
+  const handleChange = (value: string) => {
+    setText(value);
+    onPatternsChange(
+      value
+        .split('\n')
+        .map((line) => line.trim())
+        .filter((line) => line.length > 0)
+    );
+  };
+
+  return (
+    <div className="bg-white rounded-xl border border-gray-200 p-3 shadow-sm">
+      <button
+        onClick={() => setIsOpen(!isOpen)}
+        className="w-full flex items-center justify-between"
+      >
+        <span className="text-xs font-medium text-gray-700">
+          사이드카 파일
+          <span className="ml-1 text-gray-400">({patterns.length}개 패턴)</span>
+        </span>
+        <svg
+          className={cn('w-4 h-4 text-gray-400 transition-transform', isOpen && 'rotate-180')}
+          fill="none"
+          stroke="currentColor"
+          viewBox="0 0 24 24"
+        >
+          <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M19 9l-7 7-7-7" />
+        </svg>
+      </button>
+
+      {isOpen && (
+        <div className="mt-2 space-y-2">
+          <textarea
+            value={text}
+            onChange={(e) => handleChange(e.target.value)}
+            rows={Math.max(3, Math.min(patterns.length + 1, 8))}
+            className="w-full px-2 py-1 text-xs font-mono border border-gray-300 rounded focus:ring-1 focus:ring-purple-400 outline-none resize-none"
+            placeholder="{stem}.txt"
+          />
+          <div className="flex items-center justify-between">
+            <p className="text-xs text-gray-400">
+              한 줄에 하나 · {'{name}'}: 파일 이름, {'{stem}'}: 확장자를 뺀 이름
+            </p>
+            <button
+              onClick={() => handleChange(DEFAULT_SIDECAR_PATTERNS.join('\n'))}
+              className="px-2 py-0.5 text-xs bg-gray-100 text-gray-600 rounded hover:bg-gray-200"
+            >
+              기본값
+            </button>
+          </div>
+          <p className="text-xs text-gray-500">
+            💡 이후에 추가하는 파일부터 적용됩니다. 패턴을 모두 지우면 사이드카를 읽지 않습니다.
+          </p>
+        </div>
+      )}
+    </div>
+  );
+}
@@ -1,9 +1,144 @@
 import { useState, useCallback, useRef } from 'react';
 import exifr from 'exifr';
-import { ImageFile, KeywordRule, ProcessingProgress, LIMITS, PartialMatchSettings, MatchCandidate } from '../types';
+import { ImageFile, KeywordRule, ProcessingProgress, LIMITS, PartialMatchSettings, MatchCandidate, ImageMatch, VIDEO_MIME_TYPES, MetadataExtraction, MetadataField, MetadataProvenance, MetadataReport, ArchiveEntry } from '../types';
 import { parsePngTextChunks } from '../utils/pngParser';
 import { createThumbnail } from '../utils/thumbnail';
+import { fileNameOf, isArchiveFile, isSupportedFile, isVideoFile, maxFileSizeOf, mimeTypeOf } from '../utils/files';
 import { logger } from '../utils/logger';
+import { compileRuleRegex, isQueryRule, isRegexRule, isTagRule, renderFileName } from '../utils/rules';
+
+// Check if running in Tauri environment
+const isTauri = () => typeof window !== 'undefined' && '__TAURI__' in window;
+
+// 파일 하나에서 읽은 메타데이터. 출처와 진단 보고서는 Rust 추출기에서만 있음
+interface LoadedMetadata {
+  metadata: Record<string, string>;
+  provenance?: Record<string, MetadataProvenance>;
+  report?: MetadataReport;
+}
+
+// 추가할 파일 하나. 크기로 제한을 먼저 확인하고, 통과한 파일만 내용과 메타데이터를 읽음
+interface PendingFile {
+  name: string;
+  size: number;
+  load: () => Promise<LoadedMetadata & { file: File }>;
+}
+
+const fieldName = ({ namespace, key }: MetadataField) => `${namespace}:${key}`;
+
+// Rust 추출 결과를 `네임스페이스:이름` → 문자열(매칭 엔진에 그대로 등록)과 출처로 나눔
+// 항목마다 `이름[n]` 필드가 따로 있는 목록 값(XMP Bag/Seq, 반복 IPTC 데이터셋)은 빼서
+// 이어 붙인 문자열에서 키워드가 항목 경계를 넘어 매칭되지 않게 함
+const fromExtraction = ({ metadata, report }: MetadataExtraction): LoadedMetadata => {
+  const names = new Set(metadata.map(fieldName));
+  const fields = metadata.filter(
+    (field) => !(field.value?.type === 'list' && names.has(`${fieldName(field)}[1]`)),
+  );
+  return {
+    metadata: Object.fromEntries(fields.map((field) => [fieldName(field), field.text])),
+    provenance: Object.fromEntries(
+      fields.map((field) => {
+        const { source, byteRange, encoding } = field;
+        return [fieldName(field), { source, byteRange, encoding }];
+      }),
+    ),
+    report,
+  };
+};
+
+// Rust 추출기로 경로의 파일과 사이드카에서 메타데이터를 읽음
+const readMetadata = async (path: string, sidecarPatterns: string[]): Promise<LoadedMetadata> => {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return fromExtraction(await invoke<MetadataExtraction>('read_metadata', { path, sidecarPatterns }));
+};
+
+// `read_archive_entries` 한 번에 가져올 최대 바이트 수
+const ARCHIVE_BATCH_SIZE = 64 * 1024 * 1024;
+
+// 아카이브 항목의 내용을 가져오는 함수를 만듦. tar.gz는 호출마다 처음부터 풀어야 하므로
+// 요청한 항목부터 뒤의 `readable` 항목들을 ARCHIVE_BATCH_SIZE까지 한 번에 가져와 두었다가 넘겨줌
+// 항목은 `read_archive` 목록의 위치로 구분 (같은 경로의 항목이 여러 개일 수 있음)
+const createArchiveLoader = (path: string, entries: ArchiveEntry[], readable: (entry: ArchiveEntry) => boolean) => {
+  const loaded = new Map<number, Uint8Array>();
+  return async (index: number): Promise<Uint8Array> => {
+    if (!loaded.has(index)) {
+      const batch: number[] = [];
+      let batchSize = 0;
+      for (let i = index; i < entries.length; i++) {
+        if (i !== index && !readable(entries[i])) continue;
+        if (batch.length > 0 && batchSize + entries[i].size > ARCHIVE_BATCH_SIZE) break;
+        batch.push(i);
+        batchSize += entries[i].size;
+      }
+      const { invoke } = await import('@tauri-apps/api/core');
+      const buffer = await invoke<ArrayBuffer>('read_archive_entries', { path, indices: batch });
+      let offset = 0;
+      for (const i of batch) {
+        loaded.set(i, new Uint8Array(buffer, offset, entries[i].size));
+        offset += entries[i].size;
+      }
+    }
+    const data = loaded.get(index) as Uint8Array;
+    loaded.delete(index);
+    return data;
+  };
+};
+
+// 아카이브의 이미지 항목. 메타데이터는 `read_archive`가 이미 읽었으므로 내용만 나중에 가져옴
+const readArchive = async (path: string): Promise<PendingFile[]> => {
+  const { invoke } = await import('@tauri-apps/api/core');
+  const entries = await invoke<ArchiveEntry[]>('read_archive', { path });
+  for (const entry of entries) {
+    if (entry.error) {
+      logger.warn('Files', `Failed to read archive entry: ${path} → ${entry.originalName}`, entry.error);
+    }
+  }
+  // 크기 제한을 넘는 항목은 추가하지 않으므로 내용도 가져오지 않음
+  const loadEntry = createArchiveLoader(
+    path,
+    entries,
+    (entry) => !!entry.extraction && entry.size <= maxFileSizeOf(entry.originalName),
+  );
+  return entries
+    .map((entry, index) => ({ entry, index }))
+    .filter(({ entry }) => entry.extraction)
+    .map(({ entry, index }): PendingFile => ({
+      name: entry.originalName,
+      size: entry.size,
+      load: async () => {
+        const contents = await loadEntry(index);
+        const name = fileNameOf(entry.originalName);
+        const loaded = entry.extraction ? fromExtraction(entry.extraction) : { metadata: {} };
+        return { file: new File([contents], name, { type: mimeTypeOf(name) }), ...loaded };
+      },
+    }));
+};
+
+// Rust 매칭 엔진의 이미지 저장소에 등록하거나 지움
+// 등록하지 못하면 `false` (그 이미지들은 웹 매칭 로직으로 매칭)
+const registerImages = async (images: ImageFile[]): Promise<boolean> => {
+  if (!isTauri() || images.length === 0) return true;
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    await invoke('register_images', {
+      images: images.map((img) => ({ id: img.id, fields: Object.entries(img.metadata) })),
+    });
+    return true;
+  } catch (e) {
+    logger.error('Matching', 'Failed to register images', e);
+    return false;
+  }
+};
+
+const unregisterImages = async (ids?: string[]) => {
+  if (!isTauri()) return;
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    await invoke('unregister_images', { ids: ids ?? null });
+  } catch (e) {
+    logger.error('Matching', 'Failed to unregister images', e);
+  }
+};
 
 // 키워드를 토큰으로 분리
 const tokenizeKeyword = (keyword: string, separator: string): string[] => {
@@ -38,13 +173,226 @@ const calculatePartialMatchScore = (
   };
 };
 
+// 규칙 목록을 이미지 하나에 적용 (완전 일치 → 부분 매칭)
+const matchImage = (
+  img: ImageFile,
+  rules: KeywordRule[],
+  partialMatchSettings?: PartialMatchSettings
+): ImageFile => {
+  const metadataEntries = Object.entries(img.metadata);
+  if (metadataEntries.length === 0) {
+    return {
+      ...img,
+      matchedRule: null,
+      matchedField: null,
+      newFileName: null,
+      matchScore: undefined,
+      candidateMatches: undefined,
+      isPartialMatch: undefined,
+    };
+  }
+
+  const enabledRules = rules.filter((r) => r.enabled);
+  const allCandidates: MatchCandidate[] = [];
+
+  // 1단계: 완전 일치 검색 (기존 로직)
+  for (const rule of enabledRules) {
+    // 쿼리, 태그 단위 규칙은 Rust 매칭 엔진에서만 지원
+    if (isQueryRule(rule) || isTagRule(rule)) continue;
+
+    let regex: RegExp | null = null;
+    if (isRegexRule(rule)) {
+      try {
+        regex = compileRuleRegex(rule);
+      } catch {
+        continue;
+      }
+    }
+
+    for (const [fieldName, fieldValue] of metadataEntries) {
+      const regexMatch = regex ? regex.exec(fieldValue) : null;
+      if (regex ? regexMatch : fieldValue.includes(rule.keyword)) {
+        const ext = img.originalName.split('.').pop() || 'jpg';
+        const fileName = regexMatch ? renderFileName(rule.newFileName, regexMatch) : rule.newFileName;
+        const baseName = fileName.replace(/\.[^.]+$/, '');
+
+        // 완전 일치는 바로 반환 (가장 높은 우선순위)
+        return {
+          ...img,
+          matchedRule: rule,
+          matchedField: fieldName,
+          newFileName: `${baseName}.${ext}`,
+          matchScore: 1.0,
+          candidateMatches: undefined,
+          isPartialMatch: false,
+        };
+      }
+    }
+  }
+
+  // 2단계: 부분 매칭 검색 (설정이 활성화된 경우)
+  if (partialMatchSettings?.globalEnabled) {
+    const separator = partialMatchSettings.tokenSeparator || ',';
+    const minRatio = partialMatchSettings.minMatchRatio || 0.7;
+
+    for (const rule of enabledRules) {
+      // 전역 부분 매칭이 켜져 있거나, 개별 규칙의 부분 매칭이 켜져 있는 경우
+      const isPartialMatchEnabled = partialMatchSettings.globalEnabled || rule.partialMatch;
+      if (!isPartialMatchEnabled || isRegexRule(rule) || isQueryRule(rule) || isTagRule(rule)) continue;
+
+      for (const [fieldName, fieldValue] of metadataEntries) {
+        const { score, matchedTokens, totalTokens } = calculatePartialMatchScore(
+          rule.keyword,
+          fieldValue,
+          separator
+        );
+
+        // 최소 일치율을 넘는 경우 후보에 추가
+        if (score >= minRatio && score < 1.0) {
+          allCandidates.push({
+            rule,
+            matchedField: fieldName,
+            matchScore: score,
+            matchedTokens,
+            totalTokens,
+          });
+        }
+      }
+    }
+  } else {
+    // 전역 부분 매칭이 꺼져 있더라도, 개별 규칙에서 부분 매칭이 켜져 있는 경우 처리
+    const separator = partialMatchSettings?.tokenSeparator || ',';
+    const minRatio = partialMatchSettings?.minMatchRatio || 0.7;
+
+    for (const rule of enabledRules) {
+      if (!rule.partialMatch || isRegexRule(rule) || isQueryRule(rule) || isTagRule(rule)) continue;
+
+      for (const [fieldName, fieldValue] of metadataEntries) {
+        const { score, matchedTokens, totalTokens } = calculatePartialMatchScore(
+          rule.keyword,
+          fieldValue,
+          separator
+        );
+
+        if (score >= minRatio && score < 1.0) {
+          allCandidates.push({
+            rule,
+            matchedField: fieldName,
+            matchScore: score,
+            matchedTokens,
+            totalTokens,
+          });
+        }
+      }
+    }
+  }
+
+  // 부분 매칭 후보가 있는 경우
+  if (allCandidates.length > 0) {
+    // 점수 순으로 정렬 (높은 점수 우선)
+    allCandidates.sort((a, b) => b.matchScore - a.matchScore);
+
+    // 동일한 규칙의 중복 제거 (가장 높은 점수만 유지)
+    const uniqueCandidates: MatchCandidate[] = [];
+    const seenRuleIds = new Set<string>();
+    for (const candidate of allCandidates) {
+      if (!seenRuleIds.has(candidate.rule.id)) {
+        seenRuleIds.add(candidate.rule.id);
+        uniqueCandidates.push(candidate);
+      }
+    }
+
+    // 가장 높은 점수의 후보를 기본 매칭으로 설정
+    const bestMatch = uniqueCandidates[0];
+    const ext = img.originalName.split('.').pop() || 'jpg';
+    const baseName = bestMatch.rule.newFileName.replace(/\.[^.]+$/, '');
+
+    logger.debug('Matching', `Partial match found for ${img.originalName}`, {
+      bestMatch: bestMatch.rule.newFileName,
+      score: bestMatch.matchScore,
+      candidates: uniqueCandidates.length,
+    });
+
+    return {
+      ...img,
+      matchedRule: bestMatch.rule,
+      matchedField: bestMatch.matchedField,
+      newFileName: `${baseName}.${ext}`,
+      matchScore: bestMatch.matchScore,
+      candidateMatches: uniqueCandidates.length > 1 ? uniqueCandidates : undefined,
+      isPartialMatch: true,
+    };
+  }
+
+  return {
+    ...img,
+    matchedRule: null,
+    matchedField: null,
+    newFileName: null,
+    matchScore: undefined,
+    candidateMatches: undefined,
+    isPartialMatch: undefined,
+  };
+};
+
+// Rust `match_images` 결과를 이미지에 반영
+const applyImageMatch = (
+  img: ImageFile,
+  result: ImageMatch,
+  rulesById: Map<string, KeywordRule>
+): ImageFile => {
+  const rule = result.matched ? rulesById.get(result.matched.ruleId) : undefined;
+  if (!result.matched || !rule) {
+    return {
+      ...img,
+      matchedRule: null,
+      matchedField: null,
+      newFileName: null,
+      matchScore: undefined,
+      candidateMatches: undefined,
+      isPartialMatch: undefined,
+    };
+  }
+
+  const candidates: MatchCandidate[] = [];
+  for (const candidate of result.candidates) {
+    const candidateRule = rulesById.get(candidate.ruleId);
+    if (candidateRule) {
+      candidates.push({
+        rule: candidateRule,
+        matchedField: candidate.matchedField,
+        matchScore: candidate.matchScore,
+        matchedTokens: candidate.matchedTokens,
+        totalTokens: candidate.totalTokens,
+      });
+    }
+  }
+
+  const ext = img.originalName.split('.').pop() || 'jpg';
+  const baseName = result.matched.newFileName.replace(/\.[^.]+$/, '');
+  return {
+    ...img,
+    matchedRule: rule,
+    matchedField: result.matched.matchedField,
+    newFileName: `${baseName}.${ext}`,
+    matchScore: result.matched.matchScore,
+    candidateMatches: candidates.length > 1 ? candidates : undefined,
+    isPartialMatch: result.isPartialMatch,
+  };
+};
+
 export function useImageProcessor() {
   const [images, setImages] = useState<ImageFile[]>([]);
   const [isProcessing, setIsProcessing] = useState(false);
   const [progress, setProgress] = useState<ProcessingProgress | null>(null);
   const totalSizeRef = useRef(0);
+  const imagesRef = useRef(images);
+  imagesRef.current = images;
+  const matchRequestRef = useRef(0);
+  // Rust 매칭 엔진에 등록하지 못한 이미지 ID
+  const unregisteredRef = useRef(new Set<string>());
 
-  // 모든 메타데이터 추출
+  // 웹 빌드: 모든 메타데이터 추출 (Tauri에서는 Rust `read_metadata` 사용)
   const extractAllMetadata = async (file: File): Promise<Record<string, string>> => {
     const metadata: Record<string, string> = {};
     const buffer = await file.arrayBuffer();
@@ -110,26 +458,24 @@ export function useImageProcessor() {
     return metadata;
   };
 
-  const processFiles = useCallback(async (files: FileList | File[]) => {
-    const fileArray = Array.from(files).filter(f => f.type.startsWith('image/'));
-    
+  const addFiles = useCallback(async (pending: PendingFile[]) => {
     // 제한 체크
     const currentCount = images.length;
-    const newCount = fileArray.length;
+    const newCount = pending.length;
     
     if (currentCount + newCount > LIMITS.MAX_IMAGES) {
       const allowed = LIMITS.MAX_IMAGES - currentCount;
       alert(`최대 ${LIMITS.MAX_IMAGES}개까지만 업로드 가능합니다.\n현재 ${currentCount}개, 추가 가능: ${allowed}개`);
       if (allowed <= 0) return;
-      fileArray.splice(allowed);
+      pending.splice(allowed);
     }
 
     // 파일 크기 체크
-    const validFiles: File[] = [];
+    const validFiles: PendingFile[] = [];
     let addedSize = 0;
     
-    for (const file of fileArray) {
-      if (file.size > LIMITS.MAX_FILE_SIZE) {
+    for (const file of pending) {
+      if (file.size > maxFileSizeOf(file.name)) {
         console.warn(`파일 크기 초과: ${file.name} (${(file.size / 1024 / 1024).toFixed(1)}MB)`);
         continue;
       }
@@ -147,34 +493,34 @@ export function useImageProcessor() {
     const imageFiles: ImageFile[] = [];
 
     for (let i = 0; i < validFiles.length; i++) {
-      const file = validFiles[i];
+      const pendingFile = validFiles[i];
       
       setProgress({
         current: i + 1,
         total: validFiles.length,
         phase: 'processing',
-        message: `처리 중: ${file.name}`,
+        message: `처리 중: ${pendingFile.name}`,
       });
 
       try {
-        const [metadata, thumbnailUrl] = await Promise.all([
-          extractAllMetadata(file),
-          createThumbnail(file),
-        ]);
+        const { file, metadata, provenance, report } = await pendingFile.load();
+        const thumbnailUrl = await createThumbnail(file);
 
         imageFiles.push({
           id: crypto.randomUUID(),
           file,
-          originalName: file.name,
+          originalName: pendingFile.name,
           fileSize: file.size,
           metadata,
+          metadataProvenance: provenance,
+          metadataReport: report,
           matchedRule: null,
           matchedField: null,
           newFileName: null,
           thumbnailUrl,
         });
       } catch (e) {
-        console.error(`파일 처리 실패: ${file.name}`, e);
+        console.error(`파일 처리 실패: ${pendingFile.name}`, e);
       }
 
       // 10개마다 UI 업데이트를 위한 짧은 대기
@@ -184,161 +530,99 @@ export function useImageProcessor() {
     }
 
     totalSizeRef.current += addedSize;
+    if (!(await registerImages(imageFiles))) {
+      imageFiles.forEach((img) => unregisteredRef.current.add(img.id));
+      alert(`이미지 ${imageFiles.length}개를 매칭 엔진에 등록하지 못했습니다.\n이 이미지들에는 쿼리와 태그 단위 규칙이 적용되지 않습니다.`);
+    }
     setImages((prev) => [...prev, ...imageFiles]);
     setIsProcessing(false);
     setProgress(null);
   }, [images.length]);
 
-  const applyRules = useCallback((
+  // 웹 빌드: 브라우저가 준 File을 읽음
+  // 동영상 메타데이터는 Rust 추출기로만 읽을 수 있어 받지 않음
+  const processFiles = useCallback(async (files: FileList | File[]) => {
+    const videoTypes = Object.values(VIDEO_MIME_TYPES);
+    const pending = Array.from(files)
+      .filter(f => !videoTypes.includes(f.type) && !isVideoFile(f.name))
+      .filter(f => f.type.startsWith('image/') || isSupportedFile(f.name))
+      .map((file): PendingFile => ({
+        name: file.name,
+        size: file.size,
+        load: async () => ({ file, metadata: await extractAllMetadata(file) }),
+      }));
+    await addFiles(pending);
+  }, [addFiles]);
+
+  // Tauri: 경로로 읽어 Rust 메타데이터 추출기(`read_metadata`) 사용
+  // 아카이브는 `read_archive`로 풀어 항목마다 아카이브 안 경로를 원래 이름으로 추가
+  const processPaths = useCallback(async (paths: string[], sidecarPatterns: string[]) => {
+    const { readFile, stat } = await import('@tauri-apps/plugin-fs');
+    const pending: PendingFile[] = [];
+    for (const path of paths) {
+      try {
+        if (isArchiveFile(path)) {
+          pending.push(...(await readArchive(path)));
+          continue;
+        }
+        const { size } = await stat(path);
+        const name = fileNameOf(path);
+        pending.push({
+          name,
+          size,
+          load: async () => {
+            const [contents, loaded] = await Promise.all([readFile(path), readMetadata(path, sidecarPatterns)]);
+            return { file: new File([contents], name, { type: mimeTypeOf(name) }), ...loaded };
+          },
+        });
+      } catch (e) {
+        logger.error('Files', `Failed to read file: ${path}`, e);
+      }
+    }
+    await addFiles(pending);
+  }, [addFiles]);
+
+  const applyRules = useCallback(async (
     rules: KeywordRule[],
     partialMatchSettings?: PartialMatchSettings
   ) => {
     logger.info('Matching', `Applying ${rules.length} rules with partial match: ${partialMatchSettings?.globalEnabled}`);
+    const request = ++matchRequestRef.current;
 
-    setImages((prev) =>
-      prev.map((img) => {
-        const metadataEntries = Object.entries(img.metadata);
-        if (metadataEntries.length === 0) {
-          return {
-            ...img,
-            matchedRule: null,
-            matchedField: null,
-            newFileName: null,
-            matchScore: undefined,
-            candidateMatches: undefined,
-            isPartialMatch: undefined,
-          };
-        }
-
-        const enabledRules = rules.filter((r) => r.enabled);
-        const allCandidates: MatchCandidate[] = [];
-
-        // 1단계: 완전 일치 검색 (기존 로직)
-        for (const rule of enabledRules) {
-          for (const [fieldName, fieldValue] of metadataEntries) {
-            if (fieldValue.includes(rule.keyword)) {
-              const ext = img.originalName.split('.').pop() || 'jpg';
-              const baseName = rule.newFileName.replace(/\.[^.]+$/, '');
-
-              // 완전 일치는 바로 반환 (가장 높은 우선순위)
-              return {
-                ...img,
-                matchedRule: rule,
-                matchedField: fieldName,
-                newFileName: `${baseName}.${ext}`,
-                matchScore: 1.0,
-                candidateMatches: undefined,
-                isPartialMatch: false,
-              };
-            }
-          }
-        }
-
-        // 2단계: 부분 매칭 검색 (설정이 활성화된 경우)
-        if (partialMatchSettings?.globalEnabled) {
-          const separator = partialMatchSettings.tokenSeparator || ',';
-          const minRatio = partialMatchSettings.minMatchRatio || 0.7;
-
-          for (const rule of enabledRules) {
-            // 전역 부분 매칭이 켜져 있거나, 개별 규칙의 부분 매칭이 켜져 있는 경우
-            const isPartialMatchEnabled = partialMatchSettings.globalEnabled || rule.partialMatch;
-            if (!isPartialMatchEnabled) continue;
-
-            for (const [fieldName, fieldValue] of metadataEntries) {
-              const { score, matchedTokens, totalTokens } = calculatePartialMatchScore(
-                rule.keyword,
-                fieldValue,
-                separator
-              );
-
-              // 최소 일치율을 넘는 경우 후보에 추가
-              if (score >= minRatio && score < 1.0) {
-                allCandidates.push({
-                  rule,
-                  matchedField: fieldName,
-                  matchScore: score,
-                  matchedTokens,
-                  totalTokens,
-                });
-              }
-            }
-          }
-        } else {
-          // 전역 부분 매칭이 꺼져 있더라도, 개별 규칙에서 부분 매칭이 켜져 있는 경우 처리
-          const separator = partialMatchSettings?.tokenSeparator || ',';
-          const minRatio = partialMatchSettings?.minMatchRatio || 0.7;
-
-          for (const rule of enabledRules) {
-            if (!rule.partialMatch) continue;
-
-            for (const [fieldName, fieldValue] of metadataEntries) {
-              const { score, matchedTokens, totalTokens } = calculatePartialMatchScore(
-                rule.keyword,
-                fieldValue,
-                separator
-              );
-
-              if (score >= minRatio && score < 1.0) {
-                allCandidates.push({
-                  rule,
-                  matchedField: fieldName,
-                  matchScore: score,
-                  matchedTokens,
-                  totalTokens,
-                });
-              }
-            }
-          }
-        }
-
-        // 부분 매칭 후보가 있는 경우
-        if (allCandidates.length > 0) {
-          // 점수 순으로 정렬 (높은 점수 우선)
-          allCandidates.sort((a, b) => b.matchScore - a.matchScore);
-
-          // 동일한 규칙의 중복 제거 (가장 높은 점수만 유지)
-          const uniqueCandidates: MatchCandidate[] = [];
-          const seenRuleIds = new Set<string>();
-          for (const candidate of allCandidates) {
-            if (!seenRuleIds.has(candidate.rule.id)) {
-              seenRuleIds.add(candidate.rule.id);
-              uniqueCandidates.push(candidate);
-            }
-          }
-
-          // 가장 높은 점수의 후보를 기본 매칭으로 설정
-          const bestMatch = uniqueCandidates[0];
-          const ext = img.originalName.split('.').pop() || 'jpg';
-          const baseName = bestMatch.rule.newFileName.replace(/\.[^.]+$/, '');
-
-          logger.debug('Matching', `Partial match found for ${img.originalName}`, {
-            bestMatch: bestMatch.rule.newFileName,
-            score: bestMatch.matchScore,
-            candidates: uniqueCandidates.length,
-          });
+    // Tauri에서는 Rust 매칭 엔진 사용 (메인 스레드를 막지 않음)
+    if (isTauri()) {
+      try {
+        const { invoke } = await import('@tauri-apps/api/core');
+        const results = await invoke<ImageMatch[]>('match_images', {
+          ruleSet: { rules, partialMatch: partialMatchSettings ?? null },
+          // 등록하지 못한 이미지는 아래에서 웹 매칭 로직으로 매칭
+          imageIds: imagesRef.current
+            .filter((img) => !unregisteredRef.current.has(img.id))
+            .map((img) => img.id),
+        });
+        // 그 사이 다시 적용했으면 이전 결과는 버림
+        if (request !== matchRequestRef.current) return;
 
-          return {
-            ...img,
-            matchedRule: bestMatch.rule,
-            matchedField: bestMatch.matchedField,
-            newFileName: `${baseName}.${ext}`,
-            matchScore: bestMatch.matchScore,
-            candidateMatches: uniqueCandidates.length > 1 ? uniqueCandidates : undefined,
-            isPartialMatch: true,
-          };
+        const resultsById = new Map(results.map((result) => [result.imageId, result]));
+        const rulesById = new Map<string, KeywordRule>();
+        for (const rule of rules) {
+          if (!rulesById.has(rule.id)) rulesById.set(rule.id, rule);
         }
+        setImages((prev) =>
+          prev.map((img) => {
+            const result = resultsById.get(img.id);
+            return result
+              ? applyImageMatch(img, result, rulesById)
+              : matchImage(img, rules, partialMatchSettings);
+          })
+        );
+        return;
+      } catch (e) {
+        logger.error('Matching', 'Rust matching failed, falling back to web', e);
+      }
+    }
 
-        return {
-          ...img,
-          matchedRule: null,
-          matchedField: null,
-          newFileName: null,
-          matchScore: undefined,
-          candidateMatches: undefined,
-          isPartialMatch: undefined,
-        };
-      })
-    );
+    setImages((prev) => prev.map((img) => matchImage(img, rules, partialMatchSettings)));
   }, []);
 
   const removeImage = useCallback((id: string) => {
@@ -349,6 +633,8 @@ export function useImageProcessor() {
       }
       return prev.filter((i) => i.id !== id);
     });
+    unregisteredRef.current.delete(id);
+    unregisterImages([id]);
   }, []);
 
   const removeMultipleImages = useCallback((ids: Set<string>) => {
@@ -364,14 +650,21 @@ export function useImageProcessor() {
       totalSizeRef.current -= removedSize;
       return filtered;
     });
+    ids.forEach((id) => unregisteredRef.current.delete(id));
+    unregisterImages([...ids]);
   }, []);
 
   const clearAllImages = useCallback(() => {
     totalSizeRef.current = 0;
     setImages([]);
+    unregisteredRef.current.clear();
+    unregisterImages();
   }, []);
 
   const clearUnmatchedImages = useCallback(() => {
+    const unmatchedIds = imagesRef.current.filter((img) => !img.matchedRule).map((img) => img.id);
+    unmatchedIds.forEach((id) => unregisteredRef.current.delete(id));
+    unregisterImages(unmatchedIds);
     setImages((prev) => {
       let removedSize = 0;
       const filtered = prev.filter((img) => {
@@ -412,6 +705,7 @@ export function useImageProcessor() {
     isProcessing,
     progress,
     processFiles,
+    processPaths,
     applyRules,
     removeImage,
     removeMultipleImages,
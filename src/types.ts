@@ -5,8 +5,28 @@ export interface KeywordRule {
   enabled: boolean;
   matchCount?: number; // 매칭된 이미지 수
   partialMatch?: boolean; // 부분 매칭 활성화 여부 (개별 설정)
+  kind?: RuleKind; // 키워드 해석 방식 (기본: literal)
+  caseInsensitive?: boolean; // 정규식 대소문자 무시
+  normalize?: boolean; // 프롬프트 문법을 벗긴 태그로 비교 (literal 전용, 예: halo@>=1.2)
+  tagMatch?: TagMatch; // 구분자로 나눈 태그 단위 비교 (literal 전용, 없으면 필드 전체에서 찾음)
 }
 
+// exact: 태그 전체, substring: 태그 안, prefix: 태그 앞부분, word: 태그 안의 단어 (hat → red hat, chat 제외)
+export type TagMatch = 'exact' | 'substring' | 'prefix' | 'word';
+
+// literal: 그대로 포함, regex: 정규식 (새 파일명에 {1}, {name} 캡처 그룹 사용),
+// query: AND/OR/NOT 쿼리 (예: prompt:"white background" AND NOT negative:halo)
+export type RuleKind = 'literal' | 'regex' | 'query';
+
+// `validate_rule`, `match_images` 명령의 오류 (src-tauri/src/matching/mod.rs)
+export type RuleError =
+  | { kind: 'invalidPattern'; ruleId: string; message: string }
+  | { kind: 'unknownGroup'; ruleId: string; group: string }
+  | { kind: 'invalidQuery'; ruleId: string; message: string; position: number }
+  | { kind: 'invalidWeight'; ruleId: string; tag: string }
+  | { kind: 'noTags'; ruleId: string }
+  | { kind: 'tooManyPatterns'; message: string };
+
 // 부분 매칭 설정
 export interface PartialMatchSettings {
   globalEnabled: boolean; // 전체 부분 매칭 ON/OFF
@@ -29,6 +49,8 @@ export interface ImageFile {
   originalName: string;
   fileSize: number;
   metadata: Record<string, string>;
+  metadataProvenance?: Record<string, MetadataProvenance>; // 필드별 출처 (데스크톱 앱에서만)
+  metadataReport?: MetadataReport; // Rust 추출기의 진단 보고서 (데스크톱 앱에서만)
   matchedRule: KeywordRule | null;
   matchedField: string | null;
   newFileName: string | null;
@@ -39,6 +61,99 @@ export interface ImageFile {
   isPartialMatch?: boolean; // 부분 매칭으로 매칭되었는지 여부
 }
 
+// Rust `read_metadata` 명령이 돌려주는 필드 (src-tauri/src/metadata/field.rs)
+export type MetadataValue =
+  | { type: 'text'; value: string }
+  | { type: 'number'; value: number }
+  | { type: 'rational'; value: { numerator: number; denominator: number } }
+  | { type: 'list'; value: MetadataValue[] }
+  | { type: 'json'; value: unknown };
+
+// 필드를 읽은 위치
+export interface MetadataProvenance {
+  source: string | null; // 'PNG:tEXt', 'JPEG:APP1', 원본 필드 키 등
+  byteRange: { start: number; end: number } | null; // 파일 안의 위치
+  encoding: string | null; // 'UTF-8', 'ISO-8859-1' 등
+}
+
+export interface MetadataField extends MetadataProvenance {
+  namespace: string; // 'PNG'
+  key: string; // 'parameters'
+  value?: MetadataValue; // 텍스트와 JSON 값이면 없음 (text에 원문이 있음)
+  text: string; // 검색과 표시에 쓰는 문자열
+}
+
+// Rust 진단 보고서 (src-tauri/src/metadata/report.rs)
+export type MetadataIssue =
+  | { kind: 'crcMismatch' }
+  | { kind: 'truncated'; expected: number; available: number }
+  | { kind: 'invalidLength'; length: number }
+  | { kind: 'corrupt' }
+  | { kind: 'decompress' }
+  | { kind: 'malformed' }
+  | { kind: 'missing' }
+  | { kind: 'tooLarge'; limit: number }; // 크기 상한을 넘어 읽지 않음
+
+export type MetadataRecovery =
+  | { kind: 'resynced'; next: number }
+  | { kind: 'partial'; fields: number };
+
+export interface MetadataBlock {
+  name: string; // 'PNG:tEXt', 'JPEG:APP1' 등
+  byteRange: { start: number; end: number };
+  issue: MetadataIssue | null;
+  recovery: MetadataRecovery | null;
+}
+
+export interface MetadataReport {
+  format: string | null; // 'png', 'jpeg', 'quicktime' 등
+  blocks: MetadataBlock[];
+}
+
+// `read_metadata` 명령의 결과
+export interface MetadataExtraction {
+  metadata: MetadataField[];
+  report: MetadataReport;
+}
+
+export interface MetadataError {
+  kind: 'io' | 'invalidArchive' | 'missingEntry';
+  message: string;
+}
+
+// `read_archive` 명령의 항목 (src-tauri/src/metadata/archive/mod.rs)
+export type ArchiveEntryError =
+  | { kind: 'unsupportedMethod'; method: number }
+  | { kind: 'encrypted' }
+  | { kind: 'tooLarge'; size: number }
+  | { kind: 'decompress' }
+  | { kind: 'crcMismatch' }
+  | { kind: 'truncated' };
+
+export interface ArchiveEntry {
+  originalName: string; // 아카이브 안의 상대 경로 ('batch1/00001.png')
+  size: number;
+  extraction: MetadataExtraction | null;
+  error: ArchiveEntryError | null;
+}
+
+// `match_images` 명령의 결과 (src-tauri/src/matching/mod.rs)
+export interface RuleMatch {
+  ruleId: string;
+  newFileName: string; // 확장자 제외, 캡처 그룹을 채운 결과
+  matchedField: string;
+  matchScore: number;
+  matchedTokens: string[];
+  totalTokens: number;
+}
+
+export interface ImageMatch {
+  imageId: string;
+  matched: RuleMatch | null;
+  candidates: RuleMatch[]; // 부분 매칭 후보 (점수 순)
+  isPartialMatch: boolean;
+}
+
 export interface ProcessingProgress {
   current: number;
   total: number;
@@ -56,6 +171,7 @@ export interface BatchDownloadInfo {
 export const LIMITS = {
   MAX_IMAGES: 3000,           // 최대 이미지 수
   MAX_FILE_SIZE: 10 * 1024 * 1024, // 단일 파일 최대 10MB
+  MAX_VIDEO_SIZE: 256 * 1024 * 1024, // 동영상 최대 256MB (메타데이터는 Rust가 필요한 부분만 읽음)
   MAX_TOTAL_SIZE: 6 * 1024 * 1024 * 1024, // 총 6GB
   BATCH_SIZE: 100,            // ZIP 배치당 파일 수
   THUMBNAIL_SIZE: 80,         // 썸네일 크기 (px)
@@ -63,6 +179,48 @@ export const LIMITS = {
   MAX_FILENAME_LENGTH: 200,   // 파일명 최대 길이
 } as const;
 
+// Rust 메타데이터 추출기가 읽는 이미지 형식 (src-tauri/src/metadata/archive/mod.rs의 EXTENSIONS)
+export const IMAGE_MIME_TYPES: Record<string, string> = {
+  png: 'image/png',
+  jpg: 'image/jpeg',
+  jpeg: 'image/jpeg',
+  webp: 'image/webp',
+  avif: 'image/avif',
+  heic: 'image/heic',
+  heif: 'image/heif',
+  jxl: 'image/jxl',
+  gif: 'image/gif',
+  tif: 'image/tiff',
+  tiff: 'image/tiff',
+  svg: 'image/svg+xml',
+  bmp: 'image/bmp',
+  ico: 'image/x-icon',
+};
+
+// 생성 도구가 메타데이터를 남기는 동영상 형식 (MP4, MOV, WebM, MKV)
+export const VIDEO_MIME_TYPES: Record<string, string> = {
+  mp4: 'video/mp4',
+  m4v: 'video/mp4',
+  mov: 'video/quicktime',
+  webm: 'video/webm',
+  mkv: 'video/x-matroska',
+};
+
+// Tauri에서 안의 이미지를 꺼내 읽는 아카이브 (src-tauri/src/metadata/archive)
+export const ARCHIVE_EXTENSIONS = ['zip', 'tar', 'tar.gz', 'tgz'];
+
+// 사이드카 파일 이름 기본 패턴 ({name}: 이미지 파일 이름, {stem}: 확장자를 뺀 이름)
+// src-tauri/src/metadata/sidecar.rs의 DEFAULT_PATTERNS와 같음
+export const DEFAULT_SIDECAR_PATTERNS: string[] = [
+  '{stem}.xmp',
+  '{name}.xmp',
+  '{stem}.txt',
+  '{stem}.caption',
+  '{name}.json',
+  '{stem}.json',
+  '{name}.supplemental-metadata.json',
+];
+
 // 부분 매칭 기본 설정
 export const DEFAULT_PARTIAL_MATCH_SETTINGS: PartialMatchSettings = {
   globalEnabled: false,
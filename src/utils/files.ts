@@ -0,0 +1,36 @@
+import { ARCHIVE_EXTENSIONS, IMAGE_MIME_TYPES, LIMITS, VIDEO_MIME_TYPES } from '../types';
+
+// 경로의 마지막 부분 ('C:\\a\\b.png' → 'b.png', 'batch1/00001.png' → '00001.png')
+export const fileNameOf = (path: string): string => path.split(/[\\/]/).pop() || path;
+
+export const extensionOf = (name: string): string => {
+  const fileName = fileNameOf(name);
+  const dot = fileName.lastIndexOf('.');
+  return dot > 0 ? fileName.slice(dot + 1).toLowerCase() : '';
+};
+
+// 확장자로 MIME 타입 추정 (경로로 읽은 파일에는 타입 정보가 없음)
+export const mimeTypeOf = (name: string): string => {
+  const ext = extensionOf(name) || 'png';
+  return IMAGE_MIME_TYPES[ext] ?? VIDEO_MIME_TYPES[ext] ?? `image/${ext}`;
+};
+
+// Rust 추출기가 읽는 이미지나 동영상인지 (JXL, HEIC 등은 브라우저가 MIME 타입을 비워 두기도 함)
+export const isSupportedFile = (name: string): boolean => {
+  const ext = extensionOf(name);
+  return Object.prototype.hasOwnProperty.call(IMAGE_MIME_TYPES, ext)
+    || Object.prototype.hasOwnProperty.call(VIDEO_MIME_TYPES, ext);
+};
+
+export const isVideoFile = (name: string): boolean =>
+  Object.prototype.hasOwnProperty.call(VIDEO_MIME_TYPES, extensionOf(name));
+
+// 파일 하나의 크기 제한. 동영상은 따로 제한
+export const maxFileSizeOf = (name: string): number =>
+  isVideoFile(name) ? LIMITS.MAX_VIDEO_SIZE : LIMITS.MAX_FILE_SIZE;
+
+// '.tar.gz'처럼 점이 두 개인 확장자도 있어 끝부분으로 비교
+export const isArchiveFile = (name: string): boolean => {
+  const lower = fileNameOf(name).toLowerCase();
+  return ARCHIVE_EXTENSIONS.some((ext) => lower.endsWith(`.${ext}`));
+};
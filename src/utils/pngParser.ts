@@ -28,8 +28,8 @@ function readUint32(data: Uint8Array, offset: number): number {
 
 function decodeText(data: Uint8Array): string {
   try {
-    // UTF-8로 먼저 시도
-    const decoder = new TextDecoder('utf-8');
+    // UTF-8로 먼저 시도 (fatal이 아니면 잘못된 바이트가 U+FFFD로 바뀌어 폴백이 실행되지 않음)
+    const decoder = new TextDecoder('utf-8', { fatal: true });
     return decoder.decode(data);
   } catch {
     // Latin-1 폴백
@@ -0,0 +1,149 @@
+import { KeywordRule, PartialMatchSettings, RuleError, TagMatch } from '../types';
+
+// Check if running in Tauri environment
+const isTauri = () => typeof window !== 'undefined' && '__TAURI__' in window;
+
+// 새 파일명의 캡처 그룹 자리표시자: {1}, {name}
+const PLACEHOLDER = /\{([\p{L}\p{N}_.[\]]+)\}/gu;
+
+// 파일명 정규화: 띄어쓰기 → _, 위험 문자 제거
+export const sanitizeFileName = (name: string): string => {
+  return name
+    .replace(/\s+/g, '_')           // 띄어쓰기 → _
+    .replace(/[<>:"/\\|?*]/g, '_')  // Windows 금지 문자 → _
+    .replace(/_+/g, '_')            // 연속 _ → 단일 _
+    .replace(/^_|_$/g, '');         // 앞뒤 _ 제거
+};
+
+export const isRegexRule = (rule: KeywordRule) => rule.kind === 'regex';
+export const isQueryRule = (rule: KeywordRule) => rule.kind === 'query';
+export const isNormalizedRule = (rule: KeywordRule) => !isRegexRule(rule) && !isQueryRule(rule) && !!rule.normalize;
+// 태그 단위로 비교하는 규칙 (Rust 매칭 엔진 전용)
+export const isTagRule = (rule: KeywordRule) => isNormalizedRule(rule) || (!isRegexRule(rule) && !isQueryRule(rule) && !!rule.tagMatch);
+
+export const TAG_MATCH_LABELS: Record<TagMatch, string> = {
+  exact: '태그 전체',
+  substring: '태그 안',
+  prefix: '태그 앞부분',
+  word: '단어',
+};
+
+// 브라우저용 정규식 (Tauri에서는 Rust regex로 매칭)
+export const compileRuleRegex = (rule: KeywordRule): RegExp =>
+  new RegExp(rule.keyword, rule.caseInsensitive ? 'iu' : 'u');
+
+// 캡처 그룹 값으로 자리표시자 채우기 (없는 그룹은 빈 문자열)
+export const renderFileName = (template: string, match: RegExpExecArray): string =>
+  template.replace(PLACEHOLDER, (_, group: string) => {
+    const value = /^\d+$/.test(group) ? match[Number(group)] : match.groups?.[group];
+    return sanitizeFileName(value ?? '');
+  });
+
+export const describeRuleError = (error: RuleError): string => {
+  switch (error.kind) {
+    case 'invalidPattern':
+      return `잘못된 정규식입니다: ${error.message}`;
+    case 'unknownGroup':
+      return `없는 캡처 그룹입니다: {${error.group}}`;
+    case 'invalidQuery':
+      return `잘못된 쿼리입니다: ${error.message}`;
+    case 'invalidWeight':
+      return `잘못된 가중치 조건입니다: ${error.tag}`;
+    case 'noTags':
+      return '비교할 태그가 없습니다';
+    case 'tooManyPatterns':
+      return `규칙이 너무 많습니다: ${error.message}`;
+  }
+};
+
+// TXT 규칙 파일의 키워드 접두사 (접두사가 없으면 literal)
+// literal: 접두사는 다른 접두사로 시작하는 일반 키워드를 나타냅니다.
+const LITERAL_PREFIX = 'literal:';
+const KIND_PREFIXES: [string, Partial<KeywordRule>][] = [
+  ['query:', { kind: 'query' }],
+  ['regex/i:', { kind: 'regex', caseInsensitive: true }],
+  ['regex:', { kind: 'regex' }],
+];
+
+// tags: 태그 정규화, match/word: 태그 단위 비교, tags/word: 둘 다
+const TAG_PREFIX = /^(tags|match)(?:\/(exact|substring|prefix|word))?:/;
+
+export const parseKeywordPrefix = (keyword: string): Partial<KeywordRule> & { keyword: string } => {
+  if (keyword.startsWith(LITERAL_PREFIX)) {
+    return { keyword: keyword.slice(LITERAL_PREFIX.length).trim() };
+  }
+  const tagPrefix = TAG_PREFIX.exec(keyword);
+  if (tagPrefix) {
+    const [prefix, kind, tagMatch] = tagPrefix;
+    return {
+      ...(kind === 'tags' ? { normalize: true } : {}),
+      ...(tagMatch || kind === 'match' ? { tagMatch: (tagMatch || 'exact') as TagMatch } : {}),
+      keyword: keyword.slice(prefix.length).trim(),
+    };
+  }
+  for (const [prefix, options] of KIND_PREFIXES) {
+    if (keyword.startsWith(prefix)) {
+      return { ...options, keyword: keyword.slice(prefix.length).trim() };
+    }
+  }
+  return { keyword };
+};
+
+export const formatKeywordPrefix = (rule: KeywordRule): string => {
+  if (isQueryRule(rule)) return `query: ${rule.keyword}`;
+  if (isRegexRule(rule)) return `${rule.caseInsensitive ? 'regex/i' : 'regex'}: ${rule.keyword}`;
+  if (isTagRule(rule)) {
+    const kind = isNormalizedRule(rule) ? 'tags' : 'match';
+    return `${kind}${rule.tagMatch ? `/${rule.tagMatch}` : ''}: ${rule.keyword}`;
+  }
+  // 접두사처럼 읽히는 일반 키워드는 가져올 때 다른 종류가 되지 않도록 literal:을 붙입니다.
+  const hasPrefix = parseKeywordPrefix(rule.keyword).keyword !== rule.keyword;
+  return hasPrefix ? `${LITERAL_PREFIX} ${rule.keyword}` : rule.keyword;
+};
+
+// 규칙 생성/수정 시 검사. 올바르면 null, 아니면 오류 메시지
+// 태그 단위 규칙은 매칭과 같은 구분자(settings.tokenSeparator)로 검사합니다.
+export const validateRule = async (
+  rule: KeywordRule,
+  settings: PartialMatchSettings,
+): Promise<string | null> => {
+  if (!isRegexRule(rule) && !isQueryRule(rule) && !isTagRule(rule)) return null;
+
+  if (isTauri()) {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      await invoke('validate_rule', { rule, settings });
+      return null;
+    } catch (e) {
+      return typeof e === 'object' && e !== null && 'kind' in e
+        ? describeRuleError(e as RuleError)
+        : String(e);
+    }
+  }
+
+  // 쿼리와 태그 단위 비교는 Rust에서만 해석
+  if (isQueryRule(rule)) {
+    return '쿼리 규칙은 데스크톱 앱에서만 사용할 수 있습니다.';
+  }
+  if (isTagRule(rule)) {
+    return '태그 단위 규칙은 데스크톱 앱에서만 사용할 수 있습니다.';
+  }
+
+  let regex: RegExp;
+  try {
+    regex = compileRuleRegex(rule);
+  } catch (e) {
+    return describeRuleError({ kind: 'invalidPattern', ruleId: rule.id, message: (e as Error).message });
+  }
+  // 빈 대안을 붙여 모든 그룹이 참여하지 않는 결과로 그룹 수와 이름 확인
+  const empty = new RegExp(`${regex.source}|`, regex.flags).exec('')!;
+  for (const [, group] of rule.newFileName.matchAll(PLACEHOLDER)) {
+    const exists = /^\d+$/.test(group)
+      ? Number(group) < empty.length
+      : Object.prototype.hasOwnProperty.call(empty.groups ?? {}, group);
+    if (!exists) {
+      return describeRuleError({ kind: 'unknownGroup', ruleId: rule.id, group });
+    }
+  }
+  return null;
+};
@@ -1,49 +1,86 @@
 import { LIMITS } from '../types';
 
+// 동영상 프레임을 기다리는 최대 시간 (WebView가 디코딩하지 못하는 코덱 대비)
+const VIDEO_FRAME_TIMEOUT = 5000;
+
 /**
  * 이미지에서 작은 썸네일을 생성하여 메모리를 절약합니다.
- * 원본 이미지 대신 작은 data URL만 저장합니다.
+ * 원본 이미지 대신 작은 data URL만 저장합니다. 동영상은 앞부분 프레임 하나를 씁니다.
  */
 export async function createThumbnail(file: File): Promise<string> {
+  if (file.type.startsWith('video/')) {
+    return createVideoThumbnail(file);
+  }
   return new Promise((resolve) => {
     const img = new Image();
     const url = URL.createObjectURL(file);
-    
+
     img.onload = () => {
       URL.revokeObjectURL(url);
-      
-      const canvas = document.createElement('canvas');
-      const ctx = canvas.getContext('2d');
-      
-      if (!ctx) {
-        resolve('');
-        return;
-      }
-      
-      // 가로세로 비율 유지하면서 썸네일 크기로 축소
-      const size = LIMITS.THUMBNAIL_SIZE;
-      const ratio = Math.min(size / img.width, size / img.height);
-      const width = Math.round(img.width * ratio);
-      const height = Math.round(img.height * ratio);
-      
-      canvas.width = width;
-      canvas.height = height;
-      
-      ctx.drawImage(img, 0, 0, width, height);
-      
-      // 낮은 품질로 압축하여 메모리 절약
-      resolve(canvas.toDataURL('image/jpeg', 0.6));
+      resolve(drawThumbnail(img, img.width, img.height));
     };
-    
+
     img.onerror = () => {
       URL.revokeObjectURL(url);
       resolve('');
     };
-    
+
     img.src = url;
   });
 }
 
+// 1초(짧으면 가운데) 위치의 프레임. 디코딩할 수 없으면 빈 문자열
+function createVideoThumbnail(file: File): Promise<string> {
+  return new Promise((resolve) => {
+    const video = document.createElement('video');
+    const url = URL.createObjectURL(file);
+    let done = false;
+
+    const finish = (thumbnailUrl: string) => {
+      if (done) return;
+      done = true;
+      clearTimeout(timer);
+      URL.revokeObjectURL(url);
+      video.removeAttribute('src');
+      video.load();
+      resolve(thumbnailUrl);
+    };
+    const timer = setTimeout(() => finish(''), VIDEO_FRAME_TIMEOUT);
+
+    video.muted = true;
+    video.preload = 'auto';
+    video.onloadedmetadata = () => {
+      video.currentTime = Math.min(1, (video.duration || 0) / 2);
+    };
+    video.onseeked = () => finish(drawThumbnail(video, video.videoWidth, video.videoHeight));
+    video.onerror = () => finish('');
+    video.src = url;
+  });
+}
+
+function drawThumbnail(source: CanvasImageSource, sourceWidth: number, sourceHeight: number): string {
+  const canvas = document.createElement('canvas');
+  const ctx = canvas.getContext('2d');
+
+  if (!ctx || !sourceWidth || !sourceHeight) {
+    return '';
+  }
+
+  // 가로세로 비율 유지하면서 썸네일 크기로 축소
+  const size = LIMITS.THUMBNAIL_SIZE;
+  const ratio = Math.min(size / sourceWidth, size / sourceHeight);
+  const width = Math.round(sourceWidth * ratio);
+  const height = Math.round(sourceHeight * ratio);
+
+  canvas.width = width;
+  canvas.height = height;
+
+  ctx.drawImage(source, 0, 0, width, height);
+
+  // 낮은 품질로 압축하여 메모리 절약
+  return canvas.toDataURL('image/jpeg', 0.6);
+}
+
 /**
  * 파일 크기를 사람이 읽기 쉬운 형식으로 변환
  */